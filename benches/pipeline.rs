@@ -83,7 +83,7 @@ fn bench_rendering(c: &mut Criterion) {
         b.iter(|| {
             let mut renderer = ShapeRenderer::new(&palette);
             renderer.add_stamps(builtins.iter());
-            renderer.render(black_box(small))
+            renderer.render(black_box(small)).unwrap()
         })
     });
 
@@ -91,7 +91,7 @@ fn bench_rendering(c: &mut Criterion) {
         b.iter(|| {
             let mut renderer = ShapeRenderer::new(&palette);
             renderer.add_stamps(builtins.iter());
-            renderer.render(black_box(medium))
+            renderer.render(black_box(medium)).unwrap()
         })
     });
 
@@ -114,7 +114,7 @@ fn bench_packing(c: &mut Criterion) {
     let mut renderer = ShapeRenderer::new(&palette);
     renderer.add_stamps(builtins.iter());
 
-    let rendered_small: Vec<RenderedShape> = shapes.iter().map(|s| renderer.render(s)).collect();
+    let rendered_small: Vec<RenderedShape> = shapes.iter().map(|s| renderer.render(s).unwrap()).collect();
 
     // Generate 10+ sprites for medium bench
     let mut rendered_medium: Vec<RenderedShape> = Vec::new();
@@ -127,11 +127,11 @@ fn bench_packing(c: &mut Criterion) {
     let packer = SheetPacker::new(0);
 
     group.bench_function("pack_sheet_small", |b| {
-        b.iter(|| packer.pack(black_box(&rendered_small)))
+        b.iter(|| packer.pack(black_box(&rendered_small)).unwrap())
     });
 
     group.bench_function("pack_sheet_medium", |b| {
-        b.iter(|| packer.pack(black_box(&rendered_medium)))
+        b.iter(|| packer.pack(black_box(&rendered_medium)).unwrap())
     });
 
     group.finish();
@@ -160,16 +160,19 @@ fn bench_quantization(c: &mut Criterion) {
     let config_none = P8Config {
         dither: DitherMethod::None,
         transparent_index: 0,
+        ..P8Config::default()
     };
 
     let config_ordered = P8Config {
         dither: DitherMethod::Ordered,
         transparent_index: 0,
+        ..P8Config::default()
     };
 
     let config_fs = P8Config {
         dither: DitherMethod::FloydSteinberg,
         transparent_index: 0,
+        ..P8Config::default()
     };
 
     group.bench_function("quantize_direct", |b| {
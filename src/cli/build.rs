@@ -2,22 +2,31 @@
 //!
 //! Processes shape files and outputs PNG images.
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::sync::mpsc::{self, Receiver};
 use std::time::Duration;
 
 use clap::Args;
 use notify::{EventKind, RecursiveMode, Watcher};
 
-use crate::discovery::{discover, discover_paths, load_assets, LoadOptions};
+use crate::cli::cache::{content_hash, BuildCache, CacheEntry};
+use crate::cli::integrity::{write_integrity, INTEGRITY_FILE};
+use crate::cli::outputs::{write_outputs, OUTPUTS_FILE};
+use crate::cli::serve::{PreviewServer, DEFAULT_ADDR};
+use crate::cli::verify::{relative_files, scratch_dir};
+use crate::discovery::{discover, discover_paths, load_assets, load_assets_cached, LoadOptions, Manifest, ParseCache, ScanResult};
 use crate::error::{PxError, Result};
-use crate::output::{display_path, plural, Printer};
-use crate::parser::{parse_map_file, parse_prefab_file, parse_shape_file, parse_shader_file, parse_target_file};
-use crate::registry::AssetRegistry;
-use crate::render::{sprites_that_fit, write_p8, write_png, write_sheet_json, DitherMethod, MapRenderer, P8Config, PrefabRenderer, RenderedShape, ShapeRenderer, SheetPacker};
-use crate::types::{BuiltinBrushes, BuiltinShaders, BuiltinStamps, BuiltinTargets, Palette, Shader, ShapeMetadata, SheetConfig, Target};
-use crate::validation::{print_diagnostics, validate_registry};
+use crate::output::{display_path, plural, Printer, Progress, Verbosity};
+use crate::parser::map::LAYER_MARKER;
+use crate::discovery::detect_asset_kind;
+use crate::parser::{parse_documents, parse_map_file, parse_prefab_file, parse_shape_file, parse_shader_file, parse_target_file};
+use crate::registry::{AssetId, AssetKind, AssetRegistry};
+use crate::render::{is_png_ref, normal_map, p8_flags, read_png_refs, p8_map, render_assets, render_project, scale_pixels_f, shader_palette, sprite_indices, sprites_that_fit, write_gif, write_godot, write_p8, write_png, write_png_as, write_sheet_json, write_rust, write_svg, write_tiled, write_unity_meta, write_webp, AssetMetadata, DitherMethod, MapRenderer, P8Config, P8Sections, PackStrategy, PrefabRenderer, RenderOptions, RenderedAsset, RenderedProject, RenderedShape, ShapeRenderer, SheetMeta, SheetPacker, SheetSizing, MAX_INDEXED_COLOURS, P8_MAP_HEIGHT, P8_MAP_WIDTH};
+use crate::types::{Brush, BuiltinBrushes, BuiltinShaders, BuiltinStamps, BuiltinTargets, EmbeddedSource, Map, Palette, PaletteMode, Shader, ShapeMetadata, SheetConfig, Stamp, Target};
+use crate::validation::{print_diagnostics, validate_for_target};
 
 /// Build sprites and maps from definition files
 #[derive(Args, Debug)]
@@ -45,32 +54,248 @@ pub struct BuildArgs {
     #[arg(long)]
     pub validate: bool,
 
+    /// Validate and abort on warnings as well as errors (implies --validate)
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Output format for individual files (overrides target format)
+    #[arg(long, value_parser = ["png", "auto", "svg", "webp", "tiled", "godot", "unity"])]
+    pub format: Option<String>,
+
     /// Pack all sprites into a single sprite sheet
     #[arg(long)]
     pub sheet: bool,
 
+    /// Sheet dimensions: power-of-two width, tight fit, or square power of two
+    #[arg(long, value_parser = ["pot-width", "tight", "square-pot"])]
+    pub sheet_size: Option<String>,
+
+    /// Crop transparent borders from sprites before packing the sheet
+    #[arg(long)]
+    pub trim: bool,
+
+    /// Place sheet sprites in fixed WxH cells, in order, instead of packing them
+    #[arg(long, value_name = "WxH", value_parser = parse_grid_cell, requires = "sheet")]
+    pub grid: Option<(u32, u32)>,
+
+    /// Lay sheet sprites out in this many columns (overrides target columns)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub columns: Option<u32>,
+
+    /// Lay sheet sprites out as animation frames, N to a row (0 = one row), recording each frame's row and column
+    #[arg(long, value_name = "N", requires = "sheet", conflicts_with = "columns")]
+    pub frames_per_row: Option<u32>,
+
+    /// Wrap packed sheet shelves at this width (overrides target max_width)
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_width: Option<u32>,
+
     /// Padding between sprites in sheet (pixels)
     #[arg(long)]
     pub padding: Option<u32>,
 
-    /// Dithering method for indexed output (none, ordered, floyd-steinberg)
-    #[arg(long, value_parser = ["none", "ordered", "floyd-steinberg"])]
+    /// Extrude sheet sprites' edges this many pixels around each frame, against filtering bleed
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub bleed: u32,
+
+    /// Fail the build if any glyph fell back to magenta, listing each one
+    #[arg(long)]
+    pub error_on_missing: bool,
+
+    /// Dithering method for indexed output (none, ordered, floyd-steinberg, atkinson)
+    #[arg(long, value_parser = ["none", "ordered", "floyd-steinberg", "atkinson"])]
     pub dither: Option<String>,
 
     /// Watch for changes and rebuild automatically
     #[arg(long)]
     pub watch: bool,
+
+    /// With --watch, milliseconds to wait for more changes before rebuilding (0 = no wait)
+    #[arg(long, value_name = "MS", requires = "watch")]
+    pub debounce: Option<u64>,
+
+    /// With --watch, serve the output directory and reload the page after each rebuild
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = DEFAULT_ADDR, requires = "watch")]
+    pub serve: Option<String>,
+
+    /// Map output mode: composited image, or tile indices into a shared tileset
+    #[arg(long, value_parser = ["image", "tilemap"])]
+    pub map_mode: Option<String>,
+
+    /// Write multi-shape files as animated GIFs instead of one PNG per shape
+    #[arg(long)]
+    pub gif: bool,
+
+    /// GIF playback rate in frames per second
+    #[arg(long, default_value_t = 10)]
+    pub fps: u32,
+
+    /// Render rotation frames `{name}-spin-{i}` for the named shape
+    #[arg(long, value_name = "NAME")]
+    pub spin: Option<String>,
+
+    /// Number of evenly spaced rotation frames for --spin
+    #[arg(long, value_name = "N", default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    pub frames: u32,
+
+    /// Only write the named shapes, prefabs and maps (comma-separated)
+    #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// Write everything except the named shapes, prefabs and maps (comma-separated)
+    #[arg(long, value_name = "NAMES", value_delimiter = ',')]
+    pub except: Vec<String>,
+
+    /// Also write a `{name}.normal.png` normal map for each shape and prefab
+    #[arg(long)]
+    pub normals: bool,
+
+    /// Also write a `{name}.preview.png` scaled by this factor (e.g. 1.5 or 0.5)
+    #[arg(long, value_name = "FACTOR", value_parser = parse_preview_scale)]
+    pub preview_scale: Option<f32>,
+
+    /// Include each asset's grid and legend in its JSON metadata
+    #[arg(long)]
+    pub embed_source: bool,
+
+    /// Palette variant to render with, overriding the shader's
+    #[arg(long, value_name = "NAME")]
+    pub palette_variant: Option<String>,
+
+    /// Also write a Rust module of sprite constants to this path
+    #[arg(long, value_name = "PATH")]
+    pub emit_rust: Option<PathBuf>,
+
+    /// Rebuild everything, ignoring the incremental build cache
+    #[arg(long)]
+    pub force: bool,
+
+    /// Render everything and list the files a build would write, without writing them
+    #[arg(long, conflicts_with = "watch")]
+    pub dry_run: bool,
+
+    /// Pad every shape to the size of the largest, centred on a transparent canvas
+    #[arg(long)]
+    pub uniform_size: bool,
+
+    /// Number of files to render at once (default: one per CPU)
+    #[arg(long, short = 'j', value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Fail when an asset uses more colours than this (overrides target max_colours)
+    #[arg(long, value_name = "N")]
+    pub max_colours: Option<usize>,
+
+    /// Kind of definition read from stdin with `px build -`
+    #[arg(long, value_parser = ["shape", "prefab", "map"], default_value = "shape")]
+    pub kind: String,
+
+    /// Write integrity.json with a SHA-384 SRI hash of each output file
+    #[arg(long)]
+    pub emit_integrity: bool,
+
+    /// Write the output manifest here instead of outputs.json in the output directory
+    #[arg(long, value_name = "PATH")]
+    pub manifest_out: Option<PathBuf>,
+
+    /// List the builtin targets and their settings, then exit
+    #[arg(long)]
+    pub list_targets: bool,
 }
 
 pub fn run(args: BuildArgs, printer: &Printer) -> Result<()> {
-    build_once(&args, printer)?;
+    if args.list_targets {
+        for (name, settings) in target_listing() {
+            printer.info(&name, &settings);
+        }
+        return Ok(());
+    }
+
+    // `px build -` renders one definition from stdin, not discovered files
+    if args.files.iter().any(|f| f.as_os_str() == "-") {
+        return build_reader(&args, Path::new("."), &mut std::io::stdin().lock(), printer).map(|_| ());
+    }
+
+    if args.dry_run {
+        let planned = dry_run(args, printer)?;
+        for path in &planned {
+            printer.info("Would write", &display_path(path));
+        }
+        printer.success("Finished", &format!("dry run, {} not written", plural(planned.len(), "file", "files")));
+        return Ok(());
+    }
+
+    // Watch rebuilds reuse parsed files that haven't changed
+    let mut parse_cache = ParseCache::new();
+    let output = build_once(&args, &mut parse_cache, None, printer)?;
     if args.watch {
-        watch_loop(&args, printer)?;
+        let server = match &args.serve {
+            Some(addr) => {
+                let server = PreviewServer::start(addr, output)?;
+                printer.info("Serving", &format!("http://{}", server.addr()));
+                Some(server)
+            }
+            None => None,
+        };
+        watch_loop(&args, &mut parse_cache, server.as_ref(), printer)?;
     }
     Ok(())
 }
 
-fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
+/// Build into scratch directories and return the files a real build would
+/// write, mapped back to where it would write them. Nothing is written to
+/// the output directory (it isn't even created).
+///
+/// The scratch build has no cache, so every output is listed, as with
+/// `--force`.
+fn dry_run(mut args: BuildArgs, printer: &Printer) -> Result<Vec<PathBuf>> {
+    let manifest = match &args.output {
+        Some(_) => Manifest::default(),
+        None if args.files.is_empty() => discover(".")?.manifest,
+        None => discover_paths(&args.files)?.manifest,
+    };
+    let output = output_dir(&args, &manifest);
+
+    let scratch = scratch_dir("px-dry-run");
+    let rust_scratch = scratch_dir("px-dry-run-rust");
+    let rust = args.emit_rust.take();
+    if rust.is_some() {
+        fs::create_dir_all(&rust_scratch).map_err(|e| PxError::Io {
+            path: rust_scratch.clone(),
+            message: format!("Failed to create scratch directory: {}", e),
+        })?;
+    }
+    args.emit_rust = rust.as_ref().map(|path| rust_scratch.join(path.file_name().unwrap_or_default()));
+    let manifest_out = args.manifest_out.take();
+    args.output = Some(scratch.clone());
+
+    printer.status("Rendering", &format!("into {}", printer.dim(&display_path(&scratch))));
+    let result = build_once(&args, &mut ParseCache::new(), None, &Printer::with_verbosity(Verbosity::Quiet))
+        .and_then(|_| relative_files(&scratch));
+    let _ = fs::remove_dir_all(&scratch);
+    let _ = fs::remove_dir_all(&rust_scratch);
+
+    let mut planned: Vec<PathBuf> = result?
+        .into_iter()
+        .map(|relative| match &manifest_out {
+            Some(path) if relative == Path::new(OUTPUTS_FILE) => path.clone(),
+            _ => output.join(relative),
+        })
+        .collect();
+    planned.extend(rust);
+    Ok(planned)
+}
+
+/// Run a single build, returning the output directory.
+/// Asset files are loaded through `parse_cache`. When `changed` lists the
+/// files a watch rebuild was triggered by, incremental builds only rebuild
+/// the assets those changes reach.
+fn build_once(
+    args: &BuildArgs,
+    parse_cache: &mut ParseCache,
+    changed: Option<&[PathBuf]>,
+    printer: &Printer,
+) -> Result<PathBuf> {
 
     // Discover assets: no args = scan current dir (reads px.yaml), args = explicit paths
     let discovery = if args.files.is_empty() {
@@ -98,25 +323,30 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
         );
     }
 
-    // Resolve output directory: CLI > manifest > "dist"
-    let output = args
-        .output
-        .clone()
-        .unwrap_or_else(|| discovery.manifest.output.clone());
+    let output = output_dir(args, &discovery.manifest);
 
     // Load assets into registry for palette/shader resolution
-    let builder = load_assets(&discovery.scan, &LoadOptions::with_builtins())?;
+    let builder = load_assets_cached(&discovery.scan, &LoadOptions::with_builtins(), parse_cache)?;
     let registry = builder.build()?;
 
+    // Resolve target profile (if specified)
+    let target = resolve_target(args)?;
+
     // Run validation if requested
-    if args.validate {
-        let result = validate_registry(&registry);
+    if args.validate || args.strict {
+        let result =
+            validate_for_target(&registry, target.as_ref(), &discovery.manifest.lints).with_strict(args.strict);
         print_diagnostics(&result, &printer);
 
-        if result.has_errors() {
+        if result.fails() {
+            let help = if result.has_errors() {
+                "Fix the errors above and try again"
+            } else {
+                "Fix the warnings above, or build without --strict"
+            };
             return Err(PxError::Build {
                 message: "Validation failed, aborting build".to_string(),
-                help: Some("Fix the errors above and try again".to_string()),
+                help: Some(help.to_string()),
             });
         }
     }
@@ -129,8 +359,12 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
         })?;
     }
 
-    // Resolve target profile (if specified)
-    let target = resolve_target(&args)?;
+    // --only/--except pick what gets written; everything still parses and
+    // renders so prefabs and maps can compose filtered-out shapes
+    let names = NameFilter { only: &args.only, except: &args.except };
+    for name in names.unmatched(&registry) {
+        printer.warning("Warning", &format!("No shape, prefab or map named '{}'", name));
+    }
 
     // Compute effective settings: CLI > target > manifest > defaults
     let effective_scale = args
@@ -141,6 +375,9 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
         .padding
         .or_else(|| target.as_ref().and_then(|t| t.padding))
         .unwrap_or(0);
+    let max_colours = args.max_colours.or_else(|| target.as_ref().and_then(|t| t.max_colours));
+    let columns = args.columns.or_else(|| target.as_ref().and_then(|t| t.columns));
+    let max_width = args.max_width.or_else(|| target.as_ref().and_then(|t| t.max_width));
     let effective_sheet = if args.sheet {
         SheetConfig::Auto
     } else {
@@ -157,101 +394,289 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
         .or_else(|| auto_detect_shader(&registry));
 
     // Load shader from registry, builtins, or file
-    let shader = resolve_shader(effective_shader_name.as_deref(), &registry)?;
+    let mut shader = resolve_shader(effective_shader_name.as_deref(), &registry)?;
 
     // Get palette from registry or default
-    let palette = resolve_palette(&shader, &registry)?;
+    let palette = shader_palette(&shader, &registry);
 
-    // Collect builtin stamps and brushes (need to own them for lifetime)
-    let builtin_stamps = BuiltinStamps::all();
-    let builtin_brushes = BuiltinBrushes::all();
+    // CLI palette variant overrides the shader's; unknown variants fall back to base colours
+    if let Some(variant) = &args.palette_variant {
+        if palette.has_variant(variant) {
+            shader.palette_variant = Some(variant.clone());
+        } else {
+            printer.warning(
+                "Warning",
+                &format!("Palette '{}' has no variant '{}', using base colours", palette.name, variant),
+            );
+            shader.palette_variant = None;
+        }
+    }
 
-    // Create renderer
-    let mut renderer = ShapeRenderer::new(&palette);
+    // Determine effective format: CLI > target > "png"
+    let effective_format = args
+        .format
+        .as_deref()
+        .or_else(|| target.as_ref().map(|t| t.format.as_str()))
+        .unwrap_or("png");
 
-    // Add builtin stamps
-    for stamp in &builtin_stamps {
-        renderer.add_stamp(stamp);
+    let palette_mode = target.as_ref().map_or(PaletteMode::Rgba, |t| t.palette_mode.clone());
+
+    // Godot sprite frames and Unity sprites are cut from a packed sheet
+    let use_sheet = effective_sheet != SheetConfig::None || matches!(effective_format, "godot" | "unity");
+    let tiled = effective_format == "tiled";
+    let tilemap_mode = args.map_mode.as_deref() == Some("tilemap") || tiled;
+    let render_maps = (!use_sheet || tilemap_mode) && !map_files.is_empty();
+
+    // Everything renders up front through render_assets; the rest of the
+    // build decides what to write and what the cache can skip. Validated
+    // builds fail on unresolved glyphs.
+    let mut images = Vec::new();
+    for path in prefab_files {
+        for prefab in parse_prefab_file(&read_source(path)?)? {
+            images.extend(read_png_refs(prefab.referenced_names(), source_dir(path))?);
+        }
     }
-
-    // Add builtin brushes
-    for brush in &builtin_brushes {
-        renderer.add_brush(brush);
+    if render_maps && !tilemap_mode {
+        for path in map_files {
+            for map in parse_map_file(&read_source(path)?)? {
+                images.extend(read_png_refs(map.referenced_names(), source_dir(path))?);
+            }
+        }
     }
-
-    // Set palette variant if specified in shader
-    let renderer = if let Some(variant) = &shader.palette_variant {
-        renderer.with_variant(variant)
+    let mut render_options = RenderOptions::default()
+        .with_shader(shader.clone())
+        .strict(args.validate || args.strict)
+        .with_images(images)
+        .skip_maps(!render_maps || tilemap_mode);
+    if let Some(name) = &args.spin {
+        render_options = render_options.with_spin(name.clone(), args.frames as usize);
+    }
+    let project = in_pool(args.jobs, || render_assets(&registry, &render_options))?;
+
+    let uniform = args.uniform_size.then(|| {
+        let shapes = registry.shapes().filter_map(|s| project.get(AssetKind::Shape, &s.name));
+        shapes.fold((0, 0), |(w, h), shape| (w.max(shape.image.width()), h.max(shape.image.height())))
+    });
+    let file_options = FileOptions {
+        format: effective_format,
+        palette_mode: palette_mode.clone(),
+        gif_fps: args.gif.then_some(args.fps),
+        normals: args.normals,
+        preview_scale: args.preview_scale,
+        pad_to: uniform,
+        embed_source: args.embed_source,
+        names,
+    };
+    let files = (!use_sheet).then_some(&file_options);
+
+    // Incremental builds skip sources unchanged since the last build. Sheets,
+    // tilemaps, --emit-rust, --uniform-size and --spin need every sprite, so
+    // they always rebuild; --only and --except write a partial build, so
+    // they never record one.
+    let incremental = files.is_some()
+        && !tilemap_mode
+        && args.emit_rust.is_none()
+        && uniform.is_none()
+        && args.spin.is_none()
+        && names.is_empty();
+    let cache = if incremental && !args.force {
+        BuildCache::load(&output)
     } else {
-        renderer
+        BuildCache::default()
+    };
+    let mut next_cache = BuildCache::default();
+    let mut unchanged = 0;
+
+    // Prefabs depend on shapes, and maps on both, so their fingerprints
+    // cover the sources they draw from
+    let settings = build_fingerprint(args, &discovery.scan, effective_scale, effective_format, &shader);
+    let shape_hashes = source_hashes(shape_files);
+    let prefab_hashes = composite_hashes(prefab_files, |source| {
+        let prefabs = parse_prefab_file(source).unwrap_or_default();
+        png_refs(prefabs.iter().flat_map(|p| p.referenced_names()))
+    });
+    let map_hashes = composite_hashes(map_files, |source| {
+        let maps = parse_map_file(source).unwrap_or_default();
+        png_refs(maps.iter().flat_map(|m| m.referenced_names()))
+    });
+    let shape_settings = settings.clone();
+    let prefab_settings = format!("{};shapes={}", settings, content_hash(shape_hashes.concat().as_bytes()));
+    let map_settings = format!("{};prefabs={}", prefab_settings, content_hash(prefab_hashes.concat().as_bytes()));
+
+    // Files a change can't reach keep their last outputs, even though
+    // dependency edits change every fingerprint
+    let affected = changed.filter(|_| incremental).and_then(|paths| affected_assets(paths, &registry));
+    let unreached = |path: &Path| {
+        affected
+            .as_ref()
+            .is_some_and(|ids| file_assets(path).is_some_and(|own| own.iter().all(|id| !ids.contains(id))))
+    };
+    let fresh = |paths: &[PathBuf], hashes: &[String], settings: &str| -> Vec<Option<CacheEntry>> {
+        paths
+            .iter()
+            .zip(hashes)
+            .map(|(path, hash)| {
+                cache
+                    .fresh(path, hash, settings, &output)
+                    .or_else(|| cache.recorded(path, &output).filter(|_| unreached(path)))
+                    .cloned()
+            })
+            .collect()
     };
+    let shape_fresh = fresh(shape_files, &shape_hashes, &shape_settings);
+    let prefab_fresh = fresh(prefab_files, &prefab_hashes, &prefab_settings);
+    let map_fresh = fresh(map_files, &map_hashes, &map_settings);
 
-    let use_sheet = effective_sheet != SheetConfig::None;
-    let write_individual = !use_sheet;
+    let progress = Progress::new(printer, "Building", progress_total(&discovery.scan, render_maps));
 
-    // Phase 1: Render shapes
+    // Phase 1: Write shapes
     let mut total_shapes = 0;
     let mut rendered_shapes: Vec<RenderedShape> = Vec::new();
 
-    for file in shape_files {
-        let (count, rendered) =
-            process_shape_file(file, &output, effective_scale, &renderer, write_individual, &printer)?;
+    // Files render independently; results come back in file order
+    let jobs: Vec<(&PathBuf, &Option<CacheEntry>)> = shape_files.iter().zip(&shape_fresh).collect();
+    let results = for_each_file(args.jobs, &jobs, |&(file, fresh)| {
+        let result = match fresh {
+            Some(_) => Ok(None),
+            None => process_shape_file(file, &output, effective_scale, &project, files, max_colours, printer).map(Some),
+        };
+        progress.tick();
+        result
+    });
+    for (((file, fresh), hash), result) in jobs.iter().zip(&shape_hashes).zip(results) {
+        let result = result?;
+        if let Some(entry) = fresh {
+            unchanged += 1;
+            next_cache.insert(file, entry.clone());
+        }
+        let Some((count, rendered)) = result else { continue };
         total_shapes += count;
+        // A multi-shape file may have been written as one GIF named for the file
+        let mut names: Vec<String> = rendered.iter().map(|r| r.name.clone()).collect();
+        names.push(file_stem(file));
+        next_cache.insert(file, cache_entry(hash, &shape_settings, &names, effective_format, &output));
         rendered_shapes.extend(rendered);
     }
 
-    // Phase 2: Render prefabs (need rendered shapes)
+    // Spin frames join the shapes, so prefabs and sheets can use them
+    if let Some(name) = &args.spin {
+        let frames = spin_shape(name, args, &project, &output, effective_scale, files, printer)?;
+        total_shapes += frames.len();
+        rendered_shapes.extend(frames);
+    }
+
+    // Phase 2: Write prefabs
     let mut total_prefabs = 0;
     let mut rendered_prefabs: Vec<RenderedShape> = Vec::new();
-    if !prefab_files.is_empty() {
-        let mut prefab_renderer = PrefabRenderer::new();
-        for shape in &rendered_shapes {
-            prefab_renderer.add_rendered(shape.clone());
+    for ((file, fresh), hash) in prefab_files.iter().zip(&prefab_fresh).zip(&prefab_hashes) {
+        progress.tick();
+        if let Some(entry) = fresh {
+            unchanged += 1;
+            next_cache.insert(file, entry.clone());
+            continue;
         }
 
-        for file in prefab_files {
-            let (count, rendered) =
-                process_prefab_file(file, &output, effective_scale, &mut prefab_renderer, write_individual, &printer)?;
-            total_prefabs += count;
-            rendered_prefabs.extend(rendered);
-        }
+        let (count, rendered) = process_prefab_file(file, &output, effective_scale, &project, files, max_colours, printer)?;
+        total_prefabs += count;
+        let names: Vec<String> = rendered.iter().map(|r| r.name.clone()).collect();
+        next_cache.insert(file, cache_entry(hash, &prefab_settings, &names, effective_format, &output));
+        rendered_prefabs.extend(rendered);
     }
 
-    // Phase 3: Render maps (skip when packing a sheet)
+    // Phase 3: Write maps (skip when packing a sheet, unless emitting tilemaps)
     let mut total_maps = 0;
-    if !use_sheet && !map_files.is_empty() {
-        let mut map_renderer = MapRenderer::new();
-        for shape in &rendered_shapes {
-            map_renderer.add_rendered(shape.clone());
-        }
-        for prefab in &rendered_prefabs {
-            map_renderer.add_rendered(prefab.clone());
+    if render_maps {
+        if tilemap_mode {
+            let mut map_renderer = MapRenderer::new();
+            for sprite in rendered_shapes.iter().chain(&rendered_prefabs) {
+                map_renderer.add_rendered(sprite.clone());
+            }
+            let mut tiles: Vec<RenderedShape> = rendered_shapes.clone();
+            tiles.extend(rendered_prefabs.iter().cloned());
+            let options = TilesetOptions {
+                scale: effective_scale.unwrap_or(1),
+                padding: effective_padding,
+                tiled,
+                palette_mode: palette_mode.clone(),
+            };
+            total_maps += process_tilemap_files(
+                map_files,
+                &output,
+                &options,
+                &map_renderer,
+                &tiles,
+                printer,
+            )?;
+            progress.advance(map_files.len());
+        } else {
+            for ((file, fresh), hash) in map_files.iter().zip(&map_fresh).zip(&map_hashes) {
+                progress.tick();
+                if let Some(entry) = fresh {
+                    unchanged += 1;
+                    next_cache.insert(file, entry.clone());
+                    continue;
+                }
+
+                let names = process_map_file(file, &output, effective_scale, &file_options, &project, max_colours, printer)?;
+                total_maps += names.len();
+                next_cache.insert(file, cache_entry(hash, &map_settings, &names, effective_format, &output));
+            }
         }
+    }
+
+    drop(progress);
+
+    // Before the cache is saved, so failing shapes rebuild next time
+    if args.error_on_missing {
+        check_fallbacks(&project.fallbacks)?;
+    }
 
-        for file in map_files {
-            total_maps += process_map_file(file, &output, effective_scale, &map_renderer, &printer)?;
+    if incremental {
+        next_cache.save(&output)?;
+        if unchanged > 0 {
+            printer.info("Unchanged", &plural(unchanged, "file", "files"));
         }
     }
 
     let out_display = display_path(&output);
 
-    // Determine effective format
-    let effective_format = target
-        .as_ref()
-        .map(|t| t.format.as_str())
-        .unwrap_or("png");
-
     // Sheet packing mode: combine all sprites into one sheet
     if use_sheet {
         let mut all_sprites: Vec<RenderedShape> = Vec::new();
-        all_sprites.extend(rendered_shapes.iter().cloned());
+        all_sprites.extend(rendered_shapes.iter().map(|shape| match uniform {
+            Some((w, h)) => shape.pad_to(w, h).0,
+            None => shape.clone(),
+        }));
         all_sprites.extend(rendered_prefabs.iter().cloned());
-
-        let packer = SheetPacker::new(effective_padding);
-        let (sheet, mut meta) = packer.pack(&all_sprites);
+        all_sprites.retain(|sprite| names.selects(&sprite.name));
+
+        // PICO-8 sprites sit on a fixed 8x8 grid, so never trim or bleed them
+        let sizing = args
+            .sheet_size
+            .as_deref()
+            .map(SheetSizing::from_str_lossy)
+            .unwrap_or_default();
+        let packer = SheetPacker::with_strategy(effective_padding, pack_strategy(args.grid))
+            .with_sizing(sizing)
+            .with_trim(args.trim && effective_format != "p8")
+            .with_columns(columns)
+            .with_max_width(max_width)
+            .with_frames_per_row(args.frames_per_row)
+            .with_bleed(if effective_format == "p8" { 0 } else { args.bleed });
+        let (sheet, mut meta) = packer.pack(&all_sprites)?;
 
         let total = total_shapes + total_prefabs;
 
+        // PICO-8 sheets are always 1x
+        meta.scale = if effective_format == "p8" { 1 } else { effective_scale.unwrap_or(1) };
+        if effective_format == "webp" {
+            meta.image = "sheet.webp".to_string();
+        }
+        if let Some(path) = &args.emit_rust {
+            write_rust(&all_sprites, Some(&meta), path)?;
+            printer.status("Generating", &display_path(path));
+        }
+
         if effective_format == "p8" {
             // PICO-8 output
             let (_, truncated) = sprites_that_fit(&meta.frames, 128, 128);
@@ -271,10 +696,22 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
             let p8_config = P8Config {
                 dither,
                 transparent_index: 0,
+                ..P8Config::default()
+            };
+
+            // Sprite flags come from shape and prefab tags
+            let tags: BTreeMap<String, Vec<String>> = registry
+                .shapes()
+                .map(|s| (s.name.clone(), s.tags.clone()))
+                .chain(registry.prefabs().map(|p| (p.name.clone(), p.tags.clone())))
+                .collect();
+            let sections = P8Sections {
+                flags: p8_flags(&sprite_indices(&meta.frames), &tags, &p8_config.flag_bits),
+                map: p8_map_tiles(map_files, &meta, printer)?,
             };
 
             let p8_path = output.join("sprite.p8");
-            write_p8(&sheet, &p8_path, &p8_config)?;
+            write_p8(&sheet, &sections, &p8_path, &p8_config)?;
 
             printer.status(
                 "Packing",
@@ -288,14 +725,43 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
                 "Finished",
                 &format!("sprite.p8 -> {}", out_display),
             );
+        } else if matches!(effective_format, "godot" | "unity") {
+            let png_path = output.join(&meta.image);
+            let engine_file = if effective_format == "godot" {
+                "sheet.tres".to_string()
+            } else {
+                format!("{}.meta", meta.image)
+            };
+            write_png_as(&sheet, &png_path, meta.scale, &palette_mode)?;
+            if effective_format == "godot" {
+                write_godot(&meta, &output.join(&engine_file))?;
+            } else {
+                write_unity_meta(&meta, &output.join(&engine_file))?;
+            }
+
+            printer.status(
+                "Packing",
+                &format!(
+                    "{} into sheet {}",
+                    plural(total, "sprite", "sprites"),
+                    printer.dim(&format!("({}x{})", sheet.width() * meta.scale as usize, sheet.height() * meta.scale as usize)),
+                ),
+            );
+            printer.success(
+                "Finished",
+                &format!("{} + {} -> {}", meta.image, engine_file, out_display),
+            );
         } else {
-            // PNG output (default)
-            let png_path = output.join("sheet.png");
+            // PNG output (default), or WebP
+            let image_path = output.join(&meta.image);
             let json_path = output.join("sheet.json");
 
-            let sheet_scale = effective_scale.unwrap_or(1);
-            meta.scale = sheet_scale;
-            write_png(&sheet, &png_path, sheet_scale)?;
+            let sheet_scale = meta.scale;
+            match effective_format {
+                "auto" => write_auto_png(&sheet, &image_path, sheet_scale, printer)?,
+                "webp" => write_webp(&sheet, &image_path, sheet_scale)?,
+                _ => write_png_as(&sheet, &image_path, sheet_scale, &palette_mode)?,
+            }
             write_sheet_json(&meta, &json_path)?;
 
             let (sw, sh) = (sheet.width(), sheet.height());
@@ -309,10 +775,17 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
             );
             printer.success(
                 "Finished",
-                &format!("sheet.png + sheet.json -> {}", out_display),
+                &format!("{} + sheet.json -> {}", meta.image, out_display),
             );
         }
     } else {
+        if let Some(path) = &args.emit_rust {
+            let mut all_sprites = rendered_shapes.clone();
+            all_sprites.extend(rendered_prefabs.iter().cloned());
+            write_rust(&all_sprites, None, path)?;
+            printer.status("Generating", &display_path(path));
+        }
+
         let total = total_shapes + total_prefabs + total_maps;
         printer.success(
             "Finished",
@@ -320,7 +793,19 @@ fn build_once(args: &BuildArgs, printer: &Printer) -> Result<()> {
         );
     }
 
-    Ok(())
+    // Hash once everything is on disk, including outputs the cache kept
+    if args.emit_integrity {
+        let count = write_integrity(&output)?;
+        printer.status(
+            "Hashing",
+            &format!("{} into {}", plural(count, "file", "files"), display_path(&output.join(INTEGRITY_FILE))),
+        );
+    }
+    let manifest = args.manifest_out.clone().unwrap_or_else(|| output.join(OUTPUTS_FILE));
+    let count = write_outputs(&output, &manifest)?;
+    printer.verbose("Recording", &format!("{} in {}", plural(count, "file", "files"), display_path(&manifest)));
+
+    Ok(output)
 }
 
 /// Asset file extensions that trigger a rebuild.
@@ -335,6 +820,111 @@ const WATCH_EXTENSIONS: &[&str] = &[
     ".target.md",
 ];
 
+/// Render a single definition read from `reader` and write its images.
+///
+/// The project under `root` supplies the palette and shader, and its
+/// shapes and prefabs (via [`render_project`]) for prefabs and maps to
+/// reference. Only images are written, no
+/// metadata. Returns the paths written.
+pub fn build_reader(args: &BuildArgs, root: &Path, reader: &mut impl Read, printer: &Printer) -> Result<Vec<PathBuf>> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source).map_err(|e| PxError::Io {
+        path: PathBuf::from("-"),
+        message: format!("Failed to read stdin: {}", e),
+    })?;
+
+    let discovery = discover(root)?;
+    let named_shader = args.shader.clone().or_else(|| discovery.manifest.shader.clone());
+    // Piped shapes only need the project for a project shader, so a broken
+    // file elsewhere in the cwd doesn't stop them building
+    let needs_project = args.kind != "shape" || named_shader.as_deref().is_some_and(|n| BuiltinShaders::get(n).is_none());
+    let registry = match load_assets(&discovery.scan, &LoadOptions::with_builtins()).and_then(|b| b.build()) {
+        Ok(registry) => registry,
+        Err(e) if !needs_project => {
+            printer.warning("Skipping", &format!("project assets: {}", e));
+            load_assets(&ScanResult::default(), &LoadOptions::with_builtins())?.build()?
+        }
+        Err(e) => return Err(e),
+    };
+    let shader_name = named_shader.or_else(|| auto_detect_shader(&registry));
+    let shader = resolve_shader(shader_name.as_deref(), &registry)?;
+    let palette = shader_palette(&shader, &registry);
+
+    let stamps = BuiltinStamps::all();
+    let brushes = BuiltinBrushes::all();
+    let renderer = shader_renderer(&palette, &shader, &stamps, &brushes).strict(args.validate || args.strict);
+
+    // Project shapes and prefabs, for prefabs and maps to reference
+    let project_shapes = if args.kind != "shape" {
+        let options = RenderOptions::default().with_shader(shader.clone()).strict(args.validate || args.strict);
+        render_project(&registry, &options)?
+    } else {
+        Vec::new()
+    };
+
+    // (name, image, own scale) for each definition in the source
+    let mut images: Vec<(String, RenderedShape, Option<u32>)> = Vec::new();
+    match args.kind.as_str() {
+        "prefab" => {
+            let mut prefab_renderer = PrefabRenderer::new();
+            for shape in project_shapes {
+                prefab_renderer.add_rendered(shape);
+            }
+            for prefab in parse_prefab_file(&source)? {
+                for image in read_png_refs(prefab.referenced_names(), root)? {
+                    prefab_renderer.add_rendered(image);
+                }
+                let (rendered, _) = prefab_renderer.render(&prefab)?;
+                prefab_renderer.add_rendered(rendered.clone());
+                images.push((prefab.name, rendered, prefab.scale));
+            }
+        }
+        "map" => {
+            let mut map_renderer = MapRenderer::new();
+            for shape in project_shapes {
+                map_renderer.add_rendered(shape);
+            }
+            for map in parse_map_file(&source)? {
+                for image in read_png_refs(map.referenced_names(), root)? {
+                    map_renderer.add_rendered(image);
+                }
+                let (rendered, _) = map_renderer.render(&map)?;
+                images.push((map.name, rendered, map.scale));
+            }
+        }
+        _ => {
+            for shape in parse_shape_file(&source)? {
+                images.push((shape.name.clone(), renderer.render(&shape)?, shape.scale));
+            }
+        }
+    }
+
+    let output = args.output.clone().unwrap_or_else(|| discovery.manifest.output.clone());
+    fs::create_dir_all(&output).map_err(|e| PxError::Io {
+        path: output.clone(),
+        message: format!("Failed to create output directory: {}", e),
+    })?;
+
+    let format = args.format.as_deref().unwrap_or("png");
+    let extension = image_extension(format);
+    let mut written = Vec::new();
+    for (name, rendered, scale) in &images {
+        printer.status(
+            "Compiling",
+            &format!("{} {}", printer.bold(name), printer.dim(&format!("({}x{})", rendered.width(), rendered.height()))),
+        );
+        let scale = args.scale.or(*scale).unwrap_or(1);
+        write_image(rendered, &output, name, scale, format, &PaletteMode::Rgba, printer)?;
+        written.push(output.join(format!("{}.{}", name, extension)));
+    }
+
+    printer.success(
+        "Finished",
+        &format!("{} -> {}", plural(written.len(), &args.kind, &format!("{}s", args.kind)), display_path(&output)),
+    );
+    Ok(written)
+}
+
 /// Returns true if the path is a px asset file worth rebuilding for.
 fn is_asset_path(path: &Path) -> bool {
     let filename = match path.file_name().and_then(|f| f.to_str()) {
@@ -344,8 +934,70 @@ fn is_asset_path(path: &Path) -> bool {
     WATCH_EXTENSIONS.iter().any(|ext| filename.ends_with(ext))
 }
 
+/// Default wait for more changes before a watch rebuild, in milliseconds.
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// Collect `first` and every event that follows within `window` of the
+/// previous one, so a burst of writes triggers one rebuild. A zero window
+/// returns `first` alone.
+fn debounce<T>(rx: &Receiver<T>, first: T, window: Duration) -> Vec<T> {
+    let mut events = vec![first];
+    if window.is_zero() {
+        return events;
+    }
+    while let Ok(event) = rx.recv_timeout(window) {
+        events.push(event);
+    }
+    events
+}
+
+/// Assets a change to `changed` can reach: the assets defined in those
+/// files and everything that depends on them, transitively.
+///
+/// `None` when the change can't be pinned to graph assets and everything
+/// should rebuild: palettes, shaders and targets apply project-wide, and
+/// removed or unparseable files have no names to look up.
+fn affected_assets(changed: &[PathBuf], registry: &AssetRegistry) -> Option<HashSet<AssetId>> {
+    let graph = registry.graph();
+    let known: HashSet<&AssetId> = graph.assets().collect();
+
+    let mut affected = HashSet::new();
+    let mut pending = Vec::new();
+    for path in changed {
+        let ids = file_assets(path)?;
+        if ids.is_empty() || ids.iter().any(|id| !known.contains(id)) {
+            return None;
+        }
+        pending.extend(ids);
+    }
+
+    while let Some(id) = pending.pop() {
+        pending.extend(graph.dependents_of(&id).filter(|dep| !affected.contains(*dep)).cloned());
+        affected.insert(id);
+    }
+    Some(affected)
+}
+
+/// The assets defined in `path`, or `None` for kinds that apply
+/// project-wide or files that can't be read.
+fn file_assets(path: &Path) -> Option<Vec<AssetId>> {
+    let kind = detect_asset_kind(path)?;
+    if matches!(kind, AssetKind::Palette | AssetKind::Shader | AssetKind::Target) {
+        return None;
+    }
+    let source = fs::read_to_string(path).ok()?;
+    let documents = parse_documents(&source).ok()?;
+    Some(documents.into_iter().map(|doc| AssetId::new(kind, doc.name.value)).collect())
+}
+
 /// Watch source directories and rebuild on changes.
-fn watch_loop(args: &BuildArgs, printer: &Printer) -> Result<()> {
+/// A preview `server`, if running, is told about each successful rebuild.
+fn watch_loop(
+    args: &BuildArgs,
+    parse_cache: &mut ParseCache,
+    server: Option<&PreviewServer>,
+    printer: &Printer,
+) -> Result<()> {
 
     // Determine directories to watch
     let watch_dirs: Vec<PathBuf> = if args.files.is_empty() {
@@ -382,6 +1034,10 @@ fn watch_loop(args: &BuildArgs, printer: &Printer) -> Result<()> {
         })?;
     }
 
+    // Debounce window: CLI > manifest > DEFAULT_DEBOUNCE_MS
+    let manifest_debounce = if args.files.is_empty() { discover(".")?.manifest.watch.debounce } else { None };
+    let window = Duration::from_millis(args.debounce.or(manifest_debounce).unwrap_or(DEFAULT_DEBOUNCE_MS));
+
     let dirs_display: Vec<_> = watch_dirs.iter().map(|d| display_path(d)).collect();
     printer.info("Watching", &dirs_display.join(", "));
 
@@ -404,37 +1060,238 @@ fn watch_loop(args: &BuildArgs, printer: &Printer) -> Result<()> {
             continue;
         }
 
-        // Debounce: drain any additional events over 300ms
-        let deadline = Duration::from_millis(300);
-        while rx.recv_timeout(deadline).is_ok() {}
+        let mut changed: Vec<PathBuf> = debounce(&rx, event, window)
+            .into_iter()
+            .flat_map(|event| event.paths)
+            .filter(|p| is_asset_path(p))
+            .collect();
+        changed.sort();
+        changed.dedup();
 
         printer.info("Rebuilding", "change detected");
-        if let Err(e) = build_once(args, printer) {
-            printer.error("Error", &format!("{}", e));
+        match build_once(args, parse_cache, Some(&changed), printer) {
+            Ok(_) => {
+                if let Some(server) = server {
+                    server.notify_rebuild();
+                }
+            }
+            Err(e) => printer.error("Error", &format!("{}", e)),
         }
     }
 
     Ok(())
 }
 
+/// What to write per asset when building individual files rather than a sheet.
+struct FileOptions<'a> {
+    /// Image format (`png`, `auto` or `svg`).
+    format: &'a str,
+    /// How `png` output stores colours (the target's `palette_mode`).
+    palette_mode: PaletteMode,
+    /// Write multi-shape files as a GIF at this frame rate.
+    gif_fps: Option<u32>,
+    /// Write a `{name}.normal.png` beside each image.
+    normals: bool,
+    /// Write a `{name}.preview.png` scaled by this factor.
+    preview_scale: Option<f32>,
+    /// Pad each shape to this size before writing it.
+    pad_to: Option<(usize, usize)>,
+    /// Include the grid and legend in JSON metadata.
+    embed_source: bool,
+    /// Which assets to write (`--only` / `--except`).
+    names: NameFilter<'a>,
+}
+
+/// Asset names picked by `--only` and `--except`.
+#[derive(Debug, Clone, Copy)]
+struct NameFilter<'a> {
+    /// Write only these, when not empty.
+    only: &'a [String],
+    /// Never write these.
+    except: &'a [String],
+}
+
+impl NameFilter<'_> {
+    /// Whether neither flag was given.
+    fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.except.is_empty()
+    }
+
+    /// Whether the asset called `name` should be written.
+    fn selects(&self, name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|n| n == name)) && !self.except.iter().any(|n| n == name)
+    }
+
+    /// Filter names that match no shape, prefab or map in `registry`.
+    fn unmatched<'r>(&'r self, registry: &'r AssetRegistry) -> impl Iterator<Item = &'r String> {
+        self.only.iter().chain(self.except).filter(move |name| {
+            registry.get_shape(name).is_none() && registry.get_prefab(name).is_none() && registry.get_map(name).is_none()
+        })
+    }
+}
+
+/// Map `f` over `files`, across threads when the `parallel` feature is on.
+///
+/// `jobs` caps the number of threads; `None` uses one per CPU. Results
+/// keep input order either way, so output does not depend on which file
+/// finishes first.
+#[cfg(feature = "parallel")]
+fn for_each_file<I, T, F>(jobs: Option<usize>, files: &[I], f: F) -> Vec<T>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I) -> T + Sync + Send,
+{
+    use rayon::prelude::*;
+    let pool = jobs.and_then(|n| rayon::ThreadPoolBuilder::new().num_threads(n).build().ok());
+    match pool {
+        Some(pool) => pool.install(|| files.par_iter().map(f).collect()),
+        None => files.par_iter().map(f).collect(),
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn for_each_file<I, T, F>(_jobs: Option<usize>, files: &[I], f: F) -> Vec<T>
+where
+    F: Fn(&I) -> T,
+{
+    files.iter().map(f).collect()
+}
+
+/// Run `f` on a pool of `jobs` threads, or rayon's default pool.
+#[cfg(feature = "parallel")]
+fn in_pool<T: Send>(jobs: Option<usize>, f: impl FnOnce() -> T + Send) -> T {
+    match jobs.and_then(|n| rayon::ThreadPoolBuilder::new().num_threads(n).build().ok()) {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn in_pool<T>(_jobs: Option<usize>, f: impl FnOnce() -> T) -> T {
+    f()
+}
+
+/// Number of source files a build renders, for the progress bar.
+fn progress_total(scan: &ScanResult, maps: bool) -> usize {
+    scan.shapes.len() + scan.prefabs.len() + if maps { scan.maps.len() } else { 0 }
+}
+
+/// Fingerprint of everything besides a source's own contents that changes
+/// what it renders to: output settings plus palette, stamp, brush, shader
+/// and target sources.
+fn build_fingerprint(
+    args: &BuildArgs,
+    scan: &ScanResult,
+    scale: Option<u32>,
+    format: &str,
+    shader: &Shader,
+) -> String {
+    let mut deps: Vec<&PathBuf> = scan
+        .palettes
+        .iter()
+        .chain(&scan.stamps)
+        .chain(&scan.brushes)
+        .chain(&scan.shaders)
+        .chain(&scan.targets)
+        .collect();
+    deps.sort();
+    let mut deps: Vec<PathBuf> = deps.into_iter().cloned().collect();
+    // --shader and --target may name files outside the scanned paths
+    deps.extend(args.shader.iter().chain(&args.target).map(PathBuf::from).filter(|p| p.is_file()));
+
+    format!(
+        "format={};scale={:?};shader={};variant={:?};gif={:?};normals={};preview={:?};embed_source={};max_colours={:?};error_on_missing={};deps={}",
+        format,
+        scale,
+        shader.name,
+        shader.palette_variant,
+        args.gif.then_some(args.fps),
+        args.normals,
+        args.preview_scale,
+        args.embed_source,
+        args.max_colours,
+        args.error_on_missing,
+        content_hash(source_hashes(&deps).concat().as_bytes()),
+    )
+}
+
+/// Content hash of each source file; unreadable files hash to an empty string.
+fn source_hashes(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| fs::read(path).map(|bytes| content_hash(&bytes)).unwrap_or_default())
+        .collect()
+}
+
+/// Content hash of each prefab or map source, also covering the external
+/// PNGs its legends reference so that editing one rebuilds the source.
+fn composite_hashes(paths: &[PathBuf], refs: impl Fn(&str) -> Vec<String>) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| {
+            let Ok(source) = fs::read_to_string(path) else {
+                return String::new();
+            };
+            let images: Vec<PathBuf> = refs(&source).iter().map(|name| source_dir(path).join(name)).collect();
+            if images.is_empty() {
+                return content_hash(source.as_bytes());
+            }
+            let mut hashes = vec![content_hash(source.as_bytes())];
+            hashes.extend(source_hashes(&images));
+            content_hash(hashes.concat().as_bytes())
+        })
+        .collect()
+}
+
+/// The external PNG references among legend `names`.
+fn png_refs<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    names.filter(|name| is_png_ref(name)).map(String::from).collect()
+}
+
+/// Cache entry for a rebuilt source, listing whichever of the outputs for
+/// `names` were written.
+fn cache_entry(hash: &str, settings: &str, names: &[String], format: &str, output: &Path) -> CacheEntry {
+    let ext = image_extension(format);
+    let outputs = names
+        .iter()
+        .flat_map(|name| {
+            [
+                format!("{}.{}", name, ext),
+                format!("{}.normal.png", name),
+                format!("{}.json", name),
+                format!("{}.gif", name),
+            ]
+        })
+        .filter(|file| output.join(file).exists())
+        .collect();
+    CacheEntry {
+        hash: hash.to_string(),
+        settings: settings.to_string(),
+        outputs,
+    }
+}
+
 /// Process a shape file and render shapes.
-/// When `write_png_files` is true, writes individual PNGs.
+/// When `files` is set, writes individual images as it describes. When its `gif_fps` is
+/// set and the file holds more than one shape, the shapes are written as the
+/// frames of a single animated GIF instead.
 /// Returns the count and the rendered shapes (for prefab compositing or sheet packing).
 fn process_shape_file(
-    path: &PathBuf,
-    output: &PathBuf,
+    path: &Path,
+    output: &Path,
     default_scale: Option<u32>,
-    renderer: &ShapeRenderer,
-    write_png_files: bool,
+    project: &RenderedProject,
+    files: Option<&FileOptions>,
+    max_colours: Option<usize>,
     printer: &Printer,
 ) -> Result<(usize, Vec<RenderedShape>)> {
-    let source = fs::read_to_string(path).map_err(|e| PxError::Io {
-        path: path.clone(),
-        message: format!("Failed to read file: {}", e),
-    })?;
-
-    let shapes = parse_shape_file(&source)?;
+    let shapes = parse_shape_file(&read_source(path)?)?;
     let mut rendered_shapes = Vec::new();
+    let gif_fps = files
+        .filter(|f| shapes.iter().any(|s| f.names.selects(&s.name)))
+        .and_then(|f| f.gif_fps);
+    let animate = gif_fps.is_some() && shapes.len() > 1;
 
     for shape in &shapes {
         let scale = if let Some(s) = default_scale {
@@ -443,7 +1300,9 @@ fn process_shape_file(
             shape.scale.unwrap_or(1)
         };
 
-        let rendered = renderer.render(shape);
+        let rendered = rendered_image(project, AssetKind::Shape, &shape.name)?;
+        check_colour_limit("Shape", &rendered, max_colours)?;
+        let files = files.filter(|f| f.names.selects(&shape.name));
 
         printer.status(
             "Compiling",
@@ -454,16 +1313,28 @@ fn process_shape_file(
             ),
         );
 
-        if write_png_files {
-            let png_name = format!("{}.png", shape.name);
-            let png_path = output.join(&png_name);
-            write_png(&rendered, &png_path, scale)?;
+        if let Some(files) = files {
+            let padded = files.pad_to.map(|(w, h)| rendered.pad_to(w, h));
+            let image = padded.as_ref().map_or(&rendered, |(padded, _)| padded);
+            if !animate {
+                write_image(image, output, &shape.name, scale, files.format, &files.palette_mode, printer)?;
+            }
+            if files.normals {
+                write_normal_map(image, output, &shape.name, scale, &files.palette_mode)?;
+            }
+            if let Some(factor) = files.preview_scale {
+                write_preview(image, output, &shape.name, factor, &files.palette_mode)?;
+            }
 
             // Write JSON metadata
             let metadata = ShapeMetadata {
                 name: shape.name.clone(),
-                size: [rendered.width(), rendered.height()],
+                size: [image.width(), image.height()],
                 tags: shape.tags.clone(),
+                slice: image.slice().or(shape.slice),
+                source_size: padded.as_ref().map(|_| [rendered.width(), rendered.height()]),
+                offset: padded.as_ref().map(|(_, (x, y))| [*x, *y]),
+                source: files.embed_source.then(|| embedded_source([shape.grid()], shape.legend())),
             };
             let json_name = format!("{}.json", shape.name);
             let json_path = output.join(&json_name);
@@ -473,27 +1344,143 @@ fn process_shape_file(
         rendered_shapes.push(rendered);
     }
 
+    if let (true, Some(fps)) = (animate, gif_fps) {
+        let scale = default_scale
+            .filter(|&s| s > 1)
+            .or_else(|| shapes.first().and_then(|s| s.scale))
+            .unwrap_or(1);
+        let stem = file_stem(path);
+        let gif_path = output.join(format!("{}.gif", stem));
+        write_gif(&rendered_shapes, &gif_path, scale, fps)?;
+
+        printer.status(
+            "Animating",
+            &format!(
+                "{} {}",
+                printer.bold(&stem),
+                printer.dim(&format!("({}, {} fps)", plural(rendered_shapes.len(), "frame", "frames"), fps)),
+            ),
+        );
+    }
+
     Ok((shapes.len(), rendered_shapes))
 }
 
+/// Render `--spin` frames for the shape called `name`, writing each frame
+/// (or one `{name}-spin.gif` with `--gif`) when `files` is set.
+fn spin_shape(
+    name: &str,
+    args: &BuildArgs,
+    project: &RenderedProject,
+    output: &Path,
+    scale: Option<u32>,
+    files: Option<&FileOptions>,
+    printer: &Printer,
+) -> Result<Vec<RenderedShape>> {
+    let frames = (0..args.frames)
+        .map(|i| rendered_image(project, AssetKind::Shape, &format!("{}-spin-{}", name, i)))
+        .collect::<Result<Vec<_>>>()?;
+    let scale = scale.unwrap_or(1);
+
+    printer.status(
+        "Spinning",
+        &format!("{} {}", printer.bold(name), printer.dim(&format!("({})", plural(frames.len(), "frame", "frames")))),
+    );
+
+    if let Some(files) = files {
+        if args.gif {
+            write_gif(&frames, &output.join(format!("{}-spin.gif", name)), scale, args.fps)?;
+        } else {
+            for frame in &frames {
+                write_image(frame, output, &frame.name, scale, files.format, &files.palette_mode, printer)?;
+            }
+        }
+    }
+    Ok(frames)
+}
+
+/// File extension of per-asset images and the sheet for `format`.
+fn image_extension(format: &str) -> &'static str {
+    match format {
+        "svg" => "svg",
+        "webp" => "webp",
+        _ => "png",
+    }
+}
+
+/// Write a per-asset image as `{name}.svg` or `{name}.webp` for those
+/// formats, else `{name}.png` in `palette_mode` (or picked by colour count
+/// for `auto`).
+fn write_image(
+    rendered: &RenderedShape,
+    output: &Path,
+    name: &str,
+    scale: u32,
+    format: &str,
+    palette_mode: &PaletteMode,
+    printer: &Printer,
+) -> Result<()> {
+    let png_path = output.join(format!("{}.png", name));
+    match format {
+        "svg" => write_svg(rendered, &output.join(format!("{}.svg", name)), scale),
+        "webp" => write_webp(rendered, &output.join(format!("{}.webp", name)), scale),
+        "auto" => write_auto_png(rendered, &png_path, scale, printer),
+        _ => write_png_as(rendered, &png_path, scale, palette_mode),
+    }
+}
+
+/// Write a PNG for the `auto` format, reporting whether [`write_png`]
+/// chose indexed colour or RGBA.
+fn write_auto_png(rendered: &RenderedShape, path: &Path, scale: u32, printer: &Printer) -> Result<()> {
+    let colours = rendered.colour_histogram().len();
+    let indexed = colours <= MAX_INDEXED_COLOURS;
+    printer.verbose(
+        "Encoding",
+        &format!(
+            "{} as {} {}",
+            display_path(path),
+            if indexed { "indexed" } else { "RGBA" },
+            printer.dim(&format!("({})", plural(colours, "colour", "colours"))),
+        ),
+    );
+    write_png(rendered, path, scale)
+}
+
+/// Write the normal map for a per-asset image as `{name}.normal.png`.
+/// Fully transparent assets have nothing to light and are skipped.
+fn write_normal_map(rendered: &RenderedShape, output: &Path, name: &str, scale: u32, mode: &PaletteMode) -> Result<()> {
+    if rendered.pixels().iter().flatten().all(|c| c.is_transparent()) {
+        return Ok(());
+    }
+    write_png_as(&normal_map(rendered), &output.join(format!("{}.normal.png", name)), scale, mode)
+}
+
+/// Write a per-asset image scaled by `factor` as `{name}.preview.png`.
+fn write_preview(rendered: &RenderedShape, output: &Path, name: &str, factor: f32, mode: &PaletteMode) -> Result<()> {
+    let preview = RenderedShape::new(name, scale_pixels_f(rendered.pixels(), factor));
+    write_png_as(&preview, &output.join(format!("{}.preview.png", name)), 1, mode)
+}
+
+/// Asset name from a definition file path (`walk.shape.md` -> `walk`).
+fn file_stem(path: &Path) -> String {
+    let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or("output");
+    filename.split('.').next().unwrap_or(filename).to_string()
+}
+
 /// Process a prefab file and render prefabs.
-/// When `write_png_files` is true, writes individual PNGs.
+/// When `files` is set, writes individual images as it describes.
 /// Returns the count and the rendered prefabs (for map compositing or sheet packing).
 /// Rendered prefabs are also added to the renderer for nested prefab support.
 fn process_prefab_file(
-    path: &PathBuf,
-    output: &PathBuf,
+    path: &Path,
+    output: &Path,
     default_scale: Option<u32>,
-    prefab_renderer: &mut PrefabRenderer,
-    write_png_files: bool,
+    project: &RenderedProject,
+    files: Option<&FileOptions>,
+    max_colours: Option<usize>,
     printer: &Printer,
 ) -> Result<(usize, Vec<RenderedShape>)> {
-    let source = fs::read_to_string(path).map_err(|e| PxError::Io {
-        path: path.clone(),
-        message: format!("Failed to read file: {}", e),
-    })?;
-
-    let prefabs = parse_prefab_file(&source)?;
+    let prefabs = parse_prefab_file(&read_source(path)?)?;
     let mut rendered_prefabs = Vec::new();
 
     for prefab in &prefabs {
@@ -503,7 +1490,12 @@ fn process_prefab_file(
             prefab.scale.unwrap_or(1)
         };
 
-        let (rendered, metadata) = prefab_renderer.render(prefab)?;
+        let (rendered, mut metadata) = match rendered_asset(project, AssetKind::Prefab, &prefab.name)? {
+            RenderedAsset { image, metadata: Some(AssetMetadata::Prefab(metadata)), .. } => (image.clone(), metadata.clone()),
+            _ => unreachable!("prefabs render with prefab metadata"),
+        };
+        check_colour_limit("Prefab", &rendered, max_colours)?;
+        let files = files.filter(|f| f.names.selects(&prefab.name));
 
         printer.status(
             "Composing",
@@ -514,39 +1506,43 @@ fn process_prefab_file(
             ),
         );
 
-        if write_png_files {
-            let png_name = format!("{}.png", prefab.name);
-            let png_path = output.join(&png_name);
-            write_png(&rendered, &png_path, scale)?;
+        if let Some(files) = files {
+            write_image(&rendered, output, &prefab.name, scale, files.format, &files.palette_mode, printer)?;
+            if files.normals {
+                write_normal_map(&rendered, output, &prefab.name, scale, &files.palette_mode)?;
+            }
+            if let Some(factor) = files.preview_scale {
+                write_preview(&rendered, output, &prefab.name, factor, &files.palette_mode)?;
+            }
 
             // Write JSON metadata
+            if files.embed_source {
+                metadata.source = Some(embedded_source([prefab.grid()], prefab.legend()));
+            }
             let json_name = format!("{}.json", prefab.name);
             let json_path = output.join(&json_name);
             write_metadata_json(&metadata, &json_path)?;
         }
 
-        // Add rendered prefab so later prefabs can reference it
-        prefab_renderer.add_rendered(rendered.clone());
         rendered_prefabs.push(rendered);
     }
 
     Ok((prefabs.len(), rendered_prefabs))
 }
 
-/// Process a map file and write PNG + JSON output.
+/// Process a map file and write image + JSON output as `files` describes.
+/// Returns the names of the maps written.
 fn process_map_file(
-    path: &PathBuf,
-    output: &PathBuf,
+    path: &Path,
+    output: &Path,
     default_scale: Option<u32>,
-    map_renderer: &MapRenderer,
+    files: &FileOptions,
+    project: &RenderedProject,
+    max_colours: Option<usize>,
     printer: &Printer,
-) -> Result<usize> {
-    let source = fs::read_to_string(path).map_err(|e| PxError::Io {
-        path: path.clone(),
-        message: format!("Failed to read file: {}", e),
-    })?;
-
-    let maps = parse_map_file(&source)?;
+) -> Result<Vec<String>> {
+    let mut maps = parse_map_file(&read_source(path)?)?;
+    maps.retain(|map| files.names.selects(&map.name));
 
     for map in &maps {
         let scale = if let Some(s) = default_scale {
@@ -555,7 +1551,11 @@ fn process_map_file(
             map.scale.unwrap_or(1)
         };
 
-        let (rendered, metadata) = map_renderer.render(map)?;
+        let (rendered, mut metadata) = match rendered_asset(project, AssetKind::Map, &map.name)? {
+            RenderedAsset { image, metadata: Some(AssetMetadata::Map(metadata)), .. } => (image.clone(), metadata.clone()),
+            _ => unreachable!("maps render with map metadata"),
+        };
+        check_colour_limit("Map", &rendered, max_colours)?;
 
         let shape_count = map.referenced_names().len();
         printer.status(
@@ -572,20 +1572,239 @@ fn process_map_file(
             ),
         );
 
-        // Write PNG
-        let png_name = format!("{}.png", map.name);
-        let png_path = output.join(&png_name);
-        write_png(&rendered, &png_path, scale)?;
+        // Write image
+        write_image(&rendered, output, &map.name, scale, files.format, &files.palette_mode, printer)?;
 
         // Write JSON metadata
+        if files.embed_source {
+            metadata.source = Some(embedded_source(map.layers().iter().map(Vec::as_slice), map.legend()));
+        }
         let json_name = format!("{}.json", map.name);
         let json_path = output.join(&json_name);
         write_metadata_json(&metadata, &json_path)?;
     }
 
+    Ok(maps.into_iter().map(|m| m.name).collect())
+}
+
+/// Tileset settings for tilemap output.
+struct TilesetOptions {
+    /// Scale applied to the tileset image.
+    scale: u32,
+    /// Padding between tiles in the tileset.
+    padding: u32,
+    /// Also write Tiled JSON maps.
+    tiled: bool,
+    /// How the tileset image stores colours.
+    palette_mode: PaletteMode,
+}
+
+/// Process map files as tilemaps.
+///
+/// Shapes referenced by the maps are packed into a shared `tileset.png` +
+/// `tileset.json`, and each map writes a JSON grid of frame indices instead
+/// of a composited PNG. Tiles are packed in name order so indices are stable.
+/// With `options.tiled`, each map is also written as a Tiled map (`{name}.tmj`).
+fn process_tilemap_files(
+    paths: &[PathBuf],
+    output: &Path,
+    options: &TilesetOptions,
+    map_renderer: &MapRenderer,
+    sprites: &[RenderedShape],
+    printer: &Printer,
+) -> Result<usize> {
+    let mut maps = Vec::new();
+    let mut images = Vec::new();
+    for path in paths {
+        let loaded = load_maps(std::slice::from_ref(path))?;
+        for map in &loaded {
+            images.extend(read_png_refs(map.referenced_names(), source_dir(path))?);
+        }
+        maps.extend(loaded);
+    }
+
+    let referenced: BTreeSet<&str> = maps
+        .iter()
+        .flat_map(|m| m.referenced_names())
+        .filter(|name| *name != "empty")
+        .collect();
+
+    let mut tiles: Vec<RenderedShape> = sprites
+        .iter()
+        .chain(&images)
+        .filter(|s| referenced.contains(s.name.as_str()))
+        .cloned()
+        .collect();
+    tiles.sort_by(|a, b| a.name.cmp(&b.name));
+    tiles.dedup_by(|a, b| a.name == b.name);
+
+    let scale = options.scale;
+    let (tileset, mut meta) = SheetPacker::new(options.padding).pack(&tiles)?;
+    meta.image = "tileset.png".to_string();
+    meta.scale = scale;
+
+    if !tiles.is_empty() {
+        write_png_as(&tileset, &output.join("tileset.png"), scale, &options.palette_mode)?;
+        write_sheet_json(&meta, &output.join("tileset.json"))?;
+        printer.status(
+            "Packing",
+            &format!(
+                "{} into tileset {}",
+                plural(tiles.len(), "tile", "tiles"),
+                printer.dim(&format!("({}x{})", meta.size.0 * scale, meta.size.1 * scale)),
+            ),
+        );
+    }
+
+    for map in &maps {
+        let tilemap = map_renderer.render_tilemap(map, &meta)?;
+
+        printer.status(
+            "Charting",
+            &format!(
+                "{} {}",
+                printer.bold(&map.name),
+                printer.dim(&format!("({}x{} tiles)", map.width(), map.height())),
+            ),
+        );
+
+        let json_path = output.join(format!("{}.json", map.name));
+        write_metadata_json(&tilemap, &json_path)?;
+
+        if options.tiled {
+            write_tiled(&tilemap, &meta, &output.join(format!("{}.tmj", map.name)))?;
+        }
+    }
+
     Ok(maps.len())
 }
 
+/// The grid and legend for `--embed-source`. Map layers are separated by
+/// `---layer---` lines, as in the source.
+fn embedded_source<'a, V: ToString + 'a>(
+    layers: impl IntoIterator<Item = &'a [Vec<char>]>,
+    legend: &HashMap<char, V>,
+) -> EmbeddedSource {
+    let mut grid = Vec::new();
+    for (i, layer) in layers.into_iter().enumerate() {
+        if i > 0 {
+            grid.push(LAYER_MARKER.to_string());
+        }
+        grid.extend(layer.iter().map(|row| row.iter().collect::<String>()));
+    }
+    EmbeddedSource {
+        grid,
+        legend: legend.iter().map(|(glyph, value)| (*glyph, value.to_string())).collect(),
+    }
+}
+
+/// Fail when any `(shape, glyph)` in `fallbacks` fell back to magenta.
+fn check_fallbacks(fallbacks: &[(String, char)]) -> Result<()> {
+    if fallbacks.is_empty() {
+        return Ok(());
+    }
+    let listed: Vec<String> = fallbacks.iter().map(|(shape, glyph)| format!("'{}' in '{}'", glyph, shape)).collect();
+    Err(PxError::Build {
+        message: format!("{} fell back to magenta: {}", plural(fallbacks.len(), "glyph", "glyphs"), listed.join(", ")),
+        help: Some("Map the glyphs in each shape's legend, or define stamps for them".to_string()),
+    })
+}
+
+/// Fail when `rendered` uses more than `max_colours` distinct colours, not
+/// counting transparency.
+fn check_colour_limit(kind: &str, rendered: &RenderedShape, max_colours: Option<usize>) -> Result<()> {
+    let Some(max) = max_colours else {
+        return Ok(());
+    };
+    let colours = rendered.colour_histogram().into_keys().filter(|c| !c.is_transparent()).count();
+    if colours <= max {
+        return Ok(());
+    }
+    Err(PxError::Build {
+        message: format!("{} '{}' uses {} colours, more than the {} allowed", kind, rendered.name, colours, max),
+        help: Some("Use fewer colours, or raise the target's max_colours (or --max-colours)".to_string()),
+    })
+}
+
+/// Directory that external PNG references in a source file resolve against.
+fn source_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new("."))
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to read file: {}", e),
+    })
+}
+
+/// The asset `render_assets` rendered for a definition read from a file.
+fn rendered_asset<'a>(project: &'a RenderedProject, kind: AssetKind, name: &str) -> Result<&'a RenderedAsset> {
+    project.get(kind, name).ok_or_else(|| PxError::Build {
+        message: format!("{:?} '{}' was not rendered", kind, name),
+        help: Some("Check for another definition with the same name".to_string()),
+    })
+}
+
+fn rendered_image(project: &RenderedProject, kind: AssetKind, name: &str) -> Result<RenderedShape> {
+    rendered_asset(project, kind, name).map(|asset| asset.image.clone())
+}
+
+/// Parse every map in the given map files, in order.
+fn load_maps(paths: &[PathBuf]) -> Result<Vec<Map>> {
+    let mut maps = Vec::new();
+    for path in paths {
+        let source = fs::read_to_string(path).map_err(|e| PxError::Io {
+            path: path.clone(),
+            message: format!("Failed to read file: {}", e),
+        })?;
+        maps.extend(parse_map_file(&source)?);
+    }
+    Ok(maps)
+}
+
+/// Lay out the first map for a PICO-8 cart's `__map__` section.
+///
+/// A cart holds a single map, so any others are skipped with a warning, as
+/// are tiles beyond the 128x32 map region. Returns no rows without maps.
+fn p8_map_tiles(paths: &[PathBuf], sheet: &SheetMeta, printer: &Printer) -> Result<Vec<Vec<u8>>> {
+    let maps = load_maps(paths)?;
+    let Some(map) = maps.first() else {
+        return Ok(Vec::new());
+    };
+
+    for other in &maps[1..] {
+        printer.warning(
+            "Skipped",
+            &format!("map '{}': a PICO-8 cart holds one map, using '{}'", other.name, map.name),
+        );
+    }
+    if map.width() > P8_MAP_WIDTH || map.height() > P8_MAP_HEIGHT {
+        printer.warning(
+            "Truncated",
+            &format!(
+                "map '{}' ({}x{}) exceeds the {}x{} PICO-8 map region",
+                map.name,
+                map.width(),
+                map.height(),
+                P8_MAP_WIDTH,
+                P8_MAP_HEIGHT
+            ),
+        );
+    }
+
+    let tiles = p8_map(map, &sprite_indices(&sheet.frames));
+    printer.status(
+        "Charting",
+        &format!(
+            "{} {}",
+            printer.bold(&map.name),
+            printer.dim(&format!("({}x{} tiles)", map.width(), map.height())),
+        ),
+    );
+    Ok(tiles)
+}
+
 /// Write a serializable metadata value as JSON to a file.
 fn write_metadata_json(value: &impl serde::Serialize, path: &std::path::Path) -> Result<()> {
     let json = serde_json::to_string_pretty(value).map_err(|e| PxError::Build {
@@ -601,14 +1820,17 @@ fn write_metadata_json(value: &impl serde::Serialize, path: &std::path::Path) ->
 
 /// Resolve target from CLI args: check builtins, then try file path.
 fn resolve_target(args: &BuildArgs) -> Result<Option<Target>> {
-    let target_name = match &args.target {
-        Some(name) => name,
-        None => return Ok(None),
-    };
+    match &args.target {
+        Some(name) => resolve_target_name(name).map(Some),
+        None => Ok(None),
+    }
+}
 
+/// Resolve a target by name: builtins first, then a `.target.md` file path.
+pub(crate) fn resolve_target_name(target_name: &str) -> Result<Target> {
     // Check builtins first
     if let Some(target) = BuiltinTargets::get(target_name) {
-        return Ok(Some(target));
+        return Ok(target);
     }
 
     // Try as a file path
@@ -620,27 +1842,56 @@ fn resolve_target(args: &BuildArgs) -> Result<Option<Target>> {
         })?;
 
         let builders = parse_target_file(&source)?;
-        let builder = builders.into_iter().next().ok_or_else(|| PxError::Parse {
-            message: format!("No targets found in {}", target_path.display()),
-            help: None,
-        })?;
-
-        let target = builder.build().map_err(|e| PxError::Build {
+        let name = builders
+            .first()
+            .map(|b| b.name().to_string())
+            .ok_or_else(|| PxError::Parse {
+                message: format!("No targets found in {}", target_path.display()),
+                help: None,
+            })?;
+
+        // Parents may be defined in the same file or be builtins
+        let target = BuiltinTargets::resolve(&name, &builders).map_err(|e| PxError::Build {
             message: format!("Failed to build target: {}", e),
-            help: None,
+            help: Some("Check the `inherits` chain in the target file".to_string()),
         })?;
 
-        return Ok(Some(target));
+        return target.ok_or_else(|| PxError::Parse {
+            message: format!("No targets found in {}", target_path.display()),
+            help: None,
+        });
     }
 
     Err(PxError::Build {
         message: format!("Target not found: {}", target_name),
-        help: Some("Use 'web', 'sheet', or 'p8' for builtin targets, or provide a .target.md file path".to_string()),
+        help: Some("Run 'px build --list-targets' for the builtin targets, or provide a .target.md file path".to_string()),
     })
 }
 
+/// Each builtin target's name and a summary of its settings.
+fn target_listing() -> Vec<(String, String)> {
+    BuiltinTargets::all()
+        .into_iter()
+        .map(|target| {
+            let mut settings = vec![target.format.clone()];
+            if let Some(scale) = target.scale {
+                settings.push(format!("scale {}", scale));
+            }
+            settings.push(match target.sheet {
+                SheetConfig::None => "no sheet".to_string(),
+                SheetConfig::Auto => "sheet auto".to_string(),
+                SheetConfig::Fixed { width, height } => format!("sheet {}x{}", width, height),
+            });
+            if let Some(padding) = target.padding {
+                settings.push(format!("padding {}", padding));
+            }
+            (target.name, settings.join(", "))
+        })
+        .collect()
+}
+
 /// Auto-detect a shader when the project has exactly one non-default shader.
-fn auto_detect_shader(registry: &AssetRegistry) -> Option<String> {
+pub(crate) fn auto_detect_shader(registry: &AssetRegistry) -> Option<String> {
     let shaders: Vec<_> = registry
         .shaders()
         .filter(|s| s.name != "default")
@@ -652,8 +1903,63 @@ fn auto_detect_shader(registry: &AssetRegistry) -> Option<String> {
     }
 }
 
+/// Create a shape renderer for a shader.
+///
+/// Registers the given stamps and brushes, activates the shader's palette
+/// variant, and applies its post-processing effects to each rendered shape.
+pub(crate) fn shader_renderer<'a>(
+    palette: &'a Palette,
+    shader: &'a Shader,
+    stamps: &'a [Stamp],
+    brushes: &'a [Brush],
+) -> ShapeRenderer<'a> {
+    let mut renderer = ShapeRenderer::new(palette);
+    renderer.add_stamps(stamps);
+    renderer.add_brushes(brushes);
+
+    // Set palette variant if specified in shader
+    let renderer = if let Some(variant) = &shader.palette_variant {
+        renderer.with_variant(variant)
+    } else {
+        renderer
+    };
+
+    renderer.with_effects(&shader.effects)
+}
+
+/// Grid cells when `--grid` is set, otherwise shelf packing.
+pub(crate) fn pack_strategy(grid: Option<(u32, u32)>) -> PackStrategy {
+    match grid {
+        Some((width, height)) => PackStrategy::Grid { width, height },
+        None => PackStrategy::Shelf,
+    }
+}
+
+/// Parse a `--grid` cell size like `16x16`.
+pub(crate) fn parse_grid_cell(value: &str) -> std::result::Result<(u32, u32), String> {
+    let invalid = || format!("invalid grid cell '{}' (expected WxH, e.g. 16x16)", value);
+    let (w, h) = value.split_once('x').ok_or_else(invalid)?;
+    match (w.trim().parse::<u32>(), h.trim().parse::<u32>()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a `--preview-scale` factor: any positive number.
+fn parse_preview_scale(value: &str) -> std::result::Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(factor),
+        _ => Err(format!("invalid preview scale '{}' (expected a positive number, e.g. 1.5)", value)),
+    }
+}
+
+/// Resolve the output directory: CLI > manifest > [`DEFAULT_OUTPUT`](crate::discovery::DEFAULT_OUTPUT).
+pub(crate) fn output_dir(args: &BuildArgs, manifest: &Manifest) -> PathBuf {
+    args.output.clone().unwrap_or_else(|| manifest.output.clone())
+}
+
 /// Resolve shader by name: registry > builtins > file path.
-fn resolve_shader(name: Option<&str>, registry: &AssetRegistry) -> Result<Shader> {
+pub(crate) fn resolve_shader(name: Option<&str>, registry: &AssetRegistry) -> Result<Shader> {
     let shader_name = match name {
         Some(n) => n,
         None => return Ok(BuiltinShaders::get("default").unwrap()),
@@ -678,13 +1984,16 @@ fn resolve_shader(name: Option<&str>, registry: &AssetRegistry) -> Result<Shader
         })?;
 
         let builders = parse_shader_file(&source)?;
-        let builder = builders.into_iter().next().ok_or_else(|| PxError::Parse {
-            message: format!("No shaders found in {}", shader_path.display()),
-            help: None,
-        })?;
+        let shader = match builders.first() {
+            Some(first) => BuiltinShaders::resolve(first.name(), &builders).map_err(|e| PxError::Build {
+                message: format!("Failed to build shader: {}", e),
+                help: None,
+            })?,
+            None => None,
+        };
 
-        return builder.build(None).map_err(|e| PxError::Build {
-            message: format!("Failed to build shader: {}", e),
+        return shader.ok_or_else(|| PxError::Parse {
+            message: format!("No shaders found in {}", shader_path.display()),
             help: None,
         });
     }
@@ -695,24 +2004,65 @@ fn resolve_shader(name: Option<&str>, registry: &AssetRegistry) -> Result<Shader
     })
 }
 
-/// Resolve palette for a shader: registry > default.
-fn resolve_palette(shader: &Shader, registry: &AssetRegistry) -> Result<Palette> {
-    if shader.palette == "default" {
-        return Ok(Palette::default_palette());
-    }
-
-    // Look up palette by name in discovered project palettes
-    if let Some(palette) = registry.get_palette(&shader.palette) {
-        return Ok(palette.clone());
+/// Build arguments with every flag at its default, building `files` into
+/// `output`. Tests override fields with struct update syntax.
+#[cfg(test)]
+pub(crate) fn plain_build_args(files: Vec<PathBuf>, output: &Path) -> BuildArgs {
+    BuildArgs {
+        files,
+        shader: None,
+        target: None,
+        output: Some(output.to_path_buf()),
+        scale: None,
+        validate: false,
+        sheet: false,
+        padding: None,
+        dither: None,
+        watch: false,
+        map_mode: None,
+        gif: false,
+        fps: 10,
+        format: None,
+        emit_rust: None,
+        trim: false,
+        sheet_size: None,
+        normals: false,
+        palette_variant: None,
+        serve: None,
+        force: false,
+        jobs: None,
+        uniform_size: false,
+        strict: false,
+        grid: None,
+        kind: "shape".to_string(),
+        max_colours: None,
+        list_targets: false,
+        emit_integrity: false,
+        columns: None,
+        max_width: None,
+        manifest_out: None,
+        error_on_missing: false,
+        embed_source: false,
+        preview_scale: None,
+        spin: None,
+        frames: 8,
+        debounce: None,
+        only: vec![],
+        except: vec![],
+        dry_run: false,
+        bleed: 0,
+        frames_per_row: None,
     }
-
-    // Fall back to default
-    Ok(Palette::default_palette())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine;
+    use crate::cli::cache::CACHE_FILE;
+    use crate::types::Colour;
+    use sha2::{Digest, Sha384};
     use tempfile::tempdir;
 
     fn test_printer() -> Printer {
@@ -742,18 +2092,7 @@ name: test-square
         )
         .unwrap();
 
-        let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
-        };
+        let args = plain_build_args(vec![shape_path], &output_dir);
 
         run(args, &test_printer()).unwrap();
 
@@ -788,16 +2127,8 @@ name: scaled-shape
         .unwrap();
 
         let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
             scale: Some(4),
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![shape_path], &output_dir)
         };
 
         run(args, &test_printer()).unwrap();
@@ -837,18 +2168,7 @@ name: shape-b
         )
         .unwrap();
 
-        let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
-        };
+        let args = plain_build_args(vec![shape_path], &output_dir);
 
         run(args, &test_printer()).unwrap();
 
@@ -879,18 +2199,7 @@ scale: 2
         .unwrap();
 
         // CLI scale is None (default), so frontmatter scale should be used
-        let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
-        };
+        let args = plain_build_args(vec![shape_path], &output_dir);
 
         run(args, &test_printer()).unwrap();
 
@@ -926,16 +2235,8 @@ scale: 2
 
         // CLI scale is 4, should override frontmatter's 2
         let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
             scale: Some(4),
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![shape_path], &output_dir)
         };
 
         run(args, &test_printer()).unwrap();
@@ -951,16 +2252,9 @@ scale: 2
     #[test]
     fn test_resolve_builtin_target_web() {
         let args = BuildArgs {
-            files: vec![],
-            shader: None,
             target: Some("web".to_string()),
             output: None,
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![], Path::new(""))
         };
 
         let target = resolve_target(&args).unwrap().unwrap();
@@ -972,16 +2266,9 @@ scale: 2
     #[test]
     fn test_resolve_builtin_target_sheet() {
         let args = BuildArgs {
-            files: vec![],
-            shader: None,
             target: Some("sheet".to_string()),
             output: None,
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![], Path::new(""))
         };
 
         let target = resolve_target(&args).unwrap().unwrap();
@@ -992,16 +2279,8 @@ scale: 2
     #[test]
     fn test_resolve_no_target() {
         let args = BuildArgs {
-            files: vec![],
-            shader: None,
-            target: None,
             output: None,
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![], Path::new(""))
         };
 
         let target = resolve_target(&args).unwrap();
@@ -1011,22 +2290,29 @@ scale: 2
     #[test]
     fn test_resolve_unknown_target() {
         let args = BuildArgs {
-            files: vec![],
-            shader: None,
-            target: Some("pico8".to_string()),
+            target: Some("gameboy".to_string()),
             output: None,
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![], Path::new(""))
         };
 
         let result = resolve_target(&args);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_target_listing_includes_presets() {
+        let listing = target_listing();
+        let settings = |name: &str| listing.iter().find(|(n, _)| n == name).map(|(_, s)| s.as_str());
+
+        assert_eq!(settings("web"), Some("png, no sheet"));
+        assert_eq!(settings("retina"), Some("png, scale 2, no sheet"));
+        assert_eq!(settings("pico8"), Some("p8, scale 1, sheet 128x128, padding 0"));
+        // Every listed target resolves
+        for (name, _) in &listing {
+            assert!(BuiltinTargets::get(name).is_some(), "{}", name);
+        }
+    }
+
     #[test]
     fn test_resolve_target_file() {
         let dir = tempdir().unwrap();
@@ -1047,16 +2333,9 @@ padding: 2
         .unwrap();
 
         let args = BuildArgs {
-            files: vec![],
-            shader: None,
             target: Some(target_path.to_string_lossy().to_string()),
             output: None,
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![], Path::new(""))
         };
 
         let target = resolve_target(&args).unwrap().unwrap();
@@ -1066,6 +2345,36 @@ padding: 2
         assert_eq!(target.padding, Some(2));
     }
 
+    #[test]
+    fn test_resolve_target_file_inherits_builtin() {
+        let dir = tempdir().unwrap();
+        let target_path = dir.path().join("big-sheet.target.md");
+
+        fs::write(
+            &target_path,
+            r#"---
+name: big-sheet
+inherits: sheet
+---
+
+scale: 3
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            target: Some(target_path.to_string_lossy().to_string()),
+            output: None,
+            ..plain_build_args(vec![], Path::new(""))
+        };
+
+        let target = resolve_target(&args).unwrap().unwrap();
+        assert_eq!(target.name, "big-sheet");
+        assert_eq!(target.format, "png");
+        assert_eq!(target.sheet, SheetConfig::Auto);
+        assert_eq!(target.scale, Some(3));
+    }
+
     #[test]
     fn test_build_with_target_web() {
         let dir = tempdir().unwrap();
@@ -1087,16 +2396,8 @@ name: target-test
         .unwrap();
 
         let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
             target: Some("web".to_string()),
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![shape_path], &output_dir)
         };
 
         run(args, &test_printer()).unwrap();
@@ -1143,16 +2444,9 @@ name: override-target
 
         // CLI scale of 4 should override target's scale of 2
         let args = BuildArgs {
-            files: vec![shape_path],
-            shader: None,
             target: Some(target_path.to_string_lossy().to_string()),
-            output: Some(output_dir.clone()),
             scale: Some(4),
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![shape_path], &output_dir)
         };
 
         run(args, &test_printer()).unwrap();
@@ -1184,18 +2478,7 @@ name: wall
         )
         .unwrap();
 
-        let args = BuildArgs {
-            files: vec![dir.path().to_path_buf()],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
-        };
+        let args = plain_build_args(vec![dir.path().to_path_buf()], &output_dir);
 
         run(args, &test_printer()).unwrap();
 
@@ -1207,16 +2490,8 @@ name: wall
         // This test verifies that discover(".") is called when files is empty.
         // We can't easily change cwd in a test, but we can verify the args parse correctly.
         let args = BuildArgs {
-            files: vec![],
-            shader: None,
-            target: None,
             output: None,
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
+            ..plain_build_args(vec![], Path::new(""))
         };
 
         // files is empty, so discover(".") would be called
@@ -1224,6 +2499,27 @@ name: wall
         assert!(args.output.is_none());
     }
 
+    #[test]
+    fn test_output_dir_fallback_chain() {
+        let dir = tempdir().unwrap();
+        let shape = dir.path().join("test.shape.md");
+        fs::write(&shape, "---\nname: default-out\n---\n\n```px\n#\n```\n").unwrap();
+
+        // No -o and no manifest: both discovery paths land on the default
+        let mut args = plain_build_args(vec![shape.clone()], Path::new("unused"));
+        args.output = None;
+        let default = PathBuf::from(crate::discovery::DEFAULT_OUTPUT);
+        assert_eq!(output_dir(&args, &discover_paths(&args.files).unwrap().manifest), default);
+        assert_eq!(output_dir(&args, &discover(dir.path()).unwrap().manifest), default);
+
+        // Manifest, then CLI
+        fs::write(dir.path().join("px.yaml"), "output: build\n").unwrap();
+        let manifest = discover(dir.path()).unwrap().manifest;
+        assert_eq!(output_dir(&args, &manifest), PathBuf::from("build"));
+        args.output = Some(PathBuf::from("out"));
+        assert_eq!(output_dir(&args, &manifest), PathBuf::from("out"));
+    }
+
     #[test]
     fn test_build_output_defaults_to_dist() {
         let dir = tempdir().unwrap();
@@ -1238,18 +2534,7 @@ name: wall
         // Explicit file, but no -o flag: output falls back to manifest default ("dist")
         // discover_paths returns a default manifest with output="dist", so output
         // resolves relative to cwd. Use explicit output to keep test self-contained.
-        let args = BuildArgs {
-            files: vec![dir.path().join("test.shape.md")],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
-        };
+        let args = plain_build_args(vec![dir.path().join("test.shape.md")], &output_dir);
 
         run(args, &test_printer()).unwrap();
 
@@ -1278,18 +2563,7 @@ name: wall
         // Use discover via directory path (no manifest lookup in discover_paths)
         // To test manifest scale, we'd need discover(".") which reads px.yaml.
         // Instead, test explicit files with output.
-        let args = BuildArgs {
-            files: vec![dir.path().join("test.shape.md")],
-            shader: None,
-            target: None,
-            output: Some(output_dir.clone()),
-            scale: None,
-            validate: false,
-            sheet: false,
-            padding: None,
-            dither: None,
-            watch: false,
-        };
+        let args = plain_build_args(vec![dir.path().join("test.shape.md")], &output_dir);
 
         run(args, &test_printer()).unwrap();
 
@@ -1299,4 +2573,1294 @@ name: wall
         assert_eq!(img.width(), 2);
         assert_eq!(img.height(), 2);
     }
+
+    #[test]
+    fn test_build_drop_shadow_expands_metadata_size() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            dir.path().join("shadow.shader.md"),
+            r##"---
+name: shadow
+palette: default
+effects:
+  - type: drop-shadow
+    dx: 2
+    dy: -1
+---
+"##,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("box.shape.md"),
+            r#"---
+name: box
+---
+
+```px
+##
+##
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            shader: Some("shadow".to_string()),
+            ..plain_build_args(vec![dir.path().to_path_buf()], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        let img = image::open(output_dir.join("box.png")).unwrap().to_rgba8();
+        assert_eq!((img.width(), img.height()), (4, 3));
+
+        let json = fs::read_to_string(output_dir.join("box.json")).unwrap();
+        let meta: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(meta["size"], serde_json::json!([4, 3]));
+    }
+
+    #[test]
+    fn test_build_writes_slice_borders() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            dir.path().join("panel.shape.md"),
+            r#"---
+name: panel
+slice: [1, 1, 2, 1]
+---
+
+```px
+###
+###
+###
+###
+```
+"#,
+        )
+        .unwrap();
+
+        let args = plain_build_args(vec![dir.path().to_path_buf()], &output_dir);
+
+        run(args, &test_printer()).unwrap();
+
+        let json = fs::read_to_string(output_dir.join("panel.json")).unwrap();
+        let meta: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            meta["slice"],
+            serde_json::json!({"left": 1, "right": 1, "top": 2, "bottom": 1})
+        );
+    }
+
+    /// A project with an `ui` palette (black edge, white fill, and an
+    /// `inverted` variant swapping them) and shapes `a` (`#.`) and `b` (`.#`).
+    fn write_variant_project(dir: &Path) {
+        fs::write(
+            dir.join("ui.palette.md"),
+            r##"---
+name: ui
+---
+$edge: #000000
+$fill: #FFFFFF
+
+@inverted:
+  $edge: #FFFFFF
+  $fill: #000000
+"##,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("ui.shader.md"),
+            "---\nname: ui\npalette: ui\n---\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("pair.shape.md"),
+            "---\nname: a\n---\n\n```px\n#.\n```\n\n---\nname: b\n---\n\n```px\n.#\n```\n",
+        )
+        .unwrap();
+    }
+
+    fn variant_build_args(dir: &Path, output: &Path, variant: &str) -> BuildArgs {
+        BuildArgs {
+            palette_variant: Some(variant.to_string()),
+            ..plain_build_args(vec![dir.to_path_buf()], output)
+        }
+    }
+
+    #[test]
+    fn test_build_palette_variant_override() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+        write_variant_project(dir.path());
+
+        run(variant_build_args(dir.path(), &output_dir, "inverted"), &test_printer()).unwrap();
+
+        let a = image::open(output_dir.join("a.png")).unwrap().to_rgba8();
+        let b = image::open(output_dir.join("b.png")).unwrap().to_rgba8();
+        assert_eq!(a.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(a.get_pixel(1, 0).0, [0, 0, 0, 255]);
+        assert_eq!(b.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(b.get_pixel(1, 0).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_build_unknown_palette_variant_uses_base() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+        write_variant_project(dir.path());
+
+        run(variant_build_args(dir.path(), &output_dir, "night"), &test_printer()).unwrap();
+
+        let a = image::open(output_dir.join("a.png")).unwrap().to_rgba8();
+        assert_eq!(a.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(a.get_pixel(1, 0).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_build_p8_writes_map_section() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        let grass = "........\n".repeat(8);
+        let wall = "########\n".repeat(8);
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            format!("---\nname: grass\n---\n\n```px\n{grass}```\n\n---\nname: wall\n---\n\n```px\n{wall}```\n"),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("level.map.md"),
+            "---\nname: level\n---\n\n```px\nW.\nGW\n```\n\n---\nW: wall\nG: grass\n.: empty\n",
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            target: Some("p8".to_string()),
+            dither: Some("none".to_string()),
+            ..plain_build_args(vec![dir.path().to_path_buf()], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        let cart = fs::read_to_string(output_dir.join("sprite.p8")).unwrap();
+        let lines: Vec<&str> = cart.lines().collect();
+        let start = lines.iter().position(|l| *l == "__map__").unwrap();
+        assert_eq!(lines.len(), start + 3);
+
+        let (wall, grass) = (&lines[start + 1][0..2], &lines[start + 2][0..2]);
+        assert_ne!(wall, grass);
+        assert!(["00", "01"].contains(&wall) && ["00", "01"].contains(&grass));
+        assert_eq!(&lines[start + 1][2..4], "00");
+        assert_eq!(&lines[start + 2][2..4], wall);
+    }
+
+    #[test]
+    fn test_build_p8_writes_flags_from_tags() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        let block = "########\n".repeat(8);
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            format!(
+                "---\nname: a-plain\n---\n\n```px\n{block}```\n\n---\nname: b-spikes\ntags: [solid, damage]\n---\n\n```px\n{block}```\n"
+            ),
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            target: Some("p8".to_string()),
+            dither: Some("none".to_string()),
+            ..plain_build_args(vec![dir.path().to_path_buf()], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        let cart = fs::read_to_string(output_dir.join("sprite.p8")).unwrap();
+        let lines: Vec<&str> = cart.lines().collect();
+        let start = lines.iter().position(|l| *l == "__gff__").unwrap();
+        let flags = lines[start + 1];
+        // Sprites pack left to right by name: a-plain is 0, b-spikes is 1
+        assert_eq!(&flags[0..2], "00");
+        assert_eq!(&flags[2..4], "03");
+        assert!(flags[4..].chars().all(|c| c == '0'));
+    }
+
+    #[test]
+    fn test_build_map_mode_tilemap() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            r#"---
+name: grass
+---
+
+```px
+..
+..
+```
+
+---
+name: wall
+---
+
+```px
+##
+##
+```
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("level.map.md"),
+            r#"---
+name: level
+---
+
+```px
+W.
+GW
+```
+
+---
+W: wall
+G: grass
+.: empty
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            map_mode: Some("tilemap".to_string()),
+            ..plain_build_args(vec![dir.path().to_path_buf()], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        // No composited map image, but a shared tileset
+        assert!(!output_dir.join("level.png").exists());
+        assert!(output_dir.join("tileset.png").exists());
+
+        let tileset: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("tileset.json")).unwrap()).unwrap();
+        assert!(tileset["frames"]["grass"].is_object());
+        assert!(tileset["frames"]["wall"].is_object());
+
+        let tilemap: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("level.json")).unwrap()).unwrap();
+        assert_eq!(tilemap["grid"], serde_json::json!([2, 2]));
+        assert_eq!(tilemap["tileset"], "tileset.png");
+        // Tiles packed in name order: grass = 0, wall = 1
+        assert_eq!(tilemap["tiles"], serde_json::json!([[1, -1], [0, 1]]));
+    }
+
+    #[test]
+    fn test_build_map_with_external_png_tile() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n##\n##\n```\n").unwrap();
+        fs::create_dir(dir.path().join("tiles")).unwrap();
+        let rock = image::Rgba([10, 200, 30, 255]);
+        image::RgbaImage::from_pixel(2, 2, rock).save(dir.path().join("tiles/rock.png")).unwrap();
+        let map = "---\nname: level\n---\n\n```px\nWR\n```\n\n---\nW: wall\nR: ./tiles/rock.png\n";
+        fs::write(dir.path().join("level.map.md"), map).unwrap();
+
+        let args = || BuildArgs {
+            validate: true,
+            ..plain_build_args(vec![dir.path().to_path_buf()], &output_dir)
+        };
+        run(args(), &test_printer()).unwrap();
+
+        let level = image::open(output_dir.join("level.png")).unwrap().to_rgba8();
+        assert_eq!(level.dimensions(), (4, 2));
+        assert_eq!(*level.get_pixel(2, 0), rock);
+        assert_eq!(*level.get_pixel(3, 1), rock);
+        assert_ne!(*level.get_pixel(0, 0), rock);
+
+        // Removing the image invalidates the cached map, which then fails
+        fs::remove_file(dir.path().join("tiles/rock.png")).unwrap();
+        assert!(run(args(), &test_printer()).is_err());
+    }
+
+    #[test]
+    fn test_build_format_tiled() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            r#"---
+name: grass
+---
+
+```px
+..
+..
+```
+
+---
+name: wall
+---
+
+```px
+##
+##
+```
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("level.map.md"),
+            r#"---
+name: level
+---
+
+```px
+W.
+GW
+```
+
+---
+W: wall
+G: grass
+.: empty
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            scale: Some(2),
+            format: Some("tiled".to_string()),
+            ..plain_build_args(vec![dir.path().to_path_buf()], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        assert!(output_dir.join("tileset.png").exists());
+
+        let tiled: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("level.tmj")).unwrap()).unwrap();
+        assert_eq!(tiled["type"], "map");
+        assert_eq!((tiled["width"].as_u64(), tiled["height"].as_u64()), (Some(2), Some(2)));
+        assert_eq!(tiled["tilewidth"], 4);
+        // grass = 1, wall = 2, empty = 0
+        assert_eq!(tiled["layers"][0]["data"], serde_json::json!([2, 0, 1, 2]));
+        assert_eq!(tiled["tilesets"][0]["tiles"][0]["image"], "tileset.png");
+    }
+
+    #[test]
+    fn test_build_gif_from_multi_frame_file() {
+        use image::codecs::gif::GifDecoder;
+        use image::AnimationDecoder;
+
+        let dir = tempdir().unwrap();
+        let shape_path = dir.path().join("blink.shape.md");
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            &shape_path,
+            r#"---
+name: blink-1
+---
+
+```px
+#.
+```
+
+---
+name: blink-2
+---
+
+```px
+.#
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            gif: true,
+            fps: 4,
+            ..plain_build_args(vec![shape_path], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        let gif_path = output_dir.join("blink.gif");
+        assert!(gif_path.exists());
+        assert!(!output_dir.join("blink-1.png").exists());
+
+        let file = std::io::BufReader::new(fs::File::open(&gif_path).unwrap());
+        let frames = GifDecoder::new(file).unwrap().into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].delay().numer_denom_ms(), (250, 1));
+    }
+
+    #[test]
+    fn test_build_emit_rust_sheet() {
+        let dir = tempdir().unwrap();
+        let shape_path = dir.path().join("hero.shape.md");
+        let output_dir = dir.path().join("output");
+        let rust_path = dir.path().join("sprites.rs");
+
+        fs::write(
+            &shape_path,
+            r#"---
+name: hero-idle
+---
+
+```px
+###
+###
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            scale: Some(2),
+            sheet: true,
+            emit_rust: Some(rust_path.clone()),
+            ..plain_build_args(vec![shape_path], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        let src = fs::read_to_string(&rust_path).unwrap();
+        syn::parse_file(&src).unwrap();
+        assert!(src.contains("pub const HERO_IDLE: Sprite = Sprite {"));
+        assert!(src.contains("width: 3,\n    height: 2,\n    frame: Some([0, 0, 6, 4]),"));
+    }
+
+    #[test]
+    fn test_build_trim_adjusts_centre_pivot() {
+        let dir = tempdir().unwrap();
+        let shape_path = dir.path().join("gem.shape.md");
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            &shape_path,
+            r#"---
+name: gem
+anchor: center
+---
+
+```px
+xxxxxx
+x####x
+x####x
+xxxxxx
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            trim: true,
+            sheet: true,
+            ..plain_build_args(vec![shape_path], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        let sheet: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_dir.join("sheet.json")).unwrap()).unwrap();
+        let gem = &sheet["frames"]["gem"];
+        assert_eq!(gem["frame"]["w"], 4);
+        assert_eq!(gem["sourceSize"], serde_json::json!({"w": 6, "h": 4}));
+        // Centre (3, 2) minus the (1, 1) trim offset is (2, 1) in the 4x2 frame
+        assert_eq!(gem["pivot"], serde_json::json!({"x": 0.5, "y": 0.5}));
+    }
+
+    #[test]
+    fn test_build_svg_format() {
+        let dir = tempdir().unwrap();
+        let shape_path = dir.path().join("dot.shape.md");
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            &shape_path,
+            r#"---
+name: dot
+---
+
+```px
+##x
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            scale: Some(2),
+            format: Some("svg".to_string()),
+            ..plain_build_args(vec![shape_path], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        assert!(!output_dir.join("dot.png").exists());
+        assert!(output_dir.join("dot.json").exists());
+        let svg = fs::read_to_string(output_dir.join("dot.svg")).unwrap();
+        assert!(svg.contains(r#"width="6" height="2""#));
+        assert_eq!(svg.matches("<rect").count(), 1);
+    }
+
+    #[test]
+    fn test_build_normals_beside_images() {
+        let dir = tempdir().unwrap();
+        let shape_path = dir.path().join("block.shape.md");
+        let output_dir = dir.path().join("output");
+
+        fs::write(
+            &shape_path,
+            r#"---
+name: block
+---
+
+```px
+###
+###
+###
+```
+
+---
+name: empty
+---
+
+```px
+xx
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            normals: true,
+            ..plain_build_args(vec![shape_path], &output_dir)
+        };
+
+        run(args, &test_printer()).unwrap();
+
+        assert!(output_dir.join("block.png").exists());
+        assert!(!output_dir.join("empty.normal.png").exists());
+        let img = image::open(output_dir.join("block.normal.png")).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (3, 3));
+        assert_eq!(img.get_pixel(1, 1).0, [128, 128, 255, 255]);
+    }
+
+    #[test]
+    fn test_build_many_files_matches_one_at_a_time() {
+        let dir = tempdir().unwrap();
+        let source_dir = dir.path().join("shapes");
+        fs::create_dir(&source_dir).unwrap();
+
+        let mut paths = Vec::new();
+        for i in 0..50 {
+            // Vary size and pattern so a mix-up between files would show
+            let width = 1 + i % 7;
+            let row: String = (0..width).map(|x| if (x + i) % 3 == 0 { '#' } else { '.' }).collect();
+            let grid = vec![row; 1 + i % 5].join("\n");
+            let path = source_dir.join(format!("s{:02}.shape.md", i));
+            fs::write(&path, format!("---\nname: s{:02}\n---\n\n```px\n{}\n```\n", i, grid)).unwrap();
+            paths.push(path);
+        }
+
+        let all_dir = dir.path().join("all");
+        run(plain_build_args(vec![source_dir], &all_dir), &test_printer()).unwrap();
+
+        let single_dir = dir.path().join("single");
+        for path in &paths {
+            run(plain_build_args(vec![path.clone()], &single_dir), &test_printer()).unwrap();
+        }
+
+        for i in 0..50 {
+            let name = format!("s{:02}.png", i);
+            let together = fs::read(all_dir.join(&name)).unwrap();
+            let alone = fs::read(single_dir.join(&name)).unwrap();
+            assert_eq!(together, alone, "{} differs", name);
+        }
+    }
+
+    /// Two single-shape files, built once into `output`.
+    #[test]
+    fn test_build_single_job_matches_parallel() {
+        let dir = tempdir().unwrap();
+        for i in 0..8 {
+            fs::write(
+                dir.path().join(format!("s{}.shape.md", i)),
+                format!("---\nname: s{}\n---\n\n```px\n{}\n```\n", i, "#.".repeat(i + 1)),
+            )
+            .unwrap();
+        }
+
+        let parallel_dir = dir.path().join("parallel");
+        run(plain_build_args(vec![dir.path().to_path_buf()], &parallel_dir), &test_printer()).unwrap();
+
+        let serial_dir = dir.path().join("serial");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &serial_dir);
+        args.jobs = Some(1);
+        run(args, &test_printer()).unwrap();
+
+        for i in 0..8 {
+            let name = format!("s{}.png", i);
+            assert_eq!(fs::read(parallel_dir.join(&name)).unwrap(), fs::read(serial_dir.join(&name)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_progress_total_counts_rendered_sources() {
+        let scan = ScanResult {
+            shapes: vec![PathBuf::from("a.shape.md"), PathBuf::from("b.shape.md")],
+            prefabs: vec![PathBuf::from("c.prefab.md")],
+            maps: vec![PathBuf::from("d.map.md"), PathBuf::from("e.map.md"), PathBuf::from("f.map.md")],
+            ..Default::default()
+        };
+        assert_eq!(progress_total(&scan, true), 2 + 1 + 3);
+        // Sheet builds skip maps, so the bar doesn't wait for them
+        assert_eq!(progress_total(&scan, false), 2 + 1);
+    }
+
+    #[test]
+    fn test_build_uniform_size_pads_to_largest() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("small.shape.md"), "---\nname: small\n---\n\n```px\n##\n##\n```\n").unwrap();
+        fs::write(dir.path().join("big.shape.md"), "---\nname: big\n---\n\n```px\n####\n####\n####\n####\n```\n").unwrap();
+
+        let output = dir.path().join("dist");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.uniform_size = true;
+        run(args, &test_printer()).unwrap();
+
+        let big = image::open(output.join("big.png")).unwrap().to_rgba8();
+        let small = image::open(output.join("small.png")).unwrap().to_rgba8();
+        assert_eq!(big.dimensions(), (4, 4));
+        assert_eq!(small.dimensions(), (4, 4));
+        for y in 0..4 {
+            for x in 0..4 {
+                let inside = (1..3).contains(&x) && (1..3).contains(&y);
+                assert_eq!(small.get_pixel(x, y).0[3] != 0, inside, "pixel ({}, {})", x, y);
+            }
+        }
+
+        let meta: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join("small.json")).unwrap()).unwrap();
+        assert_eq!(meta["size"], serde_json::json!([4, 4]));
+        assert_eq!(meta["source_size"], serde_json::json!([2, 2]));
+        assert_eq!(meta["offset"], serde_json::json!([1, 1]));
+        let meta: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join("big.json")).unwrap()).unwrap();
+        assert_eq!(meta["offset"], serde_json::json!([0, 0]));
+    }
+
+    #[test]
+    fn test_build_strict_aborts_on_warnings() {
+        let dir = tempdir().unwrap();
+        // The unused legend entry is only a warning
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n#\n```\n\n---\nB: solid\n").unwrap();
+
+        let output = dir.path().join("dist");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.validate = true;
+        run(args, &test_printer()).unwrap();
+        assert!(output.join("wall.png").exists());
+
+        let output = dir.path().join("strict");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.strict = true;
+        assert!(run(args, &test_printer()).is_err());
+        assert!(!output.join("wall.png").exists());
+    }
+
+    fn png_colour_type(path: &Path) -> png::ColorType {
+        let file = std::io::BufReader::new(fs::File::open(path).unwrap());
+        png::Decoder::new(file).read_info().unwrap().info().color_type
+    }
+
+    #[test]
+    fn test_build_auto_format_picks_indexed_for_few_colours() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("auto.target.md"), "---\nname: auto\nformat: auto\n---\n").unwrap();
+        fs::write(dir.path().join("game.palette.md"), "---\nname: game\n---\n\n$edge: #000000\n$fill: #FFFFFF\n$red: #FF0000\n").unwrap();
+        fs::write(dir.path().join("game.shader.md"), "---\nname: game\npalette: game\n---\n").unwrap();
+        // Edge, fill, red and transparent
+        fs::write(
+            dir.path().join("wall.shape.md"),
+            "---\nname: wall\n---\n\n```px\n#.R\nxR#\n```\n\n---\nR: { stamp: solid, A: $red }\n",
+        )
+        .unwrap();
+
+        let output = dir.path().join("dist");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.target = Some(dir.path().join("auto.target.md").to_string_lossy().to_string());
+        run(args, &test_printer()).unwrap();
+
+        assert_eq!(png_colour_type(&output.join("wall.png")), png::ColorType::Indexed);
+        let img = image::open(output.join("wall.png")).unwrap().to_rgba8();
+        assert_eq!(img.get_pixel(2, 0).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(0, 1).0[3], 0);
+    }
+
+    #[test]
+    fn test_build_png_follows_palette_mode() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("rgba.target.md"), "---\nname: rgba\nformat: png\n---\n").unwrap();
+        fs::write(dir.path().join("indexed.target.md"), "---\nname: indexed\nformat: png\npalette_mode: indexed\n---\n").unwrap();
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n#.\nx#\n```\n").unwrap();
+
+        for (target, colour_type) in [("rgba", png::ColorType::Rgba), ("indexed", png::ColorType::Indexed)] {
+            let output = dir.path().join(target);
+            let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+            args.target = Some(dir.path().join(format!("{}.target.md", target)).to_string_lossy().to_string());
+            run(args, &test_printer()).unwrap();
+            assert_eq!(png_colour_type(&output.join("wall.png")), colour_type, "{}", target);
+        }
+    }
+
+    #[test]
+    fn test_build_max_colours_rejects_busy_shape() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("gb.target.md"), "---\nname: gb\nmax_colours: 4\n---\n").unwrap();
+        fs::write(
+            dir.path().join("game.palette.md"),
+            "---\nname: game\n---\n\n$edge: #000000\n$fill: #FFFFFF\n$red: #FF0000\n$green: #00FF00\n$blue: #0000FF\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("game.shader.md"), "---\nname: game\npalette: game\n---\n").unwrap();
+        // Edge, fill, red, green and blue (transparency doesn't count)
+        fs::write(
+            dir.path().join("wall.shape.md"),
+            "---\nname: wall\n---\n\n```px\n#.RGB\nxxxxx\n```\n\n---\nR: { stamp: solid, A: $red }\nG: { stamp: solid, A: $green }\nB: { stamp: solid, A: $blue }\n",
+        )
+        .unwrap();
+
+        let output = dir.path().join("dist");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.target = Some(dir.path().join("gb.target.md").to_string_lossy().to_string());
+        let err = run(args, &test_printer()).unwrap_err();
+        assert!(err.to_string().contains("Shape 'wall' uses 5 colours, more than the 4 allowed"), "{}", err);
+        assert!(!output.join("wall.png").exists());
+
+        // The CLI flag overrides the target
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.target = Some(dir.path().join("gb.target.md").to_string_lossy().to_string());
+        args.max_colours = Some(5);
+        run(args, &test_printer()).unwrap();
+        assert!(output.join("wall.png").exists());
+    }
+
+    #[test]
+    fn test_auto_png_uses_rgba_for_many_colours() {
+        let rows: Vec<Vec<Colour>> = (0..32)
+            .map(|y| (0..32).map(|x| Colour::rgb(x * 8, y * 8, 128)).collect())
+            .collect();
+        let gradient = RenderedShape::new("gradient", rows);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gradient.png");
+        write_auto_png(&gradient, &path, 1, &test_printer()).unwrap();
+        assert_eq!(png_colour_type(&path), png::ColorType::Rgba);
+    }
+
+    #[test]
+    fn test_build_emit_integrity() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n#.\n.#\n```\n").unwrap();
+
+        let output = dir.path().join("dist");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.emit_integrity = true;
+        run(args, &test_printer()).unwrap();
+
+        let manifest: BTreeMap<String, String> =
+            serde_json::from_str(&fs::read_to_string(output.join(INTEGRITY_FILE)).unwrap()).unwrap();
+        let hash = &manifest["wall.png"];
+        // 48-byte digest is 64 base64 characters
+        let digest = hash.strip_prefix("sha384-").unwrap();
+        assert_eq!(digest.len(), 64);
+        assert!(digest.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/'));
+        let expected = Sha384::digest(fs::read(output.join("wall.png")).unwrap());
+        assert_eq!(BASE64_STANDARD.decode(digest).unwrap(), expected.as_slice());
+        assert!(manifest.contains_key("wall.json"));
+        assert!(!manifest.contains_key(CACHE_FILE));
+    }
+
+    #[test]
+    fn test_build_embed_source() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("door.shape.md"),
+            "---\nname: door\n---\n\n```px\n+~+\n|B \n```\n---\nB: brick\n~: { fill: checker, A: $edge, B: $fill }\n",
+        )
+        .unwrap();
+        let output = dir.path().join("dist");
+
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.embed_source = true;
+        run(args, &test_printer()).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(output.join("door.json")).unwrap()).unwrap();
+        assert_eq!(json["source"]["grid"], serde_json::json!(["+~+", "|B "]));
+        assert_eq!(json["source"]["legend"]["B"], "brick");
+        assert_eq!(json["source"]["legend"]["~"], "{ fill: checker, A: $edge, B: $fill }");
+
+        // Opt-in only
+        run(plain_build_args(vec![dir.path().to_path_buf()], &output), &test_printer()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(output.join("door.json")).unwrap()).unwrap();
+        assert!(json.get("source").is_none());
+    }
+
+    #[test]
+    fn test_build_preview_scale() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("tile.shape.md"), "---\nname: tile\nscale: 2\n---\n\n```px\n#.\n.#\n```\n").unwrap();
+        let output = dir.path().join("dist");
+
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.preview_scale = Some(1.5);
+        run(args, &test_printer()).unwrap();
+
+        // The build output keeps its integer scale; the preview scales the sprite's pixels
+        assert_eq!(image::open(output.join("tile.png")).unwrap().to_rgba8().dimensions(), (4, 4));
+        let preview = image::open(output.join("tile.preview.png")).unwrap().to_rgba8();
+        assert_eq!(preview.dimensions(), (3, 3));
+        assert_eq!(*preview.get_pixel(0, 0), image::Rgba([0, 0, 0, 255]));
+        assert_eq!(*preview.get_pixel(2, 0), image::Rgba([255, 255, 255, 255]));
+
+        assert!(parse_preview_scale("0.5").is_ok());
+        assert!(parse_preview_scale("0").is_err());
+        assert!(parse_preview_scale("big").is_err());
+    }
+
+    #[test]
+    fn test_build_spin_frames() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("gem.shape.md"), "---\nname: gem\n---\n\n```px\n#..\n...\n...\n```\n").unwrap();
+        let output = dir.path().join("dist");
+
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.spin = Some("gem".to_string());
+        args.frames = 4;
+        run(args, &test_printer()).unwrap();
+
+        let frame = |i: usize| image::open(output.join(format!("gem-spin-{}.png", i))).unwrap().to_rgba8();
+        let black = image::Rgba([0, 0, 0, 255]);
+        assert_eq!(*frame(0).get_pixel(0, 0), black);
+        assert_eq!(*frame(1).get_pixel(2, 0), black);
+        assert_eq!(*frame(2).get_pixel(2, 2), black);
+        assert_eq!(*frame(3).get_pixel(0, 2), black);
+
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.spin = Some("ruby".to_string());
+        let err = run(args, &test_printer()).unwrap_err().to_string();
+        assert!(err.contains("no shape named 'ruby'"), "{}", err);
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("tile.shape.md"), "---\nname: tile\n---\n\n```px\n#.\n```\n").unwrap();
+        let output = dir.path().join("dist");
+        let rust = dir.path().join("sprites.rs");
+
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.emit_rust = Some(rust.clone());
+        let planned = dry_run(args, &test_printer()).unwrap();
+
+        assert!(!output.exists());
+        assert!(!rust.exists());
+        assert!(planned.contains(&output.join("tile.png")), "{:?}", planned);
+        assert!(planned.contains(&output.join("tile.json")));
+        assert!(planned.contains(&output.join(OUTPUTS_FILE)));
+        assert!(planned.contains(&rust));
+
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.dry_run = true;
+        run(args, &test_printer()).unwrap();
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_build_only_and_except() {
+        let dir = tempdir().unwrap();
+        for name in ["foo", "bar", "baz"] {
+            fs::write(dir.path().join(format!("{}.shape.md", name)), format!("---\nname: {}\n---\n\n```px\n#\n```\n", name)).unwrap();
+        }
+        fs::write(dir.path().join("room.prefab.md"), "---\nname: room\n---\n\n```px\nAB\n```\n\n---\nA: bar\nB: bar\n").unwrap();
+        let written = |output: &Path| -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(output)
+                .unwrap()
+                .filter_map(|e| e.unwrap().file_name().into_string().ok())
+                .filter(|n| n.ends_with(".png"))
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Prefabs still compose shapes left out by --only
+        let output = dir.path().join("only");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.only = vec!["foo".to_string(), "room".to_string()];
+        run(args, &test_printer()).unwrap();
+        assert_eq!(written(&output), ["foo.png", "room.png"]);
+        assert_eq!(image::open(output.join("room.png")).unwrap().to_rgba8().dimensions(), (2, 1));
+
+        let output = dir.path().join("except");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.except = vec!["foo".to_string()];
+        run(args, &test_printer()).unwrap();
+        assert_eq!(written(&output), ["bar.png", "baz.png", "room.png"]);
+
+        // Sheets pack only the selected sprites
+        let output = dir.path().join("sheet");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.sheet = true;
+        args.only = vec!["foo".to_string(), "baz".to_string()];
+        run(args, &test_printer()).unwrap();
+        let sheet: serde_json::Value = serde_json::from_str(&fs::read_to_string(output.join("sheet.json")).unwrap()).unwrap();
+        let mut frames: Vec<&String> = sheet["frames"].as_object().unwrap().keys().collect();
+        frames.sort();
+        assert_eq!(frames, ["baz", "foo"]);
+    }
+
+    #[test]
+    fn test_build_sheet_frames_per_row() {
+        let dir = tempdir().unwrap();
+        let frames: Vec<String> = (0..5).map(|i| format!("---\nname: walk-{}\n---\n\n```px\n##\n##\n```\n", i)).collect();
+        fs::write(dir.path().join("walk.shape.md"), frames.join("\n")).unwrap();
+        let output = dir.path().join("out");
+        let mut args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        args.sheet = true;
+        args.sheet_size = Some("tight".to_string());
+        args.frames_per_row = Some(2);
+        run(args, &test_printer()).unwrap();
+
+        let sheet: serde_json::Value = serde_json::from_str(&fs::read_to_string(output.join("sheet.json")).unwrap()).unwrap();
+        let cell = |name: &str| {
+            let frame = &sheet["frames"][name];
+            (frame["column"].as_u64(), frame["row"].as_u64(), frame["frame"]["x"].as_u64(), frame["frame"]["y"].as_u64())
+        };
+        assert_eq!(cell("walk-0"), (Some(0), Some(0), Some(0), Some(0)));
+        assert_eq!(cell("walk-3"), (Some(1), Some(1), Some(2), Some(2)));
+        assert_eq!(cell("walk-4"), (Some(0), Some(2), Some(0), Some(4)));
+        assert_eq!(image::open(output.join("sheet.png")).unwrap().to_rgba8().dimensions(), (4, 6));
+    }
+
+    #[test]
+    fn test_name_filter_reports_unknown_names() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("foo.shape.md"), "---\nname: foo\n---\n\n```px\n#\n```\n").unwrap();
+        let registry = discover_paths(&[dir.path().to_path_buf()]).unwrap().into_registry().unwrap();
+        let only = ["foo".to_string(), "fo".to_string()];
+        let except = ["ghost".to_string()];
+        let names = NameFilter { only: &only, except: &except };
+
+        assert_eq!(names.unmatched(&registry).collect::<Vec<_>>(), ["fo", "ghost"]);
+        assert!(names.selects("foo"));
+        assert!(!names.selects("bar"));
+        assert!(NameFilter { only: &[], except: &[] }.selects("bar"));
+    }
+
+    #[test]
+    fn test_watch_rebuild_skips_files_a_change_cannot_reach() {
+        let dir = tempdir().unwrap();
+        let stamp = dir.path().join("dot.stamp.md");
+        fs::write(&stamp, "---\nname: dot\nglyph: D\n---\n\n```px\n.\n```\n").unwrap();
+        fs::write(dir.path().join("a.shape.md"), "---\nname: a\n---\n\n```px\nD.\n```\n").unwrap();
+        fs::write(dir.path().join("b.shape.md"), "---\nname: b\n---\n\n```px\n#.\n```\n").unwrap();
+        let output = dir.path().join("dist");
+        let args = plain_build_args(vec![dir.path().to_path_buf()], &output);
+        build_once(&args, &mut ParseCache::new(), None, &test_printer()).unwrap();
+
+        // Backdate both outputs so a rewrite shows up
+        let past = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let modified = |name: &str| fs::metadata(output.join(name)).unwrap().modified().unwrap();
+        for name in ["a.png", "b.png"] {
+            fs::File::options().write(true).open(output.join(name)).unwrap().set_modified(past).unwrap();
+        }
+
+        fs::write(&stamp, "---\nname: dot\nglyph: D\n---\n\n```px\n$\n```\n").unwrap();
+        build_once(&args, &mut ParseCache::new(), Some(&[stamp]), &test_printer()).unwrap();
+
+        assert_ne!(modified("a.png"), past);
+        assert_eq!(modified("b.png"), past);
+    }
+
+    #[test]
+    fn test_debounce_collapses_burst() {
+        let (tx, rx) = mpsc::channel();
+        for i in 1..4 {
+            tx.send(i).unwrap();
+        }
+
+        // One rebuild for the whole burst, nothing left queued
+        assert_eq!(debounce(&rx, 0, Duration::from_millis(20)), [0, 1, 2, 3]);
+        assert!(rx.try_recv().is_err());
+
+        // A zero window rebuilds straight away
+        tx.send(5).unwrap();
+        assert_eq!(debounce(&rx, 4, Duration::ZERO), [4]);
+        assert_eq!(rx.try_recv(), Ok(5));
+    }
+
+    #[test]
+    fn test_affected_assets_follow_dependents() {
+        let dir = tempdir().unwrap();
+        let stamp = dir.path().join("dot.stamp.md");
+        let palette = dir.path().join("game.palette.md");
+        fs::write(&stamp, "---\nname: dot\nglyph: D\n---\n\n```px\n.\n```\n").unwrap();
+        fs::write(&palette, "---\nname: game\n---\n\n$edge: #000000\n").unwrap();
+        fs::write(dir.path().join("a.shape.md"), "---\nname: a\n---\n\n```px\nD\n```\n").unwrap();
+        fs::write(dir.path().join("b.shape.md"), "---\nname: b\n---\n\n```px\n#\n```\n").unwrap();
+        fs::write(dir.path().join("room.prefab.md"), "---\nname: room\n---\n\n```px\nA\n```\n\n---\nA: a\n").unwrap();
+
+        let discovery = discover_paths(&[dir.path().to_path_buf()]).unwrap();
+        let registry = load_assets(&discovery.scan, &LoadOptions::with_builtins()).unwrap().build().unwrap();
+
+        let affected = affected_assets(&[stamp], &registry).unwrap();
+        let mut names: Vec<String> = affected.iter().map(|id| id.to_string()).collect();
+        names.sort();
+        assert_eq!(names, ["prefab:room", "shape:a", "stamp:dot"]);
+
+        // Palettes apply everywhere, and removed files can't be looked up
+        assert!(affected_assets(&[palette], &registry).is_none());
+        assert!(affected_assets(&[dir.path().join("gone.shape.md")], &registry).is_none());
+    }
+
+    #[test]
+    fn test_build_error_on_missing_lists_fallbacks() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("odd.shape.md");
+        fs::write(&source, "---\nname: odd\n---\n\n```px\n#?\n!#\n```\n").unwrap();
+        let output = dir.path().join("dist");
+
+        let mut args = plain_build_args(vec![source.clone()], &output);
+        args.error_on_missing = true;
+        let err = run(args, &test_printer()).unwrap_err().to_string();
+        assert!(err.contains("2 glyphs fell back to magenta: '!' in 'odd', '?' in 'odd'"), "{}", err);
+
+        // Without the flag the glyphs draw magenta
+        run(plain_build_args(vec![source], &output), &test_printer()).unwrap();
+        let img = image::open(output.join("odd.png")).unwrap().to_rgba8();
+        assert_eq!(*img.get_pixel(1, 0), image::Rgba([255, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_build_writes_output_manifest() {
+        use crate::cli::outputs::OutputEntry;
+
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("wall.shape.md");
+        fs::write(&source, "---\nname: wall\n---\n\n```px\n#.\n.#\n```\n").unwrap();
+        let output = dir.path().join("dist");
+        let read_manifest = || -> BTreeMap<String, OutputEntry> {
+            serde_json::from_str(&fs::read_to_string(output.join(OUTPUTS_FILE)).unwrap()).unwrap()
+        };
+
+        run(plain_build_args(vec![source.clone()], &output), &test_printer()).unwrap();
+        let before = read_manifest();
+        assert_eq!(before.keys().collect::<Vec<_>>(), ["wall.json", "wall.png"]);
+        let png = fs::read(output.join("wall.png")).unwrap();
+        assert_eq!(before["wall.png"].size, png.len() as u64);
+        assert_eq!(before["wall.png"].hash, crate::cli::integrity::sri(&png));
+
+        fs::write(&source, "---\nname: wall\n---\n\n```px\n##\n.#\n```\n").unwrap();
+        run(plain_build_args(vec![source.clone()], &output), &test_printer()).unwrap();
+        let after = read_manifest();
+        assert_ne!(before["wall.png"].hash, after["wall.png"].hash);
+
+        // --manifest-out moves it out of the output directory
+        let elsewhere = dir.path().join("deploy.json");
+        let mut args = plain_build_args(vec![source], &output);
+        args.manifest_out = Some(elsewhere.clone());
+        run(args, &test_printer()).unwrap();
+        let moved: BTreeMap<String, OutputEntry> = serde_json::from_str(&fs::read_to_string(&elsewhere).unwrap()).unwrap();
+        assert_eq!(moved, after);
+    }
+
+    fn cached_project(dir: &Path, output: &Path) -> (PathBuf, PathBuf) {
+        let a = dir.join("a.shape.md");
+        let b = dir.join("b.shape.md");
+        fs::write(&a, "---\nname: a\n---\n\n```px\n##\n```\n").unwrap();
+        fs::write(&b, "---\nname: b\n---\n\n```px\n#.\n```\n").unwrap();
+        run(plain_build_args(vec![a.clone(), b.clone()], output), &test_printer()).unwrap();
+        (a, b)
+    }
+
+    /// Overwrite outputs with a marker, so a rebuild shows up as the marker going away.
+    fn mark_stale(output: &Path, names: &[&str]) {
+        for name in names {
+            fs::write(output.join(name), b"stale").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_build_writes_cache() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+        cached_project(dir.path(), &output_dir);
+
+        let cache = fs::read_to_string(output_dir.join(CACHE_FILE)).unwrap();
+        assert!(cache.contains("a.shape.md"));
+        assert!(cache.contains("\"a.png\""));
+        assert!(cache.contains("\"b.json\""));
+    }
+
+    #[test]
+    fn test_build_unchanged_renders_nothing() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+        let (a, b) = cached_project(dir.path(), &output_dir);
+        mark_stale(&output_dir, &["a.png", "b.png"]);
+
+        run(plain_build_args(vec![a, b], &output_dir), &test_printer()).unwrap();
+
+        assert_eq!(fs::read(output_dir.join("a.png")).unwrap(), b"stale");
+        assert_eq!(fs::read(output_dir.join("b.png")).unwrap(), b"stale");
+    }
+
+    #[test]
+    fn test_build_changed_file_rebuilds_only_it() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+        let (a, b) = cached_project(dir.path(), &output_dir);
+        mark_stale(&output_dir, &["a.png", "b.png"]);
+
+        fs::write(&b, "---\nname: b\n---\n\n```px\n.#\n```\n").unwrap();
+        run(plain_build_args(vec![a, b], &output_dir), &test_printer()).unwrap();
+
+        assert_eq!(fs::read(output_dir.join("a.png")).unwrap(), b"stale");
+        let img = image::open(output_dir.join("b.png")).unwrap().to_rgba8();
+        assert_eq!(img.get_pixel(1, 0).0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_build_force_ignores_cache() {
+        let dir = tempdir().unwrap();
+        let output_dir = dir.path().join("output");
+        let (a, b) = cached_project(dir.path(), &output_dir);
+        mark_stale(&output_dir, &["a.png"]);
+
+        let mut args = plain_build_args(vec![a, b], &output_dir);
+        args.force = true;
+        run(args, &test_printer()).unwrap();
+
+        assert!(image::open(output_dir.join("a.png")).is_ok());
+    }
+
+    #[test]
+    fn test_build_gif_mismatched_frames_error() {
+        let dir = tempdir().unwrap();
+        let shape_path = dir.path().join("bad.shape.md");
+
+        fs::write(
+            &shape_path,
+            r#"---
+name: bad-1
+---
+
+```px
+#
+```
+
+---
+name: bad-2
+---
+
+```px
+##
+```
+"#,
+        )
+        .unwrap();
+
+        let args = BuildArgs {
+            gif: true,
+            ..plain_build_args(vec![shape_path], &dir.path().join("output"))
+        };
+
+        assert!(run(args, &test_printer()).is_err());
+    }
+
+    #[test]
+    fn test_parse_grid_cell() {
+        assert_eq!(parse_grid_cell("16x16"), Ok((16, 16)));
+        assert_eq!(parse_grid_cell("8x4"), Ok((8, 4)));
+        assert!(parse_grid_cell("16").is_err());
+        assert!(parse_grid_cell("0x8").is_err());
+        assert!(parse_grid_cell("ax8").is_err());
+    }
+
+    #[test]
+    fn test_build_reader_renders_piped_shape() {
+        let dir = tempdir().unwrap();
+        let output = dir.path().join("out");
+        let mut args = plain_build_args(vec![PathBuf::from("-")], &output);
+        args.scale = Some(2);
+
+        let source = "---\nname: piped\n---\n\n```px\n+--+\n|..|\n+--+\n```\n";
+        let written = build_reader(&args, dir.path(), &mut source.as_bytes(), &test_printer()).unwrap();
+        assert_eq!(written, vec![output.join("piped.png")]);
+
+        let img = image::open(output.join("piped.png")).unwrap();
+        assert_eq!((img.width(), img.height()), (8, 6));
+    }
+
+    #[test]
+    fn test_build_reader_shape_ignores_broken_project() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("broken.shape.md"), "---\nname: [unclosed\n---\n").unwrap();
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n##\n```\n").unwrap();
+        let output = dir.path().join("out");
+        let args = plain_build_args(vec![PathBuf::from("-")], &output);
+
+        let source = "---\nname: piped\n---\n\n```px\n##\n```\n";
+        let written = build_reader(&args, dir.path(), &mut source.as_bytes(), &test_printer()).unwrap();
+        assert_eq!(written, vec![output.join("piped.png")]);
+
+        // Prefabs still need the project, so its errors still surface
+        let mut args = args;
+        args.kind = "prefab".to_string();
+        let source = "---\nname: tower\n---\n\n```px\nW\n```\n\n---\nW: wall\n";
+        assert!(build_reader(&args, dir.path(), &mut source.as_bytes(), &test_printer()).is_err());
+    }
+
+    #[test]
+    fn test_build_reader_prefab_uses_project_shapes() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n##\n##\n```\n").unwrap();
+        let output = dir.path().join("out");
+        let mut args = plain_build_args(vec![PathBuf::from("-")], &output);
+        args.kind = "prefab".to_string();
+
+        let source = "---\nname: tower\n---\n\n```px\nW\nW\n```\n\n---\nW: wall\n";
+        build_reader(&args, dir.path(), &mut source.as_bytes(), &test_printer()).unwrap();
+
+        let img = image::open(output.join("tower.png")).unwrap();
+        assert_eq!((img.width(), img.height()), (2, 4));
+        assert!(!output.join("wall.png").exists());
+    }
+
+    #[test]
+    fn test_build_reader_map_uses_project_prefabs() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\n##\n##\n```\n").unwrap();
+        fs::write(dir.path().join("tower.prefab.md"), "---\nname: tower\n---\n\n```px\nW\nW\n```\n\n---\nW: wall\n").unwrap();
+        let output = dir.path().join("out");
+        let mut args = plain_build_args(vec![PathBuf::from("-")], &output);
+        args.kind = "map".to_string();
+
+        let source = "---\nname: level\n---\n\n```px\nTT\n```\n\n---\nT: tower\n";
+        build_reader(&args, dir.path(), &mut source.as_bytes(), &test_printer()).unwrap();
+
+        let img = image::open(output.join("level.png")).unwrap();
+        assert_eq!((img.width(), img.height()), (4, 4));
+    }
 }
@@ -0,0 +1,154 @@
+//! Incremental build cache.
+//!
+//! `px build` records, per source file, a hash of its contents, a
+//! fingerprint of the settings it was built with, and the files it wrote.
+//! A later build skips any source whose hash and fingerprint match and
+//! whose outputs are all still on disk. The cache lives in the output
+//! directory as `.px-cache.json`; a missing or unreadable cache just means
+//! everything rebuilds.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{PxError, Result};
+
+/// Cache file name inside the output directory.
+pub const CACHE_FILE: &str = ".px-cache.json";
+
+/// What one source file produced last time it was built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Hash of the source file contents.
+    pub hash: String,
+    /// Fingerprint of the settings and dependencies it was built with.
+    pub settings: String,
+    /// Files written, relative to the output directory.
+    pub outputs: Vec<String>,
+}
+
+/// Cache entries keyed by source path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildCache {
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl BuildCache {
+    /// Load the cache from `output`, or an empty cache if there is none.
+    pub fn load(output: &Path) -> Self {
+        fs::read_to_string(output.join(CACHE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache to `output`.
+    pub fn save(&self, output: &Path) -> Result<()> {
+        let path = output.join(CACHE_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|e| PxError::Build {
+            message: format!("Failed to serialize build cache: {}", e),
+            help: None,
+        })?;
+        fs::write(&path, json).map_err(|e| PxError::Io {
+            path,
+            message: format!("Failed to write build cache: {}", e),
+        })
+    }
+
+    /// The entry for `source`, if it is unchanged and its outputs still exist.
+    pub fn fresh(&self, source: &Path, hash: &str, settings: &str, output: &Path) -> Option<&CacheEntry> {
+        self.entries
+            .get(&key(source))
+            .filter(|e| e.hash == hash && e.settings == settings)
+            .filter(|e| e.outputs.iter().all(|name| output.join(name).exists()))
+    }
+
+    /// The entry for `source` whatever its hash and settings, if its outputs
+    /// still exist.
+    pub fn recorded(&self, source: &Path, output: &Path) -> Option<&CacheEntry> {
+        self.entries
+            .get(&key(source))
+            .filter(|e| e.outputs.iter().all(|name| output.join(name).exists()))
+    }
+
+    /// Record what `source` produced.
+    pub fn insert(&mut self, source: &Path, entry: CacheEntry) {
+        self.entries.insert(key(source), entry);
+    }
+}
+
+fn key(source: &Path) -> String {
+    source.to_string_lossy().into_owned()
+}
+
+/// Stable 64-bit FNV-1a hash of `bytes`, as hex.
+///
+/// Stable across runs and toolchains, unlike `DefaultHasher`.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(hash: &str, outputs: &[&str]) -> CacheEntry {
+        CacheEntry {
+            hash: hash.to_string(),
+            settings: "scale=1".to_string(),
+            outputs: outputs.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash(b"#."), content_hash(b".#"));
+    }
+
+    #[test]
+    fn test_fresh_needs_matching_hash_settings_and_outputs() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.png"), b"png").unwrap();
+
+        let mut cache = BuildCache::default();
+        cache.insert(Path::new("a.shape.md"), entry("1", &["a.png"]));
+        cache.insert(Path::new("b.shape.md"), entry("2", &["b.png"]));
+
+        let source = Path::new("a.shape.md");
+        assert!(cache.fresh(source, "1", "scale=1", dir.path()).is_some());
+        assert!(cache.fresh(source, "9", "scale=1", dir.path()).is_none());
+        assert!(cache.fresh(source, "1", "scale=2", dir.path()).is_none());
+        // b.png was never written
+        assert!(cache.fresh(Path::new("b.shape.md"), "2", "scale=1", dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_cache_round_trips() {
+        let dir = tempdir().unwrap();
+        let mut cache = BuildCache::default();
+        cache.insert(Path::new("a.shape.md"), entry("1", &["a.png"]));
+        cache.save(dir.path()).unwrap();
+
+        let loaded = BuildCache::load(dir.path());
+        assert_eq!(loaded.entries.len(), 1);
+        fs::write(dir.path().join("a.png"), b"png").unwrap();
+        assert!(loaded.fresh(Path::new("a.shape.md"), "1", "scale=1", dir.path()).is_some());
+    }
+
+    #[test]
+    fn test_unreadable_cache_is_empty() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(CACHE_FILE), "not json").unwrap();
+        assert!(BuildCache::load(dir.path()).entries.is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+//! Diff command implementation.
+//!
+//! Compares the PNGs in two build output directories by decoded pixels, so
+//! re-encoded but identical sprites don't show up. With one directory, the
+//! project is rebuilt into a scratch directory and compared against it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Command, FromArgMatches};
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::cli::build::{self, BuildArgs};
+use crate::cli::verify::{relative_files, scratch_dir};
+use crate::error::{PxError, Result};
+use crate::output::{display_path, Printer, Verbosity};
+
+/// Highlight for changed pixels in the contact sheet.
+const CHANGED: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Gap between images in the contact sheet.
+const GAP: u32 = 2;
+
+/// Compare sprites between two build output directories
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Earlier output directory
+    pub old: PathBuf,
+
+    /// Later output directory (default: rebuild the current project)
+    pub new: Option<PathBuf>,
+
+    /// Write a contact sheet of changed sprites (old, new, changed pixels)
+    #[arg(long, value_name = "PNG")]
+    pub sheet: Option<PathBuf>,
+}
+
+/// How one sprite differs between the two directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpriteChange {
+    /// Only in the new directory.
+    Added(PathBuf),
+    /// Only in the old directory.
+    Removed(PathBuf),
+    /// In both, with different pixels (or a different size).
+    Changed(PathBuf),
+}
+
+pub fn run(args: DiffArgs, printer: &Printer) -> Result<()> {
+    let rebuilt = args.new.is_none();
+    let new = args.new.clone().unwrap_or_else(|| scratch_dir("px-diff"));
+    let result = changes(&args, &new, rebuilt, printer);
+    if rebuilt {
+        let _ = fs::remove_dir_all(&new);
+    }
+    let changes = result?;
+
+    let mut counts = [0; 3];
+    for change in &changes {
+        match change {
+            SpriteChange::Added(path) => {
+                counts[0] += 1;
+                printer.info("Added", &path.display().to_string());
+            }
+            SpriteChange::Removed(path) => {
+                counts[1] += 1;
+                printer.warning("Removed", &path.display().to_string());
+            }
+            SpriteChange::Changed(path) => {
+                counts[2] += 1;
+                printer.status("Changed", &path.display().to_string());
+            }
+        }
+    }
+
+    if changes.is_empty() {
+        printer.success("Finished", "no sprites changed");
+    } else {
+        printer.success(
+            "Finished",
+            &format!("{} changed, {} added, {} removed", counts[2], counts[0], counts[1]),
+        );
+    }
+    if let Some(sheet) = args.sheet.as_ref().filter(|_| counts[2] > 0) {
+        printer.info("Sheet", &display_path(sheet));
+    }
+
+    Ok(())
+}
+
+/// Rebuild into `new` if asked, compare, and write the sheet while the
+/// scratch build is still on disk.
+fn changes(args: &DiffArgs, new: &Path, rebuild_first: bool, printer: &Printer) -> Result<Vec<SpriteChange>> {
+    if rebuild_first {
+        printer.status("Rebuilding", &format!("into {}", printer.dim(&display_path(new))));
+        rebuild(new)?;
+    }
+    let changes = diff_dirs(&args.old, new)?;
+    if let Some(sheet) = &args.sheet {
+        write_sheet(&changes, &args.old, new, sheet)?;
+    }
+    Ok(changes)
+}
+
+/// Build the current project into `output` with default settings.
+fn rebuild(output: &Path) -> Result<()> {
+    let parse_error = |e: clap::Error| PxError::Build {
+        message: format!("Failed to set up rebuild: {}", e),
+        help: None,
+    };
+    let matches = BuildArgs::augment_args(Command::new("build"))
+        .try_get_matches_from(["build"])
+        .map_err(parse_error)?;
+    let mut args = BuildArgs::from_arg_matches(&matches).map_err(parse_error)?;
+    args.output = Some(output.to_path_buf());
+
+    build::run(args, &Printer::with_verbosity(Verbosity::Quiet))
+}
+
+/// Compare the PNGs in two output directories by decoded pixels.
+///
+/// Paths in the result are relative to the directories, in sorted order.
+pub fn diff_dirs(old: &Path, new: &Path) -> Result<Vec<SpriteChange>> {
+    let old_files = pngs(old)?;
+    let new_files = pngs(new)?;
+
+    let mut changes = Vec::new();
+    for path in &new_files {
+        if !old_files.contains(path) {
+            changes.push(SpriteChange::Added(path.clone()));
+        } else if decode(&old.join(path))? != decode(&new.join(path))? {
+            changes.push(SpriteChange::Changed(path.clone()));
+        }
+    }
+    for path in &old_files {
+        if !new_files.contains(path) {
+            changes.push(SpriteChange::Removed(path.clone()));
+        }
+    }
+    changes.sort_by(|a, b| change_path(a).cmp(change_path(b)));
+
+    Ok(changes)
+}
+
+fn change_path(change: &SpriteChange) -> &Path {
+    match change {
+        SpriteChange::Added(path) | SpriteChange::Removed(path) | SpriteChange::Changed(path) => path,
+    }
+}
+
+/// PNG files under `dir`, relative to it and sorted.
+fn pngs(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(relative_files(dir)?
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "png"))
+        .collect())
+}
+
+fn decode(path: &Path) -> Result<RgbaImage> {
+    image::open(path)
+        .map(|img| img.to_rgba8())
+        .map_err(|e| PxError::Io {
+            path: path.to_path_buf(),
+            message: format!("Failed to read image: {}", e),
+        })
+}
+
+/// An image the size of both inputs with changed pixels in magenta and
+/// unchanged ones faded.
+fn highlight(old: &RgbaImage, new: &RgbaImage) -> RgbaImage {
+    let width = old.width().max(new.width());
+    let height = old.height().max(new.height());
+    RgbaImage::from_fn(width, height, |x, y| {
+        let before = (x < old.width() && y < old.height()).then(|| *old.get_pixel(x, y));
+        let after = (x < new.width() && y < new.height()).then(|| *new.get_pixel(x, y));
+        match (before, after) {
+            (Some(a), Some(b)) if a == b => Rgba([b[0], b[1], b[2], b[3] / 4]),
+            _ => CHANGED,
+        }
+    })
+}
+
+/// Write one row per changed sprite: old, new, then the highlight.
+fn write_sheet(changes: &[SpriteChange], old: &Path, new: &Path, sheet: &Path) -> Result<()> {
+    let mut rows = Vec::new();
+    for change in changes {
+        if let SpriteChange::Changed(path) = change {
+            let before = decode(&old.join(path))?;
+            let after = decode(&new.join(path))?;
+            let diff = highlight(&before, &after);
+            rows.push([before, after, diff]);
+        }
+    }
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let width = rows
+        .iter()
+        .map(|row| row.iter().map(|img| img.width() + GAP).sum::<u32>() - GAP)
+        .max()
+        .unwrap_or(0);
+    let height = rows
+        .iter()
+        .map(|row| row.iter().map(|img| img.height()).max().unwrap_or(0) + GAP)
+        .sum::<u32>()
+        - GAP;
+
+    let mut canvas = RgbaImage::new(width, height);
+    let mut y = 0;
+    for row in &rows {
+        let mut x = 0;
+        for img in row {
+            imageops::overlay(&mut canvas, img, x as i64, y as i64);
+            x += img.width() + GAP;
+        }
+        y += row.iter().map(|img| img.height()).max().unwrap_or(0) + GAP;
+    }
+
+    canvas.save(sheet).map_err(|e| PxError::Io {
+        path: sheet.to_path_buf(),
+        message: format!("Failed to write diff sheet: {}", e),
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sprite(dir: &Path, name: &str, colour: [u8; 4]) {
+        RgbaImage::from_pixel(2, 2, Rgba(colour)).save(dir.join(name)).unwrap();
+    }
+
+    #[test]
+    fn test_identical_dirs_have_no_changes() {
+        let old = tempdir().unwrap();
+        let new = tempdir().unwrap();
+        for dir in [old.path(), new.path()] {
+            sprite(dir, "hero.png", [255, 0, 0, 255]);
+            sprite(dir, "wall.png", [0, 0, 255, 255]);
+            fs::write(dir.join("hero.json"), "{}").unwrap();
+        }
+
+        assert!(diff_dirs(old.path(), new.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_altered_sprite_is_changed() {
+        let old = tempdir().unwrap();
+        let new = tempdir().unwrap();
+        sprite(old.path(), "hero.png", [255, 0, 0, 255]);
+        sprite(old.path(), "wall.png", [0, 0, 255, 255]);
+        sprite(old.path(), "gone.png", [0, 0, 0, 255]);
+        sprite(new.path(), "hero.png", [255, 0, 0, 255]);
+        sprite(new.path(), "added.png", [0, 255, 0, 255]);
+
+        let mut wall = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 255]));
+        wall.put_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        wall.save(new.path().join("wall.png")).unwrap();
+
+        let changes = diff_dirs(old.path(), new.path()).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                SpriteChange::Added(PathBuf::from("added.png")),
+                SpriteChange::Removed(PathBuf::from("gone.png")),
+                SpriteChange::Changed(PathBuf::from("wall.png")),
+            ]
+        );
+
+        let sheet = new.path().join("diff.png");
+        write_sheet(&changes, old.path(), new.path(), &sheet).unwrap();
+        let sheet = image::open(&sheet).unwrap().to_rgba8();
+        assert_eq!(sheet.dimensions(), (2 * 3 + GAP * 2, 2));
+        // Highlight column: only the edited pixel is flagged
+        let highlight_x = 2 * (2 + GAP);
+        assert_eq!(*sheet.get_pixel(highlight_x + 1, 1), CHANGED);
+        assert_eq!(*sheet.get_pixel(highlight_x, 0), Rgba([0, 0, 255, 63]));
+    }
+}
@@ -0,0 +1,432 @@
+//! Fmt command implementation.
+//!
+//! Rewrites asset files in a canonical form so diffs only show real
+//! changes: frontmatter keys in a fixed order, legend entries sorted by
+//! glyph, `` ```px `` fences, no trailing whitespace, and single blank
+//! lines between sections. Comments and frontmatter keys px doesn't know
+//! are kept. Formatting never changes what a file renders.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::Args;
+
+use crate::discovery::{detect_asset_kind, discover, discover_paths};
+use crate::error::{PxError, Result};
+use crate::output::{display_path, plural, Printer};
+use crate::parser::map::LAYER_MARKER;
+use crate::parser::parse_documents;
+use crate::parser::shape::is_grid_comment;
+use crate::registry::AssetKind;
+
+/// Canonical order of frontmatter keys. Keys not listed keep their
+/// relative order after these.
+const KEY_ORDER: &[&str] = &[
+    "name",
+    "tags",
+    "glyph",
+    "inherits",
+    "palette",
+    "palette_variant",
+    "palette_mode",
+    "shader",
+    "format",
+    "scale",
+    "cell",
+    "padding",
+    "sheet",
+    "max_colours",
+    "columns",
+    "max_width",
+    "anchor",
+    "slice",
+    "background",
+    "effects",
+];
+
+/// Rewrite asset files in canonical form
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+    /// Files or directories to format (default: current directory)
+    pub files: Vec<PathBuf>,
+
+    /// Report files that need formatting without rewriting them
+    #[arg(long)]
+    pub check: bool,
+}
+
+pub fn run(args: FmtArgs, printer: &Printer) -> Result<()> {
+    let unformatted = format_files(&args, printer)?;
+
+    if args.check && !unformatted.is_empty() {
+        printer.error(
+            "Failed",
+            &format!("{} need formatting", plural(unformatted.len(), "file", "files")),
+        );
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Format every asset file named by `args`, returning those that were not
+/// already canonical. With `--check` nothing is rewritten.
+fn format_files(args: &FmtArgs, printer: &Printer) -> Result<Vec<PathBuf>> {
+    let discovery = if args.files.is_empty() {
+        discover(".")?
+    } else {
+        discover_paths(&args.files)?
+    };
+
+    let kinds = [
+        AssetKind::Palette,
+        AssetKind::Stamp,
+        AssetKind::Brush,
+        AssetKind::Shader,
+        AssetKind::Shape,
+        AssetKind::Prefab,
+        AssetKind::Map,
+        AssetKind::Target,
+    ];
+    let mut paths: Vec<&PathBuf> = kinds.iter().flat_map(|&k| discovery.scan.files_of_kind(k)).collect();
+    paths.sort();
+
+    let mut unformatted = Vec::new();
+    for path in &paths {
+        let source = fs::read_to_string(path).map_err(|e| PxError::Io {
+            path: path.to_path_buf(),
+            message: format!("Failed to read file: {}", e),
+        })?;
+        // Only well-formed files are rewritten
+        parse_documents(&source)?;
+
+        let kind = detect_asset_kind(path).unwrap_or(AssetKind::Shape);
+        let formatted = format_source(&source, kind);
+        if formatted == source {
+            continue;
+        }
+
+        if args.check {
+            printer.warning("Unformatted", &display_path(path));
+        } else {
+            write_formatted(path, &formatted)?;
+            printer.status("Formatted", &display_path(path));
+        }
+        unformatted.push(path.to_path_buf());
+    }
+
+    if !args.check {
+        printer.success(
+            "Finished",
+            &format!(
+                "{} formatted, {} unchanged",
+                plural(unformatted.len(), "file", "files"),
+                paths.len() - unformatted.len()
+            ),
+        );
+    }
+
+    Ok(unformatted)
+}
+
+fn write_formatted(path: &Path, formatted: &str) -> Result<()> {
+    fs::write(path, formatted).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write file: {}", e),
+    })
+}
+
+/// Where the formatter is within a document.
+#[derive(PartialEq)]
+enum Section {
+    /// Between sections: prose, comments, or palette and shader bodies.
+    Text,
+    /// After a `px` block, before any legend.
+    AfterBody,
+}
+
+/// Format the source of a `kind` asset file in canonical form.
+pub fn format_source(source: &str, kind: AssetKind) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut section = Section::Text;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        // Frontmatter opens the file and each later definition
+        if trimmed == "---" && (i == 0 || starts_definition(&lines, i)) {
+            let end = (i + 1..lines.len()).find(|&j| lines[j].trim() == "---").unwrap_or(lines.len());
+            out.push("---".to_string());
+            out.extend(format_frontmatter(&lines[i + 1..end]));
+            out.push("---".to_string());
+            section = Section::Text;
+            i = end + 1;
+            continue;
+        }
+
+        if trimmed.starts_with("```px") || trimmed.starts_with("``` px") {
+            let end = (i + 1..lines.len()).find(|&j| lines[j].trim() == "```").unwrap_or(lines.len());
+            out.push("```px".to_string());
+            out.extend(format_grid(&lines[i + 1..end], kind));
+            out.push("```".to_string());
+            section = Section::AfterBody;
+            i = end + 1;
+            continue;
+        }
+
+        // A `---` after the grid opens the legend, which runs to the next definition
+        if trimmed == "---" && section == Section::AfterBody {
+            let end = (i + 1..lines.len()).find(|&j| starts_definition(&lines, j)).unwrap_or(lines.len());
+            out.push("---".to_string());
+            out.extend(format_legend(&lines[i + 1..end]));
+            section = Section::Text;
+            i = end;
+            continue;
+        }
+
+        out.push(line.trim_end().to_string());
+        i += 1;
+    }
+
+    collapse_blank_lines(out)
+}
+
+/// Whether line `i` is a `---` that starts a new definition.
+fn starts_definition(lines: &[&str], i: usize) -> bool {
+    lines[i].trim() == "---" && lines.get(i + 1).is_some_and(|next| next.trim().starts_with("name:"))
+}
+
+/// Reorder top-level frontmatter keys, keeping each key's value lines with it.
+fn format_frontmatter(lines: &[&str]) -> Vec<String> {
+    // Blocks of (key, lines); anything before the first key stays on top
+    let mut preamble: Vec<String> = Vec::new();
+    let mut blocks: Vec<(String, Vec<String>)> = Vec::new();
+    for line in lines {
+        let line = line.trim_end();
+        match top_level_key(line) {
+            Some(key) => blocks.push((key.to_string(), vec![line.to_string()])),
+            None => match blocks.last_mut() {
+                Some((_, block)) => block.push(line.to_string()),
+                None => preamble.push(line.to_string()),
+            },
+        }
+    }
+
+    let rank = |key: &str| KEY_ORDER.iter().position(|&k| k == key).unwrap_or(KEY_ORDER.len());
+    // Stable, so unknown keys keep their order
+    blocks.sort_by_key(|(key, _)| rank(key));
+
+    preamble
+        .into_iter()
+        .chain(blocks.into_iter().flat_map(|(_, block)| block))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// The key of a top-level `key: value` frontmatter line.
+fn top_level_key(line: &str) -> Option<&str> {
+    if line.starts_with([' ', '\t', '-', '#']) {
+        return None;
+    }
+    let (key, _) = line.split_once(':')?;
+    (!key.is_empty() && !key.contains(' ')).then_some(key)
+}
+
+/// Tidy a grid body without changing what it renders.
+///
+/// Shape, prefab and map rows are padded with spaces when short, so
+/// trailing spaces are dropped unless they set the grid's width. Map
+/// layers are tidied one at a time. Brush rows skip whitespace entirely,
+/// so they are always trimmed. Stamp rows pad with transparency instead,
+/// where a trailing space (fill) is meaningful, so stamp grids are left
+/// as written.
+fn format_grid(lines: &[&str], kind: AssetKind) -> Vec<String> {
+    match kind {
+        AssetKind::Shape | AssetKind::Prefab => tidy_rows(lines, false),
+        AssetKind::Map => {
+            let mut out = Vec::new();
+            for (i, layer) in lines.split(|l| l.trim() == LAYER_MARKER).enumerate() {
+                if i > 0 {
+                    out.push(LAYER_MARKER.to_string());
+                }
+                // Overlays keep leading blank rows, which line them up with the base
+                out.extend(tidy_rows(layer, i > 0));
+            }
+            out
+        }
+        AssetKind::Brush => lines.iter().map(|l| l.trim_end()).filter(|l| !l.is_empty()).map(str::to_string).collect(),
+        _ => lines.iter().map(|l| l.to_string()).collect(),
+    }
+}
+
+/// Trim a space-padded grid's rows, and drop the empty rows at its edges
+/// that the parser skips (only the bottom ones with `keep_leading`).
+/// Comment lines stay where they are and don't count as rows.
+fn tidy_rows(lines: &[&str], keep_leading: bool) -> Vec<String> {
+    let is_row = |i: usize| !is_grid_comment(lines[i]);
+    let width = |trim: bool| {
+        (0..lines.len())
+            .filter(|&i| is_row(i))
+            .map(|i| if trim { lines[i].trim_end() } else { lines[i] }.chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let keeps_width = width(true) == width(false);
+
+    // Rows of spaces at the edges are still rows, so only empty ones are dropped
+    // and the rest are kept as written
+    let first = if keep_leading { 0 } else { (0..lines.len()).position(|i| is_row(i) && !lines[i].is_empty()).unwrap_or(lines.len()) };
+    let last = (0..lines.len()).rposition(|i| is_row(i) && !lines[i].is_empty()).map_or(first, |i| i + 1);
+    let top = (0..lines.len()).position(|i| is_row(i) && !lines[i].trim().is_empty()).unwrap_or(last);
+    let bottom = (0..lines.len()).rposition(|i| is_row(i) && !lines[i].trim().is_empty()).map_or(top, |i| i + 1);
+
+    (0..lines.len())
+        .filter_map(|i| {
+            if !is_row(i) {
+                return Some(lines[i].trim_end().to_string());
+            }
+            let inner = (top..bottom).contains(&i);
+            (first..last)
+                .contains(&i)
+                .then(|| if keeps_width && inner { lines[i].trim_end() } else { lines[i] }.to_string())
+        })
+        .collect()
+}
+
+/// Sort legend entries by glyph. Legends with comments or other lines
+/// are only trimmed, so nothing moves away from what it describes.
+fn format_legend(lines: &[&str]) -> Vec<String> {
+    let entries: Vec<&str> = lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    let mut keyed: Vec<(char, String)> = Vec::new();
+    for entry in &entries {
+        match legend_glyph(entry) {
+            Some(glyph) => keyed.push((glyph, entry.to_string())),
+            None => return lines.iter().map(|l| l.trim_end().to_string()).collect(),
+        }
+    }
+
+    keyed.sort_by_key(|(glyph, _)| *glyph);
+    let mut out: Vec<String> = keyed.into_iter().map(|(_, entry)| entry).collect();
+    // Keep a gap before the next definition
+    out.push(String::new());
+    out
+}
+
+/// The glyph of a legend entry line: `B: brick` or `" ": space`.
+fn legend_glyph(line: &str) -> Option<char> {
+    let mut chars = line.chars();
+    let first = chars.next()?;
+    if first == '"' || first == '\'' {
+        let glyph = chars.next()?;
+        return (chars.next() == Some(first) && chars.as_str().trim_start().starts_with(':')).then_some(glyph);
+    }
+    chars.as_str().trim_start().starts_with(':').then_some(first)
+}
+
+/// Collapse runs of blank lines, drop leading and trailing ones, and end
+/// with a single newline.
+fn collapse_blank_lines(lines: Vec<String>) -> String {
+    let mut out = String::new();
+    let mut blank = false;
+    for line in lines {
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_shape_file;
+    use tempfile::tempdir;
+
+    const MESSY: &str = "---\nscale: 2   \ntags:\n  - wall\nname: wall\n---\n\n\n``` px\n\n##..\n#.  \n\n```\n---\nB: brick\n\"~\": moss\nA: solid  \n";
+    const CANONICAL: &str = "---\nname: wall\ntags:\n  - wall\nscale: 2\n---\n\n```px\n##..\n#.\n```\n---\nA: solid\nB: brick\n\"~\": moss\n";
+
+    #[test]
+    fn test_format_messy_shape() {
+        assert_eq!(format_source(MESSY, AssetKind::Shape), CANONICAL);
+        // Canonical input is left alone
+        assert_eq!(format_source(CANONICAL, AssetKind::Shape), CANONICAL);
+    }
+
+    #[test]
+    fn test_format_keeps_rendering() {
+        let before = &parse_shape_file(MESSY).unwrap()[0];
+        let after = &parse_shape_file(&format_source(MESSY, AssetKind::Shape)).unwrap()[0];
+        assert_eq!(before.grid(), after.grid());
+        assert_eq!(before.legend(), after.legend());
+    }
+
+    #[test]
+    fn test_format_keeps_meaningful_spaces() {
+        // Every row ends in a space, which widens the grid
+        let source = "---\nname: gap\n---\n\n```px\n# \n# \n```\n";
+        assert_eq!(format_source(source, AssetKind::Shape), source);
+        // A trailing row of spaces is a transparent row
+        let source = "---\nname: gap\n---\n\n```px\n#\n \n```\n";
+        assert_eq!(format_source(source, AssetKind::Shape), source);
+        // Stamp spaces are fill, not padding
+        let source = "---\nname: dot\nglyph: D\n---\n\n```px\n# \n```\n";
+        assert_eq!(format_source(source, AssetKind::Stamp), source);
+    }
+
+    #[test]
+    fn test_format_map_layers() {
+        let source = "---\nname: room\n---\n\n```px\n## \n#.#\n---layer---  \n\n T \n.T.\n```\n";
+        let formatted = format_source(source, AssetKind::Map);
+        assert_eq!(formatted, "---\nname: room\n---\n\n```px\n##\n#.#\n---layer---\n\n T\n.T.\n```\n");
+        let before = &crate::parser::parse_map_file(source).unwrap()[0];
+        let after = &crate::parser::parse_map_file(&formatted).unwrap()[0];
+        assert_eq!(before.layers(), after.layers());
+    }
+
+    #[test]
+    fn test_format_multiple_definitions() {
+        let source = "---\nname: a\n---\n```px\n#\n```\n---\nname: b\nscale: 1\n---\n```px\n.\n```\n";
+        let formatted = format_source(source, AssetKind::Shape);
+        assert_eq!(formatted, "---\nname: a\n---\n```px\n#\n```\n---\nname: b\nscale: 1\n---\n```px\n.\n```\n");
+        assert_eq!(parse_shape_file(&formatted).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_format_keeps_comments_and_custom_keys() {
+        let source = "---\nauthor: sam  \nname: wall\n# hand-tuned\nscale: 2\n---\n\n```px\n\n// top row   \n##..\n#.  \n// end\n\n```\n";
+        let formatted = format_source(source, AssetKind::Shape);
+        assert_eq!(formatted, "---\nname: wall\n# hand-tuned\nscale: 2\nauthor: sam\n---\n\n```px\n// top row\n##..\n#.\n// end\n```\n");
+        assert_eq!(format_source(&formatted, AssetKind::Shape), formatted);
+
+        let before = &parse_shape_file(source).unwrap()[0];
+        let after = &parse_shape_file(&formatted).unwrap()[0];
+        assert_eq!(before.grid(), after.grid());
+    }
+
+    #[test]
+    fn test_check_flags_then_fmt_fixes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("wall.shape.md");
+        fs::write(&path, MESSY).unwrap();
+
+        let check = FmtArgs { files: vec![dir.path().to_path_buf()], check: true };
+        assert_eq!(format_files(&check, &Printer::new()).unwrap(), vec![path.clone()]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), MESSY);
+
+        let fmt = FmtArgs { files: vec![dir.path().to_path_buf()], check: false };
+        format_files(&fmt, &Printer::new()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), CANONICAL);
+        assert!(format_files(&check, &Printer::new()).unwrap().is_empty());
+    }
+}
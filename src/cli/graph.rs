@@ -0,0 +1,81 @@
+//! Graph command implementation.
+//!
+//! Writes the asset dependency graph as Graphviz DOT, for inspecting build
+//! order and tracking down cycles.
+
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::discovery::{discover, discover_paths, load_assets, LoadOptions};
+use crate::error::{PxError, Result};
+use crate::output::{display_path, plural, Printer};
+
+/// Write the asset dependency graph as Graphviz DOT
+#[derive(Args, Debug)]
+pub struct GraphArgs {
+    /// Files or directories to scan (default: current directory)
+    pub files: Vec<PathBuf>,
+
+    /// Write the DOT file here instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+pub fn run(args: GraphArgs, printer: &Printer) -> Result<()> {
+    let discovery = if args.files.is_empty() {
+        discover(".")?
+    } else {
+        discover_paths(&args.files)?
+    };
+
+    let registry = load_assets(&discovery.scan, &LoadOptions::with_builtins())?.build()?;
+    let graph = registry.graph();
+    let dot = graph.to_dot();
+
+    match &args.output {
+        Some(path) => {
+            fs::write(path, dot).map_err(|e| PxError::Io {
+                path: path.clone(),
+                message: format!("Failed to write graph: {}", e),
+            })?;
+            printer.success(
+                "Finished",
+                &format!("{} -> {}", plural(graph.len(), "asset", "assets"), display_path(path)),
+            );
+        }
+        None => print!("{}", dot),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_graph_writes_dependency_chain() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("ui.palette.md"), "---\nname: ui\n---\n\n$edge: #000000\n$fill: #FFFFFF\n").unwrap();
+        fs::write(dir.path().join("ui.shader.md"), "---\nname: ui\npalette: ui\n---\n").unwrap();
+        fs::write(dir.path().join("brick.stamp.md"), "---\nname: brick\nglyph: B\n---\n\n```px\n#.\n```\n").unwrap();
+        fs::write(dir.path().join("wall.shape.md"), "---\nname: wall\n---\n\n```px\nBB\n```\n\n---\nB: brick\n").unwrap();
+
+        let out = dir.path().join("graph.dot");
+        let args = GraphArgs {
+            files: vec![dir.path().to_path_buf()],
+            output: Some(out.clone()),
+        };
+        run(args, &Printer::new()).unwrap();
+
+        let dot = fs::read_to_string(&out).unwrap();
+        assert!(dot.starts_with("digraph px {"));
+        assert!(dot.contains("    \"palette:ui\";\n"));
+        assert!(dot.contains("    \"shape:wall\";\n"));
+        assert!(dot.contains("    \"palette:ui\" -> \"shader:ui\";\n"));
+        assert!(dot.contains("    \"stamp:brick\" -> \"shape:wall\";\n"));
+    }
+}
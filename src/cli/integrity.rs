@@ -0,0 +1,69 @@
+//! Subresource integrity manifest.
+//!
+//! `px build --emit-integrity` writes `integrity.json`, mapping each file in
+//! the output directory (but the output manifest, which is written after it)
+//! to its SHA-384 hash in SRI form (`sha384-<base64>`),
+//! ready for `<script integrity="...">` and friends.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha384};
+
+use crate::cli::outputs::OUTPUTS_FILE;
+use crate::cli::verify::relative_files;
+use crate::error::{PxError, Result};
+
+/// Integrity manifest name inside the output directory.
+pub const INTEGRITY_FILE: &str = "integrity.json";
+
+/// Hash every file under `output` and write `integrity.json` there.
+/// Returns the number of files hashed.
+pub fn write_integrity(output: &Path) -> Result<usize> {
+    let mut hashes = BTreeMap::new();
+    for relative in relative_files(output)? {
+        if relative == Path::new(INTEGRITY_FILE) || relative == Path::new(OUTPUTS_FILE) {
+            continue;
+        }
+        let path = output.join(&relative);
+        let bytes = fs::read(&path).map_err(|e| PxError::Io {
+            path: path.clone(),
+            message: format!("Failed to read output: {}", e),
+        })?;
+        // Forward slashes, as the files are referenced from the web
+        let key = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        hashes.insert(key, sri(&bytes));
+    }
+
+    let path = output.join(INTEGRITY_FILE);
+    let json = serde_json::to_string_pretty(&hashes).map_err(|e| PxError::Build {
+        message: format!("Failed to serialize integrity manifest: {}", e),
+        help: None,
+    })?;
+    fs::write(&path, json).map_err(|e| PxError::Io {
+        path,
+        message: format!("Failed to write integrity manifest: {}", e),
+    })?;
+    Ok(hashes.len())
+}
+
+/// SRI string for `bytes`: `sha384-` and the base64 digest.
+pub fn sri(bytes: &[u8]) -> String {
+    format!("sha384-{}", STANDARD.encode(Sha384::digest(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sri_known_digests() {
+        assert_eq!(sri(b"abc"), "sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/W+2AhgcroefMI1i67KE0yCWn");
+        assert_eq!(sri(b""), "sha384-OLBgp1GsljhM2TJ+sbHjaiH9txEUvgdDTAzHv2P24donTt6/529l+9Ua0vFImLlb");
+        // Spans two blocks
+        assert_eq!(sri(&[b'a'; 200]), "sha384-BpG26XhhS2fWBVeyos3dU0BlCFIu+iHGJNu/qKtucm1cWGtInHwJ8kEJpkwQIR1I");
+    }
+}
@@ -1,6 +1,8 @@
 //! List command implementation.
 //!
-//! Discovers assets and prints an organized inventory.
+//! Discovers assets and prints an organized inventory. `--colours` adds
+//! each palette's swatches and each shape's colour count, rendering every
+//! shape with the default shader.
 
 use std::path::PathBuf;
 
@@ -8,7 +10,10 @@ use clap::Args;
 
 use crate::discovery::{discover, discover_paths, load_assets, LoadOptions};
 use crate::error::Result;
-use crate::output::Printer;
+use crate::output::{plural, Printer};
+use crate::registry::AssetRegistry;
+use crate::render::ShapeRenderer;
+use crate::types::{BuiltinShaders, Palette};
 
 /// List discovered assets
 #[derive(Args, Debug)]
@@ -19,6 +24,10 @@ pub struct ListArgs {
     /// Show dependency relationships
     #[arg(long)]
     pub deps: bool,
+
+    /// Show palette swatches and how many colours each shape uses (renders every shape)
+    #[arg(long)]
+    pub colours: bool,
 }
 
 pub fn run(args: ListArgs, printer: &Printer) -> Result<()> {
@@ -36,12 +45,82 @@ pub fn run(args: ListArgs, printer: &Printer) -> Result<()> {
     } else {
         print_inventory(&registry, printer);
     }
+    if args.colours {
+        print_colours(&registry, printer)?;
+    }
 
     Ok(())
 }
 
-fn print_inventory(registry: &crate::registry::AssetRegistry, printer: &Printer) {
-    let groups: &[(&str, Vec<String>)] = &[
+fn print_inventory(registry: &AssetRegistry, printer: &Printer) {
+    for (label, names) in inventory(registry) {
+        printer.info(label, &names.join(", "));
+    }
+}
+
+fn print_deps(registry: &AssetRegistry, printer: &Printer) {
+    for (kind, name, deps) in dependencies(registry) {
+        if deps.is_empty() {
+            printer.info(kind, &name);
+        } else {
+            let dep_list = deps.join(", ");
+            printer.info(kind, &format!("{} {} {}", name, printer.dim("->"), dep_list));
+        }
+    }
+}
+
+fn print_colours(registry: &AssetRegistry, printer: &Printer) -> Result<()> {
+    for (name, swatches) in palette_swatches(registry) {
+        let swatches: Vec<String> = swatches.iter().map(|(colour, hex)| format!("${} {}", colour, hex)).collect();
+        printer.info("Palette", &format!("{} {} {}", name, printer.dim("->"), swatches.join(", ")));
+    }
+    for (name, count) in shape_colour_counts(registry)? {
+        printer.info("Shape", &format!("{} {} {}", name, printer.dim("->"), plural(count, "colour", "colours")));
+    }
+    Ok(())
+}
+
+/// Palettes in name order, each with its resolved swatches (name, hex) in
+/// name order.
+fn palette_swatches(registry: &AssetRegistry) -> Vec<(String, Vec<(String, String)>)> {
+    sorted_names(registry.palette_names())
+        .into_iter()
+        .filter_map(|name| {
+            let palette = registry.get_palette(&name)?;
+            let swatches = sorted_names(palette.colour_names())
+                .into_iter()
+                .filter_map(|colour| palette.get(&colour).map(|c| (colour, c.to_hex())))
+                .collect();
+            Some((name, swatches))
+        })
+        .collect()
+}
+
+/// Shapes in name order with the number of distinct colours each renders
+/// to under the default shader, not counting transparency.
+fn shape_colour_counts(registry: &AssetRegistry) -> Result<Vec<(String, usize)>> {
+    let shader = BuiltinShaders::get("default").unwrap();
+    let palette = registry.get_palette(&shader.palette).cloned().unwrap_or_else(Palette::default_palette);
+    let mut renderer = ShapeRenderer::new(&palette);
+    renderer.add_stamps(registry.stamps());
+    renderer.add_brushes(registry.brushes());
+    let renderer = renderer.with_effects(&shader.effects);
+
+    let mut counts = Vec::new();
+    for name in sorted_names(registry.shape_names()) {
+        let Some(shape) = registry.get_shape(&name) else {
+            continue;
+        };
+        let rendered = renderer.render(shape)?;
+        let count = rendered.colour_histogram().into_keys().filter(|c| !c.is_transparent()).count();
+        counts.push((name, count));
+    }
+    Ok(counts)
+}
+
+/// Non-empty asset groups with their names, sorted.
+fn inventory(registry: &AssetRegistry) -> Vec<(&'static str, Vec<String>)> {
+    let groups = vec![
         ("Palettes", sorted_names(registry.palette_names())),
         ("Stamps", sorted_names(registry.stamp_names())),
         ("Brushes", sorted_names(registry.brush_names())),
@@ -52,28 +131,21 @@ fn print_inventory(registry: &crate::registry::AssetRegistry, printer: &Printer)
         ("Targets", sorted_names(registry.target_names())),
     ];
 
-    for (label, names) in groups {
-        if names.is_empty() {
-            continue;
-        }
-        printer.info(label, &names.join(", "));
-    }
+    groups.into_iter().filter(|(_, names)| !names.is_empty()).collect()
 }
 
-fn print_deps(registry: &crate::registry::AssetRegistry, printer: &Printer) {
+/// Assets in build order as (kind, name, sorted dependency names).
+fn dependencies(registry: &AssetRegistry) -> Vec<(&'static str, String, Vec<String>)> {
     let graph = registry.graph();
 
-    for id in registry.build_order() {
-        let deps: Vec<String> = graph.dependencies_of(id).map(|d| d.name.clone()).collect();
-        let kind = id.kind.name();
-
-        if deps.is_empty() {
-            printer.info(kind, &id.name);
-        } else {
-            let dep_list = deps.join(", ");
-            printer.info(kind, &format!("{} {} {}", id.name, printer.dim("->"), dep_list));
-        }
-    }
+    registry
+        .build_order()
+        .iter()
+        .map(|id| {
+            let deps = sorted_names(graph.dependencies_of(id).map(|d| d.name.as_str()));
+            (id.kind.name(), id.name.clone(), deps)
+        })
+        .collect()
 }
 
 fn sorted_names<'a>(iter: impl Iterator<Item = &'a str>) -> Vec<String> {
@@ -81,3 +153,64 @@ fn sorted_names<'a>(iter: impl Iterator<Item = &'a str>) -> Vec<String> {
     names.sort();
     names
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn load(root: &std::path::Path) -> AssetRegistry {
+        let discovery = discover_paths(&[root.to_path_buf()]).unwrap();
+        load_assets(&discovery.scan, &LoadOptions::with_builtins())
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_list_output_is_deterministic() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            "---\nname: wall\n---\n\n```px\n##\n```\n\n---\nname: floor\n---\n\n```px\n..\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("room.prefab.md"),
+            "---\nname: room\n---\n\n```px\nWF\nFW\n```\n\n---\nW: wall\nF: floor\n",
+        )
+        .unwrap();
+
+        let first = load(dir.path());
+        let second = load(dir.path());
+
+        assert_eq!(inventory(&first), inventory(&second));
+        assert_eq!(dependencies(&first), dependencies(&second));
+
+        let room = dependencies(&first)
+            .into_iter()
+            .find(|(_, name, _)| name == "room")
+            .unwrap();
+        assert_eq!(room.2, vec!["floor", "wall"]);
+    }
+
+    #[test]
+    fn test_shape_colour_counts() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            "---\nname: brick\n---\n\n```px\n#+\n+.\n```\n\n---\nname: solid\n---\n\n```px\n##\n```\n",
+        )
+        .unwrap();
+
+        let registry = load(dir.path());
+        let counts = shape_colour_counts(&registry).unwrap();
+        assert_eq!(counts, vec![("brick".to_string(), 2), ("solid".to_string(), 1)]);
+
+        let swatches = palette_swatches(&registry);
+        let (name, colours) = swatches.iter().find(|(name, _)| name == "default").unwrap();
+        assert_eq!(name, "default");
+        assert!(colours.iter().any(|(colour, hex)| colour == "black" && hex == "#000000"));
+    }
+}
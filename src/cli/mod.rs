@@ -1,10 +1,21 @@
 pub mod build;
+mod cache;
 pub mod completions;
+pub mod diff;
+pub mod fmt;
+pub mod graph;
 pub mod init;
+mod integrity;
 pub mod list;
+mod outputs;
+pub mod pack;
 pub mod palette;
+pub mod preview;
+mod serve;
 pub mod slice;
+pub mod stats;
 pub mod validate;
+pub mod verify;
 
 use clap::{Parser, Subcommand};
 
@@ -48,18 +59,39 @@ pub enum Commands {
     /// Generate shell completions
     Completions(completions::CompletionsArgs),
 
+    /// Compare sprites between two build output directories
+    Diff(diff::DiffArgs),
+
+    /// Rewrite asset files in canonical form
+    Fmt(fmt::FmtArgs),
+
+    /// Write the asset dependency graph as Graphviz DOT
+    Graph(graph::GraphArgs),
+
     /// Initialize a px project (generates px.yaml)
     Init(init::InitArgs),
 
     /// List discovered assets
     List(list::ListArgs),
 
-    /// Extract a colour palette from a PNG file
+    /// Pack existing PNGs into a sprite sheet
+    Pack(pack::PackArgs),
+
+    /// Extract a colour palette from a PNG file, blend two palettes, or expand variants
     Palette(palette::PaletteArgs),
 
+    /// Preview an animation frame, optionally onion-skinned
+    Preview(preview::PreviewArgs),
+
     /// Slice a PNG into sprite definition files
     Slice(slice::SliceArgs),
 
+    /// Summarize a project's assets
+    Stats(stats::StatsArgs),
+
     /// Validate definition files without rendering
     Validate(validate::ValidateArgs),
+
+    /// Rebuild and check outputs match the committed output directory
+    Verify(verify::VerifyArgs),
 }
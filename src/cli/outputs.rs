@@ -0,0 +1,56 @@
+//! Output manifest.
+//!
+//! Every `px build` ends by writing `outputs.json` to the output directory,
+//! recording each file there with its size and SHA-384 hash, so a deploy
+//! can be checked against what was built. `--manifest-out` puts it
+//! elsewhere.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::integrity::sri;
+use crate::cli::verify::relative_files;
+use crate::error::{PxError, Result};
+
+/// Output manifest name inside the output directory.
+pub const OUTPUTS_FILE: &str = "outputs.json";
+
+/// One output file in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputEntry {
+    /// Size in bytes.
+    pub size: u64,
+    /// SHA-384 hash in SRI form (`sha384-<base64>`).
+    pub hash: String,
+}
+
+/// Record every file under `output` in a manifest written to `path`, keyed
+/// by path relative to `output`. Returns the number of files recorded.
+pub fn write_outputs(output: &Path, path: &Path) -> Result<usize> {
+    let mut files = BTreeMap::new();
+    for relative in relative_files(output)? {
+        let file = output.join(&relative);
+        if relative == Path::new(OUTPUTS_FILE) || file == path {
+            continue;
+        }
+        let bytes = fs::read(&file).map_err(|e| PxError::Io {
+            path: file.clone(),
+            message: format!("Failed to read output: {}", e),
+        })?;
+        let key = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        files.insert(key, OutputEntry { size: bytes.len() as u64, hash: sri(&bytes) });
+    }
+
+    let json = serde_json::to_string_pretty(&files).map_err(|e| PxError::Build {
+        message: format!("Failed to serialize output manifest: {}", e),
+        help: None,
+    })?;
+    fs::write(path, json).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write output manifest: {}", e),
+    })?;
+    Ok(files.len())
+}
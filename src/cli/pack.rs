@@ -0,0 +1,187 @@
+//! Pack command implementation.
+//!
+//! Packs PNGs from an earlier per-file build into a sprite sheet, without
+//! re-rendering their sources.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::cli::build::{pack_strategy, parse_grid_cell};
+use crate::error::{PxError, Result};
+use crate::output::{display_path, plural, Printer};
+use crate::render::{read_png, write_png, write_sheet_json, RenderedShape, SheetPacker, SheetSizing};
+
+/// Pack existing PNGs into a sprite sheet
+#[derive(Args, Debug)]
+pub struct PackArgs {
+    /// Directory of PNGs to pack (e.g. the output of `px build`)
+    pub dir: PathBuf,
+
+    /// Sheet image to write; the JSON goes beside it
+    #[arg(long, short, default_value = "sheet.png")]
+    pub output: PathBuf,
+
+    /// Padding between sprites in sheet (pixels)
+    #[arg(long, default_value_t = 0)]
+    pub padding: u32,
+
+    /// Sheet dimensions: power-of-two width, tight fit, or square power of two
+    #[arg(long, value_parser = ["pot-width", "tight", "square-pot"])]
+    pub sheet_size: Option<String>,
+
+    /// Crop transparent borders from sprites before packing the sheet
+    #[arg(long)]
+    pub trim: bool,
+
+    /// Place sprites in fixed WxH cells, in order, instead of packing them
+    #[arg(long, value_name = "WxH", value_parser = parse_grid_cell)]
+    pub grid: Option<(u32, u32)>,
+
+    /// Lay sprites out in this many columns
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub columns: Option<u32>,
+
+    /// Wrap packed shelves at this width
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_width: Option<u32>,
+
+    /// Extrude sprite edges this many pixels around each frame
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub bleed: u32,
+}
+
+pub fn run(args: PackArgs, printer: &Printer) -> Result<()> {
+    let sprites = load_sprites(&args.dir, &args.output)?;
+    if sprites.is_empty() {
+        return Err(PxError::Build {
+            message: format!("No PNGs to pack in {}", display_path(&args.dir)),
+            help: Some("Run `px build` first, or point at its output directory".to_string()),
+        });
+    }
+
+    let sizing = args
+        .sheet_size
+        .as_deref()
+        .map(SheetSizing::from_str_lossy)
+        .unwrap_or_default();
+    let packer = SheetPacker::with_strategy(args.padding, pack_strategy(args.grid))
+        .with_sizing(sizing)
+        .with_trim(args.trim)
+        .with_columns(args.columns)
+        .with_max_width(args.max_width)
+        .with_bleed(args.bleed);
+    let (sheet, mut meta) = packer.pack(&sprites)?;
+
+    let json_path = args.output.with_extension("json");
+    meta.image = args
+        .output
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "sheet.png".to_string());
+    write_png(&sheet, &args.output, 1)?;
+    write_sheet_json(&meta, &json_path)?;
+
+    printer.status(
+        "Packing",
+        &format!(
+            "{} into sheet {}",
+            plural(sprites.len(), "sprite", "sprites"),
+            printer.dim(&format!("({}x{})", sheet.width(), sheet.height())),
+        ),
+    );
+    printer.success(
+        "Finished",
+        &format!("{} + {}", display_path(&args.output), display_path(&json_path)),
+    );
+
+    Ok(())
+}
+
+/// Load every PNG directly in `dir`, named by file stem and sorted by name.
+///
+/// Normal maps and the sheet being written are left out.
+fn load_sprites(dir: &Path, sheet: &Path) -> Result<Vec<RenderedShape>> {
+    let entries = fs::read_dir(dir).map_err(|e| PxError::Io {
+        path: dir.to_path_buf(),
+        message: format!("Failed to read directory: {}", e),
+    })?;
+
+    let sheet = fs::canonicalize(sheet).ok();
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")))
+        .filter(|p| !p.to_string_lossy().ends_with(".normal.png"))
+        .filter(|p| sheet.is_none() || fs::canonicalize(p).ok() != sheet)
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            read_png(path, name)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Colour;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pack_existing_pngs() {
+        let dir = tempdir().unwrap();
+        let sprites = dir.path().join("dist");
+        fs::create_dir(&sprites).unwrap();
+        write_png(&RenderedShape::new("a", vec![vec![Colour::BLACK; 2]; 3]), &sprites.join("a.png"), 1).unwrap();
+        write_png(&RenderedShape::new("b", vec![vec![Colour::WHITE; 4]; 1]), &sprites.join("b.png"), 1).unwrap();
+        write_png(&RenderedShape::new("b", vec![vec![Colour::WHITE; 4]; 1]), &sprites.join("b.normal.png"), 1).unwrap();
+
+        let output = dir.path().join("atlas.png");
+        let args = PackArgs {
+            dir: sprites,
+            output: output.clone(),
+            padding: 0,
+            sheet_size: None,
+            trim: false,
+            grid: None,
+            columns: None,
+            max_width: None,
+            bleed: 0,
+        };
+        run(args, &Printer::new()).unwrap();
+
+        assert!(output.exists());
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("atlas.json")).unwrap()).unwrap();
+        let frames = json["frames"].as_object().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames["a"]["frame"]["w"], 2);
+        assert_eq!(frames["a"]["frame"]["h"], 3);
+        assert_eq!(frames["b"]["frame"]["w"], 4);
+        assert_eq!(frames["b"]["frame"]["h"], 1);
+        assert_eq!(json["meta"]["image"], "atlas.png");
+    }
+
+    #[test]
+    fn test_pack_empty_dir_errors() {
+        let dir = tempdir().unwrap();
+        let args = PackArgs {
+            dir: dir.path().to_path_buf(),
+            output: dir.path().join("sheet.png"),
+            padding: 0,
+            sheet_size: None,
+            trim: false,
+            grid: None,
+            columns: None,
+            max_width: None,
+            bleed: 0,
+        };
+        assert!(run(args, &Printer::new()).is_err());
+    }
+}
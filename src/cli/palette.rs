@@ -1,26 +1,74 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
 
+use crate::discovery::{discover, load_assets, LoadOptions};
 use crate::error::{PxError, Result};
 use crate::output::{display_path, plural, Printer};
-use crate::types::Colour;
+use crate::parser::parse_palette;
+use crate::render::{colour_distance, median_cut};
+use crate::types::{Colour, Palette};
 
-/// Extract a colour palette from a PNG file
+/// Extract a colour palette from a PNG file, blend two palettes, or expand variants
 #[derive(Args, Debug)]
 pub struct PaletteArgs {
     /// PNG file to extract colours from
-    #[arg(required = true)]
-    pub file: PathBuf,
+    #[arg(required_unless_present_any = ["blend", "expand_variants", "scheme"])]
+    pub file: Option<PathBuf>,
 
     /// Maximum number of colours to output
     #[arg(long)]
     pub max: Option<usize>,
+
+    /// Merge similar colours by median cut until at most N remain
+    #[arg(long, value_name = "N")]
+    pub reduce: Option<usize>,
+
+    /// Blend two palette files, mixing colours they share
+    #[arg(long, num_args = 2, value_names = ["A", "B"], conflicts_with = "file")]
+    pub blend: Option<Vec<PathBuf>>,
+
+    /// Blend position from A to B (e.g. 50% or 0.5)
+    #[arg(long, default_value = "50%", value_parser = parse_blend_factor, requires = "blend")]
+    pub at: f32,
+
+    /// Write each variant of a palette file as its own resolved palette
+    #[arg(long, value_name = "PALETTE", conflicts_with_all = ["file", "blend"])]
+    pub expand_variants: Option<PathBuf>,
+
+    /// Select every project palette with this `scheme:`
+    #[arg(long, value_name = "SCHEME", conflicts_with_all = ["file", "blend", "expand_variants"])]
+    pub scheme: Option<String>,
+
+    /// Write each selected palette as a GIMP .gpl file
+    #[arg(long, requires = "scheme")]
+    pub export_gpl: bool,
+
+    /// Blended palette file (default: stdout), or directory for expanded
+    /// variants and exported palettes (default: .)
+    #[arg(long, short, conflicts_with = "file")]
+    pub output: Option<PathBuf>,
 }
 
 pub fn run(args: PaletteArgs, printer: &Printer) -> Result<()> {
-    let path = &args.file;
+    if let Some(paths) = &args.blend {
+        return run_blend(&paths[0], &paths[1], args.at, args.output.as_deref(), printer);
+    }
+    if let Some(path) = &args.expand_variants {
+        let dir = args.output.clone().unwrap_or_else(|| PathBuf::from("."));
+        return run_expand(path, &dir, printer).map(|_| ());
+    }
+    if let Some(scheme) = &args.scheme {
+        let dir = args.output.clone().unwrap_or_else(|| PathBuf::from("."));
+        let export = args.export_gpl.then_some(dir.as_path());
+        return run_scheme(Path::new("."), scheme, export, printer).map(|_| ());
+    }
+
+    let Some(path) = &args.file else {
+        return Ok(());
+    };
     let display = display_path(path);
 
     let img = image::open(path)
@@ -40,9 +88,13 @@ pub fn run(args: PaletteArgs, printer: &Printer) -> Result<()> {
         *counts.entry(rgba).or_insert(0) += 1;
     }
 
-    // Sort by frequency (most common first)
+    if let Some(n) = args.reduce {
+        counts = reduce_counts(&counts, n);
+    }
+
+    // Sort by frequency (most common first), ties broken by RGBA for stable output
     let mut colours: Vec<([u8; 4], usize)> = counts.into_iter().collect();
-    colours.sort_by(|a, b| b.1.cmp(&a.1));
+    colours.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
     // Apply --max limit
     if let Some(max) = args.max {
@@ -55,8 +107,295 @@ pub fn run(args: PaletteArgs, printer: &Printer) -> Result<()> {
     // Print palette lines to stdout
     for (i, (rgba, _count)) in colours.iter().enumerate() {
         let colour = Colour::new(rgba[0], rgba[1], rgba[2], rgba[3]);
-        println!("$colour-{}: {}", i + 1, colour);
+        println!("$colour-{}: {}", i + 1, colour.to_hex());
+    }
+
+    Ok(())
+}
+
+/// Reduce sampled colours to at most `n` by median cut, each reduced colour
+/// counting the pixels of the colours nearest to it.
+pub(crate) fn reduce_counts(counts: &HashMap<[u8; 4], usize>, n: usize) -> HashMap<[u8; 4], usize> {
+    let mut sampled: Vec<Colour> = counts.keys().map(|rgba| Colour::new(rgba[0], rgba[1], rgba[2], rgba[3])).collect();
+    sampled.sort_by_key(|c| c.to_rgba());
+    let reduced = median_cut(&sampled, n);
+
+    let mut merged = HashMap::new();
+    for colour in &sampled {
+        let Some(nearest) = reduced.iter().min_by_key(|r| (colour_distance(colour, r), colour.a.abs_diff(r.a))) else {
+            continue;
+        };
+        *merged.entry(nearest.to_rgba()).or_insert(0) += counts[&colour.to_rgba()];
+    }
+    merged
+}
+
+/// Blend two palette files and print or write the result.
+fn run_blend(a: &Path, b: &Path, factor: f32, output: Option<&Path>, printer: &Printer) -> Result<()> {
+    let first = load_palette_file(a)?;
+    let second = load_palette_file(b)?;
+
+    let mut blended = first.blend(&second, factor);
+    if let Some(stem) = output.and_then(|p| p.file_name()).and_then(|f| f.to_str()) {
+        blended.name = stem.split('.').next().unwrap_or(stem).to_string();
+    }
+
+    printer.status(
+        "Blending",
+        &format!(
+            "{} and {} at {}%",
+            first.name,
+            second.name,
+            (factor * 100.0).round()
+        ),
+    );
+
+    let source = blended.to_source();
+    match output {
+        Some(path) => {
+            fs::write(path, source).map_err(|e| PxError::Io {
+                path: path.to_path_buf(),
+                message: format!("Failed to write palette: {}", e),
+            })?;
+            printer.success(
+                "Finished",
+                &format!("{} -> {}", plural(blended.len(), "colour", "colours"), display_path(path)),
+            );
+        }
+        None => print!("{}", source),
     }
 
     Ok(())
 }
+
+/// Write the base colours and each variant of a palette file as resolved
+/// palettes in `dir`: `name.palette.md`, then `name-{variant}.palette.md`
+/// in variant order. Returns the paths written.
+fn run_expand(path: &Path, dir: &Path, printer: &Printer) -> Result<Vec<PathBuf>> {
+    let palette = load_palette_file(path)?;
+
+    let mut variants: Vec<&str> = palette.variant_names().collect();
+    variants.sort();
+
+    fs::create_dir_all(dir).map_err(|e| PxError::Io {
+        path: dir.to_path_buf(),
+        message: format!("Failed to create output directory: {}", e),
+    })?;
+
+    let mut written = Vec::new();
+    for variant in std::iter::once(None).chain(variants.into_iter().map(Some)) {
+        let resolved = palette.resolve_variant(variant);
+        let out = dir.join(format!("{}.palette.md", resolved.name));
+
+        // The base file shares the source's name; don't replace the variants with it
+        if same_file(&out, path) {
+            return Err(PxError::Build {
+                message: format!("Expanding {} would overwrite it", display_path(path)),
+                help: Some("Pass -o to write the expanded palettes to another directory".to_string()),
+            });
+        }
+
+        fs::write(&out, resolved.to_source()).map_err(|e| PxError::Io {
+            path: out.clone(),
+            message: format!("Failed to write palette: {}", e),
+        })?;
+        printer.status(
+            "Expanding",
+            &format!("{} {}", printer.bold(&resolved.name), printer.dim(&format!("({})", display_path(&out)))),
+        );
+        written.push(out);
+    }
+
+    printer.success(
+        "Finished",
+        &format!("{} -> {}", plural(written.len(), "palette", "palettes"), display_path(dir)),
+    );
+    Ok(written)
+}
+
+/// Select the palettes under `root` with `scheme:` set to `scheme`, and
+/// write each to `export` as `{name}.gpl` if given. Returns the selected
+/// palette names.
+fn run_scheme(root: &Path, scheme: &str, export: Option<&Path>, printer: &Printer) -> Result<Vec<String>> {
+    let discovery = discover(root)?;
+    let registry = load_assets(&discovery.scan, &LoadOptions::default())?.build()?;
+
+    let selected: Vec<&Palette> = registry.palettes_in_scheme(scheme).collect();
+    if selected.is_empty() {
+        return Err(PxError::Build {
+            message: format!("No palettes with scheme '{}'", scheme),
+            help: Some("Set `scheme:` in a palette's frontmatter".to_string()),
+        });
+    }
+
+    if let Some(dir) = export {
+        fs::create_dir_all(dir).map_err(|e| PxError::Io {
+            path: dir.to_path_buf(),
+            message: format!("Failed to create output directory: {}", e),
+        })?;
+    }
+
+    for palette in &selected {
+        match export {
+            Some(dir) => {
+                let out = dir.join(format!("{}.gpl", palette.name));
+                fs::write(&out, palette.to_gpl()).map_err(|e| PxError::Io {
+                    path: out.clone(),
+                    message: format!("Failed to write palette: {}", e),
+                })?;
+                printer.status(
+                    "Exporting",
+                    &format!("{} {}", printer.bold(&palette.name), printer.dim(&format!("({})", display_path(&out)))),
+                );
+            }
+            None => printer.info("Palette", &format!("{} {}", palette.name, printer.dim(&format!("({})", plural(palette.len(), "colour", "colours"))))),
+        }
+    }
+
+    if let Some(dir) = export {
+        printer.success(
+            "Finished",
+            &format!("{} -> {}", plural(selected.len(), "palette", "palettes"), display_path(dir)),
+        );
+    }
+    Ok(selected.iter().map(|p| p.name.clone()).collect())
+}
+
+/// Whether two paths name the same existing file.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Load the first palette defined in a palette file.
+pub(crate) fn load_palette_file(path: &Path) -> Result<Palette> {
+    let source = fs::read_to_string(path).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to read palette: {}", e),
+    })?;
+
+    let builder = parse_palette(&source)?.into_iter().next().ok_or_else(|| PxError::Parse {
+        message: format!("No palette found in {}", path.display()),
+        help: None,
+    })?;
+
+    builder.build(None)
+}
+
+/// Parse a blend position: `50%` or a fraction like `0.5`.
+fn parse_blend_factor(value: &str) -> std::result::Result<f32, String> {
+    let factor = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().map(|p| p / 100.0),
+        None => value.trim().parse::<f32>(),
+    }
+    .map_err(|_| format!("invalid blend position '{}'", value))?;
+
+    if (0.0..=1.0).contains(&factor) {
+        Ok(factor)
+    } else {
+        Err(format!("blend position '{}' must be between 0% and 100%", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_blend_factor() {
+        assert_eq!(parse_blend_factor("50%"), Ok(0.5));
+        assert_eq!(parse_blend_factor("0.25"), Ok(0.25));
+        assert!(parse_blend_factor("150%").is_err());
+        assert!(parse_blend_factor("half").is_err());
+    }
+
+    #[test]
+    fn test_reduce_counts_keeps_pixel_totals() {
+        let counts: HashMap<[u8; 4], usize> =
+            HashMap::from([([0, 0, 0, 255], 5), ([3, 3, 3, 255], 1), ([255, 255, 255, 255], 4), ([250, 250, 250, 255], 2)]);
+
+        let reduced = reduce_counts(&counts, 2);
+        assert_eq!(reduced.len(), 2);
+        assert_eq!(reduced.values().sum::<usize>(), 12);
+        assert_eq!(reduced.get(&[2, 2, 2, 255]), Some(&6));
+    }
+
+    #[test]
+    fn test_blend_files() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("day.palette.md");
+        let b = dir.path().join("night.palette.md");
+        let out = dir.path().join("dusk.palette.md");
+
+        fs::write(&a, "---\nname: day\n---\n\n$sky: #0000FF\n$sun: #FFFF00\n").unwrap();
+        fs::write(&b, "---\nname: night\n---\n\n$sky: #000000\n").unwrap();
+
+        let printer = Printer::new();
+        run_blend(&a, &b, 0.5, Some(&out), &printer).unwrap();
+
+        let dusk = load_palette_file(&out).unwrap();
+        assert_eq!(dusk.name, "dusk");
+        assert_eq!(dusk.get("sky"), Some(Colour::rgb(0, 0, 128)));
+        assert_eq!(dusk.get("sun"), Some(Colour::rgb(255, 255, 0)));
+    }
+
+    #[test]
+    fn test_expand_variants() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("world.palette.md");
+        let out = dir.path().join("expanded");
+        fs::write(
+            &source,
+            "---\nname: world\n---\n\n$sky: #0000FF\n$sun: #FFFF00\n\n@day:\n  $sky: #88CCFF\n\n@night:\n  $sky: #000000\n  $sun: #CCCCCC\n",
+        )
+        .unwrap();
+
+        let written = run_expand(&source, &out, &Printer::new()).unwrap();
+        assert_eq!(written.len(), 3);
+
+        let base = load_palette_file(&out.join("world.palette.md")).unwrap();
+        assert_eq!(base.get("sky"), Some(Colour::rgb(0, 0, 255)));
+        assert!(!base.has_variant("night"));
+
+        let day = load_palette_file(&out.join("world-day.palette.md")).unwrap();
+        assert_eq!(day.name, "world-day");
+        assert_eq!(day.get("sky"), Some(Colour::rgb(136, 204, 255)));
+        assert_eq!(day.get("sun"), Some(Colour::rgb(255, 255, 0)));
+
+        let night = load_palette_file(&out.join("world-night.palette.md")).unwrap();
+        assert_eq!(night.get("sky"), Some(Colour::BLACK));
+        assert_eq!(night.get("sun"), Some(Colour::rgb(204, 204, 204)));
+    }
+
+    #[test]
+    fn test_expand_variants_refuses_to_overwrite_source() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("world.palette.md");
+        let original = "---\nname: world\n---\n\n$sky: #0000FF\n\n@night:\n  $sky: #000000\n";
+        fs::write(&source, original).unwrap();
+
+        assert!(run_expand(&source, dir.path(), &Printer::new()).is_err());
+        assert_eq!(fs::read_to_string(&source).unwrap(), original);
+    }
+
+    #[test]
+    fn test_scheme_selects_matching_palettes() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("mi.palette.md"), "---\nname: mi\nscheme: retro\n---\n\n$gold: #F7AD45\n").unwrap();
+        fs::write(dir.path().join("gb.palette.md"), "---\nname: gb\nscheme: retro\n---\n\n$dark: #0F380F\n").unwrap();
+        fs::write(dir.path().join("pac.palette.md"), "---\nname: pac\nscheme: arcade\n---\n\n$wall: #2121DE\n").unwrap();
+
+        let out = dir.path().join("gpl");
+        let selected = run_scheme(dir.path(), "retro", Some(&out), &Printer::new()).unwrap();
+        assert_eq!(selected, vec!["gb", "mi"]);
+
+        assert!(!out.join("pac.gpl").exists());
+        let gpl = fs::read_to_string(out.join("mi.gpl")).unwrap();
+        assert_eq!(gpl, "GIMP Palette\nName: mi\nColumns: 0\n#\n247 173  69\tgold\n");
+
+        assert!(run_scheme(dir.path(), "modern", None, &Printer::new()).is_err());
+    }
+}
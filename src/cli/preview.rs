@@ -0,0 +1,156 @@
+//! Preview command implementation.
+//!
+//! Renders one frame of an animation group (the shapes of a multi-shape
+//! file) to a PNG, optionally with onion skinning.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::cli::build::{auto_detect_shader, resolve_shader, shader_renderer};
+use crate::discovery::{discover, load_assets, LoadOptions};
+use crate::error::{PxError, Result};
+use crate::output::{display_path, plural, Printer};
+use crate::parser::parse_shape_file;
+use crate::render::{onion_skin, shader_palette, write_png, GUIDE_COLOUR};
+use crate::types::{BuiltinBrushes, BuiltinStamps};
+
+/// Preview an animation frame, optionally onion-skinned
+#[derive(Args, Debug)]
+pub struct PreviewArgs {
+    /// Animation group: a shape file name (`walk` for walk.shape.md) or path
+    pub group: String,
+
+    /// Overlay neighbouring frames at decreasing opacity
+    #[arg(long)]
+    pub onion: bool,
+
+    /// Number of frames shown either side of the current frame
+    #[arg(long, default_value_t = 1)]
+    pub skins: usize,
+
+    /// Current frame (1-based)
+    #[arg(long, default_value_t = 1)]
+    pub frame: usize,
+
+    /// Shader to apply
+    #[arg(long)]
+    pub shader: Option<String>,
+
+    /// Scale factor for output (integer upscaling)
+    #[arg(long)]
+    pub scale: Option<u32>,
+
+    /// Output directory
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+pub fn run(args: PreviewArgs, printer: &Printer) -> Result<()> {
+    let discovery = discover(".")?;
+    let path = find_group(&args.group, &discovery.scan.shapes)?;
+
+    let source = fs::read_to_string(&path).map_err(|e| PxError::Io {
+        path: path.clone(),
+        message: format!("Failed to read file: {}", e),
+    })?;
+    let shapes = parse_shape_file(&source)?;
+
+    if args.frame == 0 || args.frame > shapes.len() {
+        return Err(PxError::Build {
+            message: format!(
+                "Frame {} is out of range: '{}' has {}",
+                args.frame,
+                args.group,
+                plural(shapes.len(), "frame", "frames")
+            ),
+            help: None,
+        });
+    }
+
+    // Render with the project's shader and palette, as `px build` would
+    let registry = load_assets(&discovery.scan, &LoadOptions::with_builtins())?.build()?;
+    let shader_name = args
+        .shader
+        .clone()
+        .or_else(|| discovery.manifest.shader.clone())
+        .or_else(|| auto_detect_shader(&registry));
+    let shader = resolve_shader(shader_name.as_deref(), &registry)?;
+    let palette = shader_palette(&shader, &registry);
+
+    let stamps = BuiltinStamps::all();
+    let brushes = BuiltinBrushes::all();
+    // Guides show up here so construction lines can be checked
+    let renderer = shader_renderer(&palette, &shader, &stamps, &brushes).with_guides(GUIDE_COLOUR);
+
+    let frames = shapes.iter().map(|s| renderer.render(s)).collect::<Result<Vec<_>>>()?;
+    let current = args.frame - 1;
+    let skins = if args.onion { args.skins } else { 0 };
+    let preview = onion_skin(&frames, current, skins);
+
+    let output = args.output.clone().unwrap_or_else(|| discovery.manifest.output.clone());
+    fs::create_dir_all(&output).map_err(|e| PxError::Io {
+        path: output.clone(),
+        message: format!("Failed to create output directory: {}", e),
+    })?;
+
+    let name = if args.onion {
+        preview.name.clone()
+    } else {
+        frames[current].name.clone()
+    };
+    let png_path = output.join(format!("{}.png", name));
+    let scale = args.scale.or(shapes[current].scale).unwrap_or(1);
+    write_png(&preview, &png_path, scale)?;
+
+    if args.onion {
+        printer.status(
+            "Compositing",
+            &format!(
+                "{} {}",
+                printer.bold(&frames[current].name),
+                printer.dim(&format!("(frame {} of {}, {} either side)", args.frame, frames.len(), skins)),
+            ),
+        );
+    }
+    printer.success("Finished", &format!("{}.png -> {}", name, display_path(&output)));
+
+    Ok(())
+}
+
+/// Find a group's shape file: an existing path, or a discovered file whose
+/// name (before the first `.`) matches.
+fn find_group(group: &str, shape_files: &[PathBuf]) -> Result<PathBuf> {
+    let path = Path::new(group);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    shape_files
+        .iter()
+        .find(|p| {
+            p.file_name()
+                .and_then(|f| f.to_str())
+                .and_then(|f| f.split('.').next())
+                == Some(group)
+        })
+        .cloned()
+        .ok_or_else(|| PxError::Build {
+            message: format!("Animation group not found: {}", group),
+            help: Some("Pass a shape file name (e.g. 'walk' for walk.shape.md) or a path".to_string()),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_group_by_stem() {
+        let files = vec![PathBuf::from("shapes/idle.shape.md"), PathBuf::from("shapes/walk.shape.md")];
+
+        assert_eq!(find_group("walk", &files).unwrap(), PathBuf::from("shapes/walk.shape.md"));
+        assert!(find_group("run", &files).is_err());
+    }
+}
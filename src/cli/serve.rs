@@ -0,0 +1,279 @@
+//! Local preview server for `px build --watch --serve`.
+//!
+//! Serves the output directory over HTTP with a generated index page. The
+//! page polls a rebuild counter and reloads itself after each successful
+//! rebuild, so no websocket support is needed.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::{PxError, Result};
+
+/// Default address for `--serve` without a value.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8000";
+
+/// Path the index page polls for the rebuild counter.
+const RELOAD_PATH: &str = "/__px/reload";
+
+/// A preview server running on a background thread.
+pub struct PreviewServer {
+    addr: SocketAddr,
+    generation: Arc<AtomicU64>,
+}
+
+impl PreviewServer {
+    /// Bind `addr` and serve files from `root` until the process exits.
+    pub fn start(addr: &str, root: PathBuf) -> Result<Self> {
+        let server = Server::http(addr).map_err(|e| PxError::Build {
+            message: format!("Failed to start preview server on {}: {}", addr, e),
+            help: Some("Pass another address, e.g. --serve 127.0.0.1:8080".to_string()),
+        })?;
+        let addr = server.server_addr().to_ip().ok_or_else(|| PxError::Build {
+            message: "Failed to start preview server: not listening on an IP address".to_string(),
+            help: None,
+        })?;
+
+        let generation = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&generation);
+        let root = Arc::new(root);
+        thread::spawn(move || {
+            // Each request gets its own thread, so a slow client (or the
+            // index page's reload poll) never holds up the rest
+            for request in server.incoming_requests() {
+                let root = Arc::clone(&root);
+                let generation = counter.load(Ordering::SeqCst);
+                thread::spawn(move || {
+                    // A misbehaving client only costs its own connection
+                    let _ = handle(request, &root, generation);
+                });
+            }
+        });
+
+        Ok(Self { addr, generation })
+    }
+
+    /// The address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Tell open index pages to reload.
+    pub fn notify_rebuild(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Answer a single request.
+fn handle(request: Request, root: &Path, generation: u64) -> std::io::Result<()> {
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        return request.respond(response(405, "text/plain", b"Method not allowed\n".to_vec()));
+    }
+
+    let target = request.url().split(['?', '#']).next().unwrap_or("/");
+    let Some(path) = percent_decode(target) else {
+        return request.respond(response(400, "text/plain", b"Bad request\n".to_vec()));
+    };
+
+    let reply = match path.as_str() {
+        "/" | "/index.html" => response(200, "text/html; charset=utf-8", index_page(root).into_bytes()),
+        RELOAD_PATH => response(200, "text/plain", generation.to_string().into_bytes()),
+        _ => match resolve(root, &path).and_then(|file| fs::read(&file).ok().map(|body| (file, body))) {
+            Some((file, body)) => response(200, content_type(&file), body),
+            None => response(404, "text/plain", b"Not found\n".to_vec()),
+        },
+    };
+    // tiny_http drops the body for HEAD requests
+    request.respond(reply)
+}
+
+/// An uncached response with `status` and `content_type`.
+fn response(status: u16, content_type: &str, body: Vec<u8>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = |name: &str, value: &str| Header::from_bytes(name, value).expect("static header is valid");
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", content_type))
+        .with_header(header("Cache-Control", "no-store"))
+}
+
+/// Decode `%XX` escapes in a request path. `None` for malformed escapes or
+/// paths that don't decode to UTF-8.
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Map a request path to a file under `root`, refusing anything that escapes it.
+fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let file = root.join(relative);
+    file.is_file().then_some(file)
+}
+
+/// Content type by file extension.
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "json" | "tmj" => "application/json",
+        "html" => "text/html; charset=utf-8",
+        "p8" | "rs" | "tres" | "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// An index of the output directory that reloads after each rebuild.
+fn index_page(root: &Path) -> String {
+    let mut names: Vec<String> = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_file())
+                .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    let mut items = String::new();
+    for name in &names {
+        let name = escape(name);
+        let preview = match Path::new(&name).extension().and_then(|e| e.to_str()) {
+            Some("png" | "gif" | "svg" | "webp") => format!("<img src=\"{}\" alt=\"\"> ", name),
+            _ => String::new(),
+        };
+        items.push_str(&format!("<li>{}<a href=\"{}\">{}</a></li>\n", preview, name, name));
+    }
+
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>px preview</title>
+<style>
+body {{ font-family: sans-serif; background: #222; color: #ddd; }}
+a {{ color: #8cf; }}
+img {{ image-rendering: pixelated; min-width: 32px; vertical-align: middle; }}
+li {{ margin: 4px 0; list-style: none; }}
+</style>
+</head>
+<body>
+<h1>px preview</h1>
+<ul>
+{items}</ul>
+<script>
+let generation = null;
+setInterval(async () => {{
+  try {{
+    const current = await (await fetch("{reload}")).text();
+    if (generation !== null && current !== generation) location.reload();
+    generation = current;
+  }} catch (e) {{}}
+}}, 1000);
+</script>
+</body>
+</html>
+"#,
+        items = items,
+        reload = RELOAD_PATH,
+    )
+}
+
+/// Escape text for HTML.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use tempfile::tempdir;
+
+    /// Send a GET request and return the full response.
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    #[test]
+    fn test_serves_built_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("hero.png"), b"\x89PNG").unwrap();
+        let server = PreviewServer::start("127.0.0.1:0", dir.path().to_path_buf()).unwrap();
+
+        let found = get(server.addr(), "/hero.png");
+        assert!(found.starts_with("HTTP/1.1 200 OK"), "{}", found);
+        assert!(found.contains("Content-Type: image/png"));
+
+        assert!(get(server.addr(), "/missing.png").starts_with("HTTP/1.1 404"));
+        assert!(get(server.addr(), "/../hero.png").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_decodes_escaped_paths() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("big hero.png"), b"\x89PNG").unwrap();
+        let server = PreviewServer::start("127.0.0.1:0", dir.path().to_path_buf()).unwrap();
+
+        assert!(get(server.addr(), "/big%20hero.png").starts_with("HTTP/1.1 200 OK"));
+        assert!(get(server.addr(), "/%2e%2e/big%20hero.png").starts_with("HTTP/1.1 404"));
+        assert!(get(server.addr(), "/big%2").starts_with("HTTP/1.1 400"));
+    }
+
+    #[test]
+    fn test_idle_connection_does_not_block_others() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("hero.png"), b"\x89PNG").unwrap();
+        let server = PreviewServer::start("127.0.0.1:0", dir.path().to_path_buf()).unwrap();
+
+        // Half a request, left hanging
+        let mut idle = TcpStream::connect(server.addr()).unwrap();
+        write!(idle, "GET /hero.png HTTP/1.1\r\n").unwrap();
+
+        assert!(get(server.addr(), "/hero.png").starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn test_index_lists_files_and_reload_counter_advances() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("hero.png"), b"\x89PNG").unwrap();
+        fs::write(dir.path().join("hero.json"), b"{}").unwrap();
+        let server = PreviewServer::start("127.0.0.1:0", dir.path().to_path_buf()).unwrap();
+
+        let index = get(server.addr(), "/");
+        assert!(index.contains(r#"<img src="hero.png""#));
+        assert!(index.contains(r#"<a href="hero.json">"#));
+
+        assert!(get(server.addr(), RELOAD_PATH).ends_with("\r\n\r\n0"));
+        server.notify_rebuild();
+        assert!(get(server.addr(), RELOAD_PATH).ends_with("\r\n\r\n1"));
+    }
+}
@@ -1,14 +1,32 @@
 //! Slice command implementation.
 //!
 //! Imports a PNG spritesheet and generates px definition files from it.
+//!
+//! `--stamps` deduplicates structurally: each cell is thresholded into
+//! edge, fill and transparent tokens, cut into stamp-sized blocks, and
+//! every distinct block becomes a `.stamp.md` that the generated shapes
+//! reference by glyph. Otherwise each cell is rebuilt as a shape with one
+//! legend glyph per colour, mapping pixels to the nearest colour of
+//! `--palette` or of a palette sampled from the sheet.
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
 
 use crate::error::{PxError, Result};
+use crate::cli::palette::{load_palette_file, reduce_counts};
 use crate::output::{display_path, plural, Printer};
-use crate::types::Colour;
+use crate::render::colour_distance;
+use crate::types::{Colour, Palette, PaletteBuilder, PixelToken, Stamp};
+
+/// Stamp block size when `--stamps` is given without `--stamp-size`.
+const DEFAULT_STAMP_SIZE: (u32, u32) = (8, 8);
+
+/// Glyphs handed to detected stamps and reconstructed colours in order,
+/// avoiding the builtin shape glyphs and `x`/`X`.
+const STAMP_GLYPHS: &str = "ABCDEFGHIJKLMNOPQRSTUVWYZabcdefghijklmnopqrstuvwyz0123456789";
 
 /// A single cell extracted from a spritesheet grid.
 pub struct SlicedCell {
@@ -41,7 +59,7 @@ pub struct SliceArgs {
     #[arg(long)]
     pub stamps: bool,
 
-    /// Stamp detection block size as WxH (e.g. 4x4)
+    /// Stamp detection block size as WxH (e.g. 4x4, default 8x8)
     #[arg(long)]
     pub stamp_size: Option<String>,
 
@@ -49,11 +67,249 @@ pub struct SliceArgs {
     #[arg(long)]
     pub separator: Option<String>,
 
+    /// Accept separator pixels within this RGBA distance of each other, for
+    /// JPEG-sourced or dithered sheets
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub separator_tolerance: u32,
+
     /// Path to existing .palette.md to use instead of generating one
     #[arg(long)]
     pub palette: Option<PathBuf>,
 }
 
+/// Stamps found by structural deduplication, and each cell as a grid of
+/// their glyphs.
+pub struct DetectedStamps {
+    pub stamps: Vec<Stamp>,
+    /// Block size, written as each shape's `cell:` so glyphs draw whole stamps.
+    pub cell: (u32, u32),
+    /// (shape name, glyph rows) per cell; all-transparent blocks are `x`.
+    pub shapes: Vec<(String, Vec<String>)>,
+}
+
+impl DetectedStamps {
+    /// The shapes as one `.shape.md` source.
+    pub fn shape_source(&self) -> String {
+        self.shapes
+            .iter()
+            .map(|(name, rows)| {
+                format!("---\nname: {}\ncell: {}x{}\n---\n\n```px\n{}\n```\n", name, self.cell.0, self.cell.1, rows.join("\n"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// `.stamp.md` source for a stamp.
+fn stamp_source(stamp: &Stamp) -> String {
+    let mut source = format!("---\nname: {}\n", stamp.name);
+    if let Some(glyph) = stamp.glyph {
+        source.push_str(&format!("glyph: {}\n", glyph));
+    }
+    source.push_str("---\n\n```px\n");
+    for row in stamp.pixels() {
+        source.extend(row.iter().map(|t| t.to_char()));
+        source.push('\n');
+    }
+    source.push_str("```\n");
+    source
+}
+
+/// Threshold a cell into stamp tokens: mostly-transparent pixels are
+/// transparent, and opaque ones darker than the cell's mean luminance are
+/// edge, the rest fill. A cell of one colour is all fill.
+fn cell_tokens(img: &image::RgbaImage) -> Vec<Vec<PixelToken>> {
+    let luminance = |p: &image::Rgba<u8>| (299 * p[0] as u32 + 587 * p[1] as u32 + 114 * p[2] as u32) / 1000;
+    let opaque: Vec<u32> = img.pixels().filter(|p| p[3] >= 128).map(luminance).collect();
+    let mean = opaque.iter().sum::<u32>() as f32 / opaque.len().max(1) as f32;
+
+    (0..img.height())
+        .map(|y| {
+            (0..img.width())
+                .map(|x| {
+                    let p = img.get_pixel(x, y);
+                    if p[3] < 128 {
+                        PixelToken::Transparent
+                    } else if (luminance(p) as f32) < mean {
+                        PixelToken::Edge
+                    } else {
+                        PixelToken::Fill
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Cut each cell into `block_w`x`block_h` blocks (smaller at the right and
+/// bottom edges) and give every distinct token pattern one stamp, named
+/// `{base_name}-stamp-{i}` in order of first appearance.
+fn detect_stamps(cells: &[SlicedCell], block_w: u32, block_h: u32, base_name: &str) -> Result<DetectedStamps> {
+    let (block_w, block_h) = (block_w as usize, block_h as usize);
+    let mut glyphs = STAMP_GLYPHS.chars();
+    let mut seen: HashMap<Vec<Vec<PixelToken>>, char> = HashMap::new();
+    let mut stamps = Vec::new();
+    let mut shapes = Vec::new();
+
+    for cell in cells {
+        let tokens = cell_tokens(&cell.image);
+        let (width, height) = (cell.image.width() as usize, cell.image.height() as usize);
+        let mut rows = Vec::new();
+        for by in (0..height).step_by(block_h) {
+            let mut row = String::new();
+            for bx in (0..width).step_by(block_w) {
+                let block: Vec<Vec<PixelToken>> =
+                    tokens[by..(by + block_h).min(height)].iter().map(|r| r[bx..(bx + block_w).min(width)].to_vec()).collect();
+                if block.iter().flatten().all(|&t| t == PixelToken::Transparent) {
+                    row.push('x');
+                    continue;
+                }
+                let glyph = match seen.get(&block) {
+                    Some(&glyph) => glyph,
+                    None => {
+                        let glyph = glyphs.next().ok_or_else(|| PxError::Build {
+                            message: format!("More than {} distinct stamps in {}", STAMP_GLYPHS.len(), base_name),
+                            help: Some("Use a larger --stamp-size, so there are fewer distinct blocks".to_string()),
+                        })?;
+                        stamps.push(Stamp::new(format!("{}-stamp-{}", base_name, stamps.len()), Some(glyph), block.clone()));
+                        seen.insert(block, glyph);
+                        glyph
+                    }
+                };
+                row.push(glyph);
+            }
+            rows.push(row);
+        }
+        shapes.push((cell.name.clone(), rows));
+    }
+
+    Ok(DetectedStamps { stamps, cell: (block_w as u32, block_h as u32), shapes })
+}
+
+/// Write each detected stamp to `{name}.stamp.md` and the shapes to
+/// `{base_name}.shape.md` in `dir`. Returns the paths written.
+fn write_stamps(detected: &DetectedStamps, dir: &Path, base_name: &str, printer: &Printer) -> Result<Vec<PathBuf>> {
+    let files = detected
+        .stamps
+        .iter()
+        .map(|stamp| (dir.join(format!("{}.stamp.md", stamp.name)), stamp_source(stamp)))
+        .chain(std::iter::once((dir.join(format!("{}.shape.md", base_name)), detected.shape_source())));
+    write_sources(dir, files, printer)
+}
+
+/// Palette of the colours in `cells`, most common first as `$colour-1`,
+/// `$colour-2`, ..., merged by median cut when there are more than there
+/// are glyphs to draw them with.
+fn sampled_palette(cells: &[SlicedCell], name: &str) -> Result<Palette> {
+    let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+    for pixel in cells.iter().flat_map(|cell| cell.image.pixels()) {
+        if pixel[3] != 0 {
+            *counts.entry(pixel.0).or_insert(0) += 1;
+        }
+    }
+    let glyph_count = STAMP_GLYPHS.chars().count();
+    if counts.len() > glyph_count {
+        counts = reduce_counts(&counts, glyph_count);
+    }
+
+    let mut colours: Vec<([u8; 4], usize)> = counts.into_iter().collect();
+    colours.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut builder = PaletteBuilder::new(name);
+    for (i, (rgba, _)) in colours.iter().enumerate() {
+        builder.define(format!("colour-{}", i + 1), Colour::new(rgba[0], rgba[1], rgba[2], rgba[3]).to_hex());
+    }
+    builder.build(None)
+}
+
+/// Rebuild each cell as a shape drawn in `palette`'s colours, as one
+/// `.shape.md` source.
+///
+/// Every pixel takes the nearest palette colour, and each colour used gets
+/// one glyph (in order of first use, the same in every shape) mapped
+/// through the builtin `solid` brush. Transparent pixels are `x`.
+fn reconstruct_shapes(cells: &[SlicedCell], palette: &Palette) -> Result<String> {
+    let mut names: Vec<&str> = palette.colour_names().collect();
+    names.sort();
+    let swatches: Vec<(&str, Colour)> = names.into_iter().filter_map(|n| palette.get(n).map(|c| (n, c))).collect();
+    if swatches.is_empty() {
+        return Err(PxError::Build {
+            message: format!("Palette '{}' has no colours to rebuild shapes with", palette.name),
+            help: None,
+        });
+    }
+
+    let mut glyphs = STAMP_GLYPHS.chars();
+    let mut assigned: HashMap<&str, char> = HashMap::new();
+    let mut documents = Vec::new();
+
+    for cell in cells {
+        let mut legend: Vec<(char, &str)> = Vec::new();
+        let mut rows = Vec::new();
+        for y in 0..cell.image.height() {
+            let mut row = String::new();
+            for x in 0..cell.image.width() {
+                let p = cell.image.get_pixel(x, y);
+                if p[3] == 0 {
+                    row.push('x');
+                    continue;
+                }
+                let colour = Colour::new(p[0], p[1], p[2], p[3]);
+                let (name, _) = swatches
+                    .iter()
+                    .min_by_key(|(_, c)| (colour_distance(&colour, c), colour.a.abs_diff(c.a)))
+                    .unwrap();
+                let glyph = match assigned.get(name) {
+                    Some(&glyph) => glyph,
+                    None => {
+                        let glyph = glyphs.next().ok_or_else(|| PxError::Build {
+                            message: format!("More than {} colours used from palette '{}'", STAMP_GLYPHS.len(), palette.name),
+                            help: Some("Use a palette with fewer colours".to_string()),
+                        })?;
+                        assigned.insert(name, glyph);
+                        glyph
+                    }
+                };
+                if !legend.iter().any(|&(g, _)| g == glyph) {
+                    legend.push((glyph, name));
+                }
+                row.push(glyph);
+            }
+            rows.push(row);
+        }
+
+        legend.sort();
+        let legend: Vec<String> = legend.iter().map(|(glyph, name)| format!("{}: {{ stamp: solid, A: ${} }}", glyph, name)).collect();
+        let mut document = format!("---\nname: {}\n---\n\n```px\n{}\n```\n", cell.name, rows.join("\n"));
+        if !legend.is_empty() {
+            document.push_str(&format!("\n---\n{}\n", legend.join("\n")));
+        }
+        documents.push(document);
+    }
+
+    Ok(documents.join("\n"))
+}
+
+/// Write `files` (path, source) into `dir`, creating it first. Returns the
+/// paths written.
+fn write_sources(dir: &Path, files: impl IntoIterator<Item = (PathBuf, String)>, printer: &Printer) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).map_err(|e| PxError::Io {
+        path: dir.to_path_buf(),
+        message: format!("Failed to create output directory: {}", e),
+    })?;
+
+    let mut written = Vec::new();
+    for (path, source) in files {
+        fs::write(&path, source).map_err(|e| PxError::Io {
+            path: path.clone(),
+            message: format!("Failed to write file: {}", e),
+        })?;
+        printer.verbose("Wrote", &display_path(&path));
+        written.push(path);
+    }
+    Ok(written)
+}
+
 /// Parse a "WxH" dimension string into (width, height).
 fn parse_dimensions(s: &str) -> Result<(u32, u32)> {
     let parts: Vec<&str> = s.splitn(2, |c| c == 'x' || c == 'X').collect();
@@ -89,22 +345,30 @@ fn is_fully_transparent(img: &image::RgbaImage) -> bool {
     img.pixels().all(|p| p[3] == 0)
 }
 
-/// Returns `Some(rgba)` if every pixel in row `y` shares the same RGBA value.
-fn uniform_row_colour(img: &image::RgbaImage, y: u32) -> Option<[u8; 4]> {
+/// Whether two RGBA values are within Euclidean distance `tolerance`.
+fn within_tolerance(a: [u8; 4], b: [u8; 4], tolerance: u32) -> bool {
+    let distance_sq: u32 = a.iter().zip(b).map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32).sum();
+    distance_sq <= tolerance * tolerance
+}
+
+/// Returns the first pixel's `Some(rgba)` if every pixel in row `y` is
+/// within `tolerance` of it (0: the same RGBA value).
+fn uniform_row_colour(img: &image::RgbaImage, y: u32, tolerance: u32) -> Option<[u8; 4]> {
     let first = img.get_pixel(0, y).0;
     for x in 1..img.width() {
-        if img.get_pixel(x, y).0 != first {
+        if !within_tolerance(img.get_pixel(x, y).0, first, tolerance) {
             return None;
         }
     }
     Some(first)
 }
 
-/// Returns `Some(rgba)` if every pixel in column `x` shares the same RGBA value.
-fn uniform_col_colour(img: &image::RgbaImage, x: u32) -> Option<[u8; 4]> {
+/// Returns the first pixel's `Some(rgba)` if every pixel in column `x` is
+/// within `tolerance` of it (0: the same RGBA value).
+fn uniform_col_colour(img: &image::RgbaImage, x: u32, tolerance: u32) -> Option<[u8; 4]> {
     let first = img.get_pixel(x, 0).0;
     for y in 1..img.height() {
-        if img.get_pixel(x, y).0 != first {
+        if !within_tolerance(img.get_pixel(x, y).0, first, tolerance) {
             return None;
         }
     }
@@ -186,9 +450,14 @@ struct DetectedGrid {
 }
 
 /// Attempt to auto-detect a grid by scanning for uniform separator rows/columns.
+///
+/// With a `tolerance`, rows and columns count as uniform when their pixels
+/// are within that distance of the first, and as separators when that
+/// colour is within it of the separator colour.
 fn detect_grid(
     img: &image::RgbaImage,
     separator_hex: Option<&str>,
+    tolerance: u32,
     printer: &Printer,
 ) -> Result<Option<DetectedGrid>> {
     // Parse explicit separator colour if provided
@@ -203,7 +472,7 @@ fn detect_grid(
     // Scan all rows for uniform colour
     let mut uniform_rows: Vec<(u32, [u8; 4])> = Vec::new();
     for y in 0..img.height() {
-        if let Some(rgba) = uniform_row_colour(img, y) {
+        if let Some(rgba) = uniform_row_colour(img, y, tolerance) {
             uniform_rows.push((y, rgba));
         }
     }
@@ -211,7 +480,7 @@ fn detect_grid(
     // Scan all columns for uniform colour
     let mut uniform_cols: Vec<(u32, [u8; 4])> = Vec::new();
     for x in 0..img.width() {
-        if let Some(rgba) = uniform_col_colour(img, x) {
+        if let Some(rgba) = uniform_col_colour(img, x, tolerance) {
             uniform_cols.push((x, rgba));
         }
     }
@@ -238,13 +507,13 @@ fn detect_grid(
     // Filter to only rows/columns matching separator colour
     let sep_row_indices: Vec<u32> = uniform_rows
         .iter()
-        .filter(|&&(_, rgba)| rgba == sep_colour)
+        .filter(|&&(_, rgba)| within_tolerance(rgba, sep_colour, tolerance))
         .map(|&(y, _)| y)
         .collect();
 
     let sep_col_indices: Vec<u32> = uniform_cols
         .iter()
-        .filter(|&&(_, rgba)| rgba == sep_colour)
+        .filter(|&&(_, rgba)| within_tolerance(rgba, sep_colour, tolerance))
         .map(|&(x, _)| x)
         .collect();
 
@@ -278,10 +547,7 @@ fn detect_grid(
 
     printer.verbose(
         "Separator",
-        &format!(
-            "#{:02X}{:02X}{:02X}{:02X}",
-            sep_colour[0], sep_colour[1], sep_colour[2], sep_colour[3]
-        ),
+        &Colour::new(sep_colour[0], sep_colour[1], sep_colour[2], sep_colour[3]).to_hex(),
     );
 
     Ok(Some(DetectedGrid {
@@ -477,13 +743,17 @@ pub fn run(args: SliceArgs, printer: &Printer) -> Result<Vec<SlicedCell>> {
     }
 
     // Parse --stamp-size if provided
-    if let Some(ref stamp_str) = args.stamp_size {
-        let (sw, sh) = parse_dimensions(stamp_str)?;
-        printer.verbose("Stamp size", &format!("{}x{}", sw, sh));
-    }
+    let stamp_size = match args.stamp_size {
+        Some(ref stamp_str) => {
+            let (sw, sh) = parse_dimensions(stamp_str)?;
+            printer.verbose("Stamp size", &format!("{}x{}", sw, sh));
+            (sw, sh)
+        }
+        None => DEFAULT_STAMP_SIZE,
+    };
 
     // Resolve output directory (default: current directory)
-    let _output = args.output.clone().unwrap_or_else(|| PathBuf::from("."));
+    let output = args.output.clone().unwrap_or_else(|| PathBuf::from("."));
 
     // Resolve asset name (default: input file stem)
     let base_name = args.name.clone().unwrap_or_else(|| {
@@ -500,13 +770,13 @@ pub fn run(args: SliceArgs, printer: &Printer) -> Result<Vec<SlicedCell>> {
     );
 
     // Slice the image
-    let _cells = if let Some(ref cell_str) = args.cell {
+    let cells = if let Some(ref cell_str) = args.cell {
         let (cw, ch) = parse_dimensions(cell_str)?;
         printer.verbose("Cell size", &format!("{}x{}", cw, ch));
         slice_grid(&img, cw, ch, &base_name, printer)
     } else {
         // No --cell: attempt auto-detection
-        if let Some(grid) = detect_grid(&img, args.separator.as_deref(), printer)? {
+        if let Some(grid) = detect_grid(&img, args.separator.as_deref(), args.separator_tolerance, printer)? {
             printer.info(
                 "Detected",
                 &format!(
@@ -520,7 +790,7 @@ pub fn run(args: SliceArgs, printer: &Printer) -> Result<Vec<SlicedCell>> {
             // Fallback: no grid found
             printer.info("Finished", &plural(1, "cell", "cells"));
             vec![SlicedCell {
-                name: base_name,
+                name: base_name.clone(),
                 image: img,
                 row: 0,
                 col: 0,
@@ -528,7 +798,42 @@ pub fn run(args: SliceArgs, printer: &Printer) -> Result<Vec<SlicedCell>> {
         }
     };
 
-    Ok(_cells)
+    if args.stamps {
+        let detected = detect_stamps(&cells, stamp_size.0, stamp_size.1, &base_name)?;
+        write_stamps(&detected, &output, &base_name, printer)?;
+        printer.success(
+            "Finished",
+            &format!(
+                "{} for {} -> {}",
+                plural(detected.stamps.len(), "stamp", "stamps"),
+                plural(detected.shapes.len(), "shape", "shapes"),
+                display_path(&output)
+            ),
+        );
+    } else {
+        let (palette, generated) = match &args.palette {
+            Some(path) => (load_palette_file(path)?, false),
+            None => (sampled_palette(&cells, &base_name)?, true),
+        };
+        let mut files = vec![(output.join(format!("{}.shape.md", base_name)), reconstruct_shapes(&cells, &palette)?)];
+        if generated {
+            // A shader naming the palette, so `px build --shader {name}` draws with it
+            files.push((output.join(format!("{}.palette.md", base_name)), palette.to_source()));
+            files.push((output.join(format!("{}.shader.md", base_name)), format!("---\nname: {0}\npalette: {0}\n---\n", base_name)));
+        }
+        write_sources(&output, files, printer)?;
+        printer.success(
+            "Finished",
+            &format!(
+                "{} in {} -> {}",
+                plural(cells.len(), "shape", "shapes"),
+                plural(palette.len(), "colour", "colours"),
+                display_path(&output)
+            ),
+        );
+    }
+
+    Ok(cells)
 }
 
 #[cfg(test)]
@@ -673,20 +978,20 @@ mod tests {
     #[test]
     fn test_uniform_row_colour_all_same() {
         let img = image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255]));
-        assert_eq!(uniform_row_colour(&img, 0), Some([255, 0, 0, 255]));
+        assert_eq!(uniform_row_colour(&img, 0, 0), Some([255, 0, 0, 255]));
     }
 
     #[test]
     fn test_uniform_row_colour_mixed() {
         let mut img = image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255]));
         img.put_pixel(2, 0, image::Rgba([0, 255, 0, 255]));
-        assert_eq!(uniform_row_colour(&img, 0), None);
+        assert_eq!(uniform_row_colour(&img, 0, 0), None);
     }
 
     #[test]
     fn test_uniform_col_colour_all_same() {
         let img = image::RgbaImage::from_pixel(2, 4, image::Rgba([0, 0, 255, 255]));
-        assert_eq!(uniform_col_colour(&img, 0), Some([0, 0, 255, 255]));
+        assert_eq!(uniform_col_colour(&img, 0, 0), Some([0, 0, 255, 255]));
     }
 
     // -- collapse_separators --
@@ -784,7 +1089,7 @@ mod tests {
     fn test_detect_grid_transparent_separators() {
         let img = make_grid_image_transparent_seps();
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
 
         assert_eq!(grid.col_ranges.len(), 2);
         assert_eq!(grid.row_ranges.len(), 2);
@@ -810,7 +1115,7 @@ mod tests {
         for y in 5..9 { for x in 5..9 { img.put_pixel(x, y, red); } }
 
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
 
         assert_eq!(grid.col_ranges.len(), 2);
         assert_eq!(grid.row_ranges.len(), 2);
@@ -831,7 +1136,7 @@ mod tests {
         for y in 5..9 { for x in 5..9 { img.put_pixel(x, y, red); } }
 
         let p = test_printer();
-        let grid = detect_grid(&img, Some("#FF00FF"), &p).unwrap().unwrap();
+        let grid = detect_grid(&img, Some("#FF00FF"), 0, &p).unwrap().unwrap();
 
         assert_eq!(grid.col_ranges.len(), 2);
         assert_eq!(grid.row_ranges.len(), 2);
@@ -844,7 +1149,7 @@ mod tests {
         // Uniform opaque image → no grid detected
         let img = image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255]));
         let p = test_printer();
-        let result = detect_grid(&img, None, &p).unwrap();
+        let result = detect_grid(&img, None, 0, &p).unwrap();
         assert!(result.is_none());
     }
 
@@ -861,7 +1166,7 @@ mod tests {
         for y in 6..10 { for x in 6..10 { img.put_pixel(x, y, red); } }
 
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
 
         assert_eq!(grid.col_ranges.len(), 2);
         assert_eq!(grid.row_ranges.len(), 2);
@@ -892,7 +1197,7 @@ mod tests {
         }
 
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
 
         assert_eq!(grid.row_ranges.len(), 2);
         assert_eq!(grid.row_ranges[0], (0, 4));
@@ -902,13 +1207,47 @@ mod tests {
         assert_eq!(grid.col_ranges[0], (0, 8));
     }
 
+    #[test]
+    fn test_detect_grid_separator_tolerance() {
+        // 2x2 grid of 4x4 red cells split by grey lines that wander ±3 in
+        // one channel per pixel, as a lossy re-save would leave them
+        let red = image::Rgba([255, 0, 0, 255]);
+        let mut img = image::RgbaImage::new(9, 9);
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            *pixel = if x == 4 || y == 4 {
+                let mut grey = [128, 128, 128, 255];
+                let channel = ((x + y) % 3) as usize;
+                grey[channel] = if (x * 7 + y) % 2 == 0 { 131 } else { 125 };
+                image::Rgba(grey)
+            } else {
+                red
+            };
+        }
+
+        let p = test_printer();
+        assert!(detect_grid(&img, None, 0, &p).unwrap().is_none());
+
+        let grid = detect_grid(&img, None, 8, &p).unwrap().unwrap();
+        assert_eq!(grid.col_ranges, vec![(0, 4), (5, 4)]);
+        assert_eq!(grid.row_ranges, vec![(0, 4), (5, 4)]);
+    }
+
+    #[test]
+    fn test_within_tolerance() {
+        assert!(within_tolerance([10, 10, 10, 255], [10, 10, 10, 255], 0));
+        assert!(!within_tolerance([10, 10, 10, 255], [11, 10, 10, 255], 0));
+        // sqrt(3^2 + 4^2) = 5
+        assert!(within_tolerance([10, 10, 10, 255], [13, 14, 10, 255], 5));
+        assert!(!within_tolerance([10, 10, 10, 255], [13, 14, 10, 255], 4));
+    }
+
     // -- slice_detected_grid --
 
     #[test]
     fn test_slice_detected_grid_basic() {
         let img = make_grid_image_transparent_seps();
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
         let cells = slice_detected_grid(&img, &grid, "test", &p);
 
         assert_eq!(cells.len(), 4);
@@ -930,7 +1269,7 @@ mod tests {
         }
 
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
         let cells = slice_detected_grid(&img, &grid, "test", &p);
 
         assert_eq!(cells.len(), 3);
@@ -940,7 +1279,7 @@ mod tests {
     fn test_slice_detected_grid_names() {
         let img = make_grid_image_transparent_seps();
         let p = test_printer();
-        let grid = detect_grid(&img, None, &p).unwrap().unwrap();
+        let grid = detect_grid(&img, None, 0, &p).unwrap().unwrap();
         let cells = slice_detected_grid(&img, &grid, "sheet", &p);
 
         assert_eq!(cells[0].name, "sheet-0-0");
@@ -948,4 +1287,108 @@ mod tests {
         assert_eq!(cells[2].name, "sheet-1-0");
         assert_eq!(cells[3].name, "sheet-1-1");
     }
+
+    // -- detect_stamps --
+
+    #[test]
+    fn test_detect_stamps_shares_repeated_block() {
+        // Two 8x4 cells: the same 4x4 ring on the left, different right halves
+        let black = image::Rgba([0, 0, 0, 255]);
+        let white = image::Rgba([255, 255, 255, 255]);
+        let ring = |img: &mut image::RgbaImage| {
+            for y in 0..4 {
+                for x in 0..4 {
+                    let edge = x == 0 || y == 0 || x == 3 || y == 3;
+                    img.put_pixel(x, y, if edge { black } else { white });
+                }
+            }
+        };
+        let mut first = image::RgbaImage::from_pixel(8, 4, white);
+        ring(&mut first);
+        let mut second = image::RgbaImage::from_pixel(8, 4, image::Rgba([0, 0, 0, 0]));
+        ring(&mut second);
+        let cells = vec![
+            SlicedCell { name: "tiles-0-0".to_string(), image: first, row: 0, col: 0 },
+            SlicedCell { name: "tiles-0-1".to_string(), image: second, row: 0, col: 1 },
+        ];
+
+        let detected = detect_stamps(&cells, 4, 4, "tiles").unwrap();
+
+        // The ring once, plus the first cell's solid half
+        assert_eq!(detected.stamps.len(), 2);
+        assert_eq!(detected.shapes[0], ("tiles-0-0".to_string(), vec!["AB".to_string()]));
+        assert_eq!(detected.shapes[1], ("tiles-0-1".to_string(), vec!["Ax".to_string()]));
+
+        let ring_stamp = &detected.stamps[0];
+        assert_eq!((ring_stamp.name.as_str(), ring_stamp.glyph), ("tiles-stamp-0", Some('A')));
+        assert_eq!(ring_stamp.get(0, 0), Some(PixelToken::Edge));
+        assert_eq!(ring_stamp.get(1, 1), Some(PixelToken::Fill));
+    }
+
+    #[test]
+    fn test_write_stamps_round_trips() {
+        let mut img = image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 255, 255, 255]));
+        img.put_pixel(0, 0, image::Rgba([0, 0, 0, 255]));
+        let cells = vec![SlicedCell { name: "gem".to_string(), image: img, row: 0, col: 0 }];
+        let detected = detect_stamps(&cells, 2, 2, "gem").unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_stamps(&detected, dir.path(), "gem", &test_printer()).unwrap();
+        assert_eq!(written.len(), 3);
+
+        let stamps = crate::parser::parse_stamp_file(&fs::read_to_string(dir.path().join("gem-stamp-0.stamp.md")).unwrap()).unwrap();
+        assert_eq!(stamps, vec![detected.stamps[0].clone()]);
+        let shapes = crate::parser::parse_shape_file(&fs::read_to_string(dir.path().join("gem.shape.md")).unwrap()).unwrap();
+        assert_eq!(shapes[0].name, "gem");
+        assert_eq!(shapes[0].grid(), &[vec!['A', 'B']]);
+        assert_eq!(shapes[0].cell, Some(crate::types::CellSize::Pixels(2, 2)));
+    }
+
+    // -- reconstruct_shapes --
+
+    #[test]
+    fn test_reconstruct_shapes_round_trips() {
+        use crate::render::ShapeRenderer;
+        use crate::types::BuiltinBrushes;
+
+        let red = image::Rgba([255, 0, 0, 255]);
+        let blue = image::Rgba([0, 0, 255, 255]);
+        let mut img = image::RgbaImage::from_pixel(2, 2, red);
+        img.put_pixel(1, 0, blue);
+        img.put_pixel(0, 1, blue);
+        let cells = vec![SlicedCell { name: "flag".to_string(), image: img.clone(), row: 0, col: 0 }];
+
+        let palette = sampled_palette(&cells, "flag").unwrap();
+        assert_eq!(palette.len(), 2);
+        let source = reconstruct_shapes(&cells, &palette).unwrap();
+        let shapes = crate::parser::parse_shape_file(&source).unwrap();
+        assert_eq!(shapes[0].grid(), &[vec!['A', 'B'], vec!['B', 'A']]);
+
+        // The palette survives being written out and read back too
+        let palette = crate::parser::parse_palette(&palette.to_source()).unwrap().remove(0).build(None).unwrap();
+        let brushes = BuiltinBrushes::all();
+        let mut renderer = ShapeRenderer::new(&palette);
+        renderer.add_brushes(brushes.iter());
+        let rendered = renderer.render(&shapes[0]).unwrap();
+        for (x, y, pixel) in img.enumerate_pixels() {
+            assert_eq!(rendered.get(x as usize, y as usize), Some(Colour::new(pixel[0], pixel[1], pixel[2], pixel[3])));
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_shapes_snaps_to_given_palette() {
+        let mut img = image::RgbaImage::from_pixel(2, 1, image::Rgba([250, 10, 10, 255]));
+        img.put_pixel(1, 0, image::Rgba([0, 0, 0, 0]));
+        let cells = vec![SlicedCell { name: "dot".to_string(), image: img, row: 0, col: 0 }];
+        let palette = crate::parser::parse_palette("---\nname: rgb\n---\n\n$red: #FF0000\n$blue: #0000FF\n")
+            .unwrap()
+            .remove(0)
+            .build(None)
+            .unwrap();
+
+        let source = reconstruct_shapes(&cells, &palette).unwrap();
+        assert!(source.contains("```px\nAx\n```"));
+        assert!(source.contains("A: { stamp: solid, A: $red }"));
+        assert!(!source.contains("$blue"));
+    }
 }
@@ -0,0 +1,189 @@
+//! Stats command implementation.
+//!
+//! Summarizes a project before a build: asset counts by kind, grid sizes,
+//! palette colours, and how deep the dependency graph goes. Builtin stamps,
+//! brushes and palettes are not counted.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::discovery::{discover, discover_paths, load_assets, LoadOptions};
+use crate::error::{PxError, Result};
+use crate::output::Printer;
+use crate::registry::{AssetId, AssetKind, AssetRegistry};
+
+/// Summarize a project's assets
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Files or directories to scan (default: current directory)
+    pub files: Vec<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_parser = ["text", "json"], default_value = "text")]
+    pub format: String,
+}
+
+/// A project summary.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ProjectStats {
+    /// Asset counts by kind.
+    pub assets: BTreeMap<AssetKind, usize>,
+    /// Grid cells across shapes, prefabs and maps.
+    pub cells: usize,
+    /// Distinct colours across all palettes.
+    pub colours: usize,
+    /// The shape with the most cells, if any.
+    pub largest_shape: Option<LargestShape>,
+    /// Assets in the longest dependency chain.
+    pub deepest_chain: usize,
+}
+
+/// Name and size of the largest shape.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct LargestShape {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+}
+
+pub fn run(args: StatsArgs, printer: &Printer) -> Result<()> {
+    let discovery = if args.files.is_empty() {
+        discover(".")?
+    } else {
+        discover_paths(&args.files)?
+    };
+
+    let registry = load_assets(&discovery.scan, &LoadOptions::default())?.build()?;
+    let stats = stats(&registry);
+
+    if args.format == "json" {
+        let json = serde_json::to_string_pretty(&stats).map_err(|e| PxError::Build {
+            message: format!("Failed to serialize stats: {}", e),
+            help: None,
+        })?;
+        println!("{}", json);
+    } else {
+        print_stats(&stats, printer);
+    }
+
+    Ok(())
+}
+
+fn print_stats(stats: &ProjectStats, printer: &Printer) {
+    for (kind, count) in &stats.assets {
+        printer.info(label(*kind), &count.to_string());
+    }
+    printer.info("Cells", &stats.cells.to_string());
+    printer.info("Colours", &stats.colours.to_string());
+    if let Some(shape) = &stats.largest_shape {
+        printer.info("Largest", &format!("{} {}", shape.name, printer.dim(&format!("({}x{})", shape.width, shape.height))));
+    }
+    printer.info("Depth", &stats.deepest_chain.to_string());
+}
+
+/// Plural label for a kind, as `px list` prints them.
+fn label(kind: AssetKind) -> &'static str {
+    match kind {
+        AssetKind::Palette => "Palettes",
+        AssetKind::Stamp => "Stamps",
+        AssetKind::Brush => "Brushes",
+        AssetKind::Shader => "Shaders",
+        AssetKind::Shape => "Shapes",
+        AssetKind::Prefab => "Prefabs",
+        AssetKind::Map => "Maps",
+        AssetKind::Target => "Targets",
+    }
+}
+
+/// Summarize `registry`.
+pub fn stats(registry: &AssetRegistry) -> ProjectStats {
+    let mut assets = BTreeMap::new();
+    for id in registry.graph().assets() {
+        *assets.entry(id.kind).or_insert(0) += 1;
+    }
+
+    let cells = registry.shapes().map(|s| s.width() * s.height()).sum::<usize>()
+        + registry.prefabs().map(|p| p.width() * p.height()).sum::<usize>()
+        + registry.maps().map(|m| m.width() * m.height()).sum::<usize>();
+
+    let colours: HashSet<_> = registry
+        .palettes()
+        .flat_map(|p| p.colour_names().filter_map(|name| p.get(name)))
+        .collect();
+
+    // First by name among equally large shapes
+    let mut largest: Option<LargestShape> = None;
+    for shape in registry.shapes() {
+        let (width, height) = shape.size();
+        if largest.as_ref().is_none_or(|l| width * height > l.width * l.height) {
+            largest = Some(LargestShape { name: shape.name.clone(), width, height });
+        }
+    }
+
+    ProjectStats {
+        assets,
+        cells,
+        colours: colours.len(),
+        largest_shape: largest,
+        deepest_chain: deepest_chain(registry),
+    }
+}
+
+/// Assets in the longest dependency chain (0 for an empty registry).
+fn deepest_chain(registry: &AssetRegistry) -> usize {
+    let graph = registry.graph();
+    let mut depth: HashMap<&AssetId, usize> = HashMap::new();
+
+    // Build order puts dependencies first
+    for id in registry.build_order() {
+        let below = graph.dependencies_of(id).filter_map(|dep| depth.get(dep)).max().copied().unwrap_or(0);
+        depth.insert(id, below + 1);
+    }
+
+    depth.values().max().copied().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_stats_counts_fixture_project() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("ui.palette.md"), "---\nname: ui\n---\n\n$edge: #000000\n$fill: #FFFFFF\n$dark: #000000\n").unwrap();
+        fs::write(dir.path().join("ui.shader.md"), "---\nname: ui\npalette: ui\n---\n").unwrap();
+        fs::write(
+            dir.path().join("tiles.shape.md"),
+            "---\nname: wall\n---\n\n```px\n###\n###\n```\n\n---\nname: floor\n---\n\n```px\n..\n..\n```\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("room.prefab.md"), "---\nname: room\n---\n\n```px\nWF\n```\n\n---\nW: wall\nF: floor\n").unwrap();
+        fs::write(dir.path().join("level.map.md"), "---\nname: level\n---\n\n```px\nRR\n```\n\n---\nR: room\n").unwrap();
+
+        let discovery = discover_paths(&[dir.path().to_path_buf()]).unwrap();
+        let registry = load_assets(&discovery.scan, &LoadOptions::default()).unwrap().build().unwrap();
+        let stats = stats(&registry);
+
+        let counts: Vec<(AssetKind, usize)> = stats.assets.iter().map(|(k, n)| (*k, *n)).collect();
+        assert_eq!(
+            counts,
+            vec![(AssetKind::Palette, 1), (AssetKind::Shader, 1), (AssetKind::Shape, 2), (AssetKind::Prefab, 1), (AssetKind::Map, 1)]
+        );
+        // 6 + 4 shape cells, 2 prefab cells, 2 map cells
+        assert_eq!(stats.cells, 14);
+        // $dark repeats $edge
+        assert_eq!(stats.colours, 2);
+        assert_eq!(stats.largest_shape, Some(LargestShape { name: "wall".to_string(), width: 3, height: 2 }));
+        // shape -> prefab -> map
+        assert_eq!(stats.deepest_chain, 3);
+
+        let json: serde_json::Value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["assets"]["shape"], 2);
+        assert_eq!(json["largest_shape"]["name"], "wall");
+    }
+}
@@ -1,12 +1,12 @@
 use std::path::PathBuf;
-use std::process;
 
 use clap::Args;
 
-use crate::discovery::{discover_paths, LoadOptions};
+use crate::cli::build::resolve_target_name;
+use crate::discovery::{discover_paths, LoadOptions, Manifest, MANIFEST_FILENAME};
 use crate::error::Result;
 use crate::output::{plural, Printer};
-use crate::validation::{print_diagnostics, validate_registry};
+use crate::validation::{diagnostics_json, print_diagnostics, validate_for_target, Lints, ValidationResult};
 
 /// Validate definition files without rendering
 #[derive(Args, Debug)]
@@ -14,9 +14,53 @@ pub struct ValidateArgs {
     /// Files or directories to validate
     #[arg(required = true)]
     pub files: Vec<PathBuf>,
+
+    /// Also check the limits of a target (builtin name or .target.md path)
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Report diagnostics as human-readable text or a JSON array on stdout
+    #[arg(long, value_parser = ["text", "json"], default_value = "text")]
+    pub format: String,
+
+    /// Fail on warnings as well as errors (exit code 2 when only warnings fail)
+    #[arg(long, alias = "deny-warnings")]
+    pub strict: bool,
+}
+
+/// Exit code when validation finds errors.
+pub const EXIT_ERRORS: i32 = 1;
+
+/// Exit code when `--strict` fails on warnings alone.
+pub const EXIT_WARNINGS: i32 = 2;
+
+/// Validate and report, returning the process exit code: 0 when clean,
+/// [`EXIT_ERRORS`] on errors, [`EXIT_WARNINGS`] when `--strict` fails on
+/// warnings alone.
+pub fn run(args: ValidateArgs, printer: &Printer) -> Result<i32> {
+    let result = check(&args, printer)?;
+    if args.format == "json" {
+        println!("{}", diagnostics_json(&result)?);
+    } else {
+        print_diagnostics(&result, printer);
+    }
+
+    Ok(exit_code(&result))
+}
+
+/// The exit code `px validate` reports for `result`.
+pub fn exit_code(result: &ValidationResult) -> i32 {
+    if result.has_errors() {
+        EXIT_ERRORS
+    } else if result.fails() {
+        EXIT_WARNINGS
+    } else {
+        0
+    }
 }
 
-pub fn run(args: ValidateArgs, printer: &Printer) -> Result<()> {
+/// Load the assets named by `args` and run the validation checks.
+fn check(args: &ValidateArgs, printer: &Printer) -> Result<ValidationResult> {
 
     // Discover and load assets
     let discovery = discover_paths(&args.files)?;
@@ -27,12 +71,125 @@ pub fn run(args: ValidateArgs, printer: &Printer) -> Result<()> {
     let registry = builder.build()?;
 
     // Run validation checks
-    let result = validate_registry(&registry);
-    print_diagnostics(&result, &printer);
+    let target = args.target.as_deref().map(resolve_target_name).transpose()?;
+    let lints = project_lints(&args.files)?;
+    Ok(validate_for_target(&registry, target.as_ref(), &lints).with_strict(args.strict))
+}
 
-    if result.has_errors() {
-        process::exit(1);
+/// Lint overrides from the `px.yaml` in the first directory given, or
+/// else the current directory.
+fn project_lints(files: &[PathBuf]) -> Result<Lints> {
+    let manifest = files
+        .iter()
+        .filter(|path| path.is_dir())
+        .chain([PathBuf::from(".")].iter())
+        .map(|dir| dir.join(MANIFEST_FILENAME))
+        .find(|path| path.is_file());
+    match manifest {
+        Some(path) => Ok(Manifest::load(&path)?.lints),
+        None => Ok(Lints::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_manifest_lints_apply() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("wall.shape.md"),
+            "---\nname: wall\n---\n\n```px\nBX\n```\n\n---\nB: nosuch\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join(MANIFEST_FILENAME), "lints:\n  missing-stamp: off\n  unmapped-glyph: off\n").unwrap();
+
+        let args = ValidateArgs {
+            files: vec![dir.path().to_path_buf()],
+            target: None,
+            format: "text".to_string(),
+            strict: false,
+        };
+        let result = check(&args, &Printer::new()).unwrap();
+        assert!(!result.iter().any(|d| d.code == "px::validate::missing-stamp"));
+        assert!(!result.iter().any(|d| d.code == "px::validate::unmapped-glyph"));
+    }
+
+    #[test]
+    fn test_json_reports_failing_codes() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("wall.shape.md"),
+            "---\nname: wall\n---\n\n```px\nBX\n```\n\n---\nB: nosuch\n",
+        )
+        .unwrap();
+
+        let args = ValidateArgs {
+            files: vec![dir.path().to_path_buf()],
+            target: None,
+            format: "json".to_string(),
+            strict: false,
+        };
+        let result = check(&args, &Printer::new()).unwrap();
+        let json: Vec<serde_json::Value> = serde_json::from_str(&diagnostics_json(&result).unwrap()).unwrap();
+
+        let codes: Vec<&str> = json.iter().map(|d| d["code"].as_str().unwrap()).collect();
+        assert!(codes.contains(&"px::validate::missing-stamp"), "{:?}", codes);
+        assert!(codes.contains(&"px::validate::unmapped-glyph"), "{:?}", codes);
+        assert!(json.iter().any(|d| d["severity"] == "error"));
+        assert!(result.has_errors());
     }
 
-    Ok(())
+    #[test]
+    fn test_strict_fails_on_warnings() {
+        let dir = tempdir().unwrap();
+        // The unused legend entry is only a warning
+        fs::write(
+            dir.path().join("wall.shape.md"),
+            "---\nname: wall\n---\n\n```px\n#\n```\n\n---\nB: solid\n",
+        )
+        .unwrap();
+
+        let mut args = ValidateArgs {
+            files: vec![dir.path().to_path_buf()],
+            target: None,
+            format: "text".to_string(),
+            strict: false,
+        };
+        let result = check(&args, &Printer::new()).unwrap();
+        assert!(result.has_warnings() && !result.has_errors());
+        assert!(!result.fails());
+
+        args.strict = true;
+        assert!(check(&args, &Printer::new()).unwrap().fails());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let dir = tempdir().unwrap();
+        let shape = dir.path().join("wall.shape.md");
+        let args = |strict| ValidateArgs {
+            files: vec![dir.path().to_path_buf()],
+            target: None,
+            format: "text".to_string(),
+            strict,
+        };
+
+        fs::write(&shape, "---\nname: wall\n---\n\n```px\n#\n```\n").unwrap();
+        assert_eq!(run(args(false), &Printer::new()).unwrap(), 0);
+        assert_eq!(run(args(true), &Printer::new()).unwrap(), 0);
+
+        // Missing stamp is an error
+        fs::write(&shape, "---\nname: wall\n---\n\n```px\nB\n```\n\n---\nB: nosuch\n").unwrap();
+        assert_eq!(run(args(false), &Printer::new()).unwrap(), EXIT_ERRORS);
+        assert_eq!(run(args(true), &Printer::new()).unwrap(), EXIT_ERRORS);
+
+        // An unused legend entry only fails with --strict
+        fs::write(&shape, "---\nname: wall\n---\n\n```px\n#\n```\n\n---\nB: solid\n").unwrap();
+        assert_eq!(run(args(false), &Printer::new()).unwrap(), 0);
+        assert_eq!(run(args(true), &Printer::new()).unwrap(), EXIT_WARNINGS);
+    }
 }
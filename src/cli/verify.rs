@@ -0,0 +1,283 @@
+//! Verify command implementation.
+//!
+//! Rebuilds the project into a scratch directory and compares every output
+//! byte-for-byte against the committed output directory, so
+//! non-reproducible outputs show up as differences.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+use walkdir::WalkDir;
+
+use crate::cli::build::{self, BuildArgs};
+use crate::cli::cache::CACHE_FILE;
+use crate::discovery::{discover, discover_paths, Manifest};
+use crate::error::{PxError, Result};
+use crate::output::{display_path, plural, Printer, Verbosity};
+
+/// Rebuild and check outputs match the committed output directory
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    #[command(flatten)]
+    pub build: BuildArgs,
+}
+
+/// A committed output that doesn't match a fresh build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// Present in both, with different bytes.
+    Changed(PathBuf),
+    /// Produced by the build but not committed.
+    Missing(PathBuf),
+    /// Committed but no longer produced by the build.
+    Stale(PathBuf),
+}
+
+pub fn run(args: VerifyArgs, printer: &Printer) -> Result<()> {
+    let mut build_args = args.build;
+    build_args.watch = false;
+    build_args.dry_run = false;
+
+    // Committed output: CLI > manifest > DEFAULT_OUTPUT
+    let manifest = match &build_args.output {
+        Some(_) => Manifest::default(),
+        None if build_args.files.is_empty() => discover(".")?.manifest,
+        None => discover_paths(&build_args.files)?.manifest,
+    };
+    let committed = build::output_dir(&build_args, &manifest);
+
+    let scratch = scratch_dir("px-verify");
+    build_args.output = Some(scratch.clone());
+
+    // Files written outside the output dir are rebuilt into scratch too, so
+    // verify never touches the tree it checks; (committed, fresh) pairs
+    let extras_scratch = scratch_dir("px-verify-extra");
+    let mut extras = Vec::new();
+    for (kind, path) in [("rust", &mut build_args.emit_rust), ("manifest", &mut build_args.manifest_out)] {
+        if let Some(committed_path) = path.take() {
+            let fresh = extras_scratch.join(kind).join(committed_path.file_name().unwrap_or_default());
+            *path = Some(fresh.clone());
+            extras.push((committed_path, fresh));
+        }
+    }
+
+    printer.status("Rebuilding", &format!("into {}", printer.dim(&display_path(&scratch))));
+    let result = create_extra_dirs(&extras)
+        .and_then(|_| build::run(build_args, &Printer::with_verbosity(Verbosity::Quiet)))
+        .and_then(|_| compare_dirs(&committed, &scratch))
+        .and_then(|mut differences| {
+            differences.extend(compare_files(&extras)?);
+            Ok(differences)
+        });
+    let _ = fs::remove_dir_all(&scratch);
+    let _ = fs::remove_dir_all(&extras_scratch);
+    let differences = result?;
+
+    for difference in &differences {
+        match difference {
+            Difference::Changed(path) => printer.error("Changed", &path.display().to_string()),
+            Difference::Missing(path) => printer.error("Missing", &path.display().to_string()),
+            Difference::Stale(path) => printer.warning("Stale", &path.display().to_string()),
+        }
+    }
+
+    let failures = differences
+        .iter()
+        .filter(|d| !matches!(d, Difference::Stale(_)))
+        .count();
+    if failures > 0 {
+        return Err(PxError::Build {
+            message: format!(
+                "{} in {} differ from a fresh build",
+                plural(failures, "output", "outputs"),
+                display_path(&committed)
+            ),
+            help: Some("Rebuild and commit the outputs, or look for nondeterminism in the build".to_string()),
+        });
+    }
+
+    printer.success("Verified", &format!("{} matches a fresh build", display_path(&committed)));
+    Ok(())
+}
+
+/// Compare a committed output directory against a fresh build.
+///
+/// Paths in the result are relative to the directories, in sorted order.
+pub fn compare_dirs(committed: &Path, fresh: &Path) -> Result<Vec<Difference>> {
+    let committed_files = relative_files(committed)?;
+    let fresh_files = relative_files(fresh)?;
+
+    let mut differences = Vec::new();
+    for path in &fresh_files {
+        if !committed_files.contains(path) {
+            differences.push(Difference::Missing(path.clone()));
+        } else if read(&committed.join(path))? != read(&fresh.join(path))? {
+            differences.push(Difference::Changed(path.clone()));
+        }
+    }
+    for path in &committed_files {
+        if !fresh_files.contains(path) {
+            differences.push(Difference::Stale(path.clone()));
+        }
+    }
+
+    Ok(differences)
+}
+
+/// Compare committed files against fresh copies built elsewhere, as
+/// `(committed, fresh)` pairs. Paths in the result are the committed ones.
+pub fn compare_files(pairs: &[(PathBuf, PathBuf)]) -> Result<Vec<Difference>> {
+    let mut differences = Vec::new();
+    for (committed, fresh) in pairs {
+        if !fresh.exists() {
+            continue;
+        }
+        if !committed.exists() {
+            differences.push(Difference::Missing(committed.clone()));
+        } else if read(committed)? != read(fresh)? {
+            differences.push(Difference::Changed(committed.clone()));
+        }
+    }
+    Ok(differences)
+}
+
+fn create_extra_dirs(extras: &[(PathBuf, PathBuf)]) -> Result<()> {
+    for dir in extras.iter().filter_map(|(_, fresh)| fresh.parent()) {
+        fs::create_dir_all(dir).map_err(|e| PxError::Io {
+            path: dir.to_path_buf(),
+            message: format!("Failed to create scratch directory: {}", e),
+        })?;
+    }
+    Ok(())
+}
+
+/// All files under `dir`, relative to it and sorted. A missing dir is empty.
+/// The build cache is bookkeeping, not output, so it is left out.
+pub(crate) fn relative_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| PxError::Io {
+            path: dir.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        if entry.file_type().is_file() && entry.file_name() != CACHE_FILE {
+            if let Ok(relative) = entry.path().strip_prefix(dir) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to read output: {}", e),
+    })
+}
+
+/// A fresh directory under the system temp dir for a rebuild.
+pub(crate) fn scratch_dir(prefix: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("{}-{}-{}", prefix, std::process::id(), nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn project(dir: &Path) -> PathBuf {
+        let shapes = dir.join("shapes");
+        fs::create_dir_all(&shapes).unwrap();
+        fs::write(
+            shapes.join("box.shape.md"),
+            "---\nname: box\n---\n\n```px\n+--+\n|..|\n+--+\n```\n",
+        )
+        .unwrap();
+        shapes
+    }
+
+    #[test]
+    fn test_verify_clean_build() {
+        let dir = tempdir().unwrap();
+        let shapes = project(dir.path());
+        let dist = dir.path().join("dist");
+
+        build::run(build::plain_build_args(vec![shapes.clone()], &dist), &Printer::new()).unwrap();
+
+        let args = VerifyArgs { build: build::plain_build_args(vec![shapes], &dist) };
+        run(args, &Printer::new()).unwrap();
+    }
+
+    #[test]
+    fn test_verify_reports_modified_output() {
+        let dir = tempdir().unwrap();
+        let shapes = project(dir.path());
+        let dist = dir.path().join("dist");
+
+        build::run(build::plain_build_args(vec![shapes.clone()], &dist), &Printer::new()).unwrap();
+        fs::write(dist.join("box.json"), "{}").unwrap();
+
+        let args = VerifyArgs { build: build::plain_build_args(vec![shapes], &dist) };
+        assert!(run(args, &Printer::new()).is_err());
+    }
+
+    #[test]
+    fn test_verify_leaves_emitted_files_alone() {
+        let dir = tempdir().unwrap();
+        let shapes = project(dir.path());
+        let dist = dir.path().join("dist");
+        let rust = dir.path().join("sprites.rs");
+        let manifest = dir.path().join("outputs.json");
+        let args = || BuildArgs {
+            emit_rust: Some(rust.clone()),
+            manifest_out: Some(manifest.clone()),
+            ..build::plain_build_args(vec![shapes.clone()], &dist)
+        };
+
+        build::run(args(), &Printer::new()).unwrap();
+        let committed_rust = fs::read(&rust).unwrap();
+        let committed_manifest = fs::read(&manifest).unwrap();
+        run(VerifyArgs { build: args() }, &Printer::new()).unwrap();
+        assert_eq!(fs::read(&rust).unwrap(), committed_rust);
+        assert_eq!(fs::read(&manifest).unwrap(), committed_manifest);
+
+        // An edited copy is reported, and left as it was
+        fs::write(&rust, "// edited\n").unwrap();
+        assert!(run(VerifyArgs { build: args() }, &Printer::new()).is_err());
+        assert_eq!(fs::read_to_string(&rust).unwrap(), "// edited\n");
+    }
+
+    #[test]
+    fn test_compare_dirs() {
+        let committed = tempdir().unwrap();
+        let fresh = tempdir().unwrap();
+
+        fs::write(committed.path().join("same.png"), b"a").unwrap();
+        fs::write(fresh.path().join("same.png"), b"a").unwrap();
+        fs::write(committed.path().join("edited.json"), b"old").unwrap();
+        fs::write(fresh.path().join("edited.json"), b"new").unwrap();
+        fs::write(fresh.path().join("new.png"), b"n").unwrap();
+        fs::write(committed.path().join("old.png"), b"o").unwrap();
+
+        let differences = compare_dirs(committed.path(), fresh.path()).unwrap();
+        assert_eq!(
+            differences,
+            vec![
+                Difference::Changed(PathBuf::from("edited.json")),
+                Difference::Missing(PathBuf::from("new.png")),
+                Difference::Stale(PathBuf::from("old.png")),
+            ]
+        );
+    }
+}
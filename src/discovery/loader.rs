@@ -2,13 +2,15 @@
 //!
 //! Takes scan results and loads all assets into a RegistryBuilder.
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::error::{PxError, Result};
 use crate::parser::{parse_brush_file, parse_map_file, parse_palette, parse_prefab_file, parse_shader_file, parse_shape_file, parse_stamp_file, parse_target_file};
 use crate::registry::RegistryBuilder;
-use crate::types::{BuiltinBrushes, BuiltinStamps, Palette};
+use crate::types::{build_inherited, Brush, BuiltinBrushes, BuiltinStamps, Map, Palette, PaletteBuilder, Prefab, ShaderBuilder, Shape, Stamp, TargetBuilder};
 
 use super::scanner::ScanResult;
 
@@ -34,109 +36,123 @@ impl LoadOptions {
     }
 }
 
-/// Load assets from scan result into a RegistryBuilder.
+/// Parsed files from earlier loads, reused while a file's mtime is unchanged.
 ///
-/// Parses all discovered files and adds them to the builder.
-/// Returns errors for any files that fail to parse.
-pub fn load_assets(scan: &ScanResult, options: &LoadOptions) -> Result<RegistryBuilder> {
-    let mut builder = RegistryBuilder::new();
-    let mut errors: Vec<String> = Vec::new();
+/// Keep one across rebuilds (as `px build --watch` does) so only edited
+/// files are parsed again.
+#[derive(Debug, Default)]
+pub struct ParseCache {
+    palettes: FileCache<PaletteBuilder>,
+    stamps: FileCache<Stamp>,
+    brushes: FileCache<Brush>,
+    shaders: FileCache<ShaderBuilder>,
+    shapes: FileCache<Shape>,
+    prefabs: FileCache<Prefab>,
+    maps: FileCache<Map>,
+    targets: FileCache<TargetBuilder>,
+    /// Files parsed (cache misses) so far.
+    parsed: usize,
+}
 
-    // Load palettes
-    for path in &scan.palettes {
-        match load_palette(path) {
-            Ok(palettes) => {
-                builder.add_palettes(palettes);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
-            }
-        }
+impl ParseCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    // Load stamps
-    for path in &scan.stamps {
-        match load_stamps(path) {
-            Ok(stamps) => {
-                builder.add_stamps(stamps);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
-            }
-        }
+    /// Number of files parsed through this cache, i.e. cache misses.
+    pub fn parsed(&self) -> usize {
+        self.parsed
     }
+}
 
-    // Load brushes
-    for path in &scan.brushes {
-        match load_brushes(path) {
-            Ok(brushes) => {
-                builder.add_brushes(brushes);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
-            }
+/// Parsed assets of one kind, keyed by path, with the mtime they were read at.
+#[derive(Debug)]
+struct FileCache<T> {
+    entries: HashMap<PathBuf, (SystemTime, Vec<T>)>,
+}
+
+impl<T> Default for FileCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
         }
     }
+}
 
-    // Load shaders
-    for path in &scan.shaders {
-        match load_shaders(path) {
-            Ok(shaders) => {
-                builder.add_shaders(shaders);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
+impl<T: Clone> FileCache<T> {
+    /// Return the cached assets for `path`, or parse it with `load` if it is
+    /// new or its mtime has changed.
+    fn load(&mut self, path: &Path, load: fn(&Path) -> Result<Vec<T>>, parsed: &mut usize) -> Result<Vec<T>> {
+        let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if let (Some(mtime), Some((cached_at, assets))) = (mtime, self.entries.get(path)) {
+            if *cached_at == mtime {
+                return Ok(assets.clone());
             }
         }
-    }
 
-    // Load shapes
-    for path in &scan.shapes {
-        match load_shapes(path) {
-            Ok(shapes) => {
-                builder.add_shapes(shapes);
+        *parsed += 1;
+        let assets = load(path)?;
+        match mtime {
+            Some(mtime) => {
+                self.entries.insert(path.to_path_buf(), (mtime, assets.clone()));
             }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
+            None => {
+                self.entries.remove(path);
             }
         }
+        Ok(assets)
     }
+}
 
-    // Load prefabs
-    for path in &scan.prefabs {
-        match load_prefabs(path) {
-            Ok(prefabs) => {
-                builder.add_prefabs(prefabs);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
-            }
+/// Load assets from scan result into a RegistryBuilder.
+///
+/// Parses all discovered files and adds them to the builder.
+/// Returns errors for any files that fail to parse.
+pub fn load_assets(scan: &ScanResult, options: &LoadOptions) -> Result<RegistryBuilder> {
+    load_assets_cached(scan, options, &mut ParseCache::new())
+}
+
+/// Load assets like [`load_assets`], reusing files parsed into `cache` by
+/// earlier loads when they haven't changed since.
+pub fn load_assets_cached(scan: &ScanResult, options: &LoadOptions, cache: &mut ParseCache) -> Result<RegistryBuilder> {
+    let mut builder = RegistryBuilder::new();
+    let mut errors: Vec<String> = Vec::new();
+    let parsed = &mut cache.parsed;
+
+    // Palettes build once all are parsed, so parents resolve before children
+    let mut palettes = Vec::new();
+    for path in &scan.palettes {
+        match cache.palettes.load(path, load_palette, parsed) {
+            Ok(builders) => palettes.extend(builders.into_iter().map(|b| (path.clone(), b))),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
         }
     }
-
-    // Load maps
-    for path in &scan.maps {
-        match load_maps(path) {
-            Ok(maps) => {
-                builder.add_maps(maps);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
-            }
+    builder.add_palettes(build_palettes(palettes, &mut errors));
+    builder.add_stamps(load_all(&scan.stamps, &mut cache.stamps, load_stamps, parsed, &mut errors));
+    builder.add_brushes(load_all(&scan.brushes, &mut cache.brushes, load_brushes, parsed, &mut errors));
+    // Shaders resolve once all are parsed, so parents can live in other files
+    let mut shaders = Vec::new();
+    for path in &scan.shaders {
+        match cache.shaders.load(path, load_shaders, parsed) {
+            Ok(builders) => shaders.extend(builders.into_iter().map(|b| (path.clone(), b))),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
         }
     }
+    builder.add_shaders(build_inherited(&shaders, &mut errors));
+    builder.add_shapes(load_all(&scan.shapes, &mut cache.shapes, load_shapes, parsed, &mut errors));
+    builder.add_prefabs(load_all(&scan.prefabs, &mut cache.prefabs, load_prefabs, parsed, &mut errors));
+    builder.add_maps(load_all(&scan.maps, &mut cache.maps, load_maps, parsed, &mut errors));
 
-    // Load targets
+    // Targets resolve like shaders, so variants can inherit a shared base
+    let mut targets = Vec::new();
     for path in &scan.targets {
-        match load_targets(path) {
-            Ok(targets) => {
-                builder.add_targets(targets);
-            }
-            Err(e) => {
-                errors.push(format!("{}: {}", path.display(), e));
-            }
+        match cache.targets.load(path, load_targets, parsed) {
+            Ok(builders) => targets.extend(builders.into_iter().map(|b| (path.clone(), b))),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
         }
     }
+    builder.add_targets(build_inherited(&targets, &mut errors));
 
     // Add builtins if requested
     if options.include_builtin_stamps {
@@ -162,23 +178,83 @@ pub fn load_assets(scan: &ScanResult, options: &LoadOptions) -> Result<RegistryB
     Ok(builder)
 }
 
-/// Load palettes from a file.
-fn load_palette(path: &Path) -> Result<Vec<Palette>> {
+/// Load every file in `paths` through `cache`, collecting failures in `errors`.
+fn load_all<T: Clone>(
+    paths: &[PathBuf],
+    cache: &mut FileCache<T>,
+    load: fn(&Path) -> Result<Vec<T>>,
+    parsed: &mut usize,
+    errors: &mut Vec<String>,
+) -> Vec<T> {
+    let mut assets = Vec::new();
+    for path in paths {
+        match cache.load(path, load, parsed) {
+            Ok(loaded) => assets.extend(loaded),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    assets
+}
+
+/// Parse palette definitions from a file.
+fn load_palette(path: &Path) -> Result<Vec<PaletteBuilder>> {
     let content = fs::read_to_string(path).map_err(|e| PxError::Io {
         path: path.to_path_buf(),
         message: e.to_string(),
     })?;
 
-    let builders = parse_palette(&content)?;
-    builders
-        .into_iter()
-        .map(|b| {
-            b.build(None).map_err(|e| PxError::Build {
-                message: e.to_string(),
-                help: None,
-            })
-        })
-        .collect()
+    parse_palette(&content)
+}
+
+/// Build parsed palettes, each after the palette it inherits from.
+///
+/// Palettes caught in an inheritance cycle are built without their parent;
+/// the registry's dependency graph reports the cycle.
+fn build_palettes(mut pending: Vec<(PathBuf, PaletteBuilder)>, errors: &mut Vec<String>) -> Vec<Palette> {
+    let names: HashSet<String> = pending.iter().map(|(_, b)| b.name().to_string()).collect();
+    let mut built: HashMap<String, Palette> = HashMap::new();
+    let mut palettes = Vec::new();
+
+    loop {
+        let (ready, waiting): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .partition(|(_, b)| b.parent_name().is_none_or(|p| built.contains_key(p) || !names.contains(p)));
+        pending = waiting;
+        let cyclic = ready.is_empty();
+        let batch = if cyclic { std::mem::take(&mut pending) } else { ready };
+        if batch.is_empty() {
+            break;
+        }
+
+        for (path, builder) in batch {
+            let parent = if cyclic {
+                None
+            } else {
+                match builder.parent_name() {
+                    Some(p) if !built.contains_key(p) => {
+                        errors.push(format!(
+                            "{}: Palette '{}' inherits from unknown palette '{}'",
+                            path.display(),
+                            builder.name(),
+                            p
+                        ));
+                        None
+                    }
+                    Some(p) => built.get(p),
+                    None => None,
+                }
+            };
+            match builder.build(parent) {
+                Ok(palette) => {
+                    built.insert(palette.name.clone(), palette.clone());
+                    palettes.push(palette);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+
+    palettes
 }
 
 /// Load stamps from a file.
@@ -201,24 +277,14 @@ fn load_brushes(path: &Path) -> Result<Vec<crate::types::Brush>> {
     parse_brush_file(&content)
 }
 
-/// Load shaders from a file.
-fn load_shaders(path: &Path) -> Result<Vec<crate::types::Shader>> {
+/// Parse shader definitions from a file.
+fn load_shaders(path: &Path) -> Result<Vec<ShaderBuilder>> {
     let content = fs::read_to_string(path).map_err(|e| PxError::Io {
         path: path.to_path_buf(),
         message: e.to_string(),
     })?;
 
-    let builders = parse_shader_file(&content)?;
-
-    builders
-        .into_iter()
-        .map(|b| {
-            b.build(None).map_err(|e| PxError::Build {
-                message: e.to_string(),
-                help: None,
-            })
-        })
-        .collect()
+    parse_shader_file(&content)
 }
 
 /// Load shapes from a file.
@@ -251,24 +317,14 @@ fn load_maps(path: &Path) -> Result<Vec<crate::types::Map>> {
     parse_map_file(&content)
 }
 
-/// Load targets from a file.
-fn load_targets(path: &Path) -> Result<Vec<crate::types::Target>> {
+/// Parse target definitions from a file.
+fn load_targets(path: &Path) -> Result<Vec<TargetBuilder>> {
     let content = fs::read_to_string(path).map_err(|e| PxError::Io {
         path: path.to_path_buf(),
         message: e.to_string(),
     })?;
 
-    let builders = parse_target_file(&content)?;
-
-    builders
-        .into_iter()
-        .map(|b| {
-            b.build().map_err(|e| PxError::Build {
-                message: e.to_string(),
-                help: None,
-            })
-        })
-        .collect()
+    parse_target_file(&content)
 }
 
 #[cfg(test)]
@@ -276,6 +332,7 @@ mod tests {
     use super::*;
     use std::fs;
     use tempfile::tempdir;
+    use crate::types::{BuiltinTargets, Colour};
 
     #[test]
     fn test_load_empty_scan() {
@@ -394,6 +451,80 @@ name: shape-b
         assert!(registry.get_shape("shape-b").is_some());
     }
 
+    #[test]
+    fn test_parse_cache_skips_unchanged_files() {
+        let dir = tempdir().unwrap();
+        let shape = dir.path().join("a.shape.md");
+        let palette = dir.path().join("p.palette.md");
+        fs::write(&shape, "---\nname: a\n---\n\n```px\n#\n```\n").unwrap();
+        fs::write(&palette, "---\nname: p\n---\n$edge: #000000\n").unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.shapes.push(shape.clone());
+        scan.palettes.push(palette);
+
+        let mut cache = ParseCache::new();
+        let options = LoadOptions::default();
+        load_assets_cached(&scan, &options, &mut cache).unwrap();
+        assert_eq!(cache.parsed(), 2);
+
+        let registry = load_assets_cached(&scan, &options, &mut cache).unwrap().build().unwrap();
+        assert_eq!(cache.parsed(), 2);
+        assert!(registry.get_shape("a").is_some());
+        assert!(registry.get_palette("p").is_some());
+
+        // A new mtime invalidates just that file
+        fs::write(&shape, "---\nname: b\n---\n\n```px\n#\n```\n").unwrap();
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        fs::File::options().write(true).open(&shape).unwrap().set_modified(later).unwrap();
+
+        let registry = load_assets_cached(&scan, &options, &mut cache).unwrap().build().unwrap();
+        assert_eq!(cache.parsed(), 3);
+        assert!(registry.get_shape("b").is_some());
+        assert!(registry.get_shape("a").is_none());
+    }
+
+    #[test]
+    fn test_load_palette_chain_resolves_parents_first() {
+        let dir = tempdir().unwrap();
+        // Children come first in scan order
+        let child = dir.path().join("a.palette.md");
+        let parent = dir.path().join("b.palette.md");
+        fs::write(&child, "---\nname: night\ninherits: dusk\n---\n$sky: #000000\n").unwrap();
+        fs::write(
+            &parent,
+            "---\nname: dusk\ninherits: day\n---\n$sky: #442266\n\n---\nname: day\n---\n$sky: #88CCFF\n$sun: #FFFF00\n",
+        )
+        .unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.palettes.push(child);
+        scan.palettes.push(parent);
+
+        let registry = load_assets(&scan, &LoadOptions::default()).unwrap().build().unwrap();
+        let night = registry.get_palette("night").unwrap();
+        assert_eq!(night.parent(), Some("dusk"));
+        assert_eq!(night.get("sky"), Some(Colour::BLACK));
+        assert_eq!(night.get("sun"), Some(Colour::rgb(255, 255, 0)));
+    }
+
+    #[test]
+    fn test_load_palette_cycle_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("loop.palette.md");
+        fs::write(
+            &path,
+            "---\nname: a\ninherits: b\n---\n$x: #000000\n\n---\nname: b\ninherits: a\n---\n$y: #FFFFFF\n",
+        )
+        .unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.palettes.push(path);
+
+        let err = load_assets(&scan, &LoadOptions::default()).unwrap().build().unwrap_err();
+        assert!(err.to_string().contains("Circular dependency detected"), "{}", err);
+    }
+
     #[test]
     fn test_load_invalid_file_error() {
         let dir = tempdir().unwrap();
@@ -492,4 +623,95 @@ palette: default
 
         assert!(registry.get_shader("test-shader").is_some());
     }
+
+    #[test]
+    fn test_load_shader_inherits_same_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("fx.shader.md");
+
+        fs::write(
+            &path,
+            r#"---
+name: base
+palette: dungeon
+palette_variant: dark
+---
+
+---
+name: inked
+inherits: base
+effects:
+  - type: outline
+---
+"#,
+        )
+        .unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.shaders.push(path);
+
+        let builder = load_assets(&scan, &LoadOptions::default()).unwrap();
+        let registry = builder.build().unwrap();
+
+        let shader = registry.get_shader("inked").unwrap();
+        assert_eq!(shader.palette, "dungeon");
+        assert_eq!(shader.palette_variant, Some("dark".to_string()));
+        assert_eq!(shader.effects.len(), 1);
+        assert_eq!(shader.effects[0].type_name(), "outline");
+    }
+
+    #[test]
+    fn test_load_shader_inherits_across_files() {
+        let dir = tempdir().unwrap();
+        // The child comes first in scan order
+        let child = dir.path().join("child.shader.md");
+        let parent = dir.path().join("base.shader.md");
+        fs::write(&child, "---\nname: child\ninherits: base\neffects:\n  - type: outline\n---\n").unwrap();
+        fs::write(&parent, "---\nname: base\npalette: dungeon\npalette_variant: dark\n---\n").unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.shaders.push(child);
+        scan.shaders.push(parent);
+
+        let registry = load_assets(&scan, &LoadOptions::default()).unwrap().build().unwrap();
+        let shader = registry.get_shader("child").unwrap();
+        assert_eq!(shader.palette, "dungeon");
+        assert_eq!(shader.palette_variant, Some("dark".to_string()));
+        assert_eq!(shader.effects[0].type_name(), "outline");
+        assert!(registry.get_shader("base").is_some());
+    }
+
+    #[test]
+    fn test_load_shader_unknown_parent_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("child.shader.md");
+        fs::write(&path, "---\nname: child\ninherits: missing\n---\n").unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.shaders.push(path);
+
+        let err = load_assets(&scan, &LoadOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("inherits unknown shader 'missing'"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_target_inherits_across_files() {
+        let dir = tempdir().unwrap();
+        let base = dir.path().join("base.target.md");
+        let mobile = dir.path().join("mobile.target.md");
+        fs::write(&base, "---\nname: base\ninherits: web\nscale: 2\npadding: 3\n---\n").unwrap();
+        fs::write(&mobile, "---\nname: mobile\ninherits: base\nscale: 4\n---\n").unwrap();
+
+        let mut scan = ScanResult::default();
+        scan.targets.push(mobile);
+        scan.targets.push(base);
+
+        let registry = load_assets(&scan, &LoadOptions::default()).unwrap().build().unwrap();
+        let web = BuiltinTargets::get("web").unwrap();
+        let mobile = registry.get_target("mobile").unwrap();
+        assert_eq!(mobile.scale, Some(4));
+        assert_eq!(mobile.padding, Some(3));
+        assert_eq!(mobile.format, web.format);
+        assert_eq!(registry.get_target("base").unwrap().scale, Some(2));
+    }
 }
@@ -1,24 +1,37 @@
 //! Project manifest (px.yaml) parsing.
 //!
 //! The manifest defines project configuration including source paths,
-//! output settings, and default options.
+//! output settings, and default options. A manifest can `extends:` a base
+//! manifest to share settings across projects.
 
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
 
 use crate::error::{PxError, Result};
+use crate::validation::Lints;
+
+/// Output directory when neither `-o` nor the manifest sets one.
+pub const DEFAULT_OUTPUT: &str = "dist";
 
 /// Project manifest loaded from px.yaml.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Manifest {
+    /// Base manifest whose settings this one inherits, relative to this
+    /// file. Lists (`sources`, `excludes`) are appended to the base's,
+    /// maps (`lints`, `watch`) merged by key, and other values override.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<PathBuf>,
+
     /// Source directories or glob patterns to scan for assets.
     /// Defaults to current directory if empty.
     #[serde(default)]
     pub sources: Vec<String>,
 
-    /// Output directory for rendered assets.
+    /// Output directory for rendered assets (default [`DEFAULT_OUTPUT`]),
+    /// relative to the working directory.
     #[serde(default = "default_output")]
     pub output: PathBuf,
 
@@ -37,42 +50,59 @@ pub struct Manifest {
     /// Patterns to exclude from discovery.
     #[serde(default)]
     pub excludes: Vec<String>,
+
+    /// Validation overrides by diagnostic code: `off`, `warn` or `error`.
+    #[serde(default, skip_serializing_if = "Lints::is_empty")]
+    pub lints: Lints,
+
+    /// `px build --watch` settings.
+    #[serde(default)]
+    pub watch: WatchConfig,
+}
+
+/// Watch mode settings under `watch:` in px.yaml.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Milliseconds to wait for further changes before rebuilding
+    /// (0 rebuilds at once).
+    pub debounce: Option<u64>,
 }
 
 fn default_output() -> PathBuf {
-    PathBuf::from("dist")
+    PathBuf::from(DEFAULT_OUTPUT)
 }
 
 impl Default for Manifest {
     fn default() -> Self {
         Self {
+            extends: None,
             sources: vec![],
             output: default_output(),
             target: None,
             shader: None,
             scale: None,
             excludes: vec![],
+            lints: Lints::new(),
+            watch: WatchConfig::default(),
         }
     }
 }
 
 impl Manifest {
-    /// Load manifest from a px.yaml file.
+    /// Load manifest from a px.yaml file, with any `extends:` chain merged in.
+    ///
+    /// Paths inside an extended manifest stay relative to the project,
+    /// not to the base file.
     pub fn load(path: &Path) -> Result<Self> {
-        let content = std::fs::read_to_string(path).map_err(|e| PxError::Io {
-            path: path.to_path_buf(),
-            message: format!("Failed to read manifest: {}", e),
-        })?;
-
-        Self::parse(&content)
+        let value = load_value(path, &mut Vec::new())?;
+        serde_yaml::from_value(value).map_err(invalid_manifest)
     }
 
-    /// Parse manifest from YAML string.
+    /// Parse manifest from YAML string. `extends:` is recorded but not
+    /// followed; use [`load`](Self::load) for that.
     pub fn parse(content: &str) -> Result<Self> {
-        serde_yaml::from_str(content).map_err(|e| PxError::Parse {
-            message: format!("Invalid manifest: {}", e),
-            help: Some("Check px.yaml syntax".to_string()),
-        })
+        serde_yaml::from_str(content).map_err(invalid_manifest)
     }
 
     /// Check if a path should be excluded based on exclude patterns.
@@ -137,9 +167,68 @@ impl Manifest {
     }
 }
 
+fn invalid_manifest(e: serde_yaml::Error) -> PxError {
+    PxError::Parse {
+        message: format!("Invalid manifest: {}", e),
+        help: Some("Check px.yaml syntax".to_string()),
+    }
+}
+
+/// Read `path` as YAML with its `extends:` base merged underneath.
+/// `chain` holds the manifests already being loaded, to catch cycles.
+fn load_value(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Value> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if chain.contains(&canonical) {
+        let cycle: Vec<String> = chain.iter().chain([&canonical]).map(|p| p.display().to_string()).collect();
+        return Err(PxError::Parse {
+            message: format!("Manifest extends itself: {}", cycle.join(" -> ")),
+            help: Some("Remove one of the `extends:` links".to_string()),
+        });
+    }
+    chain.push(canonical);
+
+    let content = std::fs::read_to_string(path).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to read manifest: {}", e),
+    })?;
+    let value: Value = serde_yaml::from_str(&content).map_err(invalid_manifest)?;
+
+    match value.get("extends").and_then(Value::as_str) {
+        Some(base) => {
+            let base_path = path.parent().unwrap_or(Path::new(".")).join(base);
+            Ok(merge(load_value(&base_path, chain)?, value))
+        }
+        None => Ok(value),
+    }
+}
+
+/// Overlay `local` on `base`: lists append, maps merge by key, and
+/// anything else in `local` replaces the base value.
+fn merge(base: Value, local: Value) -> Value {
+    match (base, local) {
+        (base, Value::Null) => base,
+        (Value::Mapping(mut base), Value::Mapping(local)) => {
+            for (key, value) in local {
+                let merged = match base.remove(&key) {
+                    Some(below) => merge(below, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Mapping(base)
+        }
+        (Value::Sequence(mut base), Value::Sequence(local)) => {
+            base.extend(local);
+            Value::Sequence(base)
+        }
+        (_, local) => local,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     #[test]
     fn test_parse_minimal_manifest() {
@@ -164,6 +253,8 @@ scale: 4
 excludes:
   - "*.bak"
   - "**/temp/*"
+watch:
+  debounce: 800
 "#;
         let manifest = Manifest::parse(yaml).unwrap();
 
@@ -173,6 +264,22 @@ excludes:
         assert_eq!(manifest.shader, Some("dungeon-dark".to_string()));
         assert_eq!(manifest.scale, Some(4));
         assert_eq!(manifest.excludes, vec!["*.bak", "**/temp/*"]);
+        assert_eq!(manifest.watch.debounce, Some(800));
+    }
+
+    #[test]
+    fn test_parse_lints() {
+        use crate::validation::LintLevel;
+
+        let yaml = "lints:\n  unused-asset: warn\n  stamp-size-mismatch: off\n  px::validate::unused-colour: error\n";
+        let manifest = Manifest::parse(yaml).unwrap();
+
+        assert_eq!(manifest.lints.len(), 3);
+        assert_eq!(manifest.lints["unused-asset"], LintLevel::Warn);
+        assert_eq!(manifest.lints["stamp-size-mismatch"], LintLevel::Off);
+        assert_eq!(manifest.lints["px::validate::unused-colour"], LintLevel::Error);
+
+        assert!(Manifest::parse("lints:\n  unused-asset: loud\n").is_err());
     }
 
     #[test]
@@ -239,4 +346,33 @@ excludes:
         // Should use defaults
         assert_eq!(manifest.output, PathBuf::from("dist"));
     }
+
+    #[test]
+    fn test_load_extends_base_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("base.px.yaml"),
+            "sources:\n  - shared/\noutput: build\nscale: 2\nlints:\n  unused-asset: off\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("game")).unwrap();
+        let path = dir.path().join("game/px.yaml");
+        fs::write(&path, "extends: ../base.px.yaml\nsources:\n  - shapes/\noutput: dist/game\n").unwrap();
+
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(manifest.scale, Some(2));
+        assert_eq!(manifest.output, PathBuf::from("dist/game"));
+        assert_eq!(manifest.sources, vec!["shared/", "shapes/"]);
+        assert!(!manifest.lints.is_empty());
+    }
+
+    #[test]
+    fn test_load_extends_cycle_error() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.px.yaml"), "extends: b.px.yaml\n").unwrap();
+        fs::write(dir.path().join("b.px.yaml"), "extends: a.px.yaml\n").unwrap();
+
+        let err = Manifest::load(&dir.path().join("a.px.yaml")).unwrap_err().to_string();
+        assert!(err.contains("Manifest extends itself"), "{}", err);
+    }
 }
@@ -24,8 +24,8 @@ use std::path::{Path, PathBuf};
 use crate::error::Result;
 use crate::registry::{AssetRegistry, RegistryBuilder};
 
-pub use loader::{load_assets, LoadOptions};
-pub use manifest::Manifest;
+pub use loader::{load_assets, load_assets_cached, LoadOptions, ParseCache};
+pub use manifest::{Manifest, WatchConfig, DEFAULT_OUTPUT};
 pub use scanner::{detect_asset_kind, scan_directory, scan_sources, ScanResult};
 
 /// The name of the manifest file.
@@ -101,17 +101,34 @@ pub fn discover(root: impl AsRef<Path>) -> Result<DiscoveryResult> {
     })
 }
 
-/// Discover assets from specific paths (no manifest lookup).
+/// Discover assets from specific paths.
 ///
-/// Useful when you want to specify source paths directly without
-/// looking for a px.yaml manifest.
+/// Useful when you want to specify source paths directly instead of
+/// reading sources from a px.yaml manifest. The result carries the default
+/// manifest, so output falls back to [`DEFAULT_OUTPUT`] as it does for
+/// [`discover`] without a px.yaml. Directory arguments still honour the
+/// `excludes` of a px.yaml inside them.
 pub fn discover_paths(paths: &[PathBuf]) -> Result<DiscoveryResult> {
-    let manifest = Manifest::default();
+    discover_paths_with_manifest(paths, Manifest::default())
+}
+
+/// Discover assets from specific paths, skipping `manifest`'s excludes in
+/// every directory argument (plus those of a px.yaml inside it).
+///
+/// The result carries `manifest`. Files named directly are never excluded.
+pub fn discover_paths_with_manifest(paths: &[PathBuf], manifest: Manifest) -> Result<DiscoveryResult> {
     let mut scan = ScanResult::new();
 
     for path in paths {
         if path.is_dir() {
-            let dir_scan = scan_directory(path, &manifest);
+            let local = path.join(MANIFEST_FILENAME);
+            let dir_scan = if local.exists() {
+                let mut scoped = manifest.clone();
+                scoped.excludes.extend(Manifest::load(&local)?.excludes);
+                scan_directory(path, &scoped)
+            } else {
+                scan_directory(path, &manifest)
+            };
             scan.merge(dir_scan);
         } else if path.is_file() {
             // Add single file to appropriate category
@@ -176,6 +193,18 @@ mod tests {
         assert_eq!(result.scan.shapes.len(), 1);
     }
 
+    #[test]
+    fn test_discover_and_discover_paths_share_default_output() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("player.shape.md"), "---\nname: player\n---\n\n```px\n#\n```").unwrap();
+
+        let discovered = discover(dir.path()).unwrap();
+        let paths = discover_paths(&[dir.path().join("player.shape.md")]).unwrap();
+
+        assert_eq!(discovered.manifest.output, PathBuf::from(DEFAULT_OUTPUT));
+        assert_eq!(paths.manifest.output, discovered.manifest.output);
+    }
+
     #[test]
     fn test_discover_with_manifest() {
         let dir = tempdir().unwrap();
@@ -243,6 +272,57 @@ excludes:
         assert!(result.scan.shapes[0].to_string_lossy().contains("player"));
     }
 
+    #[test]
+    fn test_discover_paths_directory_with_excludes() {
+        let dir = tempdir().unwrap();
+
+        fs::write(
+            dir.path().join("px.yaml"),
+            r#"
+excludes:
+  - "**/backup/*"
+"#,
+        )
+        .unwrap();
+
+        fs::write(
+            dir.path().join("player.shape.md"),
+            "---\nname: player\n---\n\n```px\n#\n```",
+        )
+        .unwrap();
+
+        fs::create_dir_all(dir.path().join("backup")).unwrap();
+        fs::write(
+            dir.path().join("backup/old.shape.md"),
+            "---\nname: old\n---\n\n```px\n#\n```",
+        )
+        .unwrap();
+
+        // The directory's own px.yaml excludes apply
+        let result = discover_paths(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(result.scan.shapes.len(), 1);
+        assert!(result.scan.shapes[0].to_string_lossy().contains("player"));
+        assert!(!result.has_manifest);
+
+        // So do those of a passed manifest, without a px.yaml
+        fs::remove_file(dir.path().join("px.yaml")).unwrap();
+        let manifest = Manifest {
+            excludes: vec!["**/backup/*".to_string()],
+            ..Manifest::default()
+        };
+        let result = discover_paths_with_manifest(&[dir.path().to_path_buf()], manifest).unwrap();
+        assert_eq!(result.scan.shapes.len(), 1);
+        assert_eq!(discover_paths(&[dir.path().to_path_buf()]).unwrap().scan.shapes.len(), 2);
+
+        // A file named directly is kept
+        let old = dir.path().join("backup/old.shape.md");
+        let manifest = Manifest {
+            excludes: vec!["**/backup/*".to_string()],
+            ..Manifest::default()
+        };
+        assert_eq!(discover_paths_with_manifest(&[old], manifest).unwrap().scan.shapes.len(), 1);
+    }
+
     #[test]
     fn test_discover_into_registry() {
         let dir = tempdir().unwrap();
@@ -13,15 +13,15 @@ pub mod render;
 pub mod types;
 pub mod validation;
 
-pub use discovery::{discover, discover_paths, DiscoveryResult, LoadOptions, Manifest, ScanResult};
+pub use discovery::{discover, discover_paths, discover_paths_with_manifest, DiscoveryResult, LoadOptions, Manifest, ScanResult};
 pub use output::{Printer, Verbosity};
 pub use error::{PxError, Result};
 pub use registry::{AssetId, AssetKind, AssetRegistry, RegistryBuilder};
-pub use render::{quantize_sheet, scale_pixels, write_png, write_sheet_json, MapRenderer, PrefabRenderer, RenderedShape, ShapeRenderer, SheetMeta, SheetPacker};
+pub use render::{quantize_sheet, render_project, scale_pixels, write_png, write_sheet_json, MapRenderer, PrefabRenderer, RenderOptions, RenderedShape, ShapeRenderer, SheetMeta, SheetPacker};
 pub use types::{
     Brush, BuiltinBrushes, BuiltinShaders, BuiltinStamps, BuiltinTargets, Colour, ColourExpr,
     Effect, EffectParam, ExprEvaluator, LegendEntry, Map, MapInstance, MapMetadata, Palette,
     PaletteMode, PixelToken, Prefab, PrefabInstance, PrefabMetadata, Shader, ShaderBuilder, Shape,
-    ShapeMetadata, SheetConfig, Stamp, Target, TargetBuilder,
+    ShapeMetadata, SheetConfig, Stamp, Target, TargetBuilder, TilemapMetadata,
 };
-pub use validation::{validate_registry, Diagnostic, Severity, ValidationResult};
+pub use validation::{validate_for_target, validate_registry, Diagnostic, LintLevel, Lints, Severity, ValidationResult};
@@ -10,11 +10,23 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Build(args) => px::cli::build::run(args, &printer)?,
         Commands::Completions(args) => px::cli::completions::run(args)?,
+        Commands::Diff(args) => px::cli::diff::run(args, &printer)?,
+        Commands::Fmt(args) => px::cli::fmt::run(args, &printer)?,
+        Commands::Graph(args) => px::cli::graph::run(args, &printer)?,
         Commands::Init(args) => px::cli::init::run(args, &printer)?,
         Commands::List(args) => px::cli::list::run(args, &printer)?,
+        Commands::Pack(args) => px::cli::pack::run(args, &printer)?,
         Commands::Palette(args) => px::cli::palette::run(args, &printer)?,
+        Commands::Preview(args) => px::cli::preview::run(args, &printer)?,
         Commands::Slice(args) => { px::cli::slice::run(args, &printer)?; },
-        Commands::Validate(args) => px::cli::validate::run(args, &printer)?,
+        Commands::Stats(args) => px::cli::stats::run(args, &printer)?,
+        Commands::Validate(args) => {
+            let code = px::cli::validate::run(args, &printer)?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::Verify(args) => px::cli::verify::run(args, &printer)?,
     }
 
     Ok(())
@@ -4,6 +4,8 @@
 //! All status output goes to stderr; stdout is reserved for machine-readable output.
 
 use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 /// ANSI escape codes.
 const RESET: &str = "\x1b[0m";
@@ -13,10 +15,14 @@ const RED: &str = "\x1b[31m";
 const GREEN: &str = "\x1b[32m";
 const YELLOW: &str = "\x1b[33m";
 const CYAN: &str = "\x1b[36m";
+const CLEAR_LINE: &str = "\r\x1b[2K";
 
 /// Width for right-aligned verb column.
 const VERB_WIDTH: usize = 12;
 
+/// Width of the progress bar between its brackets.
+const BAR_WIDTH: usize = 30;
+
 /// Verbosity level for output.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Verbosity {
@@ -32,6 +38,8 @@ pub enum Verbosity {
 pub struct Printer {
     color: bool,
     verbosity: Verbosity,
+    /// Progress bar currently drawn below the status lines, if any.
+    bar: Mutex<Option<String>>,
 }
 
 impl Printer {
@@ -39,6 +47,7 @@ impl Printer {
         Self {
             color: io::stderr().is_terminal(),
             verbosity: Verbosity::Normal,
+            bar: Mutex::new(None),
         }
     }
 
@@ -47,6 +56,7 @@ impl Printer {
         Self {
             color: io::stderr().is_terminal(),
             verbosity,
+            bar: Mutex::new(None),
         }
     }
 
@@ -138,7 +148,12 @@ impl Printer {
     }
 
     fn print_line(&self, color: &str, verb: &str, message: &str) {
+        // Status lines scroll above the progress bar, which is redrawn after them
+        let bar = self.bar.lock().unwrap_or_else(|e| e.into_inner());
         let mut stderr = io::stderr().lock();
+        if bar.is_some() {
+            let _ = write!(stderr, "{CLEAR_LINE}");
+        }
         if self.color {
             let _ = writeln!(
                 stderr,
@@ -147,6 +162,95 @@ impl Printer {
         } else {
             let _ = writeln!(stderr, "{verb:>VERB_WIDTH$} {message}");
         }
+        if let Some(line) = bar.as_deref() {
+            let _ = write!(stderr, "{line}");
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Draw `line` as the progress bar, or clear the bar when `None`.
+    fn draw_bar(&self, line: Option<String>) {
+        let mut bar = self.bar.lock().unwrap_or_else(|e| e.into_inner());
+        if bar.is_none() && line.is_none() {
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "{CLEAR_LINE}{}", line.as_deref().unwrap_or(""));
+        let _ = stderr.flush();
+        *bar = line;
+    }
+}
+
+/// A progress bar for long-running work, drawn beneath the status lines.
+///
+/// Only drawn when stderr is a terminal and the printer is not quiet, so
+/// piped and logged output stays line-oriented. Safe to tick from several
+/// threads at once. The bar is cleared when the `Progress` is dropped.
+pub struct Progress<'a> {
+    printer: &'a Printer,
+    verb: String,
+    total: usize,
+    done: AtomicUsize,
+}
+
+impl<'a> Progress<'a> {
+    /// Start a bar labelled `verb` that completes after `total` ticks.
+    pub fn new(printer: &'a Printer, verb: &str, total: usize) -> Self {
+        Self {
+            printer,
+            verb: verb.to_string(),
+            total,
+            done: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of ticks that completes the bar.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns true if the bar is drawn at all.
+    pub fn is_visible(&self) -> bool {
+        self.printer.color && self.printer.verbosity != Verbosity::Quiet && self.total > 0
+    }
+
+    /// Advance the bar by one.
+    pub fn tick(&self) {
+        self.advance(1);
+    }
+
+    /// Advance the bar by `n`.
+    pub fn advance(&self, n: usize) {
+        let done = self.done.fetch_add(n, Ordering::SeqCst) + n;
+        if let Some(line) = self.line(done) {
+            self.printer.draw_bar(Some(line));
+        }
+    }
+
+    /// The bar as drawn after `done` ticks, or `None` when hidden.
+    fn line(&self, done: usize) -> Option<String> {
+        if !self.is_visible() {
+            return None;
+        }
+        let done = done.min(self.total);
+        let filled = done * BAR_WIDTH / self.total;
+        let mut bar = "=".repeat(filled);
+        if filled < BAR_WIDTH {
+            bar.push('>');
+            bar.push_str(&" ".repeat(BAR_WIDTH - filled - 1));
+        }
+        Some(format!(
+            "{BOLD}{CYAN}{:>VERB_WIDTH$}{RESET} [{}] {}/{}",
+            self.verb, bar, done, self.total
+        ))
+    }
+}
+
+impl Drop for Progress<'_> {
+    fn drop(&mut self) {
+        if self.is_visible() {
+            self.printer.draw_bar(None);
+        }
     }
 }
 
@@ -192,6 +296,34 @@ mod tests {
         assert_eq!(plural(5, "shape", "shapes"), "5 shapes");
     }
 
+    fn printer(color: bool, verbosity: Verbosity) -> Printer {
+        Printer {
+            color,
+            verbosity,
+            bar: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn test_progress_line() {
+        let printer = printer(true, Verbosity::Normal);
+        let progress = Progress::new(&printer, "Building", 4);
+        let line = progress.line(1).unwrap();
+        assert!(line.contains(&format!("[{}>{}] 1/4", "=".repeat(7), " ".repeat(22))));
+        assert!(progress.line(4).unwrap().contains(&format!("[{}] 4/4", "=".repeat(30))));
+    }
+
+    #[test]
+    fn test_progress_hidden_when_quiet_or_piped() {
+        for printer in [printer(true, Verbosity::Quiet), printer(false, Verbosity::Normal)] {
+            let progress = Progress::new(&printer, "Building", 3);
+            assert!(!progress.is_visible());
+            progress.tick();
+            assert_eq!(progress.line(1), None);
+            assert!(printer.bar.lock().unwrap().is_none());
+        }
+    }
+
     #[test]
     fn test_display_path_absolute() {
         use std::path::Path;
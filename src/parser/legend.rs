@@ -6,6 +6,7 @@ use crate::error::{PxError, Result};
 
 use super::span::{Span, Spanned};
 use super::types::LegendValue;
+use crate::types::GradientDirection;
 
 /// Result of extracting a legend section.
 #[derive(Debug)]
@@ -182,6 +183,15 @@ fn parse_legend_value(s: &str) -> Result<LegendValue> {
     // Check for complex value: { ... }
     if trimmed.starts_with('{') && trimmed.ends_with('}') {
         parse_complex_legend_value(trimmed)
+    } else if trimmed.contains(" offset ") {
+        // Shorthand fill with a phase shift: `~: checker offset 1,0`
+        let (name, offset) = parse_fill_offset(trimmed)?;
+        Ok(LegendValue::Complex {
+            name,
+            fill: true,
+            bindings: HashMap::new(),
+            offset,
+        })
     } else {
         // Simple reference
         Ok(LegendValue::Reference(trimmed.to_string()))
@@ -189,14 +199,19 @@ fn parse_legend_value(s: &str) -> Result<LegendValue> {
 }
 
 /// Parse a complex legend value: { fill: checker, A: $edge, B: $fill }
+/// or { gradient: vertical, from: $sky, to: $sea }
 fn parse_complex_legend_value(s: &str) -> Result<LegendValue> {
     let inner = &s[1..s.len() - 1]; // Strip { }
 
     let mut name = None;
     let mut fill = false;
+    let mut offset = (0, 0);
     let mut bindings = HashMap::new();
+    let mut direction = None;
+    let mut from = None;
+    let mut to = None;
 
-    for part in inner.split(',') {
+    for part in split_entries(inner) {
         let part = part.trim();
         if part.is_empty() {
             continue;
@@ -216,9 +231,25 @@ fn parse_complex_legend_value(s: &str) -> Result<LegendValue> {
                 fill = false;
             }
             "fill" => {
-                name = Some(value.to_string());
+                let (brush, shift) = parse_fill_offset(value)?;
+                name = Some(brush);
+                offset = shift;
                 fill = true;
             }
+            "gradient" => {
+                direction = Some(match value {
+                    "vertical" => GradientDirection::Vertical,
+                    "horizontal" => GradientDirection::Horizontal,
+                    _ => {
+                        return Err(PxError::Parse {
+                            message: format!("Unknown gradient direction: {}", value),
+                            help: Some("Use `gradient: vertical` or `gradient: horizontal`".to_string()),
+                        });
+                    }
+                });
+            }
+            "from" => from = Some(value.to_string()),
+            "to" => to = Some(value.to_string()),
             _ if key.len() == 1 => {
                 // Single char key = colour binding
                 let binding_char = key.chars().next().unwrap();
@@ -233,6 +264,16 @@ fn parse_complex_legend_value(s: &str) -> Result<LegendValue> {
         }
     }
 
+    if let Some(direction) = direction {
+        return match (from, to) {
+            (Some(from), Some(to)) => Ok(LegendValue::Gradient { from, to, direction }),
+            _ => Err(PxError::Parse {
+                message: "Gradient legend entry needs both 'from' and 'to' colours".to_string(),
+                help: Some("e.g. `~: { gradient: vertical, from: $sky, to: $sea }`".to_string()),
+            }),
+        };
+    }
+
     let name = name.ok_or_else(|| PxError::Parse {
         message: "Legend entry missing 'stamp' or 'fill' key".to_string(),
         help: None,
@@ -242,9 +283,42 @@ fn parse_complex_legend_value(s: &str) -> Result<LegendValue> {
         name,
         fill,
         bindings,
+        offset,
     })
 }
 
+/// Split the inside of `{ ... }` at commas, keeping the `dx,dy` of a fill
+/// offset in one piece.
+fn split_entries(inner: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for part in inner.split(',') {
+        match parts.last_mut() {
+            Some(last) if !part.contains(':') && last.rsplit_once(" offset ").is_some_and(|(_, o)| !o.contains(',')) => {
+                last.push(',');
+                last.push_str(part);
+            }
+            _ => parts.push(part.to_string()),
+        }
+    }
+    parts
+}
+
+/// Split a fill value into its brush name and phase offset:
+/// `checker offset 1,0` is `("checker", (1, 0))`, `checker` is `("checker", (0, 0))`.
+fn parse_fill_offset(value: &str) -> Result<(String, (i32, i32))> {
+    let Some((name, offset)) = value.split_once(" offset ") else {
+        return Ok((value.to_string(), (0, 0)));
+    };
+    let invalid = || PxError::Parse {
+        message: format!("Invalid fill offset: {}", offset.trim()),
+        help: Some("Give whole pixels as dx,dy, e.g. `~: checker offset 1,0`".to_string()),
+    };
+    let (dx, dy) = offset.split_once(',').ok_or_else(invalid)?;
+    let dx = dx.trim().parse().map_err(|_| invalid())?;
+    let dy = dy.trim().parse().map_err(|_| invalid())?;
+    Ok((name.trim().to_string(), (dx, dy)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,7 +355,7 @@ mod tests {
         let result = extract_legend(source, 0).unwrap().unwrap();
 
         match &result.entries.get(&'~').unwrap().value {
-            LegendValue::Complex { name, fill, bindings } => {
+            LegendValue::Complex { name, fill, bindings, .. } => {
                 assert_eq!(name, "checker");
                 assert!(fill);
                 assert_eq!(bindings.get(&'A'), Some(&"$edge".to_string()));
@@ -291,6 +365,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extract_fill_offset() {
+        let source = "---\n~: checker offset 1,0\n=: { fill: checker offset -1, 2, A: $edge }\n";
+
+        let result = extract_legend(source, 0).unwrap().unwrap();
+
+        match &result.entries.get(&'~').unwrap().value {
+            LegendValue::Complex { name, fill, offset, .. } => {
+                assert_eq!(name, "checker");
+                assert!(fill);
+                assert_eq!(*offset, (1, 0));
+            }
+            _ => panic!("Expected complex legend value"),
+        }
+        match &result.entries.get(&'=').unwrap().value {
+            LegendValue::Complex { name, bindings, offset, .. } => {
+                assert_eq!(name, "checker");
+                assert_eq!(*offset, (-1, 2));
+                assert_eq!(bindings.get(&'A'), Some(&"$edge".to_string()));
+            }
+            _ => panic!("Expected complex legend value"),
+        }
+
+        assert!(extract_legend("---\n~: checker offset 1\n", 0).is_err());
+    }
+
     #[test]
     fn test_extract_legend_stamp_not_fill() {
         let source = "---\nC: { stamp: checker, A: $edge }\n";
@@ -305,6 +405,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extract_gradient_legend_entry() {
+        let source = "---\n~: { gradient: horizontal, from: $sky, to: #FFFFFF }\n";
+
+        let result = extract_legend(source, 0).unwrap().unwrap();
+
+        assert_eq!(
+            result.entries.get(&'~').unwrap().value,
+            LegendValue::Gradient {
+                from: "$sky".to_string(),
+                to: "#FFFFFF".to_string(),
+                direction: GradientDirection::Horizontal,
+            }
+        );
+        assert!(parse_complex_legend_value("{ gradient: vertical, from: $sky }").is_err());
+        assert!(parse_complex_legend_value("{ gradient: diagonal, from: $sky, to: $sea }").is_err());
+    }
+
     #[test]
     fn test_legend_ends_at_new_definition() {
         let source = "---\nB: brick\n\n---\nname: next-shape\n";
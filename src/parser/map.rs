@@ -2,16 +2,30 @@
 //!
 //! Parses `.map.md` files into `Map` instances.
 //! Structurally identical to prefab parsing - legend entries must be
-//! simple name references (not brush/fill).
+//! simple name references (not brush/fill). A `---layer---` line inside
+//! the grid starts another layer, drawn over the ones before it:
+//!
+//! ````text
+//! ```px
+//! ####
+//! #..#
+//! ---layer---
+//!
+//!  T
+//! ```
+//! ````
 
 use std::collections::HashMap;
 
 use crate::error::{PxError, Result};
-use crate::parser::shape::parse_grid;
+use crate::parser::shape::{is_grid_comment, parse_grid};
 use crate::parser::types::LegendValue;
 use crate::parser::{parse_documents, RawDocument};
 use crate::types::Map;
 
+/// Marker line between stacked layers in a map grid.
+pub const LAYER_MARKER: &str = "---layer---";
+
 /// Parse a map file into one or more maps.
 ///
 /// Each document in the file becomes a separate map.
@@ -31,15 +45,50 @@ fn parse_map_document(doc: RawDocument) -> Result<Map> {
         .and_then(|v| v.value.as_u64())
         .map(|s| s as u32);
 
-    let grid = if let Some(body) = &doc.body {
-        parse_grid(&body.value)
-    } else {
-        vec![vec![' ']]
+    let layers: Vec<Vec<Vec<char>>> = match &doc.body {
+        Some(body) => split_layers(&body.value)
+            .iter()
+            .enumerate()
+            .map(|(i, layer)| if i == 0 { parse_grid(layer) } else { parse_overlay(layer) })
+            .collect(),
+        None => vec![vec![vec![' ']]],
     };
 
     let legend = convert_map_legend(&name, doc.legend)?;
 
-    Ok(Map::with_scale(name, tags, grid, legend, scale))
+    Ok(Map::with_scale(name, tags, vec![], legend, scale).with_layers(layers))
+}
+
+/// Parse an overlay layer's grid. Unlike the base grid, leading blank rows
+/// are kept so the layer lines up with the ones below.
+fn parse_overlay(source: &str) -> Vec<Vec<char>> {
+    let mut rows: Vec<Vec<char>> = source
+        .lines()
+        .filter(|l| !is_grid_comment(l))
+        .map(|line| line.chars().collect())
+        .collect();
+    while rows.last().is_some_and(|r| r.is_empty()) {
+        rows.pop();
+    }
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, ' ');
+    }
+    rows
+}
+
+/// Split a map body into the source of each layer, bottom first.
+fn split_layers(body: &str) -> Vec<String> {
+    let mut layers = vec![String::new()];
+    for line in body.lines() {
+        if line.trim() == LAYER_MARKER {
+            layers.push(String::new());
+        } else if let Some(layer) = layers.last_mut() {
+            layer.push_str(line);
+            layer.push('\n');
+        }
+    }
+    layers
 }
 
 /// Convert parser legend to map legend (only simple references allowed).
@@ -58,11 +107,11 @@ fn convert_map_legend(
             LegendValue::Reference(name) => {
                 result.insert(glyph, name);
             }
-            LegendValue::Complex { .. } => {
+            LegendValue::Complex { .. } | LegendValue::Gradient { .. } => {
                 return Err(PxError::Parse {
                     message: format!(
                         "Map '{}': legend entry '{}' must be a simple name reference, \
-                         not a brush/fill binding",
+                         not a brush, fill or gradient",
                         map_name, glyph
                     ),
                     help: Some("Use `G: shape-name` format in map legends".to_string()),
@@ -244,4 +293,25 @@ name: empty-map
         assert_eq!(map.width(), 1);
         assert_eq!(map.height(), 1);
     }
+
+    #[test]
+    fn test_parse_single_layer() {
+        let maps = parse_map_file("---\nname: one\n---\n\n```px\nAB\n```\n\n---\nA: a\nB: b\n").unwrap();
+        assert_eq!(maps[0].layers().len(), 1);
+        assert_eq!(maps[0].grid(), &[vec!['A', 'B']]);
+    }
+
+    #[test]
+    fn test_parse_layers() {
+        let source = "---\nname: room\n---\n\n```px\n###\n#.#\n---layer---\n\n T\n```\n\n---\n#: wall\n.: floor\nT: torch\n";
+        let maps = parse_map_file(source).unwrap();
+        let map = &maps[0];
+
+        assert_eq!(map.layers().len(), 2);
+        assert_eq!(map.grid(), &[vec!['#', '#', '#'], vec!['#', '.', '#']]);
+        // The blank row keeps the torch on the second row
+        assert_eq!(map.layers()[1], vec![vec![' ', ' '], vec![' ', 'T']]);
+        assert_eq!(map.size(), (3, 2));
+        assert!(map.glyphs().contains(&'T'));
+    }
 }
@@ -287,6 +287,10 @@ fn parse_single_palette(source: &str) -> Result<PaletteBuilder> {
         builder.inherits(inherits);
     }
 
+    if let Some(scheme) = frontmatter.get("scheme").and_then(|v| v.as_str()) {
+        builder.scheme(scheme);
+    }
+
     // Parse colour definitions from body
     parse_palette_content(body, &mut builder)?;
 
@@ -371,6 +375,7 @@ $background: $green-light
         let palette = builders.into_iter().next().unwrap().build(None).unwrap();
 
         assert_eq!(palette.name, "mi");
+        assert_eq!(palette.scheme(), Some("retro"));
         assert_eq!(palette.get("gold"), Some(Colour::from_hex("#F7AD45").unwrap()));
         assert_eq!(palette.get("edge"), palette.get("black"));
         assert_eq!(palette.get("fill"), palette.get("green-dark"));
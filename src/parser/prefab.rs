@@ -57,11 +57,11 @@ fn convert_prefab_legend(
             LegendValue::Reference(name) => {
                 result.insert(glyph, name);
             }
-            LegendValue::Complex { .. } => {
+            LegendValue::Complex { .. } | LegendValue::Gradient { .. } => {
                 return Err(PxError::Parse {
                     message: format!(
                         "Prefab '{}': legend entry '{}' must be a simple name reference, \
-                         not a brush/fill binding",
+                         not a brush, fill or gradient",
                         prefab_name, glyph
                     ),
                     help: Some("Use `G: shape-name` format in prefab legends".to_string()),
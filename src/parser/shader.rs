@@ -101,6 +101,39 @@ fn parse_effect(value: &serde_yaml::Value) -> Option<Effect> {
                 .unwrap_or(0.0) as f32;
             Some(Effect::contrast(amount))
         }
+        "outline" => {
+            let colour = map
+                .get("colour")
+                .and_then(|v| v.as_str())
+                .unwrap_or("$edge");
+            let thickness = map
+                .get("thickness")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(1) as u32;
+            Some(Effect::outline(colour, thickness))
+        }
+        "drop-shadow" => {
+            let dx = map.get("dx").and_then(|v| v.as_i64()).unwrap_or(1) as i32;
+            let dy = map.get("dy").and_then(|v| v.as_i64()).unwrap_or(1) as i32;
+            let colour = map
+                .get("colour")
+                .and_then(|v| v.as_str())
+                .unwrap_or("#000000");
+            let alpha = map
+                .get("alpha")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(128)
+                .min(255) as u8;
+            Some(Effect::drop_shadow(dx, dy, colour, alpha))
+        }
+        "levels" => {
+            let get = |key: &str, default: f64| map.get(key).and_then(|v| v.as_f64()).unwrap_or(default) as f32;
+            Some(Effect::levels(get("brightness", 0.0), get("contrast", 0.0), get("gamma", 1.0)))
+        }
+        "posterize" => {
+            let levels = map.get("levels").and_then(|v| v.as_u64()).unwrap_or(4).min(255) as u8;
+            Some(Effect::posterize(levels))
+        }
         _ => {
             // Unknown effect - store as custom
             let mut params = HashMap::new();
@@ -226,6 +259,91 @@ effects:
         assert_eq!(shader.effects[1].type_name(), "scanlines");
     }
 
+    #[test]
+    fn test_parse_shader_outline_effect() {
+        let source = r##"---
+name: outlined
+palette: dungeon
+effects:
+  - type: outline
+    colour: "#FF0000"
+    thickness: 2
+  - type: outline
+---
+"##;
+
+        let builders = parse_shader_file(source).unwrap();
+        let shader = builders[0].clone().build(None).unwrap();
+
+        assert_eq!(shader.effects[0], Effect::outline("#FF0000", 2));
+        assert_eq!(shader.effects[1], Effect::outline("$edge", 1));
+    }
+
+    #[test]
+    fn test_parse_shader_drop_shadow_effect() {
+        let source = r##"---
+name: shadowed
+palette: dungeon
+effects:
+  - type: drop-shadow
+    dx: -2
+    dy: 3
+    colour: $edge
+    alpha: 200
+  - type: drop-shadow
+---
+"##;
+
+        let builders = parse_shader_file(source).unwrap();
+        let shader = builders[0].clone().build(None).unwrap();
+
+        assert_eq!(shader.effects[0], Effect::drop_shadow(-2, 3, "$edge", 200));
+        assert_eq!(shader.effects[1], Effect::drop_shadow(1, 1, "#000000", 128));
+    }
+
+    #[test]
+    fn test_parse_shader_levels_effect() {
+        let source = r##"---
+name: graded
+palette: dungeon
+effects:
+  - type: levels
+    brightness: 20
+    contrast: -10.5
+    gamma: 2.2
+  - type: levels
+---
+"##;
+
+        let builders = parse_shader_file(source).unwrap();
+        let shader = builders[0].clone().build(None).unwrap();
+
+        assert_eq!(shader.effects[0], Effect::levels(20.0, -10.5, 2.2));
+        assert_eq!(shader.effects[1], Effect::levels(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_parse_shader_posterize_effect() {
+        let source = r##"---
+name: poster
+palette: dungeon
+effects:
+  - type: posterize
+    levels: 2
+  - type: posterize
+  - type: posterize
+    levels: 1
+---
+"##;
+
+        let builders = parse_shader_file(source).unwrap();
+        let shader = builders[0].clone().build(None).unwrap();
+
+        assert_eq!(shader.effects[0], Effect::Posterize { levels: 2 });
+        assert_eq!(shader.effects[1], Effect::Posterize { levels: 4 });
+        assert_eq!(shader.effects[2], Effect::Posterize { levels: 2 });
+    }
+
     #[test]
     fn test_parse_shader_with_inheritance() {
         let source = r#"---
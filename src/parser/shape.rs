@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use crate::error::Result;
 use crate::parser::types::LegendValue;
 use crate::parser::{parse_documents, RawDocument};
-use crate::types::{LegendEntry, Shape};
+use crate::types::{Anchor, CellSize, LegendEntry, NineSlice, Shape};
 
 /// Parse a shape file into one or more shapes.
 ///
@@ -31,6 +31,18 @@ fn parse_shape_document(doc: RawDocument) -> Result<Shape> {
         .and_then(|v| v.value.as_u64())
         .map(|s| s as u32);
 
+    // Get anchor (pivot) from frontmatter
+    let anchor = doc.get_frontmatter_str("anchor").and_then(Anchor::parse);
+
+    // Get background colour ($name or #hex)
+    let background = doc.get_frontmatter_str("background").map(str::to_string);
+
+    // Get 9-slice borders: [left, right, top, bottom]
+    let slice = doc.get_frontmatter("slice").and_then(|v| parse_slice(&v.value));
+
+    // Get cell size: pixels per glyph as WxH, or `stamp`
+    let cell = doc.get_frontmatter_str("cell").and_then(CellSize::parse);
+
     // Parse ASCII grid from body
     let grid = if let Some(body) = &doc.body {
         parse_grid(&body.value)
@@ -41,15 +53,43 @@ fn parse_shape_document(doc: RawDocument) -> Result<Shape> {
     // Convert legend
     let legend = convert_legend(doc.legend);
 
-    Ok(Shape::with_scale(name, tags, grid, legend, scale))
+    let mut shape = Shape::with_scale(name, tags, grid, legend, scale);
+    shape.anchor = anchor;
+    shape.slice = slice;
+    shape.background = background;
+    shape.cell = cell;
+
+    Ok(shape)
+}
+
+/// Parse `[left, right, top, bottom]` slice borders.
+fn parse_slice(value: &serde_yaml::Value) -> Option<NineSlice> {
+    let borders: Vec<usize> = value
+        .as_sequence()?
+        .iter()
+        .map(|v| v.as_u64().map(|n| n as usize))
+        .collect::<Option<_>>()?;
+    match borders[..] {
+        [left, right, top, bottom] => Some(NineSlice::new(left, right, top, bottom)),
+        _ => None,
+    }
+}
+
+/// Starts a comment line in a shape, prefab or map grid.
+pub(crate) const GRID_COMMENT: &str = "//";
+
+/// Whether a grid line is a comment rather than a row. The `//` must open
+/// the line, since leading spaces are part of a row.
+pub(crate) fn is_grid_comment(line: &str) -> bool {
+    line.starts_with(GRID_COMMENT)
 }
 
-/// Parse the ASCII grid from body content.
+/// Parse the ASCII grid from body content. Comment lines are skipped.
 pub(crate) fn parse_grid(body: &str) -> Vec<Vec<char>> {
     let mut rows: Vec<Vec<char>> = Vec::new();
     let mut max_width = 0;
 
-    for line in body.lines() {
+    for line in body.lines().filter(|l| !is_grid_comment(l)) {
         // Collect all characters (preserve spaces within the line)
         let row: Vec<char> = line.chars().collect();
 
@@ -92,19 +132,22 @@ fn convert_legend(
         .into_iter()
         .map(|(glyph, spanned)| {
             let entry = match spanned.value {
+                LegendValue::Reference(name) if name == "guide" => LegendEntry::Guide,
                 LegendValue::Reference(name) => LegendEntry::StampRef(name),
                 LegendValue::Complex {
                     name,
                     fill,
                     bindings,
+                    offset,
                 } => {
                     // bindings is already HashMap<char, String>
                     if fill {
-                        LegendEntry::Fill { name, bindings }
+                        LegendEntry::Fill { name, bindings, offset }
                     } else {
                         LegendEntry::BrushRef { name, bindings }
                     }
                 }
+                LegendValue::Gradient { from, to, direction } => LegendEntry::Gradient { from, to, direction },
             };
             (glyph, entry)
         })
@@ -139,6 +182,14 @@ name: test
         assert_eq!(shape.get(1, 1), Some('.'));
     }
 
+    #[test]
+    fn test_parse_grid_skips_comments() {
+        let grid = parse_grid("// roof\n+--+\n//   long comment line\n|..|\n");
+        assert_eq!(grid, vec![vec!['+', '-', '-', '+'], vec!['|', '.', '.', '|']]);
+        // Only a `//` at the start of the line is a comment
+        assert_eq!(parse_grid(" //\n"), vec![vec![' ', '/', '/']]);
+    }
+
     #[test]
     fn test_parse_shape_with_tags() {
         // Tags with # must be quoted in YAML (# starts a comment otherwise)
@@ -184,6 +235,14 @@ B: brick
         }
     }
 
+    #[test]
+    fn test_parse_shape_guide_legend() {
+        let source = "---\nname: test\n---\n\n```px\n#G\n```\n\n---\nG: guide\n";
+
+        let shapes = parse_shape_file(source).unwrap();
+        assert_eq!(shapes[0].get_legend('G'), Some(&LegendEntry::Guide));
+    }
+
     #[test]
     fn test_parse_multiple_shapes() {
         let source = r#"---
@@ -271,7 +330,7 @@ name: test
         let shapes = parse_shape_file(source).unwrap();
         let shape = &shapes[0];
 
-        if let Some(LegendEntry::Fill { name, bindings }) = shape.get_legend('~') {
+        if let Some(LegendEntry::Fill { name, bindings, .. }) = shape.get_legend('~') {
             assert_eq!(name, "checker");
             assert_eq!(bindings.get(&'A'), Some(&"$edge".to_string()));
             assert_eq!(bindings.get(&'B'), Some(&"$fill".to_string()));
@@ -334,6 +393,85 @@ scale: 4
         assert_eq!(shape.scale, Some(4));
     }
 
+    #[test]
+    fn test_parse_shape_with_anchor() {
+        let source = r#"---
+name: anchored
+anchor: bottom
+---
+
+```px
+##
+```
+"#;
+
+        let shapes = parse_shape_file(source).unwrap();
+        assert_eq!(shapes[0].anchor, Some(Anchor::Relative(0.5, 1.0)));
+    }
+
+    #[test]
+    fn test_parse_shape_cell_size() {
+        let source = "---\nname: floor\ncell: 2x3\n---\n\n```px\nBB\n```\n\n---\nname: flat\ncell: 0x2\n---\n\n```px\nB\n```\n\n---\nname: wall\ncell: stamp\n---\n\n```px\nB\n```\n";
+        let shapes = parse_shape_file(source).unwrap();
+        assert_eq!(shapes[0].cell, Some(CellSize::Pixels(2, 3)));
+        assert_eq!(shapes[1].cell, None);
+        assert_eq!(shapes[2].cell, Some(CellSize::Stamp));
+    }
+
+    #[test]
+    fn test_parse_shape_with_background() {
+        let source = r##"---
+name: tile
+background: $fill
+---
+
+```px
+#x
+```
+
+---
+name: sky
+background: "#87CEEB"
+---
+
+```px
+x
+```
+"##;
+
+        let shapes = parse_shape_file(source).unwrap();
+        assert_eq!(shapes[0].background.as_deref(), Some("$fill"));
+        assert_eq!(shapes[1].background.as_deref(), Some("#87CEEB"));
+    }
+
+    #[test]
+    fn test_parse_shape_with_slice() {
+        let source = r#"---
+name: panel
+slice: [1, 2, 1, 1]
+---
+
+```px
+####
+####
+####
+```
+
+---
+name: bad-slice
+slice: [1, 2]
+---
+
+```px
+#
+```
+"#;
+
+        let shapes = parse_shape_file(source).unwrap();
+        assert_eq!(shapes[0].slice, Some(NineSlice::new(1, 2, 1, 1)));
+        assert_eq!(shapes[1].slice, None);
+    }
+
     #[test]
     fn test_parse_shape_without_scale() {
         let source = r#"---
@@ -150,6 +150,18 @@ fn parse_single_target(source: &str) -> Result<TargetBuilder> {
     if let Some(shader) = frontmatter.get("shader").and_then(|v| v.as_str()) {
         builder.shader(shader);
     }
+    if let Some(max) = frontmatter.get("max_colours").and_then(|v| v.as_u64()) {
+        builder.max_colours(max as usize);
+    }
+    if let Some(columns) = frontmatter.get("columns").and_then(|v| v.as_u64()) {
+        builder.columns(columns as u32);
+    }
+    if let Some(width) = frontmatter.get("max_width").and_then(|v| v.as_u64()) {
+        builder.max_width(width as u32);
+    }
+    if let Some(inherits) = frontmatter.get("inherits").and_then(|v| v.as_str()) {
+        builder.inherits(inherits);
+    }
 
     // Parse body key-value lines (body values override frontmatter)
     parse_target_body(body, &mut builder)?;
@@ -165,6 +177,10 @@ fn parse_single_target(source: &str) -> Result<TargetBuilder> {
 /// - `padding: 1`
 /// - `shader: dark`
 /// - `palette_mode: indexed`
+/// - `max_colours: 4`
+/// - `columns: 3`
+/// - `max_width: 256`
+/// - `inherits: web`
 ///
 /// Unknown keys are ignored for forward compatibility.
 fn parse_target_body(body: &str, builder: &mut TargetBuilder) -> Result<()> {
@@ -211,6 +227,24 @@ fn parse_target_body(body: &str, builder: &mut TargetBuilder) -> Result<()> {
                 "shader" => {
                     builder.shader(value);
                 }
+                "max_colours" => {
+                    if let Ok(n) = value.parse::<usize>() {
+                        builder.max_colours(n);
+                    }
+                }
+                "columns" => {
+                    if let Ok(n) = value.parse::<u32>() {
+                        builder.columns(n);
+                    }
+                }
+                "max_width" => {
+                    if let Ok(n) = value.parse::<u32>() {
+                        builder.max_width(n);
+                    }
+                }
+                "inherits" => {
+                    builder.inherits(value);
+                }
                 // Unknown keys ignored for forward compat (tile, colors, etc.)
                 _ => {}
             }
@@ -252,6 +286,9 @@ scale: 4
 sheet: auto
 padding: 2
 shader: dark
+max_colours: 4
+columns: 3
+max_width: 256
 "#;
 
         let builders = parse_target_file(source).unwrap();
@@ -262,6 +299,9 @@ shader: dark
         assert_eq!(target.sheet, SheetConfig::Auto);
         assert_eq!(target.padding, Some(2));
         assert_eq!(target.shader, Some("dark".to_string()));
+        assert_eq!(target.max_colours, Some(4));
+        assert_eq!(target.columns, Some(3));
+        assert_eq!(target.max_width, Some(256));
     }
 
     #[test]
@@ -398,4 +438,28 @@ name: no-format
 
         assert_eq!(target.format, "png");
     }
+
+    #[test]
+    fn test_parse_target_inherits() {
+        let source = r#"---
+name: web-4x
+inherits: web
+---
+
+scale: 4
+"#;
+
+        let builders = parse_target_file(source).unwrap();
+        assert_eq!(builders[0].parent_name(), Some("web"));
+
+        let body_source = r#"---
+name: retro
+---
+
+inherits: sheet
+"#;
+
+        let builders = parse_target_file(body_source).unwrap();
+        assert_eq!(builders[0].parent_name(), Some("sheet"));
+    }
 }
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 
 use super::span::{Span, Spanned};
+use crate::types::GradientDirection;
 
 /// A raw parsed document before type-specific processing.
 ///
@@ -45,6 +46,16 @@ pub enum LegendValue {
         fill: bool,
         /// Colour bindings for brush tokens
         bindings: HashMap<char, String>,
+        /// Fill phase shift: `fill: checker offset 1,0`
+        offset: (i32, i32),
+    },
+
+    /// Gradient between two colours
+    /// `~: { gradient: vertical, from: $sky, to: $sea }`
+    Gradient {
+        from: String,
+        to: String,
+        direction: GradientDirection,
     },
 }
 
@@ -122,6 +133,7 @@ mod tests {
             name: "checker".to_string(),
             fill: true,
             bindings: [('A', "$edge".to_string())].into_iter().collect(),
+            offset: (0, 0),
         };
 
         assert!(matches!(simple, LegendValue::Reference(_)));
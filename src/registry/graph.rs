@@ -1,9 +1,10 @@
 //! Dependency graph for assets.
 //!
 //! Tracks which assets depend on which other assets, enabling
-//! topological sort for build order and cycle detection.
+//! topological sort for build order and cycle detection. Ordered collections
+//! keep the build order stable between runs.
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 use super::types::AssetId;
 
@@ -11,13 +12,13 @@ use super::types::AssetId;
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
     /// Adjacency list: asset -> assets it depends on.
-    dependencies: HashMap<AssetId, HashSet<AssetId>>,
+    dependencies: BTreeMap<AssetId, BTreeSet<AssetId>>,
 
     /// Reverse adjacency list: asset -> assets that depend on it.
-    dependents: HashMap<AssetId, HashSet<AssetId>>,
+    dependents: BTreeMap<AssetId, BTreeSet<AssetId>>,
 
     /// All known assets (including those with no dependencies).
-    assets: HashSet<AssetId>,
+    assets: BTreeSet<AssetId>,
 }
 
 impl DependencyGraph {
@@ -108,7 +109,7 @@ impl DependencyGraph {
 
         // Actually, let's recalculate correctly:
         // in_degree[x] = number of assets that x depends on (that haven't been processed)
-        let mut in_degree: HashMap<&AssetId, usize> = HashMap::new();
+        let mut in_degree: BTreeMap<&AssetId, usize> = BTreeMap::new();
 
         for asset in &self.assets {
             let count = self
@@ -152,6 +153,24 @@ impl DependencyGraph {
         Ok(result)
     }
 
+    /// Render the graph as Graphviz DOT.
+    ///
+    /// Nodes are named `kind:name`. Edges point from each dependency to the
+    /// assets that use it, so arrows follow build order. Output is sorted.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph px {\n    rankdir=LR;\n");
+        for asset in &self.assets {
+            dot.push_str(&format!("    {};\n", dot_id(asset)));
+        }
+        for (asset, deps) in &self.dependencies {
+            for dep in deps {
+                dot.push_str(&format!("    {} -> {};\n", dot_id(dep), dot_id(asset)));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
     /// Find a cycle in the graph (for error reporting).
     fn find_cycle(&self) -> Vec<AssetId> {
         // DFS to find a cycle
@@ -207,6 +226,11 @@ impl DependencyGraph {
     }
 }
 
+/// A quoted DOT node ID for an asset.
+fn dot_id(id: &AssetId) -> String {
+    format!("\"{}\"", id.to_string().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
 /// Error returned when a cycle is detected in the dependency graph.
 #[derive(Debug)]
 pub struct CycleError {
@@ -338,6 +362,20 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_to_dot() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(AssetId::shader("ui"), AssetId::palette("ui"));
+        graph.register(AssetId::shape("say \"hi\""));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph px {\n"));
+        assert!(dot.contains("    \"palette:ui\";\n"));
+        assert!(dot.contains("    \"palette:ui\" -> \"shader:ui\";\n"));
+        assert!(dot.contains(r#"    "shape:say \"hi\"";"#));
+        assert!(dot.ends_with("}\n"));
+    }
+
     #[test]
     fn test_dependency_count() {
         let mut graph = DependencyGraph::new();
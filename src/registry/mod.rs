@@ -19,7 +19,7 @@
 mod graph;
 pub mod types;
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::error::{PxError, Result};
 use crate::types::{Brush, Map, Palette, Prefab, Shader, Shape, Stamp, Target};
@@ -30,17 +30,18 @@ pub use types::{AssetId, AssetKind, AssetRef};
 /// Centralized storage for all px assets.
 ///
 /// The registry is immutable after construction - use `RegistryBuilder`
-/// to create a new registry.
+/// to create a new registry. Assets are stored by name in sorted order, so
+/// iteration (and anything built from it) is deterministic.
 #[derive(Debug)]
 pub struct AssetRegistry {
-    palettes: HashMap<String, Palette>,
-    stamps: HashMap<String, Stamp>,
-    brushes: HashMap<String, Brush>,
-    shaders: HashMap<String, Shader>,
-    shapes: HashMap<String, Shape>,
-    prefabs: HashMap<String, Prefab>,
-    maps: HashMap<String, Map>,
-    targets: HashMap<String, Target>,
+    palettes: BTreeMap<String, Palette>,
+    stamps: BTreeMap<String, Stamp>,
+    brushes: BTreeMap<String, Brush>,
+    shaders: BTreeMap<String, Shader>,
+    shapes: BTreeMap<String, Shape>,
+    prefabs: BTreeMap<String, Prefab>,
+    maps: BTreeMap<String, Map>,
+    targets: BTreeMap<String, Target>,
 
     /// Dependency graph for all assets.
     graph: DependencyGraph,
@@ -125,6 +126,11 @@ impl AssetRegistry {
         self.palettes.values()
     }
 
+    /// Palettes whose `scheme:` is `scheme`, in name order.
+    pub fn palettes_in_scheme<'a>(&'a self, scheme: &'a str) -> impl Iterator<Item = &'a Palette> {
+        self.palettes().filter(move |p| p.scheme() == Some(scheme))
+    }
+
     /// Get all stamps.
     pub fn stamps(&self) -> impl Iterator<Item = &Stamp> {
         self.stamps.values()
@@ -170,6 +176,30 @@ impl AssetRegistry {
         self.targets.values()
     }
 
+    /// Find the one asset called `name`, whatever its kind.
+    ///
+    /// Fails when no asset has that name, or when assets of several kinds
+    /// share it, since a `kind:name` ID is needed to pick one.
+    pub fn find_by_name(&self, name: &str) -> Result<AssetId> {
+        let mut matches: Vec<&AssetId> = self.graph.assets().filter(|id| id.name == name).collect();
+        matches.sort();
+
+        match matches.as_slice() {
+            [] => Err(PxError::Validation {
+                message: format!("No asset named '{}'", name),
+                help: Some("Run 'px list' to see the available assets".to_string()),
+            }),
+            [id] => Ok((*id).clone()),
+            ids => {
+                let options: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                Err(PxError::Validation {
+                    message: format!("'{}' is ambiguous: it names {} assets", name, ids.len()),
+                    help: Some(format!("Pick one with a kind: {}", options.join(", "))),
+                })
+            }
+        }
+    }
+
     /// Get the dependency graph.
     pub fn graph(&self) -> &DependencyGraph {
         &self.graph
@@ -201,14 +231,14 @@ impl AssetRegistry {
 /// Builder for constructing an AssetRegistry.
 #[derive(Debug, Default)]
 pub struct RegistryBuilder {
-    palettes: HashMap<String, Palette>,
-    stamps: HashMap<String, Stamp>,
-    brushes: HashMap<String, Brush>,
-    shaders: HashMap<String, Shader>,
-    shapes: HashMap<String, Shape>,
-    prefabs: HashMap<String, Prefab>,
-    maps: HashMap<String, Map>,
-    targets: HashMap<String, Target>,
+    palettes: BTreeMap<String, Palette>,
+    stamps: BTreeMap<String, Stamp>,
+    brushes: BTreeMap<String, Brush>,
+    shaders: BTreeMap<String, Shader>,
+    shapes: BTreeMap<String, Shape>,
+    prefabs: BTreeMap<String, Prefab>,
+    maps: BTreeMap<String, Map>,
+    targets: BTreeMap<String, Target>,
 }
 
 impl RegistryBuilder {
@@ -341,8 +371,11 @@ impl RegistryBuilder {
             graph.register(id.clone());
 
             // Palette inheritance creates a dependency
-            // Note: Palette struct doesn't expose parent, so we skip this for now
-            // This would need to be added to the Palette type or tracked separately
+            if let Some(parent) = palette.parent() {
+                if self.palettes.contains_key(parent) {
+                    graph.add_dependency(id, AssetId::palette(parent));
+                }
+            }
         }
 
         // Stamps (no dependencies on other assets currently)
@@ -390,6 +423,16 @@ impl RegistryBuilder {
                             graph.add_dependency(id.clone(), AssetId::brush(name));
                         }
                     }
+                    crate::types::LegendEntry::Guide | crate::types::LegendEntry::Gradient { .. } => {}
+                }
+            }
+
+            // Stamps drawn by their own glyph, where the legend doesn't remap it
+            for stamp in self.stamps.values() {
+                if let Some(glyph) = stamp.glyph.filter(|g| !shape.legend().contains_key(g)) {
+                    if shape.grid().iter().flatten().any(|&c| c == glyph) {
+                        graph.add_dependency(id.clone(), AssetId::stamp(&stamp.name));
+                    }
                 }
             }
         }
@@ -400,6 +443,10 @@ impl RegistryBuilder {
             graph.register(id.clone());
 
             for ref_name in prefab.referenced_names() {
+                // Self-references are left to validation, which names the glyph
+                if *ref_name == prefab.name {
+                    continue;
+                }
                 // Could reference a shape or another prefab
                 if self.shapes.contains_key(ref_name) {
                     graph.add_dependency(id.clone(), AssetId::shape(ref_name));
@@ -456,7 +503,38 @@ impl RegistryBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{BuiltinStamps, LegendEntry};
+    use crate::types::{BuiltinStamps, LegendEntry, PaletteBuilder};
+
+    fn inheriting_palette(name: &str, parent: &str) -> Palette {
+        let mut builder = PaletteBuilder::new(name);
+        builder.define("edge", "#000000");
+        builder.inherits(parent);
+        builder.build(None).unwrap()
+    }
+
+    #[test]
+    fn test_palette_inheritance_orders_parents_first() {
+        let mut builder = RegistryBuilder::new();
+        builder.add_palette(inheriting_palette("night", "base"));
+        builder.add_palette(Palette::new("base"));
+        let registry = builder.build().unwrap();
+
+        let order = registry.build_order();
+        let base = order.iter().position(|id| *id == AssetId::palette("base")).unwrap();
+        let night = order.iter().position(|id| *id == AssetId::palette("night")).unwrap();
+        assert!(base < night);
+    }
+
+    #[test]
+    fn test_palette_inheritance_cycle_error() {
+        let mut builder = RegistryBuilder::new();
+        builder.add_palette(inheriting_palette("a", "b"));
+        builder.add_palette(inheriting_palette("b", "a"));
+
+        let err = builder.build().unwrap_err().to_string();
+        assert!(err.contains("Circular dependency detected"), "{}", err);
+        assert!(err.contains("palette:a") && err.contains("palette:b"), "{}", err);
+    }
 
     #[test]
     fn test_empty_registry() {
@@ -541,6 +619,24 @@ mod tests {
         assert!(stamp_pos < shape_pos);
     }
 
+    #[test]
+    fn test_shape_depends_on_stamp_by_glyph() {
+        let mut builder = RegistryBuilder::new();
+        builder.add_stamp(Stamp::single("dot", Some('D'), crate::types::PixelToken::Edge));
+        builder.add_stamp(Stamp::single("ring", Some('R'), crate::types::PixelToken::Edge));
+        builder.add_shape(Shape::new("pip", vec![], vec![vec!['D', '#']], std::collections::HashMap::new()));
+
+        // The legend remaps R, so the ring stamp isn't drawn
+        let mut legend = std::collections::HashMap::new();
+        legend.insert('R', LegendEntry::StampRef("solid".to_string()));
+        builder.add_shape(Shape::new("plate", vec![], vec![vec!['R']], legend));
+
+        let registry = builder.build().unwrap();
+        let deps: Vec<_> = registry.graph().dependencies_of(&AssetId::shape("pip")).collect();
+        assert_eq!(deps, [&AssetId::stamp("dot")]);
+        assert_eq!(registry.graph().dependencies_of(&AssetId::shape("plate")).count(), 0);
+    }
+
     #[test]
     fn test_build_order_complex() {
         // palette -> shader -> (implicit, shapes use palette via shader)
@@ -559,6 +655,7 @@ mod tests {
             LegendEntry::Fill {
                 name: "solid".to_string(),
                 bindings: std::collections::HashMap::new(),
+                offset: (0, 0),
             },
         );
 
@@ -611,4 +708,24 @@ mod tests {
         let stamp_names: Vec<_> = registry.stamp_names().collect();
         assert_eq!(stamp_names, vec!["test"]);
     }
+
+    #[test]
+    fn test_find_by_name() {
+        let mut builder = RegistryBuilder::new();
+        builder.add_shape(Shape::new("wall", vec![], vec![vec!['#']], std::collections::HashMap::new()));
+        builder.add_shape(Shape::new("door", vec![], vec![vec!['#']], std::collections::HashMap::new()));
+        builder.add_brush(Brush::new("wall", vec![vec!['#']]));
+        let registry = builder.build().unwrap();
+
+        assert_eq!(registry.find_by_name("door").unwrap(), AssetId::shape("door"));
+        assert!(registry.find_by_name("window").is_err());
+
+        // A shape and a brush share "wall"
+        let err = registry.find_by_name("wall").unwrap_err();
+        assert!(err.to_string().contains("'wall' is ambiguous"), "{}", err);
+        let PxError::Validation { help: Some(help), .. } = err else {
+            panic!("expected a validation error with help");
+        };
+        assert!(help.contains("brush:wall, shape:wall"), "{}", help);
+    }
 }
@@ -5,8 +5,13 @@
 
 use std::fmt;
 
+use serde::Serialize;
+
+use crate::error::{PxError, Result};
+
 /// The kind of asset.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum AssetKind {
     Palette,
     Stamp,
@@ -19,6 +24,23 @@ pub enum AssetKind {
 }
 
 impl AssetKind {
+    /// Every kind, in declaration order.
+    pub const ALL: [AssetKind; 8] = [
+        AssetKind::Palette,
+        AssetKind::Stamp,
+        AssetKind::Brush,
+        AssetKind::Shader,
+        AssetKind::Shape,
+        AssetKind::Prefab,
+        AssetKind::Map,
+        AssetKind::Target,
+    ];
+
+    /// Look up a kind by its short name (the inverse of [`name`](Self::name)).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
     /// Get the file extension for this asset kind.
     pub fn extension(&self) -> &'static str {
         match self {
@@ -58,7 +80,7 @@ impl fmt::Display for AssetKind {
 ///
 /// Combines the asset kind and name, allowing different types
 /// to have the same name (e.g., shape:wall and brush:wall).
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct AssetId {
     pub kind: AssetKind,
     pub name: String,
@@ -112,6 +134,35 @@ impl AssetId {
     pub fn target(name: impl Into<String>) -> Self {
         Self::new(AssetKind::Target, name)
     }
+
+    /// Parse a `kind:name` ID, like `shape:player`, as [`Display`](fmt::Display)
+    /// writes them.
+    ///
+    /// Bare names have no kind to parse; look those up with
+    /// [`AssetRegistry::find_by_name`](super::AssetRegistry::find_by_name).
+    pub fn parse(s: &str) -> Result<Self> {
+        let Some((kind, name)) = s.split_once(':') else {
+            return Err(PxError::Parse {
+                message: format!("Asset ID '{}' has no kind", s),
+                help: Some(format!("Write it as kind:name, like shape:{}", s)),
+            });
+        };
+        let Some(kind) = AssetKind::from_name(kind.trim()) else {
+            let kinds: Vec<&str> = AssetKind::ALL.iter().map(|k| k.name()).collect();
+            return Err(PxError::Parse {
+                message: format!("Unknown asset kind '{}' in '{}'", kind.trim(), s),
+                help: Some(format!("Use one of: {}", kinds.join(", "))),
+            });
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(PxError::Parse {
+                message: format!("Asset ID '{}' has no name", s),
+                help: None,
+            });
+        }
+        Ok(Self::new(kind, name))
+    }
 }
 
 impl fmt::Display for AssetId {
@@ -190,6 +241,19 @@ mod tests {
         assert_ne!(a, c); // Same name, different kind
     }
 
+    #[test]
+    fn test_asset_id_parse() {
+        assert_eq!(AssetId::parse("shape:player").unwrap(), AssetId::shape("player"));
+        assert_eq!(AssetId::parse("map: level-1").unwrap(), AssetId::map("level-1"));
+        // Round-trips Display
+        assert_eq!(AssetId::parse(&AssetId::brush("wall").to_string()).unwrap(), AssetId::brush("wall"));
+
+        let err = AssetId::parse("sprite:player").unwrap_err().to_string();
+        assert!(err.contains("Unknown asset kind 'sprite'"), "{}", err);
+        assert!(AssetId::parse("player").is_err());
+        assert!(AssetId::parse("shape:").is_err());
+    }
+
     #[test]
     fn test_asset_ref_to_id() {
         let typed = AssetRef::new(AssetKind::Shape, "wall");
@@ -0,0 +1,290 @@
+//! Post-processing effects for rendered shapes.
+//!
+//! Effects operate on a finished `RenderedShape` and return a new one, so a
+//! shader's effect list can be applied in order after rendering.
+
+use crate::types::Colour;
+
+use super::shape::RenderedShape;
+
+/// 4-connected neighbour offsets.
+const NEIGHBOURS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+impl RenderedShape {
+    /// Draw an outline ring around opaque pixels.
+    ///
+    /// Transparent pixels 4-adjacent to a drawn pixel take `colour`. The ring
+    /// is dilated once per unit of `thickness`. The canvas is not expanded,
+    /// so sides that touch the sprite's edge get no outline.
+    pub fn outlined(&self, colour: Colour, thickness: u32) -> RenderedShape {
+        let (width, height) = self.size();
+        let mut pixels = self.pixels().to_vec();
+        let mut drawn: Vec<Vec<bool>> = pixels
+            .iter()
+            .map(|row| row.iter().map(|c| !c.is_transparent()).collect())
+            .collect();
+
+        for _ in 0..thickness {
+            let mut ring = Vec::new();
+
+            for y in 0..height {
+                for x in 0..width {
+                    if drawn[y][x] {
+                        continue;
+                    }
+                    let touches = NEIGHBOURS.iter().any(|&(dx, dy)| {
+                        let nx = x as isize + dx;
+                        let ny = y as isize + dy;
+                        nx >= 0
+                            && ny >= 0
+                            && (nx as usize) < width
+                            && (ny as usize) < height
+                            && drawn[ny as usize][nx as usize]
+                    });
+                    if touches {
+                        ring.push((x, y));
+                    }
+                }
+            }
+
+            if ring.is_empty() {
+                break;
+            }
+            for (x, y) in ring {
+                pixels[y][x] = colour;
+                drawn[y][x] = true;
+            }
+        }
+
+        self.carry_holes(RenderedShape::new(&self.name, pixels), 0, 0)
+    }
+
+    /// Composite a drop shadow beneath the sprite.
+    ///
+    /// The shadow is the sprite's alpha mask filled with `colour`, offset by
+    /// `(dx, dy)`. The canvas grows by `|dx|` x `|dy|` so the shadow isn't
+    /// clipped; for negative offsets the sprite shifts right/down to make room.
+    pub fn with_drop_shadow(&self, dx: i32, dy: i32, colour: Colour) -> RenderedShape {
+        let (width, height) = self.size();
+        let shift_x = dx.unsigned_abs() as usize;
+        let shift_y = dy.unsigned_abs() as usize;
+        let (sprite_x, shadow_x) = if dx < 0 { (shift_x, 0) } else { (0, shift_x) };
+        let (sprite_y, shadow_y) = if dy < 0 { (shift_y, 0) } else { (0, shift_y) };
+
+        let mut pixels = vec![vec![Colour::TRANSPARENT; width + shift_x]; height + shift_y];
+
+        for (y, row) in self.pixels().iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                if !pixel.is_transparent() {
+                    pixels[y + shadow_y][x + shadow_x] = colour;
+                }
+            }
+        }
+
+        for (y, row) in self.pixels().iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                if !pixel.is_transparent() {
+                    let below = &mut pixels[y + sprite_y][x + sprite_x];
+                    *below = pixel.over(*below);
+                }
+            }
+        }
+
+        self.carry_holes(RenderedShape::new(&self.name, pixels), sprite_x, sprite_y)
+    }
+
+    /// Adjust the tone of every drawn pixel: add `brightness`, stretch
+    /// around mid-grey by `contrast` (both -255 to 255), then apply `gamma`.
+    /// Alpha and fully transparent pixels are untouched.
+    pub fn leveled(&self, brightness: f32, contrast: f32, gamma: f32) -> RenderedShape {
+        // Standard contrast correction factor: 1.0 at 0, flat grey at -255
+        let factor = 259.0 * (contrast + 255.0) / (255.0 * (259.0 - contrast));
+        let level = |channel: u8| {
+            let v = (channel as f32 + brightness).clamp(0.0, 255.0);
+            let v = (factor * (v - 128.0) + 128.0).clamp(0.0, 255.0);
+            (255.0 * (v / 255.0).powf(1.0 / gamma)).round().clamp(0.0, 255.0) as u8
+        };
+        self.map_drawn(|c| Colour::new(level(c.r), level(c.g), level(c.b), c.a))
+    }
+
+    /// Snap each colour channel of every drawn pixel to the nearest of
+    /// `levels` evenly spaced steps from 0 to 255. Alpha is untouched.
+    pub fn posterized(&self, levels: u8) -> RenderedShape {
+        let step = 255.0 / (levels.max(2) - 1) as f32;
+        let snap = |channel: u8| ((channel as f32 / step).round() * step).round() as u8;
+        self.map_drawn(|c| Colour::new(snap(c.r), snap(c.g), snap(c.b), c.a))
+    }
+
+    /// Apply `f` to every pixel that isn't fully transparent.
+    fn map_drawn(&self, f: impl Fn(Colour) -> Colour) -> RenderedShape {
+        let pixels = self
+            .pixels()
+            .iter()
+            .map(|row| row.iter().map(|&c| if c.is_transparent() { c } else { f(c) }).collect())
+            .collect();
+        self.carry_holes(RenderedShape::new(&self.name, pixels), 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const RED: Colour = Colour::rgb(255, 0, 0);
+
+    /// A `size`x`size` canvas with a 2x2 white square in the middle.
+    fn square(size: usize) -> RenderedShape {
+        let mut pixels = vec![vec![Colour::TRANSPARENT; size]; size];
+        let start = size / 2 - 1;
+        for row in pixels.iter_mut().skip(start).take(2) {
+            for pixel in row.iter_mut().skip(start).take(2) {
+                *pixel = Colour::WHITE;
+            }
+        }
+        RenderedShape::new("square", pixels)
+    }
+
+    #[test]
+    fn test_outline_on_transparent_border() {
+        let outlined = square(4).outlined(RED, 1);
+
+        // Original square untouched
+        assert_eq!(outlined.get(1, 1), Some(Colour::WHITE));
+        assert_eq!(outlined.get(2, 2), Some(Colour::WHITE));
+
+        // Ring on edge-adjacent transparent pixels
+        assert_eq!(outlined.get(1, 0), Some(RED));
+        assert_eq!(outlined.get(2, 0), Some(RED));
+        assert_eq!(outlined.get(0, 1), Some(RED));
+        assert_eq!(outlined.get(3, 2), Some(RED));
+        assert_eq!(outlined.get(2, 3), Some(RED));
+
+        // Diagonal corners stay transparent
+        assert_eq!(outlined.get(0, 0), Some(Colour::TRANSPARENT));
+        assert_eq!(outlined.get(3, 3), Some(Colour::TRANSPARENT));
+    }
+
+    #[test]
+    fn test_outline_thickness_dilates() {
+        let outlined = square(6).outlined(RED, 2);
+
+        // Second ring reaches two pixels out and fills the first ring's corners
+        assert_eq!(outlined.get(2, 0), Some(RED));
+        assert_eq!(outlined.get(1, 1), Some(RED));
+        assert_eq!(outlined.get(0, 0), Some(Colour::TRANSPARENT));
+        assert_eq!(outlined.get(2, 2), Some(Colour::WHITE));
+    }
+
+    #[test]
+    fn test_outline_opaque_sprite_unchanged() {
+        let pixels = vec![vec![Colour::WHITE; 3]; 3];
+        let rendered = RenderedShape::new("solid", pixels.clone());
+
+        let outlined = rendered.outlined(RED, 3);
+        assert_eq!(outlined.pixels(), pixels.as_slice());
+    }
+
+    #[test]
+    fn test_outline_zero_thickness() {
+        let rendered = square(4);
+        let outlined = rendered.outlined(RED, 0);
+        assert_eq!(outlined.pixels(), rendered.pixels());
+    }
+
+    #[test]
+    fn test_levels_brightness_lightens() {
+        let grey = RenderedShape::new("grey", vec![vec![Colour::rgb(128, 128, 128), Colour::TRANSPARENT]]);
+
+        let lighter = grey.leveled(50.0, 0.0, 1.0);
+        assert_eq!(lighter.get(0, 0), Some(Colour::rgb(178, 178, 178)));
+        assert_eq!(lighter.get(1, 0), Some(Colour::TRANSPARENT));
+        assert_eq!(grey.leveled(0.0, 0.0, 1.0).pixels(), grey.pixels());
+    }
+
+    #[test]
+    fn test_levels_gamma_moves_mid_tones_only() {
+        let ramp = RenderedShape::new("ramp", vec![vec![Colour::BLACK, Colour::rgb(128, 128, 128), Colour::WHITE]]);
+
+        let adjusted = ramp.leveled(0.0, 0.0, 2.2);
+        assert_eq!(adjusted.get(0, 0), Some(Colour::BLACK));
+        assert_eq!(adjusted.get(2, 0), Some(Colour::WHITE));
+        let mid = adjusted.get(1, 0).unwrap();
+        assert!(mid.r > 128 && mid.r == mid.g && mid.g == mid.b);
+    }
+
+    #[test]
+    fn test_levels_contrast_spreads_from_mid_grey() {
+        let ramp = RenderedShape::new("ramp", vec![vec![Colour::rgb(100, 100, 100), Colour::rgb(160, 160, 160)]]);
+
+        let adjusted = ramp.leveled(0.0, 64.0, 1.0);
+        assert!(adjusted.get(0, 0).unwrap().r < 100);
+        assert!(adjusted.get(1, 0).unwrap().r > 160);
+    }
+
+    #[test]
+    fn test_posterize_grey_ramp_to_four_values() {
+        let ramp = RenderedShape::new("ramp", vec![(0..=255).map(|v| Colour::rgb(v, v, v)).collect()]);
+
+        let posterized = ramp.posterized(4);
+        let values: BTreeSet<u8> = posterized.pixels()[0].iter().map(|c| c.r).collect();
+        assert_eq!(values.into_iter().collect::<Vec<_>>(), [0, 85, 170, 255]);
+        assert!(posterized.pixels()[0].iter().all(|c| c.r == c.g && c.g == c.b));
+    }
+
+    #[test]
+    fn test_posterize_two_levels_snaps_to_extremes() {
+        let shape = RenderedShape::new("snap", vec![vec![Colour::new(100, 128, 200, 90), Colour::TRANSPARENT]]);
+
+        let posterized = shape.posterized(2);
+        assert_eq!(posterized.get(0, 0), Some(Colour::new(0, 255, 255, 90)));
+        assert_eq!(posterized.get(1, 0), Some(Colour::TRANSPARENT));
+    }
+
+    #[test]
+    fn test_drop_shadow_positive_offset() {
+        let rendered = RenderedShape::new("dot", vec![vec![Colour::WHITE]]);
+        let shadow = Colour::new(0, 0, 0, 128);
+
+        let result = rendered.with_drop_shadow(2, 1, shadow);
+        assert_eq!(result.size(), (3, 2));
+        assert_eq!(result.get(0, 0), Some(Colour::WHITE));
+        assert_eq!(result.get(2, 1), Some(shadow));
+        assert_eq!(result.get(1, 0), Some(Colour::TRANSPARENT));
+    }
+
+    #[test]
+    fn test_drop_shadow_blends_under_translucent_edge() {
+        let edge = Colour::new(255, 255, 255, 128);
+        let rendered = RenderedShape::new("bar", vec![vec![Colour::WHITE, edge]]);
+
+        let result = rendered.with_drop_shadow(1, 0, Colour::BLACK);
+        // The half-alpha edge sits over the shadow of the pixel beside it
+        assert_eq!(result.get(1, 0), Some(edge.over(Colour::BLACK)));
+        assert_eq!(result.get(1, 0).map(|c| c.a), Some(255));
+        assert_eq!(result.get(2, 0), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_drop_shadow_negative_offset() {
+        let rendered = RenderedShape::new("dot", vec![vec![Colour::WHITE]]);
+        let shadow = Colour::new(0, 0, 0, 128);
+
+        let result = rendered.with_drop_shadow(-1, -2, shadow);
+        assert_eq!(result.size(), (2, 3));
+        assert_eq!(result.get(0, 0), Some(shadow));
+        assert_eq!(result.get(1, 2), Some(Colour::WHITE));
+        assert_eq!(result.get(1, 0), Some(Colour::TRANSPARENT));
+    }
+
+    #[test]
+    fn test_drop_shadow_sits_beneath_sprite() {
+        let rendered = RenderedShape::new("bar", vec![vec![Colour::WHITE, Colour::WHITE]]);
+
+        let result = rendered.with_drop_shadow(1, 0, RED);
+        assert_eq!(result.size(), (3, 1));
+        assert_eq!(result.get(0, 0), Some(Colour::WHITE));
+        assert_eq!(result.get(1, 0), Some(Colour::WHITE));
+        assert_eq!(result.get(2, 0), Some(RED));
+    }
+}
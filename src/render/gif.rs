@@ -0,0 +1,120 @@
+//! Animated GIF output for multi-frame shapes.
+//!
+//! Encodes a sequence of equally sized rendered shapes as a looping GIF,
+//! with the same integer scaling as PNG output.
+
+use std::fs::File;
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageBuffer, Rgba, RgbaImage};
+
+use crate::error::{PxError, Result};
+
+use super::{scale_pixels, RenderedShape};
+
+/// Write rendered frames to an animated GIF.
+///
+/// # Arguments
+///
+/// * `frames` - Frames in playback order; all must share the same size
+/// * `path` - Output file path
+/// * `scale` - Integer scale factor (1 = no scaling)
+/// * `fps` - Playback rate in frames per second
+pub fn write_gif(frames: &[RenderedShape], path: &Path, scale: u32, fps: u32) -> Result<()> {
+    let Some(first) = frames.first() else {
+        return Err(PxError::Build {
+            message: "Cannot write a GIF with no frames".to_string(),
+            help: None,
+        });
+    };
+
+    if let Some(odd) = frames.iter().find(|f| f.size() != first.size()) {
+        return Err(PxError::Build {
+            message: format!(
+                "GIF frames must share one size: '{}' is {}x{} but '{}' is {}x{}",
+                first.name,
+                first.width(),
+                first.height(),
+                odd.name,
+                odd.width(),
+                odd.height()
+            ),
+            help: Some("Make every shape in the animation file the same size".to_string()),
+        });
+    }
+
+    let scale = scale.max(1);
+    let delay = Delay::from_numer_denom_ms(1000, fps.max(1));
+
+    let file = File::create(path).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to create GIF: {}", e),
+    })?;
+
+    let mut encoder = GifEncoder::new(file);
+    let gif_error = |e: image::ImageError| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write GIF: {}", e),
+    };
+    encoder.set_repeat(Repeat::Infinite).map_err(gif_error)?;
+
+    for rendered in frames {
+        let pixels = scale_pixels(rendered.pixels(), scale);
+        let width = (rendered.width() as u32) * scale;
+        let height = (rendered.height() as u32) * scale;
+
+        let mut img: RgbaImage = ImageBuffer::new(width, height);
+        for (y, row) in pixels.iter().enumerate() {
+            for (x, colour) in row.iter().enumerate() {
+                img.put_pixel(x as u32, y as u32, Rgba(colour.to_rgba()));
+            }
+        }
+
+        encoder
+            .encode_frame(Frame::from_parts(img, 0, 0, delay))
+            .map_err(gif_error)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Colour;
+    use image::codecs::gif::GifDecoder;
+    use image::AnimationDecoder;
+    use std::io::BufReader;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_gif_frames() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("anim.gif");
+
+        let frames = vec![
+            RenderedShape::new("a", vec![vec![Colour::BLACK, Colour::WHITE]]),
+            RenderedShape::new("b", vec![vec![Colour::WHITE, Colour::BLACK]]),
+        ];
+        write_gif(&frames, &path, 2, 10).unwrap();
+
+        let decoder = GifDecoder::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].buffer().dimensions(), (4, 2));
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (100, 1));
+    }
+
+    #[test]
+    fn test_write_gif_size_mismatch() {
+        let dir = tempdir().unwrap();
+        let frames = vec![
+            RenderedShape::new("small", vec![vec![Colour::BLACK]]),
+            RenderedShape::new("wide", vec![vec![Colour::BLACK, Colour::WHITE]]),
+        ];
+
+        let err = write_gif(&frames, &dir.path().join("bad.gif"), 1, 10).unwrap_err();
+        assert!(err.to_string().contains("'wide' is 2x1"));
+    }
+}
@@ -0,0 +1,115 @@
+//! Godot sprite frames export.
+//!
+//! Writes a packed sheet as a Godot 4 `SpriteFrames` text resource
+//! (`.tres`). Each frame becomes an `AtlasTexture` sub-resource cut from the
+//! sheet image, and a one-frame animation named after the sprite.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{PxError, Result};
+
+use super::{Frame, SheetMeta};
+
+/// Build the `SpriteFrames` resource for a packed sheet.
+///
+/// The sheet image is referenced by `meta.image`, relative to the resource,
+/// and regions are in scaled sheet pixels. Trimmed frames get a `margin`
+/// restoring their untrimmed size.
+pub fn godot_sprite_frames(meta: &SheetMeta) -> String {
+    let s = meta.scale.max(1);
+
+    // One step per sub-resource, plus the sheet and the resource itself
+    let mut tres = String::new();
+    let _ = writeln!(tres, "[gd_resource type=\"SpriteFrames\" load_steps={} format=3]\n", meta.frames.len() + 2);
+    let _ = writeln!(tres, "[ext_resource type=\"Texture2D\" path=\"{}\" id=\"1_sheet\"]\n", escape(&meta.image));
+
+    for (i, frame) in meta.frames.iter().enumerate() {
+        let _ = writeln!(tres, "[sub_resource type=\"AtlasTexture\" id=\"{}\"]", atlas_id(i));
+        let _ = writeln!(tres, "atlas = ExtResource(\"1_sheet\")");
+        let _ = writeln!(tres, "region = Rect2({}, {}, {}, {})", frame.x * s, frame.y * s, frame.w * s, frame.h * s);
+        if let Some((x, y, w, h)) = margin(frame) {
+            let _ = writeln!(tres, "margin = Rect2({}, {}, {}, {})", x * s, y * s, w * s, h * s);
+        }
+        tres.push('\n');
+    }
+
+    let animations: Vec<String> = meta
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            format!(
+                "{{\n\"frames\": [{{\n\"duration\": 1.0,\n\"texture\": SubResource(\"{}\")\n}}],\n\"loop\": true,\n\"name\": &\"{}\",\n\"speed\": 5.0\n}}",
+                atlas_id(i),
+                escape(&frame.name)
+            )
+        })
+        .collect();
+    let _ = writeln!(tres, "[resource]");
+    let _ = writeln!(tres, "animations = [{}]", animations.join(", "));
+
+    tres
+}
+
+/// Write the `SpriteFrames` resource for a packed sheet to `path`.
+pub fn write_godot(meta: &SheetMeta, path: &Path) -> Result<()> {
+    fs::write(path, godot_sprite_frames(meta)).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write Godot resource: {}", e),
+    })
+}
+
+fn atlas_id(index: usize) -> String {
+    format!("AtlasTexture_{}", index)
+}
+
+/// Godot's `margin`: offset within the untrimmed sprite, then how much
+/// larger the untrimmed sprite is. `None` for untrimmed frames.
+fn margin(frame: &Frame) -> Option<(u32, u32, u32, u32)> {
+    let trim = frame.trim?;
+    Some((trim.x, trim.y, trim.source_w - frame.w, trim.source_h - frame.h))
+}
+
+/// Escape a string for a Godot string literal.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{RenderedShape, SheetPacker};
+    use crate::types::Colour;
+
+    fn sprite(name: &str, w: usize, h: usize) -> RenderedShape {
+        RenderedShape::new(name, vec![vec![Colour::WHITE; w]; h])
+    }
+
+    #[test]
+    fn test_godot_sprite_frames_regions() {
+        let sprites = vec![sprite("hero", 4, 4), sprite("coin", 2, 2), sprite("wall", 3, 2)];
+        let Ok((_, mut meta)) = SheetPacker::new(0).pack(&sprites) else {
+            panic!("pack failed");
+        };
+        meta.scale = 2;
+        let tres = godot_sprite_frames(&meta);
+
+        assert!(tres.starts_with("[gd_resource type=\"SpriteFrames\" load_steps=5 format=3]"));
+        assert!(tres.contains("[ext_resource type=\"Texture2D\" path=\"sheet.png\" id=\"1_sheet\"]"));
+        let subs: Vec<&str> = tres.split("[sub_resource").skip(1).collect();
+        assert_eq!(subs.len(), 3);
+
+        // Find coin's region via its animation's sub-resource id
+        let index = meta.frames.iter().position(|f| f.name == "coin").unwrap();
+        let frame = &meta.frames[index];
+        let sub = subs
+            .iter()
+            .find(|s| s.starts_with(&format!(" type=\"AtlasTexture\" id=\"{}\"]", atlas_id(index))))
+            .unwrap();
+        let region = sub.lines().find_map(|l| l.strip_prefix("region = ")).unwrap();
+        assert_eq!(region, format!("Rect2({}, {}, 4, 4)", frame.x * 2, frame.y * 2));
+        assert!(tres.contains(&format!("\"texture\": SubResource(\"{}\")\n}}],\n\"loop\": true,\n\"name\": &\"coin\"", atlas_id(index))));
+    }
+}
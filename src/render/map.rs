@@ -7,9 +7,9 @@
 use std::collections::HashMap;
 
 use crate::error::{PxError, Result};
-use crate::types::{Colour, Map, MapInstance, MapMetadata};
+use crate::types::{Colour, Map, MapInstance, MapMetadata, TilemapMetadata};
 
-use super::RenderedShape;
+use super::{RenderedShape, SheetMeta};
 
 /// Map renderer that composites shapes and produces metadata.
 pub struct MapRenderer {
@@ -39,6 +39,7 @@ impl MapRenderer {
                 grid: [0, 0],
                 cell_size: [1, 1],
                 shapes: vec![],
+                source: None,
             };
             return Ok((
                 RenderedShape::new(&map.name, vec![vec![Colour::TRANSPARENT]]),
@@ -113,11 +114,56 @@ impl MapRenderer {
             grid: [map.width(), map.height()],
             cell_size: [cell_w, cell_h],
             shapes,
+            source: None,
         };
 
         Ok((RenderedShape::new(&map.name, pixels), metadata))
     }
 
+    /// Render a map as a tile index grid into a packed tileset.
+    ///
+    /// Each cell holds the index of its shape's frame in `tileset`, or -1
+    /// for `empty` and unmapped cells. No pixels are composited.
+    pub fn render_tilemap(&self, map: &Map, tileset: &SheetMeta) -> Result<TilemapMetadata> {
+        let (cell_w, cell_h) = if map.is_empty() { (1, 1) } else { self.cell_size(map)? };
+
+        let mut tiles = vec![vec![-1; map.width()]; map.height()];
+
+        for (cx, cy, glyph) in map.iter_cells() {
+            if glyph == ' ' && map.get_legend(' ').is_none() {
+                continue;
+            }
+
+            let Some(ref_name) = map.get_legend(glyph) else {
+                continue;
+            };
+
+            if ref_name == "empty" {
+                continue;
+            }
+
+            let Some(index) = tileset.frames.iter().position(|f| f.name == ref_name) else {
+                return Err(PxError::Build {
+                    message: format!(
+                        "Map '{}': legend glyph '{}' references '{}' which is not in the tileset",
+                        map.name, glyph, ref_name
+                    ),
+                    help: Some("Ensure all referenced shapes are rendered before the map".to_string()),
+                });
+            };
+
+            tiles[cy][cx] = index as i32;
+        }
+
+        Ok(TilemapMetadata {
+            name: map.name.clone(),
+            grid: [map.width(), map.height()],
+            cell_size: [cell_w, cell_h],
+            tileset: tileset.image.clone(),
+            tiles,
+        })
+    }
+
     /// Calculate the uniform cell size (max width x max height of all referenced shapes).
     /// Skips "empty" references.
     fn cell_size(&self, map: &Map) -> Result<(usize, usize)> {
@@ -147,7 +193,8 @@ impl MapRenderer {
     }
 }
 
-/// Copy source pixels onto destination at offset, skipping transparent pixels.
+/// Copy source pixels onto destination at offset, skipping transparent
+/// pixels. Holes in the source clear the destination.
 fn blit(dest: &mut [Vec<Colour>], source: &RenderedShape, offset_x: usize, offset_y: usize) {
     for sy in 0..source.height() {
         let dy = offset_y + sy;
@@ -159,7 +206,9 @@ fn blit(dest: &mut [Vec<Colour>], source: &RenderedShape, offset_x: usize, offse
             if dx >= dest[dy].len() {
                 break;
             }
-            if let Some(pixel) = source.get(sx, sy) {
+            if source.is_hole(sx, sy) {
+                dest[dy][dx] = Colour::TRANSPARENT;
+            } else if let Some(pixel) = source.get(sx, sy) {
                 if pixel.a > 0 {
                     dest[dy][dx] = pixel;
                 }
@@ -346,4 +395,76 @@ mod tests {
         // Last cell: green
         assert_eq!(result.get(4, 0), Some(green()));
     }
+
+    #[test]
+    fn test_render_tilemap_indices() {
+        let mut renderer = MapRenderer::new();
+        let wall = make_rendered("wall", 2, 2, red());
+        let door = make_rendered("door", 2, 2, blue());
+        renderer.add_rendered(wall.clone());
+        renderer.add_rendered(door.clone());
+
+        let (_, tileset) = crate::render::SheetPacker::new(0).pack(&[door, wall]).unwrap();
+
+        let mut legend = HashMap::new();
+        legend.insert('W', "wall".to_string());
+        legend.insert('D', "door".to_string());
+        legend.insert('.', "empty".to_string());
+
+        let map = Map::new(
+            "tiles",
+            vec![],
+            vec![vec!['W', '.'], vec!['D', 'W']],
+            legend,
+        );
+
+        let tilemap = renderer.render_tilemap(&map, &tileset).unwrap();
+        assert_eq!(tilemap.grid, [2, 2]);
+        assert_eq!(tilemap.cell_size, [2, 2]);
+        assert_eq!(tilemap.tiles, vec![vec![1, -1], vec![0, 1]]);
+        assert_eq!(tileset.frames[1].name, "wall");
+        assert_eq!(tileset.frames[0].name, "door");
+    }
+
+    #[test]
+    fn test_render_tilemap_missing_tile_error() {
+        let mut renderer = MapRenderer::new();
+        renderer.add_rendered(make_rendered("wall", 2, 2, red()));
+
+        let (_, tileset) = crate::render::SheetPacker::new(0).pack(&[]).unwrap();
+
+        let mut legend = HashMap::new();
+        legend.insert('W', "wall".to_string());
+        let map = Map::new("bad", vec![], vec![vec!['W']], legend);
+
+        assert!(renderer.render_tilemap(&map, &tileset).is_err());
+    }
+
+    #[test]
+    fn test_render_layers_bottom_to_top() {
+        let mut renderer = MapRenderer::new();
+        renderer.add_rendered(make_rendered("floor", 2, 2, red()));
+        // A 2x2 torch with one transparent corner
+        let mut torch = vec![vec![blue(); 2]; 2];
+        torch[0][0] = Colour::TRANSPARENT;
+        renderer.add_rendered(RenderedShape::new("torch", torch));
+
+        let mut legend = HashMap::new();
+        legend.insert('.', "floor".to_string());
+        legend.insert('T', "torch".to_string());
+        let map = Map::new("room", vec![], vec![], legend).with_layers(vec![
+            vec![vec!['.', '.']],
+            vec![vec![' ', 'T']],
+        ]);
+
+        let (result, metadata) = renderer.render(&map).unwrap();
+        assert_eq!(result.size(), (4, 2));
+        // Only floor under the blank overlay cell
+        assert_eq!(result.get(0, 0), Some(red()));
+        // The torch covers the floor, which shows through its transparent corner
+        assert_eq!(result.get(2, 0), Some(red()));
+        assert_eq!(result.get(3, 0), Some(blue()));
+        assert_eq!(result.get(2, 1), Some(blue()));
+        assert_eq!(metadata.shapes.len(), 2);
+    }
 }
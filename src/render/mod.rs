@@ -3,16 +3,45 @@
 //! This module handles converting shapes to pixel grids using stamps,
 //! palettes, and shaders.
 
+mod effects;
+mod gif;
+mod godot;
 mod map;
+mod normals;
+mod onion;
 mod p8;
 mod png;
 mod prefab;
+mod project;
+mod reduce;
+mod rotate;
+mod rust;
 mod shape;
 mod sheet;
+mod svg;
+mod tiled;
+mod unity;
+mod webp;
 
+pub use gif::write_gif;
+pub use godot::{godot_sprite_frames, write_godot};
 pub use map::MapRenderer;
-pub use p8::{quantize_sheet, sprites_that_fit, write_p8, DitherMethod, P8Config};
-pub use png::{scale_pixels, write_png};
+pub use normals::{normal_map, FLAT_NORMAL};
+pub use onion::onion_skin;
+pub use p8::{
+    p8_flags, p8_map, quantize_nearest, quantize_sheet, sprite_indices, sprites_that_fit, write_p8, DitherMethod, P8Config,
+    P8Sections, DEFAULT_FLAG_BITS, P8_MAP_HEIGHT, P8_MAP_WIDTH, PICO8_PALETTE, PICO8_SECRET_PALETTE,
+};
+pub(crate) use p8::colour_distance;
+pub use png::{is_png_ref, read_png, read_png_refs, scale_pixels, scale_pixels_f, write_indexed_png, write_png, write_png_as, write_rgba_png, MAX_INDEXED_COLOURS};
 pub use prefab::PrefabRenderer;
-pub use shape::{RenderedShape, ShapeRenderer};
-pub use sheet::{write_sheet_json, SheetMeta, SheetPacker};
+pub use project::{render_assets, render_project, shader_palette, AssetMetadata, RenderOptions, RenderedAsset, RenderedProject};
+pub use reduce::median_cut;
+pub use rotate::spin_frames;
+pub use rust::{rust_source, write_rust};
+pub use shape::{RenderedShape, ShapeRenderer, GUIDE_COLOUR};
+pub use sheet::{write_sheet_json, Frame, PackStrategy, SheetMeta, SheetPacker, SheetSizing, Trim};
+pub use svg::write_svg;
+pub use tiled::{tiled_map, write_tiled, TiledLayer, TiledMap, TiledTile, TiledTileset};
+pub use unity::{unity_meta, write_unity_meta, SpriteSheet, TextureImporter, UnityMeta, UnityPoint, UnityRect, UnitySprite};
+pub use webp::write_webp;
@@ -0,0 +1,131 @@
+//! Normal maps for dynamic lighting.
+//!
+//! Treats each sprite as a height field, with transparent pixels at zero
+//! and opaque pixels raised by their brightness, so outlines and darker
+//! edge tokens fall away from lighter fill. A Sobel filter over that field
+//! gives the surface slope, encoded as a tangent-space normal with X in red
+//! and Y (down, following image rows) in green.
+
+use crate::types::Colour;
+
+use super::RenderedShape;
+
+/// How steeply height differences tilt the normal.
+const STRENGTH: f32 = 2.0;
+
+/// The encoded normal of a flat surface facing the viewer.
+pub const FLAT_NORMAL: Colour = Colour::rgb(128, 128, 255);
+
+/// Compute a tangent-space normal map for `shape`.
+///
+/// Flat areas encode as [`FLAT_NORMAL`]. Transparent pixels stay
+/// transparent, so the map lines up with the sprite it lights.
+pub fn normal_map(shape: &RenderedShape) -> RenderedShape {
+    let (width, height) = shape.size();
+    let heights: Vec<Vec<f32>> = shape
+        .pixels()
+        .iter()
+        .map(|row| row.iter().map(|&c| height_of(c)).collect())
+        .collect();
+
+    // Samples outside the sprite read as empty space
+    let at = |x: isize, y: isize| -> f32 {
+        if x < 0 || y < 0 {
+            return 0.0;
+        }
+        heights
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(0.0)
+    };
+
+    let mut pixels = vec![vec![Colour::TRANSPARENT; width]; height];
+    for (y, row) in pixels.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let source = shape.pixels()[y][x];
+            if source.is_transparent() {
+                continue;
+            }
+
+            let (x, y) = (x as isize, y as isize);
+            let gx = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x - 1, y) + at(x - 1, y + 1));
+            let gy = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1))
+                - (at(x - 1, y - 1) + 2.0 * at(x, y - 1) + at(x + 1, y - 1));
+
+            *pixel = Colour {
+                a: source.a,
+                ..encode(-gx * STRENGTH, -gy * STRENGTH, 1.0)
+            };
+        }
+    }
+
+    RenderedShape::new(shape.name.clone(), pixels)
+}
+
+/// Height of a pixel: zero when transparent, 0.5..1.0 by brightness when opaque.
+fn height_of(colour: Colour) -> f32 {
+    let alpha = colour.a as f32 / 255.0;
+    let luma = (0.299 * colour.r as f32 + 0.587 * colour.g as f32 + 0.114 * colour.b as f32) / 255.0;
+    alpha * (0.5 + 0.5 * luma)
+}
+
+/// Normalise `(x, y, z)` and pack it into RGB.
+fn encode(x: f32, y: f32, z: f32) -> Colour {
+    let length = (x * x + y * y + z * z).sqrt();
+    let channel = |v: f32| ((v / length * 0.5 + 0.5) * 255.0).round() as u8;
+    Colour::rgb(channel(x), channel(y), channel(z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(width: usize, height: usize, colour: Colour) -> RenderedShape {
+        RenderedShape::new("fill", vec![vec![colour; width]; height])
+    }
+
+    #[test]
+    fn test_uniform_fill_is_flat() {
+        let map = normal_map(&filled(5, 5, Colour::rgb(90, 90, 90)));
+        for y in 1..4 {
+            for x in 1..4 {
+                assert_eq!(map.get(x, y), Some(FLAT_NORMAL), "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gradient_tilts_normals() {
+        let row: Vec<Colour> = (0..5).map(|i| Colour::rgb(i * 60, i * 60, i * 60)).collect();
+        let shape = RenderedShape::new("ramp", vec![row; 5]);
+        let map = normal_map(&shape);
+
+        let centre = map.get(2, 2).unwrap();
+        assert_ne!(centre, FLAT_NORMAL);
+        // Brightness rises to the right, so the surface faces left
+        assert!(centre.r < 128);
+        assert_eq!(centre.g, 128);
+    }
+
+    #[test]
+    fn test_silhouette_edges_face_outward() {
+        let map = normal_map(&filled(3, 3, Colour::rgb(200, 200, 200)));
+        assert!(map.get(0, 1).unwrap().r < 128);
+        assert!(map.get(2, 1).unwrap().r > 128);
+        assert!(map.get(1, 0).unwrap().g < 128);
+        assert!(map.get(1, 2).unwrap().g > 128);
+    }
+
+    #[test]
+    fn test_transparent_pixels_stay_transparent() {
+        let shape = RenderedShape::new(
+            "gap",
+            vec![vec![Colour::rgb(255, 0, 0), Colour::TRANSPARENT]],
+        );
+        let map = normal_map(&shape);
+        assert_eq!(map.get(1, 0), Some(Colour::TRANSPARENT));
+        assert_eq!(map.get(0, 0).unwrap().a, 255);
+    }
+}
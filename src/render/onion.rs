@@ -0,0 +1,103 @@
+//! Onion-skin previews for animation frames.
+//!
+//! Overlays the frames either side of the current one at decreasing
+//! opacity, with the current frame drawn opaque on top.
+
+use crate::types::Colour;
+
+use super::RenderedShape;
+
+/// Composite an onion-skin preview of `frames` around `current`.
+///
+/// Up to `skins` frames either side of `current` are drawn beneath it,
+/// each at half the opacity of the next nearest (50%, 25%, ...). The
+/// canvas is the size of the largest frame, with frames aligned top-left.
+pub fn onion_skin(frames: &[RenderedShape], current: usize, skins: usize) -> RenderedShape {
+    let width = frames.iter().map(|f| f.width()).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.height()).max().unwrap_or(0);
+    let mut pixels = vec![vec![Colour::TRANSPARENT; width]; height];
+
+    let Some(frame) = frames.get(current) else {
+        return RenderedShape::new("onion", pixels);
+    };
+
+    // Farthest skins first so nearer frames land on top
+    for distance in (1..=skins).rev() {
+        let opacity = 0.5_f32.powi(distance as i32);
+        let neighbours = [current.checked_sub(distance), Some(current + distance)];
+        for skin in neighbours.into_iter().flatten().filter_map(|i| frames.get(i)) {
+            blit(&mut pixels, skin, opacity);
+        }
+    }
+    blit(&mut pixels, frame, 1.0);
+
+    RenderedShape::new(format!("{}-onion", frame.name), pixels)
+}
+
+/// Draw `shape` over `pixels` with its alpha scaled by `opacity`.
+fn blit(pixels: &mut [Vec<Colour>], shape: &RenderedShape, opacity: f32) {
+    for (y, row) in shape.pixels().iter().enumerate() {
+        for (x, &colour) in row.iter().enumerate() {
+            let faded = Colour {
+                a: (colour.a as f32 * opacity).round() as u8,
+                ..colour
+            };
+            pixels[y][x] = faded.over(pixels[y][x]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Colour = Colour::rgb(255, 0, 0);
+    const GREEN: Colour = Colour::rgb(0, 255, 0);
+    const BLUE: Colour = Colour::rgb(0, 0, 255);
+
+    /// A 3x1 frame with a single pixel set at `x`.
+    fn frame(name: &str, x: usize, colour: Colour) -> RenderedShape {
+        let mut row = vec![Colour::TRANSPARENT; 3];
+        row[x] = colour;
+        RenderedShape::new(name, vec![row])
+    }
+
+    fn three_frames() -> Vec<RenderedShape> {
+        vec![frame("walk-1", 0, RED), frame("walk-2", 1, GREEN), frame("walk-3", 2, BLUE)]
+    }
+
+    #[test]
+    fn test_onion_current_opaque_neighbours_faded() {
+        let onion = onion_skin(&three_frames(), 1, 1);
+
+        assert_eq!(onion.name, "walk-2-onion");
+        assert_eq!(onion.get(1, 0), Some(GREEN));
+        assert_eq!(onion.get(0, 0), Some(Colour::new(255, 0, 0, 128)));
+        assert_eq!(onion.get(2, 0), Some(Colour::new(0, 0, 255, 128)));
+    }
+
+    #[test]
+    fn test_onion_current_covers_skins() {
+        let frames = vec![frame("a", 1, RED), frame("b", 1, GREEN), frame("c", 1, BLUE)];
+        let onion = onion_skin(&frames, 1, 1);
+
+        assert_eq!(onion.get(1, 0), Some(GREEN));
+    }
+
+    #[test]
+    fn test_onion_skins_fade_with_distance() {
+        let onion = onion_skin(&three_frames(), 0, 2);
+
+        assert_eq!(onion.get(0, 0), Some(RED));
+        assert_eq!(onion.get(1, 0).unwrap().a, 128);
+        assert_eq!(onion.get(2, 0).unwrap().a, 64);
+    }
+
+    #[test]
+    fn test_onion_no_skins() {
+        let onion = onion_skin(&three_frames(), 1, 0);
+
+        assert_eq!(onion.get(0, 0), Some(Colour::TRANSPARENT));
+        assert_eq!(onion.get(1, 0), Some(GREEN));
+    }
+}
@@ -1,14 +1,17 @@
 //! PICO-8 cartridge output.
 //!
 //! Quantizes sprites to the PICO-8 16-colour palette, applies optional
-//! dithering, and writes a `.p8` cartridge file with the `__gfx__` section.
+//! dithering, and writes a `.p8` cartridge file with the `__gfx__` section
+//! and, optionally, `__gff__` sprite flags from shape tags and a `__map__`
+//! section laid out from a px map.
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs;
 use std::path::Path;
 
 use crate::error::{PxError, Result};
-use crate::types::Colour;
+use crate::types::{Colour, Map};
 
 use super::sheet::Frame;
 use super::RenderedShape;
@@ -17,6 +20,14 @@ use super::RenderedShape;
 pub const P8_WIDTH: usize = 128;
 pub const P8_HEIGHT: usize = 128;
 
+/// The PICO-8 map region written to `__map__`, in tiles. Only the upper
+/// half of the map is used; the lower half shares memory with the sheet.
+pub const P8_MAP_WIDTH: usize = 128;
+pub const P8_MAP_HEIGHT: usize = 32;
+
+/// PICO-8 sprites are 8x8 cells on the sheet.
+const P8_CELL: u32 = 8;
+
 /// The standard PICO-8 16-colour palette.
 pub const PICO8_PALETTE: [Colour; 16] = [
     Colour::rgb(0, 0, 0),       // 0  black
@@ -37,6 +48,27 @@ pub const PICO8_PALETTE: [Colour; 16] = [
     Colour::rgb(255, 204, 170), // 15 peach
 ];
 
+/// The PICO-8 "secret" extended palette (colours 128-143), usable
+/// via `pal()` in place of any of the standard 16.
+pub const PICO8_SECRET_PALETTE: [Colour; 16] = [
+    Colour::rgb(41, 24, 20),    // 128 darkest grey
+    Colour::rgb(17, 29, 53),    // 129 darker blue
+    Colour::rgb(66, 33, 54),    // 130 darker purple
+    Colour::rgb(18, 83, 89),    // 131 blue green
+    Colour::rgb(116, 47, 41),   // 132 dark brown
+    Colour::rgb(73, 51, 59),    // 133 darker grey
+    Colour::rgb(162, 136, 121), // 134 medium grey
+    Colour::rgb(243, 239, 125), // 135 light yellow
+    Colour::rgb(190, 18, 80),   // 136 dark red
+    Colour::rgb(255, 108, 36),  // 137 dark orange
+    Colour::rgb(168, 231, 46),  // 138 lime green
+    Colour::rgb(0, 181, 67),    // 139 medium green
+    Colour::rgb(6, 90, 181),    // 140 true blue
+    Colour::rgb(117, 70, 101),  // 141 mauve
+    Colour::rgb(255, 110, 89),  // 142 dark peach
+    Colour::rgb(255, 157, 129), // 143 peach
+];
+
 /// Dithering method for colour quantization.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DitherMethod {
@@ -46,6 +78,9 @@ pub enum DitherMethod {
     Ordered,
     /// Floyd-Steinberg error diffusion dithering.
     FloydSteinberg,
+    /// Atkinson error diffusion: spreads only 3/4 of the error, for
+    /// cleaner flat areas and higher contrast.
+    Atkinson,
 }
 
 impl DitherMethod {
@@ -55,6 +90,7 @@ impl DitherMethod {
             "none" => DitherMethod::None,
             "ordered" | "bayer" => DitherMethod::Ordered,
             "floyd-steinberg" | "fs" => DitherMethod::FloydSteinberg,
+            "atkinson" => DitherMethod::Atkinson,
             _ => DitherMethod::Ordered,
         }
     }
@@ -66,16 +102,46 @@ impl fmt::Display for DitherMethod {
             DitherMethod::None => write!(f, "none"),
             DitherMethod::Ordered => write!(f, "ordered"),
             DitherMethod::FloydSteinberg => write!(f, "floyd-steinberg"),
+            DitherMethod::Atkinson => write!(f, "atkinson"),
         }
     }
 }
 
+/// The default tag to sprite flag convention, as (tag, bit).
+///
+/// | Bit | Tag        |
+/// |-----|------------|
+/// | 0   | `solid`    |
+/// | 1   | `damage`   |
+/// | 2   | `ladder`   |
+/// | 3   | `water`    |
+/// | 4   | `platform` |
+/// | 5   | `pickup`   |
+/// | 6   | `door`     |
+/// | 7   | `trigger`  |
+pub const DEFAULT_FLAG_BITS: [(&str, u8); 8] = [
+    ("solid", 0),
+    ("damage", 1),
+    ("ladder", 2),
+    ("water", 3),
+    ("platform", 4),
+    ("pickup", 5),
+    ("door", 6),
+    ("trigger", 7),
+];
+
 /// Configuration for PICO-8 output.
 pub struct P8Config {
     /// Dithering method to use.
     pub dither: DitherMethod,
     /// Palette index to use for transparent pixels (default: 0).
     pub transparent_index: u8,
+    /// The 16 colours indices 0-15 are quantized against (default:
+    /// [`PICO8_PALETTE`]). Set this to match a cart's `pal()` setup, e.g.
+    /// with colours from [`PICO8_SECRET_PALETTE`].
+    pub palette: [Colour; 16],
+    /// Tag to sprite flag bit table (default: [`DEFAULT_FLAG_BITS`]).
+    pub flag_bits: Vec<(String, u8)>,
 }
 
 impl Default for P8Config {
@@ -83,10 +149,24 @@ impl Default for P8Config {
         Self {
             dither: DitherMethod::Ordered,
             transparent_index: 0,
+            palette: PICO8_PALETTE,
+            flag_bits: DEFAULT_FLAG_BITS
+                .iter()
+                .map(|&(tag, bit)| (tag.to_string(), bit))
+                .collect(),
         }
     }
 }
 
+/// Cartridge sections written after `__gfx__`. Empty sections are omitted.
+#[derive(Debug, Clone, Default)]
+pub struct P8Sections {
+    /// Flag byte per sprite index (see [`p8_flags`]).
+    pub flags: Vec<u8>,
+    /// Map tile rows (see [`p8_map`]).
+    pub map: Vec<Vec<u8>>,
+}
+
 /// Bayer 4x4 ordered dithering threshold matrix.
 /// Values are in the range [0, 16) and should be normalized to [-0.5, 0.5)
 /// by computing (value / 16.0 - 0.5) * spread.
@@ -97,11 +177,11 @@ const BAYER_4X4: [[u8; 4]; 4] = [
     [15, 7, 13, 5],
 ];
 
-/// Find the nearest PICO-8 palette index for a colour.
+/// Find the nearest index in a 16-colour PICO-8 palette for a colour.
 ///
 /// Uses weighted RGB distance that accounts for human colour perception.
 /// Transparent pixels return the configured transparent index.
-pub fn quantize_nearest(colour: &Colour, transparent_index: u8) -> u8 {
+pub fn quantize_nearest(colour: &Colour, palette: &[Colour; 16], transparent_index: u8) -> u8 {
     if colour.is_transparent() {
         return transparent_index;
     }
@@ -109,7 +189,7 @@ pub fn quantize_nearest(colour: &Colour, transparent_index: u8) -> u8 {
     let mut best_index: u8 = 0;
     let mut best_dist = u32::MAX;
 
-    for (i, pc) in PICO8_PALETTE.iter().enumerate() {
+    for (i, pc) in palette.iter().enumerate() {
         let dist = colour_distance(colour, pc);
         if dist < best_dist {
             best_dist = dist;
@@ -125,7 +205,7 @@ pub fn quantize_nearest(colour: &Colour, transparent_index: u8) -> u8 {
 /// Uses the low-cost approximation from https://www.compuphase.com/cmetric.htm
 /// which weights channels based on the mean red value, giving better
 /// perceptual results than plain Euclidean distance.
-fn colour_distance(a: &Colour, b: &Colour) -> u32 {
+pub(crate) fn colour_distance(a: &Colour, b: &Colour) -> u32 {
     let rmean = (a.r as i32 + b.r as i32) / 2;
     let dr = a.r as i32 - b.r as i32;
     let dg = a.g as i32 - b.g as i32;
@@ -143,19 +223,20 @@ fn colour_distance(a: &Colour, b: &Colour) -> u32 {
 /// Dispatches to the configured dithering method.
 pub fn quantize_sheet(pixels: &[Vec<Colour>], config: &P8Config) -> Vec<Vec<u8>> {
     match config.dither {
-        DitherMethod::None => quantize_direct(pixels, config.transparent_index),
-        DitherMethod::Ordered => dither_ordered(pixels, config.transparent_index),
-        DitherMethod::FloydSteinberg => dither_floyd_steinberg(pixels, config.transparent_index),
+        DitherMethod::None => quantize_direct(pixels, &config.palette, config.transparent_index),
+        DitherMethod::Ordered => dither_ordered(pixels, &config.palette, config.transparent_index),
+        DitherMethod::FloydSteinberg => dither_floyd_steinberg(pixels, &config.palette, config.transparent_index),
+        DitherMethod::Atkinson => dither_atkinson(pixels, &config.palette, config.transparent_index),
     }
 }
 
 /// Direct quantization without dithering.
-fn quantize_direct(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<Vec<u8>> {
+fn quantize_direct(pixels: &[Vec<Colour>], palette: &[Colour; 16], transparent_index: u8) -> Vec<Vec<u8>> {
     pixels
         .iter()
         .map(|row| {
             row.iter()
-                .map(|c| quantize_nearest(c, transparent_index))
+                .map(|c| quantize_nearest(c, palette, transparent_index))
                 .collect()
         })
         .collect()
@@ -166,7 +247,7 @@ fn quantize_direct(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<Vec<u8>
 /// Adjusts each pixel's RGB channels by the threshold offset before
 /// finding the nearest palette colour. The spread controls how much
 /// the threshold shifts the colour.
-fn dither_ordered(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<Vec<u8>> {
+fn dither_ordered(pixels: &[Vec<Colour>], palette: &[Colour; 16], transparent_index: u8) -> Vec<Vec<u8>> {
     let spread = 32.0_f32; // dither strength
 
     pixels
@@ -188,7 +269,7 @@ fn dither_ordered(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<Vec<u8>>
                     let b = (c.b as f32 + offset).clamp(0.0, 255.0) as u8;
 
                     let adjusted = Colour::rgb(r, g, b);
-                    quantize_nearest(&adjusted, transparent_index)
+                    quantize_nearest(&adjusted, palette, transparent_index)
                 })
                 .collect()
         })
@@ -204,7 +285,52 @@ fn dither_ordered(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<Vec<u8>>
 ///        *   7/16
 ///  3/16 5/16 1/16
 /// ```
-fn dither_floyd_steinberg(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<Vec<u8>> {
+fn dither_floyd_steinberg(pixels: &[Vec<Colour>], palette: &[Colour; 16], transparent_index: u8) -> Vec<Vec<u8>> {
+    diffuse_error(pixels, palette, transparent_index, &FLOYD_STEINBERG)
+}
+
+/// Atkinson error diffusion dithering.
+///
+/// Each of six neighbours gets 1/8 of the error, so only 6/8 is carried
+/// forward and the rest is dropped:
+///
+/// ```text
+///       *   1/8 1/8
+///  1/8 1/8 1/8
+///      1/8
+/// ```
+fn dither_atkinson(pixels: &[Vec<Colour>], palette: &[Colour; 16], transparent_index: u8) -> Vec<Vec<u8>> {
+    diffuse_error(pixels, palette, transparent_index, &ATKINSON)
+}
+
+/// Floyd-Steinberg error weights as (dx, dy, weight).
+const FLOYD_STEINBERG: [(i32, i32, f32); 4] = [
+    (1, 0, 7.0 / 16.0),
+    (-1, 1, 3.0 / 16.0),
+    (0, 1, 5.0 / 16.0),
+    (1, 1, 1.0 / 16.0),
+];
+
+/// Atkinson error weights as (dx, dy, weight).
+const ATKINSON: [(i32, i32, f32); 6] = [
+    (1, 0, 1.0 / 8.0),
+    (2, 0, 1.0 / 8.0),
+    (-1, 1, 1.0 / 8.0),
+    (0, 1, 1.0 / 8.0),
+    (1, 1, 1.0 / 8.0),
+    (0, 2, 1.0 / 8.0),
+];
+
+/// Error diffusion dithering with the given kernel.
+///
+/// Processes pixels left-to-right, top-to-bottom, pushing each pixel's
+/// quantization error onto its opaque neighbours by the kernel weights.
+fn diffuse_error(
+    pixels: &[Vec<Colour>],
+    palette: &[Colour; 16],
+    transparent_index: u8,
+    kernel: &[(i32, i32, f32)],
+) -> Vec<Vec<u8>> {
     let height = pixels.len();
     if height == 0 {
         return vec![];
@@ -243,10 +369,10 @@ fn dither_floyd_steinberg(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<
                 old[2].clamp(0.0, 255.0) as u8,
             );
 
-            let idx = quantize_nearest(&old_colour, transparent_index);
+            let idx = quantize_nearest(&old_colour, palette, transparent_index);
             result[y][x] = idx;
 
-            let new = &PICO8_PALETTE[idx as usize];
+            let new = &palette[idx as usize];
             let err = [
                 old[0] - new.r as f32,
                 old[1] - new.g as f32,
@@ -254,14 +380,7 @@ fn dither_floyd_steinberg(pixels: &[Vec<Colour>], transparent_index: u8) -> Vec<
             ];
 
             // Distribute error to neighbours
-            let neighbours: [(i32, i32, f32); 4] = [
-                (1, 0, 7.0 / 16.0),
-                (-1, 1, 3.0 / 16.0),
-                (0, 1, 5.0 / 16.0),
-                (1, 1, 1.0 / 16.0),
-            ];
-
-            for (dx, dy, weight) in &neighbours {
+            for (dx, dy, weight) in kernel {
                 let nx = x as i32 + dx;
                 let ny = y as i32 + dy;
 
@@ -300,11 +419,68 @@ pub fn sprites_that_fit(frames: &[Frame], max_w: u32, max_h: u32) -> (Vec<&Frame
     (fitting, truncated)
 }
 
+/// Sprite index of each frame on a PICO-8 sheet, keyed by frame name.
+///
+/// A frame's index is the 8x8 cell holding its top-left corner. Frames that
+/// don't fit within 128x128 have no index.
+pub fn sprite_indices(frames: &[Frame]) -> BTreeMap<String, u8> {
+    let (fitting, _) = sprites_that_fit(frames, P8_WIDTH as u32, P8_HEIGHT as u32);
+    fitting
+        .into_iter()
+        .map(|f| {
+            let index = (f.y / P8_CELL) * (P8_WIDTH as u32 / P8_CELL) + f.x / P8_CELL;
+            (f.name.clone(), index as u8)
+        })
+        .collect()
+}
+
+/// Sprite flag bytes for a PICO-8 cart, one per sprite index (256 in all).
+///
+/// Each sprite's byte sets the bit for every tag in `tags` that appears in
+/// `flag_bits`. Sprites without tags, or with no known tags, get 0.
+pub fn p8_flags(
+    sprites: &BTreeMap<String, u8>,
+    tags: &BTreeMap<String, Vec<String>>,
+    flag_bits: &[(String, u8)],
+) -> Vec<u8> {
+    let mut flags = vec![0u8; 256];
+    for (name, &index) in sprites {
+        for tag in tags.get(name).into_iter().flatten() {
+            for (_, bit) in flag_bits.iter().filter(|(t, bit)| t == tag && *bit < 8) {
+                flags[index as usize] |= 1 << bit;
+            }
+        }
+    }
+    flags
+}
+
+/// Lay out `map` as PICO-8 map tiles using sprite indices from [`sprite_indices`].
+///
+/// Returns one row per map row, each [`P8_MAP_WIDTH`] tiles wide. Cells that
+/// are `empty`, unmapped, or reference a sprite with no index are 0 (PICO-8's
+/// empty sprite). Maps larger than [`P8_MAP_WIDTH`] x [`P8_MAP_HEIGHT`] are cropped.
+pub fn p8_map(map: &Map, sprites: &BTreeMap<String, u8>) -> Vec<Vec<u8>> {
+    let rows = map.height().min(P8_MAP_HEIGHT);
+    let mut tiles = vec![vec![0; P8_MAP_WIDTH]; rows];
+
+    for (x, y, glyph) in map.iter_cells() {
+        if x >= P8_MAP_WIDTH || y >= P8_MAP_HEIGHT {
+            continue;
+        }
+        if let Some(&index) = map.get_legend(glyph).and_then(|name| sprites.get(name)) {
+            tiles[y][x] = index;
+        }
+    }
+
+    tiles
+}
+
 /// Write a PICO-8 cartridge file from a rendered sprite sheet.
 ///
 /// The sheet is cropped (or padded) to 128x128 pixels, quantized to the
 /// PICO-8 palette, and written as a `.p8` cartridge with the `__gfx__` section.
-pub fn write_p8(sheet: &RenderedShape, path: &Path, config: &P8Config) -> Result<()> {
+/// Non-empty `sections` follow as `__gff__` (only if a flag is set) and `__map__`.
+pub fn write_p8(sheet: &RenderedShape, sections: &P8Sections, path: &Path, config: &P8Config) -> Result<()> {
     // Crop/pad the sheet to 128x128
     let mut pixels = Vec::with_capacity(P8_HEIGHT);
     for y in 0..P8_HEIGHT {
@@ -335,6 +511,26 @@ pub fn write_p8(sheet: &RenderedShape, path: &Path, config: &P8Config) -> Result
         output.push('\n');
     }
 
+    if sections.flags.iter().any(|&f| f != 0) {
+        output.push_str("__gff__\n");
+        for row in sections.flags.chunks(128) {
+            for &flag in row {
+                output.push_str(&format!("{:02x}", flag));
+            }
+            output.push('\n');
+        }
+    }
+
+    if !sections.map.is_empty() {
+        output.push_str("__map__\n");
+        for row in &sections.map {
+            for &tile in row {
+                output.push_str(&format!("{:02x}", tile));
+            }
+            output.push('\n');
+        }
+    }
+
     fs::write(path, output).map_err(|e| PxError::Io {
         path: path.to_path_buf(),
         message: format!("Failed to write P8 cartridge: {}", e),
@@ -359,28 +555,55 @@ mod tests {
     #[test]
     fn test_quantize_nearest_exact_match() {
         // Black (index 0) should map to 0
-        assert_eq!(quantize_nearest(&Colour::rgb(0, 0, 0), 0), 0);
+        assert_eq!(quantize_nearest(&Colour::rgb(0, 0, 0), &PICO8_PALETTE, 0), 0);
         // Red (index 8) should map to 8
-        assert_eq!(quantize_nearest(&Colour::rgb(255, 0, 77), 0), 8);
+        assert_eq!(quantize_nearest(&Colour::rgb(255, 0, 77), &PICO8_PALETTE, 0), 8);
         // White-ish (index 7) should map to 7
-        assert_eq!(quantize_nearest(&Colour::rgb(255, 241, 232), 0), 7);
+        assert_eq!(quantize_nearest(&Colour::rgb(255, 241, 232), &PICO8_PALETTE, 0), 7);
     }
 
     #[test]
     fn test_quantize_nearest_transparent() {
-        assert_eq!(quantize_nearest(&Colour::TRANSPARENT, 0), 0);
-        assert_eq!(quantize_nearest(&Colour::TRANSPARENT, 5), 5);
-        assert_eq!(quantize_nearest(&Colour::new(255, 0, 0, 0), 3), 3);
+        assert_eq!(quantize_nearest(&Colour::TRANSPARENT, &PICO8_PALETTE, 0), 0);
+        assert_eq!(quantize_nearest(&Colour::TRANSPARENT, &PICO8_PALETTE, 5), 5);
+        assert_eq!(quantize_nearest(&Colour::new(255, 0, 0, 0), &PICO8_PALETTE, 3), 3);
+    }
+
+    #[test]
+    fn test_quantize_nearest_custom_palette() {
+        let mut palette = PICO8_PALETTE;
+        palette[8] = PICO8_SECRET_PALETTE[8]; // dark red in place of red
+
+        let dark_red = Colour::rgb(190, 18, 80);
+        assert_ne!(quantize_nearest(&dark_red, &PICO8_PALETTE, 0), 8);
+        assert_eq!(quantize_nearest(&dark_red, &palette, 0), 8);
+    }
+
+    #[test]
+    fn test_quantize_sheet_uses_config_palette() {
+        let pixels = vec![PICO8_SECRET_PALETTE.to_vec()];
+        let standard = quantize_sheet(&pixels, &P8Config { dither: DitherMethod::None, ..P8Config::default() });
+        let secret = quantize_sheet(
+            &pixels,
+            &P8Config {
+                dither: DitherMethod::None,
+                palette: PICO8_SECRET_PALETTE,
+                ..P8Config::default()
+            },
+        );
+
+        assert_eq!(secret[0], (0..16).collect::<Vec<u8>>());
+        assert_ne!(standard, secret);
     }
 
     #[test]
     fn test_quantize_nearest_closest() {
         // Pure red (#FF0000) should map to PICO-8 red (#FF004D, index 8)
-        let idx = quantize_nearest(&Colour::rgb(255, 0, 0), 0);
+        let idx = quantize_nearest(&Colour::rgb(255, 0, 0), &PICO8_PALETTE, 0);
         assert_eq!(idx, 8);
 
         // Mid grey maps to indigo (13: #83769C) via weighted distance
-        let idx = quantize_nearest(&Colour::rgb(128, 128, 128), 0);
+        let idx = quantize_nearest(&Colour::rgb(128, 128, 128), &PICO8_PALETTE, 0);
         assert_eq!(idx, 13);
     }
 
@@ -394,10 +617,11 @@ mod tests {
         let config = P8Config {
             dither: DitherMethod::None,
             transparent_index: 0,
+            ..P8Config::default()
         };
 
         let result = quantize_sheet(&pixels, &config);
-        let direct = quantize_direct(&pixels, 0);
+        let direct = quantize_direct(&pixels, &PICO8_PALETTE, 0);
 
         assert_eq!(result, direct);
     }
@@ -411,7 +635,7 @@ mod tests {
             vec![Colour::rgb(30, 30, 200); 8],
         ];
 
-        let result = dither_ordered(&pixels, 0);
+        let result = dither_ordered(&pixels, &PICO8_PALETTE, 0);
 
         for row in &result {
             for &idx in row {
@@ -429,7 +653,7 @@ mod tests {
             vec![Colour::rgb(30, 30, 200); 8],
         ];
 
-        let result = dither_floyd_steinberg(&pixels, 0);
+        let result = dither_floyd_steinberg(&pixels, &PICO8_PALETTE, 0);
 
         for row in &result {
             for &idx in row {
@@ -438,10 +662,39 @@ mod tests {
         }
     }
 
+    /// A horizontal grey ramp, 32 pixels wide and 4 rows tall.
+    fn grey_ramp() -> Vec<Vec<Colour>> {
+        let row: Vec<Colour> = (0..32).map(|x| Colour::rgb(x * 8, x * 8, x * 8)).collect();
+        vec![row; 4]
+    }
+
+    #[test]
+    fn test_dither_atkinson_produces_valid_indices() {
+        let result = dither_atkinson(&grey_ramp(), &PICO8_PALETTE, 0);
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().flatten().all(|&idx| idx < 16));
+    }
+
+    #[test]
+    fn test_atkinson_propagates_less_error() {
+        let spread = |kernel: &[(i32, i32, f32)]| kernel.iter().map(|k| k.2).sum::<f32>();
+        assert!((spread(&FLOYD_STEINBERG) - 1.0).abs() < 1e-6);
+        assert!((spread(&ATKINSON) - 0.75).abs() < 1e-6);
+
+        // Dropping error leaves fewer speckles: fewer colour changes across the ramp
+        let changes = |indices: &[Vec<u8>]| -> usize {
+            indices.iter().map(|row| row.windows(2).filter(|w| w[0] != w[1]).count()).sum()
+        };
+        let ramp = grey_ramp();
+        let fs = changes(&dither_floyd_steinberg(&ramp, &PICO8_PALETTE, 0));
+        let atkinson = changes(&dither_atkinson(&ramp, &PICO8_PALETTE, 0));
+        assert!(atkinson < fs, "atkinson {} vs floyd-steinberg {}", atkinson, fs);
+    }
+
     #[test]
     fn test_dither_floyd_steinberg_empty() {
         let pixels: Vec<Vec<Colour>> = vec![];
-        let result = dither_floyd_steinberg(&pixels, 0);
+        let result = dither_floyd_steinberg(&pixels, &PICO8_PALETTE, 0);
         assert!(result.is_empty());
     }
 
@@ -455,9 +708,10 @@ mod tests {
         let config = P8Config {
             dither: DitherMethod::None,
             transparent_index: 0,
+            ..P8Config::default()
         };
 
-        write_p8(&sheet, &path, &config).unwrap();
+        write_p8(&sheet, &P8Sections::default(), &path, &config).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         let lines: Vec<&str> = content.lines().collect();
@@ -493,7 +747,7 @@ mod tests {
         let path = dir.path().join("crop.p8");
 
         let config = P8Config::default();
-        write_p8(&sheet, &path, &config).unwrap();
+        write_p8(&sheet, &P8Sections::default(), &path, &config).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         let gfx_lines: Vec<&str> = content.lines().skip(3).collect();
@@ -514,9 +768,10 @@ mod tests {
         let config = P8Config {
             dither: DitherMethod::None,
             transparent_index: 0,
+            ..P8Config::default()
         };
 
-        write_p8(&sheet, &path, &config).unwrap();
+        write_p8(&sheet, &P8Sections::default(), &path, &config).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         let gfx_lines: Vec<&str> = content.lines().skip(3).collect();
@@ -533,9 +788,9 @@ mod tests {
     #[test]
     fn test_sprites_that_fit() {
         let frames = vec![
-            Frame { name: "a".into(), x: 0, y: 0, w: 8, h: 8 },
-            Frame { name: "b".into(), x: 8, y: 0, w: 8, h: 8 },
-            Frame { name: "c".into(), x: 120, y: 120, w: 16, h: 16 }, // exceeds 128x128
+            Frame { name: "a".into(), x: 0, y: 0, w: 8, h: 8, trim: None, pivot: None, cell: None },
+            Frame { name: "b".into(), x: 8, y: 0, w: 8, h: 8, trim: None, pivot: None, cell: None },
+            Frame { name: "c".into(), x: 120, y: 120, w: 16, h: 16, trim: None, pivot: None, cell: None }, // exceeds 128x128
         ];
 
         let (fit, trunc) = sprites_that_fit(&frames, 128, 128);
@@ -544,11 +799,142 @@ mod tests {
         assert_eq!(trunc[0].name, "c");
     }
 
+    #[test]
+    fn test_sprite_indices() {
+        let frames = vec![
+            Frame { name: "a".into(), x: 0, y: 0, w: 8, h: 8, trim: None, pivot: None, cell: None },
+            Frame { name: "b".into(), x: 8, y: 0, w: 8, h: 8, trim: None, pivot: None, cell: None },
+            Frame { name: "c".into(), x: 16, y: 8, w: 8, h: 8, trim: None, pivot: None, cell: None },
+            Frame { name: "d".into(), x: 120, y: 120, w: 16, h: 16, trim: None, pivot: None, cell: None },
+        ];
+
+        let indices = sprite_indices(&frames);
+        assert_eq!(indices.get("a"), Some(&0));
+        assert_eq!(indices.get("b"), Some(&1));
+        assert_eq!(indices.get("c"), Some(&18));
+        assert_eq!(indices.get("d"), None);
+    }
+
+    fn test_map(rows: &[&str]) -> Map {
+        let grid = rows.iter().map(|r| r.chars().collect()).collect();
+        let legend = [('W', "wall".to_string()), ('F', "floor".to_string()), ('.', "empty".to_string())]
+            .into_iter()
+            .collect();
+        Map::new("level", vec![], grid, legend)
+    }
+
+    fn test_sprites() -> BTreeMap<String, u8> {
+        [("wall".to_string(), 1), ("floor".to_string(), 0x12)].into_iter().collect()
+    }
+
+    #[test]
+    fn test_p8_map_rows() {
+        let map = test_map(&["WWW", "WF.", "W?W"]);
+        let tiles = p8_map(&map, &test_sprites());
+
+        assert_eq!(tiles.len(), 3);
+        assert!(tiles.iter().all(|row| row.len() == P8_MAP_WIDTH));
+        assert_eq!(tiles[0][..3], [1, 1, 1]);
+        assert_eq!(tiles[1][..3], [1, 0x12, 0]);
+        // Unmapped glyphs are empty
+        assert_eq!(tiles[2][..3], [1, 0, 1]);
+    }
+
+    #[test]
+    fn test_p8_map_truncates_to_region() {
+        let wide = "W".repeat(P8_MAP_WIDTH + 10);
+        let rows: Vec<&str> = vec![wide.as_str(); P8_MAP_HEIGHT + 4];
+        let tiles = p8_map(&test_map(&rows), &test_sprites());
+
+        assert_eq!(tiles.len(), P8_MAP_HEIGHT);
+        assert!(tiles.iter().all(|row| row.len() == P8_MAP_WIDTH && row.iter().all(|&t| t == 1)));
+    }
+
+    #[test]
+    fn test_write_p8_map_section() {
+        let sheet = RenderedShape::new("test", vec![vec![Colour::TRANSPARENT; 8]; 8]);
+        let tiles = p8_map(&test_map(&["WF", "FW"]), &test_sprites());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.p8");
+
+        write_p8(&sheet, &P8Sections { map: tiles, ..Default::default() }, &path, &P8Config::default()).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        let start = lines.iter().position(|l| *l == "__map__").unwrap();
+        assert_eq!(start, 3 + P8_HEIGHT);
+        assert_eq!(lines.len(), start + 3);
+        assert_eq!(lines[start + 1].len(), P8_MAP_WIDTH * 2);
+        assert!(lines[start + 1].starts_with("011200"));
+        assert!(lines[start + 2].starts_with("120100"));
+    }
+
+    #[test]
+    fn test_write_p8_without_map_has_no_map_section() {
+        let sheet = RenderedShape::new("test", vec![vec![Colour::TRANSPARENT; 8]; 8]);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.p8");
+
+        write_p8(&sheet, &P8Sections::default(), &path, &P8Config::default()).unwrap();
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("__map__"));
+    }
+
+    #[test]
+    fn test_p8_flags_from_tags() {
+        let sprites: BTreeMap<String, u8> =
+            [("spikes".to_string(), 3), ("grass".to_string(), 4), ("crate".to_string(), 5)].into_iter().collect();
+        let tags: BTreeMap<String, Vec<String>> = [
+            ("spikes".to_string(), vec!["solid".to_string(), "damage".to_string()]),
+            ("crate".to_string(), vec!["wooden".to_string()]),
+        ]
+        .into_iter()
+        .collect();
+
+        let flags = p8_flags(&sprites, &tags, &P8Config::default().flag_bits);
+
+        assert_eq!(flags.len(), 256);
+        assert_eq!(flags[3], 0b11);
+        assert_eq!(flags[4], 0);
+        assert_eq!(flags[5], 0);
+        assert_eq!(flags.iter().filter(|&&f| f != 0).count(), 1);
+    }
+
+    #[test]
+    fn test_p8_flags_custom_bits() {
+        let sprites: BTreeMap<String, u8> = [("door".to_string(), 0)].into_iter().collect();
+        let tags: BTreeMap<String, Vec<String>> =
+            [("door".to_string(), vec!["locked".to_string()])].into_iter().collect();
+
+        let flags = p8_flags(&sprites, &tags, &[("locked".to_string(), 7)]);
+        assert_eq!(flags[0], 0x80);
+    }
+
+    #[test]
+    fn test_write_p8_gff_section() {
+        let sheet = RenderedShape::new("test", vec![vec![Colour::TRANSPARENT; 8]; 8]);
+        let mut flags = vec![0u8; 256];
+        flags[1] = 0b11;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.p8");
+
+        let sections = P8Sections { flags, ..Default::default() };
+        write_p8(&sheet, &sections, &path, &P8Config::default()).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[3 + P8_HEIGHT], "__gff__");
+        assert_eq!(lines.len(), 3 + P8_HEIGHT + 3);
+        assert!(lines[4 + P8_HEIGHT].starts_with("000300"));
+        assert!(lines[4 + P8_HEIGHT..].iter().all(|l| l.len() == 256));
+    }
+
     #[test]
     fn test_dither_method_display() {
         assert_eq!(format!("{}", DitherMethod::None), "none");
         assert_eq!(format!("{}", DitherMethod::Ordered), "ordered");
         assert_eq!(format!("{}", DitherMethod::FloydSteinberg), "floyd-steinberg");
+        assert_eq!(format!("{}", DitherMethod::Atkinson), "atkinson");
     }
 
     #[test]
@@ -558,6 +944,7 @@ mod tests {
         assert_eq!(DitherMethod::from_str_lossy("bayer"), DitherMethod::Ordered);
         assert_eq!(DitherMethod::from_str_lossy("floyd-steinberg"), DitherMethod::FloydSteinberg);
         assert_eq!(DitherMethod::from_str_lossy("fs"), DitherMethod::FloydSteinberg);
+        assert_eq!(DitherMethod::from_str_lossy("atkinson"), DitherMethod::Atkinson);
         // Unknown defaults to ordered
         assert_eq!(DitherMethod::from_str_lossy("unknown"), DitherMethod::Ordered);
     }
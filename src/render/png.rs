@@ -1,24 +1,53 @@
 //! PNG output for rendered shapes.
 //!
-//! Converts rendered shapes to PNG files with optional integer scaling.
-
+//! Converts rendered shapes to PNG files with optional integer scaling,
+//! as RGBA or as indexed colour with a palette. [`write_png`] picks
+//! indexed colour whenever the palette fits, since pixel art rarely needs
+//! more and the files are much smaller; [`write_png_as`] follows a
+//! target's palette mode instead.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
 use std::path::Path;
 
 use image::{ImageBuffer, Rgba, RgbaImage};
 
 use crate::error::{PxError, Result};
-use crate::types::Colour;
+use crate::types::{Colour, PaletteMode};
 
 use super::RenderedShape;
 
 /// Write a rendered shape to a PNG file.
 ///
+/// Shapes with at most [`MAX_INDEXED_COLOURS`] distinct colours are
+/// written as indexed PNGs (see [`write_indexed_png`]), others as RGBA.
+///
 /// # Arguments
 ///
 /// * `rendered` - The rendered shape to write
 /// * `path` - Output file path
 /// * `scale` - Integer scale factor (1 = no scaling)
 pub fn write_png(rendered: &RenderedShape, path: &Path, scale: u32) -> Result<()> {
+    if rendered.colour_histogram().len() <= MAX_INDEXED_COLOURS {
+        write_indexed_png(rendered, path, scale)
+    } else {
+        write_rgba_png(rendered, path, scale)
+    }
+}
+
+/// Write a rendered shape to a PNG file in a target's palette mode:
+/// always RGBA for [`PaletteMode::Rgba`], indexed when the palette fits
+/// (as [`write_png`]) for [`PaletteMode::Indexed`].
+pub fn write_png_as(rendered: &RenderedShape, path: &Path, scale: u32, mode: &PaletteMode) -> Result<()> {
+    match mode {
+        PaletteMode::Rgba => write_rgba_png(rendered, path, scale),
+        PaletteMode::Indexed => write_png(rendered, path, scale),
+    }
+}
+
+/// Write a rendered shape to an RGBA PNG file, however few colours it has.
+pub fn write_rgba_png(rendered: &RenderedShape, path: &Path, scale: u32) -> Result<()> {
     let scale = scale.max(1); // Minimum scale of 1
 
     let width = rendered.width() as u32 * scale;
@@ -49,6 +78,96 @@ pub fn write_png(rendered: &RenderedShape, path: &Path, scale: u32) -> Result<()
     Ok(())
 }
 
+/// Most colours an indexed PNG palette can hold.
+pub const MAX_INDEXED_COLOURS: usize = 256;
+
+/// Write a rendered shape to an indexed-colour PNG file.
+///
+/// The palette holds each distinct colour in the order first seen, with
+/// alpha in a `tRNS` chunk when any colour is translucent. Fails if the
+/// shape uses more than [`MAX_INDEXED_COLOURS`] colours.
+pub fn write_indexed_png(rendered: &RenderedShape, path: &Path, scale: u32) -> Result<()> {
+    let scale = scale.max(1) as usize;
+
+    let mut palette: Vec<Colour> = Vec::new();
+    let mut lookup: HashMap<Colour, u8> = HashMap::new();
+    let mut data = Vec::with_capacity(rendered.width() * rendered.height() * scale * scale);
+    for row in rendered.pixels() {
+        let mut indices = Vec::with_capacity(row.len());
+        for &colour in row {
+            let index = match lookup.get(&colour) {
+                Some(&index) => index,
+                None if palette.len() < MAX_INDEXED_COLOURS => {
+                    let index = palette.len() as u8;
+                    palette.push(colour);
+                    lookup.insert(colour, index);
+                    index
+                }
+                None => {
+                    return Err(PxError::Build {
+                        message: format!(
+                            "'{}' uses more than {} colours and can't be written as an indexed PNG",
+                            rendered.name, MAX_INDEXED_COLOURS
+                        ),
+                        help: Some("Write it as an RGBA PNG instead".to_string()),
+                    });
+                }
+            };
+            indices.extend(std::iter::repeat_n(index, scale));
+        }
+        for _ in 0..scale {
+            data.extend_from_slice(&indices);
+        }
+    }
+
+    let io_error = |e: &dyn std::fmt::Display| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write PNG: {}", e),
+    };
+    let file = File::create(path).map_err(|e| io_error(&e))?;
+    let width = (rendered.width() * scale) as u32;
+    let height = (rendered.height() * scale) as u32;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.iter().flat_map(|c| [c.r, c.g, c.b]).collect::<Vec<u8>>());
+    if palette.iter().any(|c| c.a < 255) {
+        encoder.set_trns(palette.iter().map(|c| c.a).collect::<Vec<u8>>());
+    }
+
+    let mut writer = encoder.write_header().map_err(|e| io_error(&e))?;
+    writer.write_image_data(&data).map_err(|e| io_error(&e))?;
+    writer.finish().map_err(|e| io_error(&e))
+}
+
+/// Read a PNG file back into a rendered shape called `name`.
+pub fn read_png(path: &Path, name: impl Into<String>) -> Result<RenderedShape> {
+    let img = image::open(path).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to read PNG: {}", e),
+    })?;
+    Ok(RenderedShape::from_rgba(name, &img.to_rgba8()))
+}
+
+/// Whether a prefab or map legend value names an external PNG rather than
+/// a shape or prefab, as in `X: ./tiles/rock.png`.
+pub fn is_png_ref(name: &str) -> bool {
+    name.ends_with(".png")
+}
+
+/// Read the external PNGs among legend `names`, resolving paths relative
+/// to `dir`.
+///
+/// Each is named by its legend value, so registering it with a prefab or
+/// map renderer lets it composite like a rendered shape.
+pub fn read_png_refs<'a>(names: impl IntoIterator<Item = &'a str>, dir: &Path) -> Result<Vec<RenderedShape>> {
+    names
+        .into_iter()
+        .filter(|name| is_png_ref(name))
+        .map(|name| read_png(&dir.join(name), name))
+        .collect()
+}
+
 /// Scale a rendered shape's pixels by an integer factor.
 ///
 /// Uses nearest-neighbour scaling for crisp pixel art.
@@ -80,6 +199,30 @@ pub fn scale_pixels(pixels: &[Vec<Colour>], scale: u32) -> Vec<Vec<Colour>> {
     scaled
 }
 
+/// Scale a rendered shape's pixels by any positive factor.
+///
+/// Nearest-neighbour like [`scale_pixels`], so edges stay hard; target
+/// dimensions are rounded (never below 1 pixel).
+pub fn scale_pixels_f(pixels: &[Vec<Colour>], factor: f32) -> Vec<Vec<Colour>> {
+    let height = pixels.len();
+    let width = pixels.first().map_or(0, |r| r.len());
+    if width == 0 || height == 0 {
+        return pixels.to_vec();
+    }
+
+    let new_width = ((width as f32 * factor).round() as usize).max(1);
+    let new_height = ((height as f32 * factor).round() as usize).max(1);
+
+    (0..new_height)
+        .map(|ny| {
+            let y = (ny * height / new_height).min(height - 1);
+            (0..new_width)
+                .map(|nx| pixels[y][(nx * width / new_width).min(width - 1)])
+                .collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +251,18 @@ mod tests {
         assert_eq!(img.get_pixel(1, 0).0, [255, 255, 255, 255]); // White
     }
 
+    #[test]
+    fn test_read_png_round_trip() {
+        let pixels = vec![vec![Colour::rgb(255, 0, 0), Colour::TRANSPARENT, Colour::new(0, 0, 255, 128)]];
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("row.png");
+        write_png(&RenderedShape::new("row", pixels.clone()), &path, 1).unwrap();
+
+        let read = read_png(&path, "copy").unwrap();
+        assert_eq!(read.name, "copy");
+        assert_eq!(read.pixels(), &pixels[..]);
+    }
+
     #[test]
     fn test_write_png_scaled() {
         let pixels = vec![vec![Colour::rgb(255, 0, 0), Colour::rgb(0, 255, 0)]];
@@ -171,6 +326,36 @@ mod tests {
         assert_eq!(scaled, pixels);
     }
 
+    /// 2x2 grid with a distinct colour in each corner.
+    fn corners() -> Vec<Vec<Colour>> {
+        vec![
+            vec![Colour::BLACK, Colour::WHITE],
+            vec![Colour::MAGENTA, Colour::TRANSPARENT],
+        ]
+    }
+
+    #[test]
+    fn test_scale_pixels_f_up() {
+        let scaled = scale_pixels_f(&corners(), 1.5);
+
+        assert_eq!(scaled.len(), 3);
+        assert_eq!(scaled[0].len(), 3);
+        assert_eq!(scaled[0][0], Colour::BLACK);
+        assert_eq!(scaled[0][2], Colour::WHITE);
+        assert_eq!(scaled[2][0], Colour::MAGENTA);
+        assert_eq!(scaled[2][2], Colour::TRANSPARENT);
+    }
+
+    #[test]
+    fn test_scale_pixels_f_down() {
+        let pixels = scale_pixels(&corners(), 2);
+        let scaled = scale_pixels_f(&pixels, 0.5);
+        assert_eq!(scaled, corners());
+
+        // Never below one pixel
+        assert_eq!(scale_pixels_f(&corners(), 0.1), vec![vec![Colour::BLACK]]);
+    }
+
     #[test]
     fn test_write_png_scale_zero_treated_as_one() {
         let pixels = vec![vec![Colour::BLACK]];
@@ -185,4 +370,89 @@ mod tests {
         assert_eq!(img.width(), 1);
         assert_eq!(img.height(), 1);
     }
+
+    #[test]
+    fn test_write_indexed_png_round_trips() {
+        let red = Colour::rgb(255, 0, 0);
+        let ghost = Colour::new(0, 0, 255, 128);
+        let pixels = vec![
+            vec![Colour::BLACK, Colour::WHITE, red],
+            vec![Colour::TRANSPARENT, ghost, Colour::BLACK],
+        ];
+        let rendered = RenderedShape::new("test", pixels);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.png");
+        write_indexed_png(&rendered, &path, 2).unwrap();
+
+        let reader = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap())).read_info().unwrap();
+        assert_eq!(reader.info().color_type, png::ColorType::Indexed);
+        assert_eq!(reader.info().palette.as_ref().unwrap().len(), 5 * 3);
+
+        let img = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (6, 4));
+        assert_eq!(img.get_pixel(5, 1).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(0, 2).0[3], 0);
+        assert_eq!(img.get_pixel(3, 3).0, [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn test_write_png_indexes_few_colours() {
+        let red = Colour::rgb(255, 0, 0);
+        let pixels = vec![
+            vec![Colour::BLACK, Colour::WHITE],
+            vec![red, Colour::TRANSPARENT],
+        ];
+        let rendered = RenderedShape::new("test", pixels);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.png");
+        write_png(&rendered, &path, 1).unwrap();
+
+        let reader = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap())).read_info().unwrap();
+        assert_eq!(reader.info().color_type, png::ColorType::Indexed);
+        assert_eq!(read_png(&path, "test").unwrap().pixels(), rendered.pixels());
+    }
+
+    #[test]
+    fn test_write_png_many_colours_stays_rgba() {
+        let rows: Vec<Vec<Colour>> = (0..32)
+            .map(|y| (0..32).map(|x| Colour::rgb(x * 8, y * 8, 128)).collect())
+            .collect();
+        let rendered = RenderedShape::new("gradient", rows);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gradient.png");
+        write_png(&rendered, &path, 1).unwrap();
+
+        let reader = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap())).read_info().unwrap();
+        assert_eq!(reader.info().color_type, png::ColorType::Rgba);
+        assert_eq!(read_png(&path, "gradient").unwrap().pixels(), rendered.pixels());
+    }
+
+    #[test]
+    fn test_write_png_as_follows_palette_mode() {
+        let dir = tempdir().unwrap();
+        let shape = RenderedShape::new("duo", vec![vec![Colour::BLACK, Colour::WHITE]]);
+        let colour_type = |path: &Path| {
+            png::Decoder::new(std::io::BufReader::new(File::open(path).unwrap())).read_info().unwrap().info().color_type
+        };
+
+        let rgba = dir.path().join("rgba.png");
+        write_png_as(&shape, &rgba, 1, &PaletteMode::Rgba).unwrap();
+        assert_eq!(colour_type(&rgba), png::ColorType::Rgba);
+
+        let indexed = dir.path().join("indexed.png");
+        write_png_as(&shape, &indexed, 1, &PaletteMode::Indexed).unwrap();
+        assert_eq!(colour_type(&indexed), png::ColorType::Indexed);
+    }
+
+    #[test]
+    fn test_write_indexed_png_too_many_colours() {
+        let row: Vec<Colour> = (0..=MAX_INDEXED_COLOURS).map(|i| Colour::rgb(i as u8, (i >> 8) as u8, 0)).collect();
+        let rendered = RenderedShape::new("gradient", vec![row]);
+
+        let dir = tempdir().unwrap();
+        assert!(write_indexed_png(&rendered, &dir.path().join("gradient.png"), 1).is_err());
+    }
 }
@@ -40,6 +40,7 @@ impl PrefabRenderer {
                 grid: [0, 0],
                 cell_size: [1, 1],
                 shapes: vec![],
+                source: None,
             };
             return Ok((
                 RenderedShape::new(&prefab.name, vec![vec![Colour::TRANSPARENT]]),
@@ -106,6 +107,7 @@ impl PrefabRenderer {
             grid: [prefab.width(), prefab.height()],
             cell_size: [cell_w, cell_h],
             shapes,
+            source: None,
         };
 
         Ok((RenderedShape::new(&prefab.name, pixels), metadata))
@@ -135,7 +137,8 @@ impl PrefabRenderer {
     }
 }
 
-/// Copy source pixels onto destination at offset, skipping transparent pixels.
+/// Copy source pixels onto destination at offset, skipping transparent
+/// pixels. Holes in the source clear the destination.
 fn blit(dest: &mut [Vec<Colour>], source: &RenderedShape, offset_x: usize, offset_y: usize) {
     for sy in 0..source.height() {
         let dy = offset_y + sy;
@@ -147,7 +150,9 @@ fn blit(dest: &mut [Vec<Colour>], source: &RenderedShape, offset_x: usize, offse
             if dx >= dest[dy].len() {
                 break;
             }
-            if let Some(pixel) = source.get(sx, sy) {
+            if source.is_hole(sx, sy) {
+                dest[dy][dx] = Colour::TRANSPARENT;
+            } else if let Some(pixel) = source.get(sx, sy) {
                 if pixel.a > 0 {
                     dest[dy][dx] = pixel;
                 }
@@ -0,0 +1,252 @@
+//! In-memory project rendering.
+//!
+//! `render_project` renders every shape, prefab and map in a registry
+//! without touching the filesystem, for embedding px as a library. The CLI
+//! build renders through [`render_assets`] too, adding caching and file
+//! output on top.
+
+use crate::error::{PxError, Result};
+use crate::registry::{AssetId, AssetKind, AssetRegistry};
+use crate::types::{BuiltinShaders, MapMetadata, Palette, PrefabMetadata, Shader, Shape};
+
+use super::{spin_frames, MapRenderer, PrefabRenderer, RenderedShape, ShapeRenderer};
+
+/// Settings for [`render_project`].
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Shader to render with; `None` uses the builtin default shader.
+    pub shader: Option<Shader>,
+    /// Fail on glyphs that resolve to nothing, instead of drawing them magenta.
+    pub strict: bool,
+    /// Images prefabs and maps can reference by name, such as PNG
+    /// references read by the caller. They aren't returned.
+    pub images: Vec<RenderedShape>,
+    /// Shape to turn into rotation frames (`{name}-spin-{i}`), and how many.
+    /// The frames render as shapes, so prefabs and maps can use them.
+    pub spin: Option<(String, usize)>,
+    /// Skip maps, for callers that only need shapes and prefabs.
+    pub skip_maps: bool,
+}
+
+impl RenderOptions {
+    /// Render with `shader` (its palette, variant and effects).
+    pub fn with_shader(mut self, shader: Shader) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+
+    /// Fail on unresolved glyphs.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Make `images` available to prefabs and maps.
+    pub fn with_images(mut self, images: Vec<RenderedShape>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Render `count` rotation frames of the shape called `name`.
+    pub fn with_spin(mut self, name: impl Into<String>, count: usize) -> Self {
+        self.spin = Some((name.into(), count));
+        self
+    }
+
+    /// Leave maps out.
+    pub fn skip_maps(mut self, skip: bool) -> Self {
+        self.skip_maps = skip;
+        self
+    }
+}
+
+/// Layout metadata from rendering a prefab or map.
+#[derive(Debug, Clone)]
+pub enum AssetMetadata {
+    Prefab(PrefabMetadata),
+    Map(MapMetadata),
+}
+
+/// One asset rendered by [`render_assets`].
+#[derive(Debug, Clone)]
+pub struct RenderedAsset {
+    pub id: AssetId,
+    pub image: RenderedShape,
+    /// Set for prefabs and maps.
+    pub metadata: Option<AssetMetadata>,
+}
+
+/// Everything [`render_assets`] rendered.
+#[derive(Debug, Clone, Default)]
+pub struct RenderedProject {
+    /// Shapes (spin frames after their shape), then prefabs and maps in
+    /// build order.
+    pub assets: Vec<RenderedAsset>,
+    /// `(shape, glyph)` pairs that fell back to magenta.
+    pub fallbacks: Vec<(String, char)>,
+}
+
+impl RenderedProject {
+    /// The rendered asset with this kind and name.
+    pub fn get(&self, kind: AssetKind, name: &str) -> Option<&RenderedAsset> {
+        self.assets.iter().find(|a| a.id.kind == kind && a.id.name == name)
+    }
+}
+
+/// The palette `shader` draws with: the builtin default for `default`,
+/// otherwise the registry's palette of that name, falling back to the
+/// default palette.
+pub fn shader_palette(shader: &Shader, registry: &AssetRegistry) -> Palette {
+    if shader.palette == "default" {
+        return Palette::default_palette();
+    }
+    registry.get_palette(&shader.palette).cloned().unwrap_or_else(Palette::default_palette)
+}
+
+/// Render every shape, prefab and map in `registry`, in build order
+/// (dependencies first), so prefabs and maps compose the shapes and
+/// prefabs rendered before them.
+///
+/// The palette comes from [`shader_palette`]. Stamps and brushes come from
+/// the registry too, so load it with builtins for the builtin ones. PNG
+/// references (`hero.png`) are files, so they aren't resolved here; pass
+/// them in as [`RenderOptions::images`].
+pub fn render_project(registry: &AssetRegistry, options: &RenderOptions) -> Result<Vec<RenderedShape>> {
+    Ok(render_assets(registry, options)?.assets.into_iter().map(|a| a.image).collect())
+}
+
+/// Render like [`render_project`], keeping each asset's id and metadata
+/// and the glyphs that fell back to magenta.
+pub fn render_assets(registry: &AssetRegistry, options: &RenderOptions) -> Result<RenderedProject> {
+    let shader = match &options.shader {
+        Some(shader) => shader.clone(),
+        None => BuiltinShaders::get("default").unwrap(),
+    };
+    let palette = shader_palette(&shader, registry);
+
+    let mut renderer = ShapeRenderer::new(&palette);
+    renderer.add_stamps(registry.stamps());
+    renderer.add_brushes(registry.brushes());
+    if let Some(variant) = &shader.palette_variant {
+        renderer = renderer.with_variant(variant);
+    }
+    let renderer = renderer.with_effects(&shader.effects).strict(options.strict);
+
+    let mut composer = Composer::new();
+    for image in &options.images {
+        composer.add(image);
+    }
+
+    // Shapes only draw stamps and brushes, so they render independently
+    let shapes: Vec<&Shape> = registry
+        .build_order()
+        .iter()
+        .filter(|id| id.kind == AssetKind::Shape)
+        .filter_map(|id| registry.get_shape(&id.name))
+        .collect();
+    let mut assets = Vec::new();
+    let mut spun = false;
+    for (shape, image) in shapes.iter().zip(render_shapes(&renderer, &shapes)) {
+        let image = image?;
+        composer.add(&image);
+        let spin = options.spin.as_ref().filter(|(name, _)| *name == shape.name);
+        let frames = spin.map(|(_, count)| spin_frames(&image, *count)).unwrap_or_default();
+        assets.push(RenderedAsset { id: AssetId::new(AssetKind::Shape, &shape.name), image, metadata: None });
+        for frame in frames {
+            spun = true;
+            composer.add(&frame);
+            assets.push(RenderedAsset { id: AssetId::new(AssetKind::Shape, &frame.name), image: frame, metadata: None });
+        }
+    }
+    if let Some((name, _)) = options.spin.as_ref().filter(|_| !spun) {
+        return Err(PxError::Build {
+            message: format!("Can't spin: no shape named '{}'", name),
+            help: Some("Run `px list` to see the shapes in the project".to_string()),
+        });
+    }
+
+    for id in registry.build_order() {
+        let (image, metadata) = match id.kind {
+            AssetKind::Prefab => match registry.get_prefab(&id.name) {
+                Some(prefab) => {
+                    let (image, metadata) = composer.prefabs.render(prefab)?;
+                    composer.add(&image);
+                    (image, AssetMetadata::Prefab(metadata))
+                }
+                None => continue,
+            },
+            AssetKind::Map if !options.skip_maps => match registry.get_map(&id.name) {
+                Some(map) => {
+                    let (image, metadata) = composer.maps.render(map)?;
+                    (image, AssetMetadata::Map(metadata))
+                }
+                None => continue,
+            },
+            _ => continue,
+        };
+        assets.push(RenderedAsset { id: id.clone(), image, metadata: Some(metadata) });
+    }
+
+    Ok(RenderedProject { assets, fallbacks: renderer.fallbacks() })
+}
+
+/// Prefab and map renderers, fed every image later assets may reference.
+struct Composer {
+    prefabs: PrefabRenderer,
+    maps: MapRenderer,
+}
+
+impl Composer {
+    fn new() -> Self {
+        Self { prefabs: PrefabRenderer::new(), maps: MapRenderer::new() }
+    }
+
+    fn add(&mut self, image: &RenderedShape) {
+        self.prefabs.add_rendered(image.clone());
+        self.maps.add_rendered(image.clone());
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn render_shapes(renderer: &ShapeRenderer, shapes: &[&Shape]) -> Vec<Result<RenderedShape>> {
+    use rayon::prelude::*;
+    shapes.par_iter().map(|shape| renderer.render(shape)).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn render_shapes(renderer: &ShapeRenderer, shapes: &[&Shape]) -> Vec<Result<RenderedShape>> {
+    shapes.iter().map(|shape| renderer.render(shape)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_map_file, parse_prefab_file, parse_shape_file};
+    use crate::registry::RegistryBuilder;
+    use crate::types::{BuiltinBrushes, BuiltinStamps, Colour};
+
+    #[test]
+    fn test_render_project_in_memory() {
+        let mut builder = RegistryBuilder::new();
+        builder.add_stamps(BuiltinStamps::all());
+        builder.add_brushes(BuiltinBrushes::all());
+        builder.add_shapes(parse_shape_file("---\nname: block\n---\n\n```px\n##\n#.\n```\n").unwrap());
+        builder.add_prefabs(parse_prefab_file("---\nname: wall\n---\n\n```px\nBB\n```\n\n---\nB: block\n").unwrap());
+        builder.add_prefabs(parse_prefab_file("---\nname: tower\n---\n\n```px\nW\nW\n```\n\n---\nW: wall\n").unwrap());
+        builder.add_maps(parse_map_file("---\nname: level\n---\n\n```px\nT.T\n```\n\n---\nT: tower\n").unwrap());
+        let registry = builder.build().unwrap();
+
+        let rendered = render_project(&registry, &RenderOptions::default()).unwrap();
+        let size = |name: &str| rendered.iter().find(|r| r.name == name).map(|r| r.size());
+
+        assert_eq!(rendered.len(), 4);
+        assert_eq!(size("block"), Some((2, 2)));
+        assert_eq!(size("wall"), Some((4, 2)));
+        assert_eq!(size("tower"), Some((4, 4)));
+        assert_eq!(size("level").map(|(w, _)| w), Some(12));
+        // Dependencies come first
+        let position = |name: &str| rendered.iter().position(|r| r.name == name).unwrap();
+        assert!(position("block") < position("wall") && position("wall") < position("tower"));
+        assert_eq!(rendered[position("block")].get(1, 1), Some(Colour::WHITE));
+    }
+}
@@ -0,0 +1,99 @@
+//! Colour reduction.
+//!
+//! Median cut: start with every colour in one box, repeatedly split the box
+//! with the widest channel range at its median, then average each box into
+//! one representative colour.
+
+use crate::types::Colour;
+
+/// Reduce `colours` to at most `max` representative colours by median cut.
+///
+/// Sets already within `max` come back unchanged. Otherwise the result has
+/// one colour per box, each the average (alpha included) of the colours in
+/// it, so repeat a colour to give it more weight. Boxes of identical
+/// colours can't split, so fewer than `max` come back when `colours` holds
+/// fewer distinct colours.
+pub fn median_cut(colours: &[Colour], max: usize) -> Vec<Colour> {
+    if colours.len() <= max {
+        return colours.to_vec();
+    }
+    if max == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes: Vec<Vec<Colour>> = vec![colours.to_vec()];
+    while boxes.len() < max {
+        // Widest box by its widest channel; ties go to the earlier box
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (i, widest_channel(b)))
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by(|(i, (_, a)), (j, (_, b))| a.cmp(b).then(j.cmp(i)));
+        let Some((index, (channel, _))) = widest else {
+            break;
+        };
+
+        let mut split = boxes.swap_remove(index);
+        split.sort_by_key(|c| (c.to_rgba()[channel], c.to_rgba()));
+        let upper = split.split_off(split.len() / 2);
+        boxes.push(split);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|b| average(b)).collect()
+}
+
+/// The RGBA channel with the widest range in `colours`, and that range.
+fn widest_channel(colours: &[Colour]) -> (usize, u8) {
+    (0..4)
+        .map(|channel| {
+            let values = colours.iter().map(|c| c.to_rgba()[channel]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (channel, range)
+        })
+        .fold((0, 0), |best, next| if next.1 > best.1 { next } else { best })
+}
+
+fn average(colours: &[Colour]) -> Colour {
+    let mut sums = [0usize; 4];
+    for colour in colours {
+        for (sum, value) in sums.iter_mut().zip(colour.to_rgba()) {
+            *sum += value as usize;
+        }
+    }
+    let n = colours.len().max(1);
+    let [r, g, b, a] = sums.map(|sum| ((sum + n / 2) / n) as u8);
+    Colour::new(r, g, b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_median_cut_reduces_to_max() {
+        // A 10x10x3 grid of the RGB cube
+        let colours: Vec<Colour> =
+            (0..300usize).map(|i| Colour::rgb((i % 10 * 25) as u8, (i / 10 % 10 * 25) as u8, (i / 100 * 80) as u8)).collect();
+        assert_eq!(colours.iter().collect::<HashSet<_>>().len(), 300);
+
+        let reduced = median_cut(&colours, 8);
+        assert_eq!(reduced.len(), 8);
+        assert_eq!(reduced.iter().collect::<HashSet<_>>().len(), 8);
+    }
+
+    #[test]
+    fn test_median_cut_keeps_small_sets() {
+        let colours = vec![Colour::BLACK, Colour::WHITE, Colour::rgb(255, 0, 0)];
+        assert_eq!(median_cut(&colours, 8), colours);
+        assert_eq!(median_cut(&colours, 3), colours);
+    }
+
+    #[test]
+    fn test_median_cut_merges_near_duplicates() {
+        let colours = vec![Colour::rgb(0, 0, 0), Colour::rgb(2, 2, 2), Colour::rgb(250, 250, 250), Colour::rgb(254, 254, 254)];
+        assert_eq!(median_cut(&colours, 2), vec![Colour::rgb(1, 1, 1), Colour::rgb(252, 252, 252)]);
+    }
+}
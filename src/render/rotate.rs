@@ -0,0 +1,148 @@
+//! Rotation for rendered shapes.
+//!
+//! Quarter turns move pixels exactly; other angles sample nearest-neighbour
+//! so edges stay hard. `spin_frames` turns a shape into an animation that
+//! rotates once clockwise.
+
+use crate::types::Colour;
+
+use super::shape::RenderedShape;
+
+impl RenderedShape {
+    /// Rotate a quarter turn clockwise. Width and height swap.
+    pub fn rotate_90(&self) -> RenderedShape {
+        let (width, height) = self.size();
+        let pixels = (0..width)
+            .map(|y| (0..height).map(|x| self.pixels()[height - 1 - x][y]).collect())
+            .collect();
+        RenderedShape::new(self.name.clone(), pixels)
+    }
+
+    /// Rotate clockwise by `degrees` about the centre.
+    ///
+    /// Multiples of 90° use [`rotate_90`](Self::rotate_90). Other angles
+    /// grow the canvas to fit the rotated bounds and sample the nearest
+    /// source pixel, leaving the corners transparent.
+    pub fn rotated(&self, degrees: f32) -> RenderedShape {
+        let degrees = degrees.rem_euclid(360.0);
+        let quarters = (degrees / 90.0).round();
+        if (degrees - quarters * 90.0).abs() < 1e-3 {
+            let mut rotated = RenderedShape::new(self.name.clone(), self.pixels().to_vec());
+            for _ in 0..quarters as u32 % 4 {
+                rotated = rotated.rotate_90();
+            }
+            return rotated;
+        }
+
+        let (width, height) = self.size();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (w, h) = (width as f32, height as f32);
+        let new_width = (w * cos.abs() + h * sin.abs()).ceil() as usize;
+        let new_height = (w * sin.abs() + h * cos.abs()).ceil() as usize;
+
+        let pixels = (0..new_height)
+            .map(|ty| {
+                (0..new_width)
+                    .map(|tx| {
+                        // Undo the rotation to find the source pixel
+                        let cx = tx as f32 + 0.5 - new_width as f32 / 2.0;
+                        let cy = ty as f32 + 0.5 - new_height as f32 / 2.0;
+                        let sx = (cx * cos + cy * sin + w / 2.0).floor();
+                        let sy = (-cx * sin + cy * cos + h / 2.0).floor();
+                        if sx >= 0.0 && sy >= 0.0 && sx < w && sy < h {
+                            self.pixels()[sy as usize][sx as usize]
+                        } else {
+                            Colour::TRANSPARENT
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        RenderedShape::new(self.name.clone(), pixels)
+    }
+}
+
+/// `count` frames of `shape` turning once clockwise in even steps, named
+/// `{name}-spin-{i}`.
+///
+/// Frames are padded to a shared size, centred, so the sprite turns in
+/// place and the frames can share a GIF.
+pub fn spin_frames(shape: &RenderedShape, count: usize) -> Vec<RenderedShape> {
+    let frames: Vec<RenderedShape> = (0..count)
+        .map(|i| shape.rotated(360.0 * i as f32 / count as f32))
+        .collect();
+    let width = frames.iter().map(|f| f.width()).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.height()).max().unwrap_or(0);
+
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let (mut padded, _) = frame.pad_to(width, height);
+            padded.name = format!("{}-spin-{}", shape.name, i);
+            padded
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x3 grid with a distinct colour per cell (value = 10 * (y * 2 + x + 1)).
+    fn numbered_2x3() -> RenderedShape {
+        let pixels = (0..3)
+            .map(|y| (0..2).map(|x| Colour::rgb(10 * (y * 2 + x + 1), 0, 0)).collect())
+            .collect();
+        RenderedShape::new("bar", pixels)
+    }
+
+    #[test]
+    fn test_rotate_90_clockwise() {
+        let shape = numbered_2x3();
+        let rotated = shape.rotate_90();
+
+        assert_eq!(rotated.size(), (3, 2));
+        // Bottom-left moves to the top-left, top-left to the top-right
+        assert_eq!(rotated.get(0, 0), shape.get(0, 2));
+        assert_eq!(rotated.get(2, 0), shape.get(0, 0));
+        assert_eq!(rotated.get(2, 1), shape.get(1, 0));
+
+        // Four quarter turns come back round
+        let back = rotated.rotate_90().rotate_90().rotate_90();
+        assert_eq!(back.pixels(), shape.pixels());
+        assert_eq!(shape.rotated(180.0).pixels(), shape.rotate_90().rotate_90().pixels());
+    }
+
+    #[test]
+    fn test_rotated_arbitrary_angle_grows_canvas() {
+        let square = RenderedShape::new("block", vec![vec![Colour::BLACK; 4]; 4]);
+        let rotated = square.rotated(45.0);
+
+        // 4 * sqrt(2), rounded up
+        assert_eq!(rotated.size(), (6, 6));
+        assert_eq!(rotated.get(3, 3), Some(Colour::BLACK));
+        assert_eq!(rotated.get(0, 0), Some(Colour::TRANSPARENT));
+    }
+
+    #[test]
+    fn test_spin_frames_quarter_turns() {
+        let mut pixels = vec![vec![Colour::WHITE; 3]; 3];
+        pixels[0][0] = Colour::BLACK;
+        let gem = RenderedShape::new("gem", pixels);
+
+        let frames = spin_frames(&gem, 4);
+        let names: Vec<&str> = frames.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["gem-spin-0", "gem-spin-1", "gem-spin-2", "gem-spin-3"]);
+        assert_eq!(frames[0].pixels(), gem.pixels());
+        assert_eq!(frames[1].pixels(), frames[0].rotate_90().pixels());
+        assert_eq!(frames[1].get(2, 0), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_spin_frames_share_size() {
+        let frames = spin_frames(&numbered_2x3(), 8);
+        assert_eq!(frames.len(), 8);
+        assert!(frames.iter().all(|f| f.size() == frames[0].size()));
+    }
+}
@@ -0,0 +1,182 @@
+//! Rust source generation for sprite constants.
+//!
+//! Emits a module with one `Sprite` constant per rendered sprite so game
+//! code gets compile-time-checked names. Sprite names are converted to
+//! SCREAMING_SNAKE_CASE identifiers (`hero-walk-1` -> `HERO_WALK_1`).
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{PxError, Result};
+
+use super::{RenderedShape, SheetMeta};
+
+/// Generate Rust source with a constant per sprite.
+///
+/// `width`/`height` are source pixels. When `sheet` is given, each sprite
+/// also gets its `frame` as `[x, y, w, h]` in sheet image pixels, matching
+/// `sheet.json`.
+pub fn rust_source(sprites: &[RenderedShape], sheet: Option<&SheetMeta>) -> String {
+    let mut src = String::new();
+    src.push_str("//! Sprite constants generated by px. Do not edit.\n\n");
+    src.push_str("/// A built sprite.\n");
+    src.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n");
+    src.push_str("pub struct Sprite {\n");
+    src.push_str("    /// Asset name.\n");
+    src.push_str("    pub name: &'static str,\n");
+    src.push_str("    /// Width in source pixels.\n");
+    src.push_str("    pub width: u32,\n");
+    src.push_str("    /// Height in source pixels.\n");
+    src.push_str("    pub height: u32,\n");
+    src.push_str("    /// Sheet frame `[x, y, w, h]` in sheet pixels (sheet builds only).\n");
+    src.push_str("    pub frame: Option<[u32; 4]>,\n");
+    src.push_str("}\n");
+
+    let mut used = HashSet::new();
+    let mut idents = Vec::new();
+
+    for sprite in sprites {
+        let ident = unique_ident(&sprite.name, &mut used);
+        let frame = sheet
+            .and_then(|meta| meta.frames.iter().find(|f| f.name == sprite.name).map(|f| (f, meta.scale.max(1))))
+            .map(|(f, s)| format!("Some([{}, {}, {}, {}])", f.x * s, f.y * s, f.w * s, f.h * s))
+            .unwrap_or_else(|| "None".to_string());
+
+        let _ = write!(
+            src,
+            "\npub const {}: Sprite = Sprite {{\n    name: {:?},\n    width: {},\n    height: {},\n    frame: {},\n}};\n",
+            ident,
+            sprite.name,
+            sprite.width(),
+            sprite.height(),
+            frame
+        );
+        idents.push(ident);
+    }
+
+    src.push_str("\n/// All sprites, in build order.\n");
+    let _ = writeln!(src, "pub const ALL: &[Sprite] = &[{}];", idents.join(", "));
+    src
+}
+
+/// Write generated sprite constants to a Rust source file.
+pub fn write_rust(sprites: &[RenderedShape], sheet: Option<&SheetMeta>, path: &Path) -> Result<()> {
+    fs::write(path, rust_source(sprites, sheet)).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write Rust source: {}", e),
+    })
+}
+
+/// Convert a sprite name to a SCREAMING_SNAKE_CASE constant name.
+///
+/// Runs of non-alphanumeric characters become one `_`, and a leading digit
+/// gets a `_` prefix.
+fn const_ident(name: &str) -> String {
+    let mut ident = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            ident.push(c.to_ascii_uppercase());
+        } else if !ident.ends_with('_') {
+            ident.push('_');
+        }
+    }
+
+    let ident = ident.trim_matches('_');
+    match ident.chars().next() {
+        None => "_SPRITE".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", ident),
+        Some(_) if ident == "ALL" => "ALL_".to_string(),
+        Some(_) => ident.to_string(),
+    }
+}
+
+/// A constant name not yet in `used`, suffixing `_2`, `_3`... on collision.
+fn unique_ident(name: &str, used: &mut HashSet<String>) -> String {
+    let base = const_ident(name);
+    let mut ident = base.clone();
+    let mut n = 2;
+    while !used.insert(ident.clone()) {
+        ident = format!("{}_{}", base, n);
+        n += 1;
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::SheetPacker;
+    use crate::types::Colour;
+
+    fn sprite(name: &str, w: usize, h: usize) -> RenderedShape {
+        RenderedShape::new(name, vec![vec![Colour::BLACK; w]; h])
+    }
+
+    /// Find `pub const NAME` in a parsed file and return its initializer.
+    fn const_expr<'a>(file: &'a syn::File, name: &str) -> Option<&'a syn::Expr> {
+        file.items.iter().find_map(|item| match item {
+            syn::Item::Const(c) if c.ident == name => Some(c.expr.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Integer value of a field in a `Sprite { .. }` initializer.
+    fn int_field(expr: &syn::Expr, field: &str) -> Option<u32> {
+        let syn::Expr::Struct(init) = expr else { return None };
+        init.fields.iter().find_map(|f| match (&f.member, &f.expr) {
+            (syn::Member::Named(ident), syn::Expr::Lit(lit)) if ident == field => match &lit.lit {
+                syn::Lit::Int(int) => int.base10_parse().ok(),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_const_ident() {
+        assert_eq!(const_ident("hero-walk-1"), "HERO_WALK_1");
+        assert_eq!(const_ident("wall corner"), "WALL_CORNER");
+        assert_eq!(const_ident("--a..b"), "A_B");
+        assert_eq!(const_ident("1up"), "_1UP");
+        assert_eq!(const_ident("all"), "ALL_");
+        assert_eq!(const_ident("é"), "_SPRITE");
+    }
+
+    #[test]
+    fn test_unique_ident() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_ident("a-b", &mut used), "A_B");
+        assert_eq!(unique_ident("a_b", &mut used), "A_B_2");
+        assert_eq!(unique_ident("a.b", &mut used), "A_B_3");
+    }
+
+    #[test]
+    fn test_rust_source_parses() {
+        let sprites = vec![sprite("hero-walk-1", 8, 16), sprite("coin", 4, 4)];
+
+        let src = rust_source(&sprites, None);
+        let file = syn::parse_file(&src).unwrap();
+
+        let hero = const_expr(&file, "HERO_WALK_1").unwrap();
+        assert_eq!(int_field(hero, "width"), Some(8));
+        assert_eq!(int_field(hero, "height"), Some(16));
+        assert!(src.contains("name: \"hero-walk-1\""));
+        assert!(const_expr(&file, "COIN").is_some());
+        assert!(src.contains("pub const ALL: &[Sprite] = &[HERO_WALK_1, COIN];"));
+    }
+
+    #[test]
+    fn test_rust_source_sheet_frames() {
+        let sprites = vec![sprite("coin", 4, 4)];
+        let (_, mut meta) = SheetPacker::new(0).pack(&sprites).unwrap();
+        meta.scale = 2;
+
+        let src = rust_source(&sprites, Some(&meta));
+        let file = syn::parse_file(&src).unwrap();
+
+        assert!(const_expr(&file, "COIN").is_some());
+        assert!(src.contains("frame: Some([0, 0, 8, 8]),"));
+    }
+}
@@ -3,10 +3,13 @@
 //! The renderer resolves glyphs to stamps/brushes and applies colours
 //! from the palette via a shader.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Mutex;
 
+use crate::error::{PxError, Result};
 use crate::types::{
-    BuiltinStamps, Brush, Colour, LegendEntry, Palette, PixelToken, Shape, Stamp,
+    mix_colours, BuiltinStamps, Brush, CellSize, Colour, Effect, GradientDirection, LegendEntry, NineSlice,
+    Palette, PixelToken, Shape, Stamp,
 };
 
 /// A rendered shape - a grid of colours.
@@ -18,11 +21,20 @@ pub struct RenderedShape {
     /// Pixel grid (row-major: pixels[y][x]).
     pixels: Vec<Vec<Colour>>,
 
+    /// Anchor (pivot) in pixels from the top-left, if the shape sets one.
+    anchor: Option<(f32, f32)>,
+
+    /// 9-slice borders, if the shape defines them.
+    slice: Option<NineSlice>,
+
     /// Width in pixels.
     width: usize,
 
     /// Height in pixels.
     height: usize,
+
+    /// Cells punched out by hole tokens (row-major), empty when there are none.
+    holes: Vec<Vec<bool>>,
 }
 
 impl RenderedShape {
@@ -34,9 +46,156 @@ impl RenderedShape {
         Self {
             name: name.into(),
             pixels,
+            anchor: None,
+            slice: None,
             width,
             height,
+            holes: Vec::new(),
+        }
+    }
+
+    /// Wrap an already-rendered RGBA image, e.g. a PNG from an earlier build.
+    pub fn from_rgba(name: impl Into<String>, image: &image::RgbaImage) -> Self {
+        let pixels = image
+            .rows()
+            .map(|row| row.map(|p| Colour::new(p[0], p[1], p[2], p[3])).collect())
+            .collect();
+        Self::new(name, pixels)
+    }
+
+    /// Set the anchor (pivot) point in pixels.
+    pub fn with_anchor(mut self, anchor: (f32, f32)) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Get the anchor (pivot) point in pixels, if set.
+    pub fn anchor(&self) -> Option<(f32, f32)> {
+        self.anchor
+    }
+
+    /// Set the 9-slice borders.
+    pub fn with_slice(mut self, slice: NineSlice) -> Self {
+        self.slice = Some(slice);
+        self
+    }
+
+    /// Get the 9-slice borders, if set.
+    pub fn slice(&self) -> Option<NineSlice> {
+        self.slice
+    }
+
+    /// Stretch to `target_w` x `target_h` using the 9-slice borders.
+    ///
+    /// Corners are copied unchanged, edges stretch along their length and
+    /// the centre fills the rest (nearest neighbour). Without borders the
+    /// whole shape stretches. If the target is smaller than the borders,
+    /// the near borders win and the far ones are clipped.
+    pub fn nine_slice(&self, target_w: usize, target_h: usize) -> RenderedShape {
+        let slice = self.slice.unwrap_or(NineSlice::new(0, 0, 0, 0));
+        let columns: Vec<Option<usize>> = (0..target_w)
+            .map(|x| slice_source(x, target_w, self.width, slice.left, slice.right))
+            .collect();
+
+        let pixels = (0..target_h)
+            .map(|y| {
+                let row = slice_source(y, target_h, self.height, slice.top, slice.bottom);
+                columns
+                    .iter()
+                    .map(|&column| match (row, column) {
+                        (Some(sy), Some(sx)) => self.pixels[sy][sx],
+                        _ => Colour::TRANSPARENT,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut stretched = RenderedShape::new(self.name.clone(), pixels);
+        stretched.slice = self.slice;
+        stretched
+    }
+
+    /// Pad with transparent pixels to at least `width` x `height`, centred.
+    ///
+    /// Returns the padded shape and the offset of the original within it.
+    /// The anchor moves with the pixels and the 9-slice borders grow to
+    /// take in the padding.
+    pub fn pad_to(&self, width: usize, height: usize) -> (RenderedShape, (usize, usize)) {
+        let (width, height) = (width.max(self.width), height.max(self.height));
+        let (ox, oy) = ((width - self.width) / 2, (height - self.height) / 2);
+
+        let mut pixels = vec![vec![Colour::TRANSPARENT; width]; height];
+        for (y, row) in self.pixels.iter().enumerate() {
+            pixels[y + oy][ox..ox + row.len()].copy_from_slice(row);
+        }
+
+        let mut padded = self.carry_holes(RenderedShape::new(self.name.clone(), pixels), ox, oy);
+        padded.anchor = self.anchor.map(|(x, y)| (x + ox as f32, y + oy as f32));
+        padded.slice = self.slice.map(|s| {
+            NineSlice::new(
+                s.left + ox,
+                s.right + width - self.width - ox,
+                s.top + oy,
+                s.bottom + height - self.height - oy,
+            )
+        });
+        (padded, (ox, oy))
+    }
+
+    /// Mark cells as holes (row-major, same size as the pixels).
+    pub fn with_holes(mut self, holes: Vec<Vec<bool>>) -> Self {
+        self.holes = if holes.iter().flatten().any(|&h| h) { holes } else { Vec::new() };
+        self
+    }
+
+    /// Check if the pixel at the given position is a hole.
+    ///
+    /// Holes are transparent, but when this shape is layered over another
+    /// they clear what is beneath instead of letting it show through.
+    pub fn is_hole(&self, x: usize, y: usize) -> bool {
+        self.holes.get(y).and_then(|row| row.get(x)).copied().unwrap_or(false)
+    }
+
+    /// Copy this shape's holes into `into`, offset by `(dx, dy)`.
+    ///
+    /// For effects that build a new canvas: holes stay only where the new
+    /// canvas is still transparent.
+    pub(crate) fn carry_holes(&self, into: RenderedShape, dx: usize, dy: usize) -> RenderedShape {
+        if self.holes.is_empty() {
+            return into;
         }
+        let mut holes = vec![vec![false; into.width]; into.height];
+        for (y, row) in self.holes.iter().enumerate() {
+            for (x, &hole) in row.iter().enumerate() {
+                let (tx, ty) = (x + dx, y + dy);
+                if hole && into.get(tx, ty).is_some_and(|c| c.is_transparent()) {
+                    holes[ty][tx] = true;
+                }
+            }
+        }
+        into.with_holes(holes)
+    }
+
+    /// Layer `top` over this shape with its top-left at `(x, y)`.
+    ///
+    /// `top` composites over what is beneath, except that its holes always
+    /// win: they leave the cells beneath transparent. Parts of `top` past
+    /// the edges are clipped.
+    pub fn layered(&self, top: &RenderedShape, x: usize, y: usize) -> RenderedShape {
+        let mut pixels = self.pixels.clone();
+        for (ty, row) in top.pixels.iter().enumerate() {
+            for (tx, &pixel) in row.iter().enumerate() {
+                let Some(below) = pixels.get_mut(y + ty).and_then(|r| r.get_mut(x + tx)) else {
+                    continue;
+                };
+                *below = if top.is_hole(tx, ty) { Colour::TRANSPARENT } else { pixel.over(*below) };
+            }
+        }
+
+        let mut layered = RenderedShape::new(self.name.clone(), pixels);
+        layered.anchor = self.anchor;
+        layered.slice = self.slice;
+        layered
     }
 
     /// Get the width in pixels.
@@ -64,6 +223,32 @@ impl RenderedShape {
         &self.pixels
     }
 
+    /// Run-length encode each row as `(colour, count)` pairs.
+    pub fn rle_rows(&self) -> Vec<Vec<(Colour, usize)>> {
+        self.pixels
+            .iter()
+            .map(|row| {
+                let mut runs: Vec<(Colour, usize)> = Vec::new();
+                for &colour in row {
+                    match runs.last_mut() {
+                        Some((last, count)) if *last == colour => *count += 1,
+                        _ => runs.push((colour, 1)),
+                    }
+                }
+                runs
+            })
+            .collect()
+    }
+
+    /// Count how many pixels use each colour.
+    pub fn colour_histogram(&self) -> HashMap<Colour, usize> {
+        let mut counts = HashMap::new();
+        for &colour in self.pixels.iter().flatten() {
+            *counts.entry(colour).or_insert(0) += 1;
+        }
+        counts
+    }
+
     /// Convert to a flat RGBA buffer (for image output).
     pub fn to_rgba_buffer(&self) -> Vec<u8> {
         let mut buffer = Vec::with_capacity(self.width * self.height * 4);
@@ -76,6 +261,31 @@ impl RenderedShape {
     }
 }
 
+/// Map a target position along one axis to its source position for a 9-slice
+/// stretch with borders `low` and `high`. `None` when there is no source pixel.
+fn slice_source(pos: usize, target: usize, size: usize, low: usize, high: usize) -> Option<usize> {
+    let low = low.min(size);
+    let high = high.min(size - low);
+    if pos < low {
+        return Some(pos);
+    }
+    if target < low + high {
+        // Too small for both borders: the far one is cut short
+        return Some(size - high + (pos - low));
+    }
+    if pos + high >= target {
+        return Some(size - (target - pos));
+    }
+    let centre = size - low - high;
+    if centre == 0 {
+        return None;
+    }
+    Some(low + (pos - low) * centre / (target - low - high))
+}
+
+/// Highlight for guide cells in previews.
+pub const GUIDE_COLOUR: Colour = Colour::new(0, 200, 255, 160);
+
 /// Shape renderer configuration and state.
 pub struct ShapeRenderer<'a> {
     /// Stamps available for rendering (name -> stamp).
@@ -89,6 +299,19 @@ pub struct ShapeRenderer<'a> {
 
     /// Palette variant to use (if any).
     variant: Option<&'a str>,
+
+    /// Shader effects applied after rendering.
+    effects: &'a [Effect],
+
+    /// Colour for guide cells (transparent unless previewing).
+    guide: Colour,
+
+    /// Fail on unresolved glyphs instead of drawing them magenta.
+    strict: bool,
+
+    /// Shape and glyph of every magenta fallback so far. Shared by
+    /// parallel renders, hence the lock.
+    fallbacks: Mutex<BTreeSet<(String, char)>>,
 }
 
 impl<'a> ShapeRenderer<'a> {
@@ -99,6 +322,10 @@ impl<'a> ShapeRenderer<'a> {
             brushes: HashMap::new(),
             palette,
             variant: None,
+            effects: &[],
+            guide: Colour::TRANSPARENT,
+            strict: false,
+            fallbacks: Mutex::new(BTreeSet::new()),
         }
     }
 
@@ -108,6 +335,29 @@ impl<'a> ShapeRenderer<'a> {
         self
     }
 
+    /// Set the shader effects to apply after rendering.
+    pub fn with_effects(mut self, effects: &'a [Effect]) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    /// Draw guide cells in `colour` instead of leaving them transparent.
+    pub fn with_guides(mut self, colour: Colour) -> Self {
+        self.guide = colour;
+        self
+    }
+
+    /// Fail on glyphs that resolve to nothing, instead of drawing them magenta.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Shapes and glyphs drawn magenta by renders so far, by shape then glyph.
+    pub fn fallbacks(&self) -> Vec<(String, char)> {
+        self.fallbacks.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
     /// Add a stamp to the renderer.
     pub fn add_stamp(&mut self, stamp: &'a Stamp) {
         self.stamps.insert(stamp.name.clone(), stamp);
@@ -133,77 +383,242 @@ impl<'a> ShapeRenderer<'a> {
     }
 
     /// Render a shape to pixels.
-    pub fn render(&self, shape: &Shape) -> RenderedShape {
-        let width = shape.width();
-        let height = shape.height();
+    ///
+    /// Unresolved glyphs render magenta, and are recorded in
+    /// [`fallbacks`](Self::fallbacks), or fail the render in strict mode.
+    pub fn render(&self, shape: &Shape) -> Result<RenderedShape> {
+        let (cell_w, cell_h) = self.cell_size(shape);
+        let width = shape.width() * cell_w;
+        let height = shape.height() * cell_h;
+
+        let background = match shape.background.as_deref() {
+            Some(colour_ref) => match self.resolve_colour_ref(colour_ref) {
+                Some(colour) => colour,
+                None if self.strict => {
+                    return Err(PxError::Build {
+                        message: format!("Shape '{}': background '{}' is not a known colour", shape.name, colour_ref),
+                        help: Some("Use a palette colour ($name) or a hex colour".to_string()),
+                    });
+                }
+                None => Colour::MAGENTA,
+            },
+            None => Colour::TRANSPARENT,
+        };
+
+        let mut pixels = vec![vec![background; width]; height];
+        let mut holes = vec![vec![false; width]; height];
+
+        // Each glyph fills a cell_w x cell_h block (1x1 unless `cell:` is set)
+        let blocks = shape.iter_cells().flat_map(|(gx, gy, glyph)| {
+            (0..cell_h).flat_map(move |sy| (0..cell_w).map(move |sx| (gx, gy, glyph, (sx, sy))))
+        });
+        for (gx, gy, glyph, local) in blocks {
+            let (x, y) = (gx * cell_w + local.0, gy * cell_h + local.1);
+            let colour = match self.resolve_glyph(glyph, shape, (x, y), local, (width, height)) {
+                Ok(colour) => colour,
+                Err(reason) if self.strict => {
+                    return Err(PxError::Build {
+                        message: format!("Shape '{}': glyph '{}' at ({}, {}) {}", shape.name, glyph, gx, gy, reason),
+                        help: Some("Map the glyph in the shape's legend, or define a stamp for it".to_string()),
+                    });
+                }
+                Err(_) => {
+                    self.fallbacks
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert((shape.name.clone(), glyph));
+                    Some(Colour::MAGENTA)
+                }
+            };
+            match colour {
+                Some(colour) => pixels[y][x] = colour.over(background),
+                None => {
+                    // Holes cut through the background too
+                    pixels[y][x] = Colour::TRANSPARENT;
+                    holes[y][x] = true;
+                }
+            }
+        }
 
-        let mut pixels = vec![vec![Colour::TRANSPARENT; width]; height];
+        let (rendered, (ox, oy)) = self.apply_effects(RenderedShape::new(&shape.name, pixels).with_holes(holes));
+
+        // Drop shadows grow the canvas and shift the sprite by (ox, oy);
+        // slices and anchors stay put on the sprite
+        let (grow_w, grow_h) = (rendered.width() - width, rendered.height() - height);
+        let rendered = match shape.slice {
+            Some(slice) => rendered.with_slice(NineSlice::new(
+                slice.left + ox,
+                slice.right + grow_w - ox,
+                slice.top + oy,
+                slice.bottom + grow_h - oy,
+            )),
+            None => rendered,
+        };
+
+        Ok(match shape.anchor {
+            Some(anchor) => {
+                let (ax, ay) = anchor.resolve(width, height);
+                rendered.with_anchor((ax + ox as f32, ay + oy as f32))
+            }
+            None => rendered,
+        })
+    }
 
-        for (x, y, glyph) in shape.iter_cells() {
-            let colour = self.resolve_glyph(glyph, shape, x, y);
-            pixels[y][x] = colour;
+    /// Apply shader effects in order. Also returns where the sprite's
+    /// top-left corner ended up on the (possibly larger) canvas.
+    fn apply_effects(&self, mut rendered: RenderedShape) -> (RenderedShape, (usize, usize)) {
+        let mut offset = (0, 0);
+        for effect in self.effects {
+            rendered = match effect {
+                Effect::Outline { colour, thickness } => {
+                    let colour = self.resolve_colour_ref(colour).unwrap_or(Colour::MAGENTA);
+                    rendered.outlined(colour, *thickness)
+                }
+                Effect::DropShadow { dx, dy, colour, alpha } => {
+                    let base = self.resolve_colour_ref(colour).unwrap_or(Colour::MAGENTA);
+                    let colour = Colour::new(base.r, base.g, base.b, *alpha);
+                    // A shadow up or left pushes the sprite the other way
+                    offset.0 += if *dx < 0 { dx.unsigned_abs() as usize } else { 0 };
+                    offset.1 += if *dy < 0 { dy.unsigned_abs() as usize } else { 0 };
+                    rendered.with_drop_shadow(*dx, *dy, colour)
+                }
+                Effect::Levels { brightness, contrast, gamma } => rendered.leveled(*brightness, *contrast, *gamma),
+                Effect::Posterize { levels } => rendered.posterized(*levels),
+                // Other effects are parsed but not yet applied
+                _ => rendered,
+            };
         }
-
-        RenderedShape::new(&shape.name, pixels)
+        (rendered, offset)
     }
 
-    /// Resolve a glyph to a colour.
-    fn resolve_glyph(&self, glyph: char, shape: &Shape, x: usize, y: usize) -> Colour {
+    /// Resolve a glyph to a colour, or `None` for a hole, for the pixel at
+    /// `pos` of a `size` canvas, `local` pixels into the glyph's cell.
+    ///
+    /// Errors with the reason when the glyph resolves to nothing.
+    fn resolve_glyph(
+        &self,
+        glyph: char,
+        shape: &Shape,
+        pos: (usize, usize),
+        local: (usize, usize),
+        size: (usize, usize),
+    ) -> std::result::Result<Option<Colour>, String> {
         // 1. Check shape's legend
         if let Some(entry) = shape.get_legend(glyph) {
-            return self.resolve_legend_entry(entry, x, y);
+            return self.resolve_legend_entry(entry, pos, local, size);
         }
 
         // 2. Check stamps by glyph
         if let Some(stamp) = self.find_stamp_by_glyph(glyph) {
-            return self.render_stamp_pixel(stamp, 0, 0);
+            return Ok(self.render_stamp_pixel(stamp, local.0, local.1));
         }
 
         // 3. Check builtin stamps
         if let Some(stamp) = BuiltinStamps::get_by_glyph(glyph) {
-            return self.render_stamp_pixel(&stamp, 0, 0);
+            return Ok(self.render_stamp_pixel(&stamp, local.0, local.1));
         }
 
-        // 4. Fallback: magenta for missing glyphs
-        Colour::MAGENTA
+        // 4. Missing glyph (magenta unless strict)
+        Err("is not in the legend and matches no stamp".to_string())
     }
 
-    /// Resolve a legend entry to a colour.
-    fn resolve_legend_entry(&self, entry: &LegendEntry, x: usize, y: usize) -> Colour {
+    /// Resolve a legend entry to a colour, or `None` for a hole, for the
+    /// pixel at `(x, y)` of a `size` canvas, `local` pixels into its cell.
+    ///
+    /// Stamps and single brushes are sampled at `local`, so each cell shows
+    /// the whole stamp (tiled if the cell is larger); fills and gradients
+    /// run across the whole canvas.
+    fn resolve_legend_entry(
+        &self,
+        entry: &LegendEntry,
+        (x, y): (usize, usize),
+        local: (usize, usize),
+        size: (usize, usize),
+    ) -> std::result::Result<Option<Colour>, String> {
         match entry {
             LegendEntry::StampRef(name) => {
                 // Look up stamp by name
                 if let Some(stamp) = self.stamps.get(name) {
-                    return self.render_stamp_pixel(stamp, 0, 0);
+                    return Ok(self.render_stamp_pixel(stamp, local.0, local.1));
                 }
                 // Try builtin stamps by name
                 if let Some(stamp) = BuiltinStamps::get(name) {
-                    return self.render_stamp_pixel(&stamp, 0, 0);
+                    return Ok(self.render_stamp_pixel(&stamp, local.0, local.1));
                 }
-                // Missing stamp
-                Colour::MAGENTA
+                Err(format!("references missing stamp '{}'", name))
             }
 
             LegendEntry::BrushRef { name, bindings } => {
-                // Look up brush and render single pixel
+                // Look up brush and render its pixel for this spot in the cell
                 if let Some(brush) = self.brushes.get(name) {
                     let colour_bindings = self.resolve_bindings(bindings);
-                    return brush.render_pixel(0, 0, &colour_bindings);
+                    return Ok(Some(brush.render_pixel(local.0, local.1, &colour_bindings)));
                 }
-                Colour::MAGENTA
+                Err(format!("references missing brush '{}'", name))
             }
 
-            LegendEntry::Fill { name, bindings } => {
+            LegendEntry::Fill { name, bindings, offset } => {
                 // Look up brush and render with tiling
                 if let Some(brush) = self.brushes.get(name) {
                     let colour_bindings = self.resolve_bindings(bindings);
-                    return brush.render_pixel(x, y, &colour_bindings);
+                    // Shift the phase; wrapping keeps negative offsets in the pattern
+                    let shift = |pos: usize, by: i32, len: usize| (pos as i64 + by as i64).rem_euclid(len.max(1) as i64) as usize;
+                    let x = shift(x, offset.0, brush.width());
+                    let y = shift(y, offset.1, brush.height());
+                    return Ok(Some(brush.render_pixel(x, y, &colour_bindings)));
                 }
-                Colour::MAGENTA
+                Err(format!("references missing brush '{}'", name))
+            }
+
+            LegendEntry::Guide => Ok(Some(self.guide)),
+
+            LegendEntry::Gradient { from, to, direction } => {
+                let missing = |colour: &str| format!("references missing colour '{}'", colour);
+                let start = self.resolve_colour_ref(from).ok_or_else(|| missing(from))?;
+                let end = self.resolve_colour_ref(to).ok_or_else(|| missing(to))?;
+                // Ends land on the first and last cells
+                let (pos, span) = match direction {
+                    GradientDirection::Vertical => (y, size.1),
+                    GradientDirection::Horizontal => (x, size.0),
+                };
+                let factor = if span > 1 { pos as f32 / (span - 1) as f32 } else { 0.0 };
+                Ok(Some(mix_colours(start, end, factor)))
             }
         }
     }
 
+    /// Pixels each glyph of `shape` covers: its fixed `cell:` size, the
+    /// largest stamp it places for `cell: stamp`, or 1x1.
+    fn cell_size(&self, shape: &Shape) -> (usize, usize) {
+        match shape.cell {
+            Some(CellSize::Pixels(w, h)) => (w, h),
+            Some(CellSize::Stamp) => {
+                let glyphs: HashSet<char> = shape.iter_cells().map(|(_, _, glyph)| glyph).collect();
+                glyphs
+                    .into_iter()
+                    .filter_map(|glyph| self.glyph_stamp_size(glyph, shape))
+                    .fold((1, 1), |(w, h), (sw, sh)| (w.max(sw), h.max(sh)))
+            }
+            None => (1, 1),
+        }
+    }
+
+    /// Size of the stamp a glyph places, following the same lookup as
+    /// [`resolve_glyph`](Self::resolve_glyph); `None` for brushes and the rest.
+    fn glyph_stamp_size(&self, glyph: char, shape: &Shape) -> Option<(usize, usize)> {
+        match shape.get_legend(glyph) {
+            Some(LegendEntry::StampRef(name)) => match self.stamps.get(name) {
+                Some(stamp) => Some(stamp.size()),
+                None => BuiltinStamps::get(name).map(|s| s.size()),
+            },
+            Some(_) => None,
+            None => match self.find_stamp_by_glyph(glyph) {
+                Some(stamp) => Some(stamp.size()),
+                None => BuiltinStamps::get_by_glyph(glyph).map(|s| s.size()),
+            },
+        }
+    }
+
     /// Find a stamp by its default glyph.
     fn find_stamp_by_glyph(&self, glyph: char) -> Option<&Stamp> {
         self.stamps
@@ -212,10 +627,14 @@ impl<'a> ShapeRenderer<'a> {
             .copied()
     }
 
-    /// Render a single pixel from a stamp.
-    fn render_stamp_pixel(&self, stamp: &Stamp, x: usize, y: usize) -> Colour {
-        let token = stamp.get(x, y).unwrap_or(PixelToken::Transparent);
-        self.resolve_token(token)
+    /// Render a single pixel from a stamp, wrapping past its edges, or
+    /// `None` for a hole.
+    fn render_stamp_pixel(&self, stamp: &Stamp, x: usize, y: usize) -> Option<Colour> {
+        let (width, height) = stamp.size();
+        match stamp.get(x % width.max(1), y % height.max(1)).unwrap_or(PixelToken::Transparent) {
+            PixelToken::Hole => None,
+            token => Some(self.resolve_token(token)),
+        }
     }
 
     /// Resolve a pixel token to a colour using the palette.
@@ -223,7 +642,7 @@ impl<'a> ShapeRenderer<'a> {
         match token {
             PixelToken::Edge => self.get_colour("edge").unwrap_or(Colour::BLACK),
             PixelToken::Fill => self.get_colour("fill").unwrap_or(Colour::WHITE),
-            PixelToken::Transparent => Colour::TRANSPARENT,
+            PixelToken::Transparent | PixelToken::Hole => Colour::TRANSPARENT,
         }
     }
 
@@ -262,12 +681,42 @@ impl<'a> ShapeRenderer<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::BuiltinBrushes;
+    use crate::types::{Anchor, BuiltinBrushes, ShaderBuilder};
 
     fn default_palette() -> Palette {
         Palette::default_palette()
     }
 
+    #[test]
+    fn test_rle_rows() {
+        let (r, g, b) = (Colour::rgb(255, 0, 0), Colour::rgb(0, 255, 0), Colour::rgb(0, 0, 255));
+        let rendered = RenderedShape::new("runs", vec![vec![r, r, g, g, b], vec![]]);
+
+        let rows = rendered.rle_rows();
+        assert_eq!(rows[0], vec![(r, 2), (g, 2), (b, 1)]);
+        assert!(rows[1].is_empty());
+    }
+
+    #[test]
+    fn test_pad_to_centres() {
+        let rendered = RenderedShape::new("dot", vec![vec![Colour::BLACK; 2]; 2]).with_anchor((1.0, 1.0));
+        let (padded, offset) = rendered.pad_to(5, 4);
+
+        assert_eq!(offset, (1, 1));
+        assert_eq!(padded.size(), (5, 4));
+        assert_eq!(padded.anchor(), Some((2.0, 2.0)));
+        for y in 0..4 {
+            for x in 0..5 {
+                let inside = (1..3).contains(&x) && (1..3).contains(&y);
+                let expected = if inside { Colour::BLACK } else { Colour::TRANSPARENT };
+                assert_eq!(padded.get(x, y), Some(expected), "pixel ({}, {})", x, y);
+            }
+        }
+
+        // Never shrinks
+        assert_eq!(rendered.pad_to(1, 1).0.size(), (2, 2));
+    }
+
     #[test]
     fn test_rendered_shape_new() {
         let pixels = vec![
@@ -281,6 +730,53 @@ mod tests {
         assert_eq!(rendered.height(), 2);
     }
 
+    /// A 3x3 grid with a distinct colour per cell (value = 10 * (y * 3 + x)).
+    fn numbered_3x3() -> RenderedShape {
+        let pixels = (0..3)
+            .map(|y| (0..3).map(|x| Colour::rgb(10 * (y * 3 + x), 0, 0)).collect())
+            .collect();
+        RenderedShape::new("panel", pixels)
+    }
+
+    #[test]
+    fn test_nine_slice_keeps_corners() {
+        let panel = numbered_3x3().with_slice(NineSlice::new(1, 1, 1, 1));
+        let stretched = panel.nine_slice(7, 5);
+
+        assert_eq!(stretched.size(), (7, 5));
+        assert_eq!(stretched.get(0, 0), panel.get(0, 0));
+        assert_eq!(stretched.get(6, 0), panel.get(2, 0));
+        assert_eq!(stretched.get(0, 4), panel.get(0, 2));
+        assert_eq!(stretched.get(6, 4), panel.get(2, 2));
+
+        // Edges stretch along their length, the centre fills the middle
+        for x in 1..6 {
+            assert_eq!(stretched.get(x, 0), panel.get(1, 0));
+            assert_eq!(stretched.get(x, 4), panel.get(1, 2));
+        }
+        for y in 1..4 {
+            assert_eq!(stretched.get(0, y), panel.get(0, 1));
+            assert_eq!(stretched.get(6, y), panel.get(2, 1));
+            assert_eq!(stretched.get(3, y), panel.get(1, 1));
+        }
+        assert_eq!(stretched.slice(), panel.slice());
+    }
+
+    #[test]
+    fn test_nine_slice_without_borders_stretches() {
+        let stretched = numbered_3x3().nine_slice(6, 3);
+        assert_eq!(stretched.get(0, 0), stretched.get(1, 0));
+        assert_eq!(stretched.get(4, 2), numbered_3x3().get(2, 2));
+    }
+
+    #[test]
+    fn test_nine_slice_smaller_than_borders() {
+        let panel = numbered_3x3().with_slice(NineSlice::new(2, 1, 1, 1));
+        let shrunk = panel.nine_slice(2, 3);
+        assert_eq!(shrunk.get(0, 0), panel.get(0, 0));
+        assert_eq!(shrunk.get(1, 0), panel.get(1, 0));
+    }
+
     #[test]
     fn test_rendered_shape_get() {
         let pixels = vec![
@@ -319,7 +815,7 @@ mod tests {
             HashMap::new(),
         );
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // All builtin glyphs should render without magenta
         assert_eq!(rendered.get(0, 0), Some(Colour::BLACK)); // +
@@ -337,7 +833,7 @@ mod tests {
 
         let shape = Shape::new("test", vec![], vec![vec![' ']], HashMap::new());
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // Space should render as fill colour
         assert_eq!(rendered.get(0, 0), Some(Colour::WHITE));
@@ -353,13 +849,51 @@ mod tests {
 
         let shape = Shape::new("test", vec![], vec![vec!['B', 'B']], legend);
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // 'B' mapped to 'solid' stamp (edge colour = black)
         assert_eq!(rendered.get(0, 0), Some(Colour::BLACK));
         assert_eq!(rendered.get(1, 0), Some(Colour::BLACK));
     }
 
+    #[test]
+    fn test_render_guides_transparent_unless_previewing() {
+        let palette = default_palette();
+
+        let mut legend = HashMap::new();
+        legend.insert('G', LegendEntry::Guide);
+        let shape = Shape::new("test", vec![], vec![vec!['#', 'G']], legend);
+
+        let rendered = ShapeRenderer::new(&palette).render(&shape).unwrap();
+        assert_eq!(rendered.get(0, 0), Some(Colour::BLACK));
+        assert_eq!(rendered.get(1, 0), Some(Colour::TRANSPARENT));
+
+        let preview = ShapeRenderer::new(&palette).with_guides(GUIDE_COLOUR).render(&shape).unwrap();
+        assert_eq!(preview.get(1, 0), Some(GUIDE_COLOUR));
+    }
+
+    #[test]
+    fn test_render_background_fills_transparent_cells() {
+        let palette = default_palette();
+        let renderer = ShapeRenderer::new(&palette);
+
+        let mut shape = Shape::new("test", vec![], vec![vec!['#', 'x'], vec!['x', '#']], HashMap::new());
+        let plain = renderer.render(&shape).unwrap();
+        assert_eq!(plain.get(1, 0), Some(Colour::TRANSPARENT));
+
+        shape.background = Some("$fill".to_string());
+        let filled = renderer.render(&shape).unwrap();
+        assert_eq!(filled.get(0, 0), Some(Colour::BLACK));
+        assert_eq!(filled.get(1, 0), Some(Colour::WHITE));
+        assert!(filled.pixels().iter().flatten().all(|c| !c.is_transparent()));
+
+        // Unknown colours fall back to magenta, or fail when strict
+        shape.background = Some("$missing".to_string());
+        assert_eq!(renderer.render(&shape).unwrap().get(1, 0), Some(Colour::MAGENTA));
+        let err = ShapeRenderer::new(&palette).strict(true).render(&shape).unwrap_err().to_string();
+        assert!(err.contains("background '$missing'"), "{}", err);
+    }
+
     #[test]
     fn test_render_missing_glyph() {
         let palette = default_palette();
@@ -367,10 +901,61 @@ mod tests {
 
         let shape = Shape::new("test", vec![], vec![vec!['?']], HashMap::new());
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // Unknown glyph should render as magenta
         assert_eq!(rendered.get(0, 0), Some(Colour::MAGENTA));
+        assert_eq!(renderer.fallbacks(), vec![("test".to_string(), '?')]);
+    }
+
+    #[test]
+    fn test_strict_render_fails_on_missing_glyph() {
+        let palette = default_palette();
+        let renderer = ShapeRenderer::new(&palette).strict(true);
+
+        let shape = Shape::new("test", vec![], vec![vec!['#', '?']], HashMap::new());
+
+        let err = renderer.render(&shape).unwrap_err().to_string();
+        assert!(err.contains("Shape 'test': glyph '?' at (1, 0)"), "{}", err);
+
+        // Resolvable shapes still render
+        let ok = Shape::new("ok", vec![], vec![vec!['#', '.']], HashMap::new());
+        assert!(renderer.render(&ok).is_ok());
+    }
+
+    #[test]
+    fn test_hole_stamp_cuts_through_fill() {
+        let palette = default_palette();
+        let hole = Stamp::single("window", Some('W'), PixelToken::Hole);
+        let mut renderer = ShapeRenderer::new(&palette);
+        renderer.add_stamp(&hole);
+
+        let wall = renderer.render(&Shape::new("wall", vec![], vec![vec!['#'; 4]; 3], HashMap::new())).unwrap();
+        let window = renderer.render(&Shape::new("window", vec![], vec![vec!['W', 'W'], vec!['.', 'x']], HashMap::new())).unwrap();
+        assert!(window.is_hole(0, 0) && window.is_hole(1, 0));
+        assert!(!window.is_hole(1, 1));
+        assert_eq!(window.get(0, 0), Some(Colour::TRANSPARENT));
+
+        let layered = wall.layered(&window, 1, 1);
+        let edge = wall.get(0, 0).unwrap();
+        // Holes clear the wall beneath them
+        assert_eq!(layered.get(1, 1), Some(Colour::TRANSPARENT));
+        assert_eq!(layered.get(2, 1), Some(Colour::TRANSPARENT));
+        // Fill paints over it, plain transparency lets it show through
+        assert_eq!(layered.get(1, 2), Some(Colour::WHITE));
+        assert_eq!(layered.get(2, 2), Some(edge));
+        assert_eq!(layered.get(0, 1), Some(edge));
+        assert_eq!(layered.get(3, 1), Some(edge));
+    }
+
+    #[test]
+    fn test_holes_survive_drop_shadow() {
+        let shape = RenderedShape::new("cut", vec![vec![Colour::BLACK, Colour::TRANSPARENT]])
+            .with_holes(vec![vec![false, true]]);
+        let shadowed = shape.with_drop_shadow(-1, 0, Colour::rgb(9, 9, 9));
+        // The sprite moves right by one to make room for the shadow
+        assert!(shadowed.is_hole(2, 0));
+        assert!(!shadowed.is_hole(1, 0));
     }
 
     #[test]
@@ -383,12 +968,117 @@ mod tests {
 
         let shape = Shape::new("test", vec![], vec![vec!['C']], HashMap::new());
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // 'C' should use custom stamp (fill colour = white)
         assert_eq!(rendered.get(0, 0), Some(Colour::WHITE));
     }
 
+    #[test]
+    fn test_render_cell_shows_whole_stamp() {
+        let palette = default_palette();
+        let tile = Stamp::new(
+            "tile",
+            Some('T'),
+            vec![vec![PixelToken::Edge, PixelToken::Fill], vec![PixelToken::Transparent, PixelToken::Edge]],
+        );
+        let mut renderer = ShapeRenderer::new(&palette);
+        renderer.add_stamp(&tile);
+
+        let mut shape = Shape::new("floor", vec![], vec![vec!['T', '#']], HashMap::new());
+        shape.cell = Some(CellSize::Pixels(2, 2));
+        let rendered = renderer.render(&shape).unwrap();
+
+        assert_eq!(rendered.size(), (4, 2));
+        assert_eq!(rendered.get(0, 0), Some(Colour::BLACK));
+        assert_eq!(rendered.get(1, 0), Some(Colour::WHITE));
+        assert_eq!(rendered.get(0, 1), Some(Colour::TRANSPARENT));
+        assert_eq!(rendered.get(1, 1), Some(Colour::BLACK));
+        // A 1x1 stamp fills its whole cell
+        assert!((2..4).all(|x| (0..2).all(|y| rendered.get(x, y) == rendered.get(2, 0))));
+
+        // Without a cell size the glyph is one pixel, its stamp's top-left
+        shape.cell = None;
+        let single = renderer.render(&shape).unwrap();
+        assert_eq!(single.size(), (2, 1));
+        assert_eq!(single.get(0, 0), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_render_stamp_cells_show_every_token() {
+        let palette = default_palette();
+        let brick = Stamp::new(
+            "brick",
+            Some('B'),
+            vec![
+                vec![PixelToken::Edge, PixelToken::Edge, PixelToken::Edge],
+                vec![PixelToken::Edge, PixelToken::Fill, PixelToken::Transparent],
+            ],
+        );
+        let mut renderer = ShapeRenderer::new(&palette);
+        renderer.add_stamp(&brick);
+
+        let mut shape = Shape::new("wall", vec![], vec![vec!['B', 'B'], vec!['x', 'B']], HashMap::new());
+        shape.cell = Some(CellSize::Stamp);
+        let rendered = renderer.render(&shape).unwrap();
+
+        // Cells are brick-sized, and each brick shows all three tokens
+        assert_eq!(rendered.size(), (6, 4));
+        for (ox, oy) in [(0, 0), (3, 0), (3, 2)] {
+            for (x, y, token) in brick.iter_pixels() {
+                let expected = token.resolve(Colour::BLACK, Colour::WHITE);
+                assert_eq!(rendered.get(ox + x, oy + y), Some(expected), "brick at ({}, {})", ox, oy);
+            }
+        }
+        assert!((0..3).all(|x| (2..4).all(|y| rendered.get(x, y) == Some(Colour::TRANSPARENT))));
+    }
+
+    #[test]
+    fn test_render_vertical_gradient() {
+        let palette = default_palette();
+        let renderer = ShapeRenderer::new(&palette);
+
+        let mut legend = HashMap::new();
+        legend.insert(
+            '~',
+            LegendEntry::Gradient {
+                from: "$edge".to_string(),
+                to: "$fill".to_string(),
+                direction: GradientDirection::Vertical,
+            },
+        );
+        let shape = Shape::new("sky", vec![], vec![vec!['~']; 4], legend);
+
+        let rendered = renderer.render(&shape).unwrap();
+
+        // Black to white, ends on the first and last rows
+        let column: Vec<_> = (0..4).map(|y| rendered.get(0, y).unwrap()).collect();
+        assert_eq!(
+            column,
+            vec![Colour::BLACK, Colour::rgb(85, 85, 85), Colour::rgb(170, 170, 170), Colour::WHITE]
+        );
+    }
+
+    #[test]
+    fn test_render_horizontal_gradient_missing_colour() {
+        let palette = default_palette();
+        let renderer = ShapeRenderer::new(&palette).strict(true);
+
+        let mut legend = HashMap::new();
+        legend.insert(
+            '~',
+            LegendEntry::Gradient {
+                from: "#FF0000".to_string(),
+                to: "$nowhere".to_string(),
+                direction: GradientDirection::Horizontal,
+            },
+        );
+        let shape = Shape::new("sunset", vec![], vec![vec!['~', '~']], legend);
+
+        let err = renderer.render(&shape).unwrap_err().to_string();
+        assert!(err.contains("references missing colour '$nowhere'"), "{}", err);
+    }
+
     #[test]
     fn test_render_with_brush_fill() {
         let palette = default_palette();
@@ -405,6 +1095,7 @@ mod tests {
                 bindings: [('A', "$edge".to_string()), ('B', "$fill".to_string())]
                     .into_iter()
                     .collect(),
+                offset: (0, 0),
             },
         );
 
@@ -415,7 +1106,7 @@ mod tests {
             legend,
         );
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // Checker pattern should tile: AB/BA
         assert_eq!(rendered.get(0, 0), Some(Colour::BLACK)); // A = edge
@@ -424,6 +1115,34 @@ mod tests {
         assert_eq!(rendered.get(1, 1), Some(Colour::BLACK)); // A = edge
     }
 
+    #[test]
+    fn test_render_fill_offset_shifts_pattern() {
+        let palette = default_palette();
+        let checker = BuiltinBrushes::get("checker").unwrap();
+
+        let mut renderer = ShapeRenderer::new(&palette);
+        renderer.add_brush(&checker);
+
+        let render = |offset| {
+            let bindings = [('A', "$edge".to_string()), ('B', "$fill".to_string())].into_iter().collect();
+            let legend = [('~', LegendEntry::Fill { name: "checker".to_string(), bindings, offset })].into_iter().collect();
+            let shape = Shape::new("test", vec![], vec![vec!['~'; 4]; 2], legend);
+            renderer.render(&shape).unwrap()
+        };
+        let plain = render((0, 0));
+        let shifted = render((1, 0));
+
+        // One column along: each cell shows its right-hand neighbour's colour
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(shifted.get(x, y), plain.get(x + 1, y));
+            }
+        }
+        assert_ne!(shifted.get(0, 0), plain.get(0, 0));
+        // Negative offsets wrap the same way
+        assert_eq!(render((-1, 0)).pixels(), shifted.pixels());
+    }
+
     #[test]
     fn test_render_with_palette_variant() {
         // Create a palette with variants
@@ -440,13 +1159,13 @@ mod tests {
         // Render without variant
         let renderer = ShapeRenderer::new(&palette);
         let shape = Shape::new("test", vec![], vec![vec!['+', '.']], HashMap::new());
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
         assert_eq!(rendered.get(0, 0), Some(Colour::BLACK)); // edge
         assert_eq!(rendered.get(1, 0), Some(Colour::WHITE)); // fill
 
         // Render with variant
         let renderer = ShapeRenderer::new(&palette).with_variant("inverted");
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
         assert_eq!(rendered.get(0, 0), Some(Colour::WHITE)); // inverted edge
         assert_eq!(rendered.get(1, 0), Some(Colour::BLACK)); // inverted fill
     }
@@ -462,9 +1181,75 @@ mod tests {
 
         let shape = Shape::new("test", vec![], vec![vec!['+']], legend);
 
-        let rendered = renderer.render(&shape);
+        let rendered = renderer.render(&shape).unwrap();
 
         // Should use legend override (fill = white), not builtin (edge = black)
         assert_eq!(rendered.get(0, 0), Some(Colour::WHITE));
     }
+
+    #[test]
+    fn test_render_with_outline_effect() {
+        let palette = default_palette();
+        let effects = vec![Effect::outline("$edge", 1)];
+        let renderer = ShapeRenderer::new(&palette).with_effects(&effects);
+
+        let shape = Shape::new("test", vec![], vec![vec!['x', '.', 'x']], HashMap::new());
+
+        let rendered = renderer.render(&shape).unwrap();
+        assert_eq!(rendered.get(0, 0), Some(Colour::BLACK));
+        assert_eq!(rendered.get(1, 0), Some(Colour::WHITE));
+        assert_eq!(rendered.get(2, 0), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_drop_shadow_keeps_slice_and_anchor_on_sprite() {
+        let palette = default_palette();
+        let effects = vec![Effect::drop_shadow(-1, 2, "$edge", 255)];
+        let renderer = ShapeRenderer::new(&palette).with_effects(&effects);
+
+        let mut shape = Shape::new("panel", vec![], vec![vec!['.'; 4]; 3], HashMap::new());
+        shape.slice = Some(NineSlice::new(1, 1, 1, 1));
+        shape.anchor = Some(Anchor::Relative(0.5, 1.0));
+
+        // 4x3 sprite, moved right by one on a 5x5 canvas
+        let rendered = renderer.render(&shape).unwrap();
+        assert_eq!(rendered.size(), (5, 5));
+        assert_eq!(rendered.slice(), Some(NineSlice::new(2, 1, 1, 3)));
+        assert_eq!(rendered.anchor(), Some((3.0, 3.0)));
+    }
+
+    #[test]
+    fn test_render_with_levels_effect() {
+        let palette = default_palette();
+        let mut builder = ShaderBuilder::new("bright");
+        builder.palette("default");
+        builder.add_effect(Effect::levels(50.0, 0.0, 1.0));
+        let shader = builder.build(None).unwrap();
+        let renderer = ShapeRenderer::new(&palette).with_effects(&shader.effects);
+
+        let shape = Shape::new("test", vec![], vec![vec!['#', '.', 'x']], HashMap::new());
+
+        let rendered = renderer.render(&shape).unwrap();
+        assert_eq!(rendered.get(0, 0), Some(Colour::rgb(50, 50, 50)));
+        assert_eq!(rendered.get(1, 0), Some(Colour::WHITE));
+        assert_eq!(rendered.get(2, 0), Some(Colour::TRANSPARENT));
+    }
+
+    #[test]
+    fn test_render_with_posterize_effect() {
+        let palette = default_palette();
+        let mut builder = ShaderBuilder::new("poster");
+        builder.palette("default");
+        builder.add_effect(Effect::levels(100.0, 0.0, 1.0));
+        builder.add_effect(Effect::posterize(2));
+        let shader = builder.build(None).unwrap();
+        let renderer = ShapeRenderer::new(&palette).with_effects(&shader.effects);
+
+        let shape = Shape::new("test", vec![], vec![vec!['#', 'x']], HashMap::new());
+
+        let rendered = renderer.render(&shape).unwrap();
+        // Black lifted to 100 snaps back down; effects run in order
+        assert_eq!(rendered.get(0, 0), Some(Colour::BLACK));
+        assert_eq!(rendered.get(1, 0), Some(Colour::TRANSPARENT));
+    }
 }
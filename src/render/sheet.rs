@@ -1,9 +1,10 @@
 //! Sprite sheet packer.
 //!
-//! Packs rendered shapes into a single sprite sheet using shelf packing.
+//! Packs rendered shapes into a single sprite sheet using shelf or MaxRects packing.
 //! Outputs a TexturePacker-compatible JSON Hash format for game engine interop.
 
 use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::path::Path;
 
@@ -22,6 +23,36 @@ pub struct Frame {
     pub y: u32,
     pub w: u32,
     pub h: u32,
+    /// Set when transparent borders were trimmed before packing.
+    pub trim: Option<Trim>,
+    /// Sprite anchor in frame pixels, adjusted for any trim offset.
+    pub pivot: Option<(f32, f32)>,
+    /// Column and row in a frames-per-row layout.
+    pub cell: Option<(u32, u32)>,
+}
+
+impl Frame {
+    /// Size of the sprite this frame was cut from: the untrimmed size when
+    /// trimmed, otherwise the frame's own size.
+    pub fn source_size(&self) -> (u32, u32) {
+        self.trim.map_or((self.w, self.h), |t| (t.source_w, t.source_h))
+    }
+
+    /// Offset of the frame within its source sprite, (0, 0) when untrimmed.
+    pub fn source_offset(&self) -> (u32, u32) {
+        self.trim.map_or((0, 0), |t| (t.x, t.y))
+    }
+}
+
+/// How a frame was trimmed from its source sprite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trim {
+    /// Offset of the kept region within the source sprite.
+    pub x: u32,
+    pub y: u32,
+    /// Original (untrimmed) sprite size.
+    pub source_w: u32,
+    pub source_h: u32,
 }
 
 /// Sprite sheet metadata.
@@ -32,20 +63,137 @@ pub struct SheetMeta {
     pub scale: u32,
 }
 
-/// Sprite sheet packer using shelf (row-based) packing.
+/// How sprites are arranged on the sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PackStrategy {
+    /// Rows of sprites sorted by height (fast, wastes space with mixed heights).
+    #[default]
+    Shelf,
+    /// MaxRects with the best-area-fit heuristic (tighter for mixed sizes).
+    MaxRects,
+    /// Fixed-size cells in sprite order, each sprite at its cell's top-left.
+    Grid { width: u32, height: u32 },
+}
+
+/// How the sheet's final dimensions are chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SheetSizing {
+    /// Power-of-two width, height fitted to the content.
+    #[default]
+    PotWidth,
+    /// Width and height fitted to the content.
+    Tight,
+    /// Square, with the smallest power-of-two side that fits both dimensions.
+    SquarePot,
+}
+
+impl SheetSizing {
+    /// Parse a sizing mode from a string.
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "tight" => SheetSizing::Tight,
+            "square-pot" | "square" => SheetSizing::SquarePot,
+            _ => SheetSizing::PotWidth,
+        }
+    }
+}
+
+impl fmt::Display for SheetSizing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetSizing::PotWidth => write!(f, "pot-width"),
+            SheetSizing::Tight => write!(f, "tight"),
+            SheetSizing::SquarePot => write!(f, "square-pot"),
+        }
+    }
+}
+
+/// Placements by sprite index, then the sheet width and height.
+type Layout = (Vec<(u32, u32)>, u32, u32);
+
+/// Sprite sheet packer.
 pub struct SheetPacker {
     pub padding: u32,
+    pub strategy: PackStrategy,
+    pub sizing: SheetSizing,
+    /// Crop transparent borders from each sprite before packing.
+    pub trim: bool,
+    /// Lay sprites out in this many columns, in order, instead of packing.
+    pub columns: Option<u32>,
+    /// Wrap shelves at this width instead of the computed one.
+    pub max_width: Option<u32>,
+    /// Extrude each sprite's edge pixels this far around its frame.
+    pub bleed: u32,
+    /// Lay sprites out as animation frames, this many to a row (0 = one
+    /// row), recording each frame's column and row.
+    pub frames_per_row: Option<u32>,
 }
 
 impl SheetPacker {
     pub fn new(padding: u32) -> Self {
-        Self { padding }
+        Self::with_strategy(padding, PackStrategy::Shelf)
+    }
+
+    /// Create a packer with an explicit packing strategy.
+    pub fn with_strategy(padding: u32, strategy: PackStrategy) -> Self {
+        Self {
+            padding,
+            strategy,
+            sizing: SheetSizing::PotWidth,
+            trim: false,
+            columns: None,
+            max_width: None,
+            bleed: 0,
+            frames_per_row: None,
+        }
+    }
+
+    /// Set how the sheet's final dimensions are chosen.
+    pub fn with_sizing(mut self, sizing: SheetSizing) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// Enable or disable trimming of transparent sprite borders.
+    pub fn with_trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Use a fixed column count (also the column count of a grid).
+    pub fn with_columns(mut self, columns: Option<u32>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Cap the shelf width for shelf and MaxRects packing.
+    pub fn with_max_width(mut self, max_width: Option<u32>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Extrude sprite edges `bleed` pixels outwards, so filtering at a
+    /// frame's border samples the sprite rather than its neighbours. Room
+    /// for the bleed is reserved around each frame; frame rects still
+    /// cover just the sprite.
+    pub fn with_bleed(mut self, bleed: u32) -> Self {
+        self.bleed = bleed;
+        self
+    }
+
+    /// Lay sprites out in order, `frames_per_row` to a row (0 for a single
+    /// row), like [`with_columns`](Self::with_columns), and record each
+    /// frame's column and row in its metadata.
+    pub fn with_frames_per_row(mut self, frames_per_row: Option<u32>) -> Self {
+        self.frames_per_row = frames_per_row;
+        self
     }
 
     /// Pack rendered shapes into a sprite sheet.
     ///
-    /// Returns the composited image and frame metadata.
-    pub fn pack(&self, sprites: &[RenderedShape]) -> (RenderedShape, SheetMeta) {
+    /// Returns the composited image and frame metadata. Fails in grid mode
+    /// when a sprite is larger than a cell.
+    pub fn pack(&self, sprites: &[RenderedShape]) -> Result<(RenderedShape, SheetMeta)> {
         if sprites.is_empty() {
             let empty = RenderedShape::new("sheet", vec![]);
             let meta = SheetMeta {
@@ -54,19 +202,87 @@ impl SheetPacker {
                 size: (0, 0),
                 scale: 1,
             };
-            return (empty, meta);
+            return Ok((empty, meta));
         }
 
-        // Build index sorted by height descending (stable sort preserves name order)
-        let mut indices: Vec<usize> = (0..sprites.len()).collect();
-        indices.sort_by(|&a, &b| {
-            sprites[b]
-                .height()
-                .cmp(&sprites[a].height())
-                .then_with(|| a.cmp(&b))
-        });
+        let anchors: Vec<Option<(f32, f32)>> = sprites.iter().map(|s| s.anchor()).collect();
+        let (trimmed, trims): (Vec<RenderedShape>, Vec<Option<Trim>>) = if self.trim {
+            sprites.iter().map(trim_sprite).unzip()
+        } else {
+            (Vec::new(), vec![None; sprites.len()])
+        };
+        let sprites = if self.trim { &trimmed[..] } else { sprites };
+
+        // Sprites are laid out and blitted with their bleed border
+        let bled: Vec<RenderedShape> = if self.bleed > 0 {
+            sprites.iter().map(|s| extrude(s, self.bleed as usize)).collect()
+        } else {
+            Vec::new()
+        };
+        let blits = if self.bleed > 0 { &bled[..] } else { sprites };
+
+        let count = sprites.len() as u32;
+        let columns = match self.frames_per_row {
+            Some(0) => Some(count),
+            Some(n) => Some(n.min(count)),
+            None => self.columns,
+        };
+        let (placements, sheet_width, sheet_height) = match (self.strategy, columns) {
+            (PackStrategy::Grid { width, height }, _) => self.grid_layout(sprites, width, height, columns)?,
+            (_, Some(columns)) => self.column_layout(blits, columns),
+            _ => self.packed_layout(blits),
+        };
+
+        // Build frames in original sprite order
+        let mut frames: Vec<Frame> = Vec::with_capacity(sprites.len());
+        for (idx, sprite) in sprites.iter().enumerate() {
+            let (x, y) = placements[idx];
+            let trim = trims[idx];
+            let pivot = anchors[idx].map(|(ax, ay)| match trim {
+                Some(t) => (ax - t.x as f32, ay - t.y as f32),
+                None => (ax, ay),
+            });
+            frames.push(Frame {
+                name: sprite.name.clone(),
+                x: x + self.bleed,
+                y: y + self.bleed,
+                w: sprite.width() as u32,
+                h: sprite.height() as u32,
+                trim,
+                pivot,
+                cell: self.frames_per_row.and(columns).map(|cols| (idx as u32 % cols, idx as u32 / cols)),
+            });
+        }
+
+        // Blit sprites onto the canvas
+        let mut pixels =
+            vec![vec![Colour::TRANSPARENT; sheet_width as usize]; sheet_height as usize];
+
+        for (idx, sprite) in blits.iter().enumerate() {
+            let (ox, oy) = placements[idx];
+            for sy in 0..sprite.height() {
+                for sx in 0..sprite.width() {
+                    if let Some(c) = sprite.get(sx, sy) {
+                        pixels[oy as usize + sy][ox as usize + sx] = c;
+                    }
+                }
+            }
+        }
+
+        let sheet = RenderedShape::new("sheet", pixels);
+        let meta = SheetMeta {
+            frames,
+            image: "sheet.png".to_string(),
+            size: (sheet_width, sheet_height),
+            scale: 1,
+        };
 
-        // Compute sheet width as smallest power-of-two that fits
+        Ok((sheet, meta))
+    }
+
+    /// Shelf or MaxRects placements, with the sheet size from `sizing`.
+    fn packed_layout(&self, sprites: &[RenderedShape]) -> Layout {
+        // Layout width: smallest power-of-two that fits (exact when sizing tight)
         let max_w = sprites.iter().map(|s| s.width() as u32).max().unwrap_or(1);
         let total_area: u32 = sprites
             .iter()
@@ -76,10 +292,145 @@ impl SheetPacker {
             .sum();
         let sqrt_area = (total_area as f64).sqrt().ceil() as u32;
         let min_width = max_w.max(sqrt_area);
-        let sheet_width = next_power_of_two(min_width);
+        let sheet_width = match (self.max_width, self.sizing) {
+            // Never narrower than the widest sprite
+            (Some(limit), _) => limit.max(max_w),
+            (None, SheetSizing::Tight) => min_width,
+            (None, _) => next_power_of_two(min_width),
+        };
 
-        // Shelf-pack: place sprites left-to-right, new row when full
-        let mut frames: Vec<Frame> = Vec::with_capacity(sprites.len());
+        // Shelf result is also the height bound for MaxRects; if MaxRects
+        // cannot fit everything within it, keep the shelf layout.
+        let (mut placements, mut sheet_height) = self.shelf_placements(sprites, sheet_width);
+        if self.strategy == PackStrategy::MaxRects {
+            if let Some(packed) = self.maxrects_placements(sprites, sheet_width, sheet_height) {
+                sheet_height = sprites
+                    .iter()
+                    .zip(&packed)
+                    .map(|(s, &(_, y))| y + s.height() as u32)
+                    .max()
+                    .unwrap_or(0);
+                placements = packed;
+            }
+        }
+
+        let (sheet_width, sheet_height) = match self.sizing {
+            SheetSizing::PotWidth => (sheet_width, sheet_height),
+            SheetSizing::Tight => {
+                let right = sprites
+                    .iter()
+                    .zip(&placements)
+                    .map(|(s, &(x, _))| x + s.width() as u32)
+                    .max()
+                    .unwrap_or(0);
+                (right, sheet_height)
+            }
+            SheetSizing::SquarePot => {
+                let side = next_power_of_two(sheet_width.max(sheet_height));
+                (side, side)
+            }
+        };
+
+        (placements, sheet_width, sheet_height)
+    }
+
+    /// Place sprites in `cell_w` x `cell_h` cells, left to right then top to
+    /// bottom in sprite order. The column count fills the sheet width that
+    /// `sizing` picks for a roughly square grid.
+    ///
+    /// `sprites` are checked against the cell without their bleed; the
+    /// bleed border widens the cell pitch instead, and placements are where
+    /// each bled sprite goes.
+    fn grid_layout(&self, sprites: &[RenderedShape], cell_w: u32, cell_h: u32, columns: Option<u32>) -> Result<Layout> {
+        if let Some(sprite) = sprites
+            .iter()
+            .find(|s| s.width() as u32 > cell_w || s.height() as u32 > cell_h)
+        {
+            return Err(PxError::Build {
+                message: format!(
+                    "Sprite '{}' ({}x{}) does not fit a {}x{} grid cell",
+                    sprite.name,
+                    sprite.width(),
+                    sprite.height(),
+                    cell_w,
+                    cell_h
+                ),
+                help: Some("Use a larger grid cell".to_string()),
+            });
+        }
+
+        let (cell_w, cell_h) = (cell_w + 2 * self.bleed, cell_h + 2 * self.bleed);
+        let pad = self.padding;
+        let span = |cells: u32, size: u32| cells * (size + pad) - pad;
+        let count = sprites.len() as u32;
+        let square = ((count as f64).sqrt().ceil() as u32).max(1);
+
+        let cols = match (columns, self.sizing) {
+            (Some(columns), _) => columns.clamp(1, count),
+            // Use the whole power-of-two width
+            (None, SheetSizing::PotWidth) => ((next_power_of_two(span(square, cell_w)) + pad) / (cell_w + pad)).min(count),
+            (None, _) => square,
+        };
+        let rows = count.div_ceil(cols);
+
+        let (width, height) = (span(cols, cell_w), span(rows, cell_h));
+        let (sheet_width, sheet_height) = match self.sizing {
+            SheetSizing::PotWidth => (next_power_of_two(width), height),
+            SheetSizing::Tight => (width, height),
+            SheetSizing::SquarePot => {
+                let side = next_power_of_two(width.max(height));
+                (side, side)
+            }
+        };
+
+        let placements = (0..count)
+            .map(|i| ((i % cols) * (cell_w + pad), (i / cols) * (cell_h + pad)))
+            .collect();
+        Ok((placements, sheet_width, sheet_height))
+    }
+
+    /// Place sprites in `columns` columns, left to right then top to bottom
+    /// in sprite order. Columns are as wide as the widest sprite and each row
+    /// as tall as its tallest.
+    fn column_layout(&self, sprites: &[RenderedShape], columns: u32) -> Layout {
+        let pad = self.padding;
+        let count = sprites.len() as u32;
+        let cols = columns.clamp(1, count);
+        let cell_w = sprites.iter().map(|s| s.width() as u32).max().unwrap_or(1);
+
+        let mut placements = Vec::with_capacity(sprites.len());
+        let mut y = 0;
+        for row in sprites.chunks(cols as usize) {
+            for col in 0..row.len() as u32 {
+                placements.push((col * (cell_w + pad), y));
+            }
+            y += row.iter().map(|s| s.height() as u32).max().unwrap_or(0) + pad;
+        }
+
+        let (width, height) = (cols * (cell_w + pad) - pad, y - pad);
+        let (sheet_width, sheet_height) = match self.sizing {
+            SheetSizing::PotWidth => (next_power_of_two(width), height),
+            SheetSizing::Tight => (width, height),
+            SheetSizing::SquarePot => {
+                let side = next_power_of_two(width.max(height));
+                (side, side)
+            }
+        };
+        (placements, sheet_width, sheet_height)
+    }
+
+    /// Shelf-pack sprites into rows. Returns placements (by sprite index) and sheet height.
+    fn shelf_placements(&self, sprites: &[RenderedShape], sheet_width: u32) -> (Vec<(u32, u32)>, u32) {
+        // Build index sorted by height descending (stable sort preserves name order)
+        let mut indices: Vec<usize> = (0..sprites.len()).collect();
+        indices.sort_by(|&a, &b| {
+            sprites[b]
+                .height()
+                .cmp(&sprites[a].height())
+                .then_with(|| a.cmp(&b))
+        });
+
+        // Place sprites left-to-right, new row when full
         let mut cursor_x: u32 = 0;
         let mut cursor_y: u32 = 0;
         let mut row_height: u32 = 0;
@@ -104,45 +455,162 @@ impl SheetPacker {
             cursor_x += w + self.padding;
         }
 
-        let sheet_height = cursor_y + row_height;
+        (placements, cursor_y + row_height)
+    }
 
-        // Build frames in original sprite order
-        for (idx, sprite) in sprites.iter().enumerate() {
-            let (x, y) = placements[idx];
-            frames.push(Frame {
-                name: sprite.name.clone(),
-                x,
-                y,
-                w: sprite.width() as u32,
-                h: sprite.height() as u32,
-            });
+    /// MaxRects-pack sprites (largest first) into a `width` x `height` bin.
+    ///
+    /// Each sprite goes into the free rectangle it fills most completely
+    /// (best area fit, ties broken by shorter leftover side, then top-left).
+    /// Returns `None` if a sprite doesn't fit.
+    fn maxrects_placements(&self, sprites: &[RenderedShape], width: u32, height: u32) -> Option<Vec<(u32, u32)>> {
+        // Padding is reserved on the right/bottom of every sprite, so the bin
+        // grows by one padding to keep the last row/column flush with the edge.
+        let pad = self.padding;
+        let mut free = vec![Rect { x: 0, y: 0, w: width + pad, h: height + pad }];
+
+        let mut indices: Vec<usize> = (0..sprites.len()).collect();
+        indices.sort_by(|&a, &b| {
+            let area = |i: usize| sprites[i].width() * sprites[i].height();
+            area(b).cmp(&area(a)).then_with(|| a.cmp(&b))
+        });
+
+        let mut placements: Vec<(u32, u32)> = vec![(0, 0); sprites.len()];
+
+        for &idx in &indices {
+            let w = sprites[idx].width() as u32 + pad;
+            let h = sprites[idx].height() as u32 + pad;
+
+            let best = free
+                .iter()
+                .filter(|f| f.w >= w && f.h >= h)
+                .min_by_key(|f| (f.w * f.h - w * h, (f.w - w).min(f.h - h), f.y, f.x))?;
+
+            let used = Rect { x: best.x, y: best.y, w, h };
+            placements[idx] = (used.x, used.y);
+
+            free = free.iter().flat_map(|f| f.split(&used)).collect();
+            prune_contained(&mut free);
         }
 
-        // Blit sprites onto the canvas
-        let mut pixels =
-            vec![vec![Colour::TRANSPARENT; sheet_width as usize]; sheet_height as usize];
+        Some(placements)
+    }
+}
 
-        for (idx, sprite) in sprites.iter().enumerate() {
-            let (ox, oy) = placements[idx];
-            for sy in 0..sprite.height() {
-                for sx in 0..sprite.width() {
-                    if let Some(c) = sprite.get(sx, sy) {
-                        pixels[oy as usize + sy][ox as usize + sx] = c;
-                    }
-                }
-            }
+/// `sprite` with its edge pixels repeated `bleed` pixels out on every side.
+fn extrude(sprite: &RenderedShape, bleed: usize) -> RenderedShape {
+    let (w, h) = sprite.size();
+    let pixels = (0..h + 2 * bleed)
+        .map(|y| {
+            (0..w + 2 * bleed)
+                .map(|x| {
+                    // Clamp to the nearest edge pixel
+                    let sx = x.saturating_sub(bleed).min(w.saturating_sub(1));
+                    let sy = y.saturating_sub(bleed).min(h.saturating_sub(1));
+                    sprite.get(sx, sy).unwrap_or(Colour::TRANSPARENT)
+                })
+                .collect()
+        })
+        .collect();
+    RenderedShape::new(sprite.name.clone(), pixels)
+}
+
+/// Crop a sprite to its non-transparent bounding box.
+///
+/// Returns the sprite unchanged with no trim if nothing can be removed. A
+/// fully transparent sprite is cut down to a single transparent pixel.
+fn trim_sprite(sprite: &RenderedShape) -> (RenderedShape, Option<Trim>) {
+    let (width, height) = sprite.size();
+    let opaque = |x: usize, y: usize| sprite.get(x, y).is_some_and(|c| !c.is_transparent());
+
+    let rows: Vec<usize> = (0..height).filter(|&y| (0..width).any(|x| opaque(x, y))).collect();
+    let cols: Vec<usize> = (0..width).filter(|&x| (0..height).any(|y| opaque(x, y))).collect();
+
+    let (x0, y0, w, h) = match (rows.first(), rows.last(), cols.first(), cols.last()) {
+        (Some(&top), Some(&bottom), Some(&left), Some(&right)) => (left, top, right - left + 1, bottom - top + 1),
+        _ => (0, 0, 1.min(width), 1.min(height)),
+    };
+
+    if (w, h) == (width, height) {
+        return (sprite.clone(), None);
+    }
+
+    let pixels = sprite.pixels()[y0..y0 + h]
+        .iter()
+        .map(|row| row[x0..x0 + w].to_vec())
+        .collect();
+    let trim = Trim {
+        x: x0 as u32,
+        y: y0 as u32,
+        source_w: width as u32,
+        source_h: height as u32,
+    };
+
+    (RenderedShape::new(&sprite.name, pixels), Some(trim))
+}
+
+/// A rectangle in sheet pixels, used by MaxRects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+impl Rect {
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+
+    fn contains(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.x + other.w <= self.x + self.w
+            && other.y + other.h <= self.y + self.h
+    }
+
+    /// The maximal free rectangles left from `self` after `used` is taken.
+    fn split(&self, used: &Rect) -> Vec<Rect> {
+        if !self.overlaps(used) {
+            return vec![*self];
         }
 
-        let sheet = RenderedShape::new("sheet", pixels);
-        let meta = SheetMeta {
-            frames,
-            image: "sheet.png".to_string(),
-            size: (sheet_width, sheet_height),
-            scale: 1,
-        };
+        let mut parts = Vec::with_capacity(4);
+        if used.x > self.x {
+            parts.push(Rect { x: self.x, y: self.y, w: used.x - self.x, h: self.h });
+        }
+        if used.x + used.w < self.x + self.w {
+            let x = used.x + used.w;
+            parts.push(Rect { x, y: self.y, w: self.x + self.w - x, h: self.h });
+        }
+        if used.y > self.y {
+            parts.push(Rect { x: self.x, y: self.y, w: self.w, h: used.y - self.y });
+        }
+        if used.y + used.h < self.y + self.h {
+            let y = used.y + used.h;
+            parts.push(Rect { x: self.x, y, w: self.w, h: self.y + self.h - y });
+        }
+        parts
+    }
+}
 
-        (sheet, meta)
+/// Drop free rectangles that lie inside another (keeps the first of duplicates).
+fn prune_contained(free: &mut Vec<Rect>) {
+    let mut keep = vec![true; free.len()];
+    for i in 0..free.len() {
+        for j in 0..free.len() {
+            if i != j && keep[j] && free[j].contains(&free[i]) && (free[i] != free[j] || j < i) {
+                keep[i] = false;
+                break;
+            }
+        }
     }
+    let mut flags = keep.into_iter();
+    free.retain(|_| flags.next().unwrap_or(true));
 }
 
 /// Write sheet metadata as TexturePacker-compatible JSON Hash format.
@@ -184,6 +652,20 @@ struct TPFrame {
     sprite_source_size: TPRect,
     #[serde(rename = "sourceSize")]
     source_size: TPSize,
+    /// Anchor as a fraction of the untrimmed source size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pivot: Option<TPPoint>,
+    /// Position in a frames-per-row layout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    row: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct TPPoint {
+    x: f32,
+    y: f32,
 }
 
 #[derive(Serialize)]
@@ -214,6 +696,8 @@ impl TexturePackerJson {
         let s = meta.scale;
         let mut frames = BTreeMap::new();
         for f in &meta.frames {
+            let (ox, oy) = f.source_offset();
+            let (sw, sh) = f.source_size();
             frames.insert(
                 f.name.clone(),
                 TPFrame {
@@ -224,14 +708,21 @@ impl TexturePackerJson {
                         h: f.h * s,
                     },
                     rotated: false,
-                    trimmed: false,
+                    trimmed: f.trim.is_some(),
                     sprite_source_size: TPRect {
-                        x: 0,
-                        y: 0,
+                        x: ox * s,
+                        y: oy * s,
                         w: f.w * s,
                         h: f.h * s,
                     },
-                    source_size: TPSize { w: f.w * s, h: f.h * s },
+                    source_size: TPSize { w: sw * s, h: sh * s },
+                    // Normalised against the untrimmed source, as TexturePacker does
+                    pivot: f.pivot.map(|(px, py)| TPPoint {
+                        x: (px + ox as f32) / sw.max(1) as f32,
+                        y: (py + oy as f32) / sh.max(1) as f32,
+                    }),
+                    row: f.cell.map(|(_, row)| row),
+                    column: f.cell.map(|(column, _)| column),
                 },
             );
         }
@@ -264,7 +755,7 @@ mod tests {
     #[test]
     fn test_pack_empty() {
         let packer = SheetPacker::new(0);
-        let (sheet, meta) = packer.pack(&[]);
+        let (sheet, meta) = packer.pack(&[]).unwrap();
         assert_eq!(sheet.width(), 0);
         assert_eq!(sheet.height(), 0);
         assert!(meta.frames.is_empty());
@@ -274,7 +765,7 @@ mod tests {
     fn test_pack_single_sprite() {
         let packer = SheetPacker::new(0);
         let sprites = vec![make_sprite("a", 4, 4)];
-        let (sheet, meta) = packer.pack(&sprites);
+        let (sheet, meta) = packer.pack(&sprites).unwrap();
 
         assert_eq!(meta.frames.len(), 1);
         assert_eq!(meta.frames[0].name, "a");
@@ -294,7 +785,7 @@ mod tests {
             make_sprite("b", 4, 4),
             make_sprite("c", 4, 4),
         ];
-        let (_sheet, meta) = packer.pack(&sprites);
+        let (_sheet, meta) = packer.pack(&sprites).unwrap();
 
         assert_eq!(meta.frames.len(), 3);
         // All frames should have valid positions
@@ -311,7 +802,7 @@ mod tests {
             make_sprite("a", 4, 4),
             make_sprite("b", 4, 4),
         ];
-        let (_sheet, meta) = packer.pack(&sprites);
+        let (_sheet, meta) = packer.pack(&sprites).unwrap();
 
         assert_eq!(meta.frames.len(), 2);
         // With padding, sprites should not overlap
@@ -327,6 +818,33 @@ mod tests {
         assert!(no_overlap, "sprites should not overlap: a={:?} b={:?}", (a.x, a.y, a.w, a.h), (b.x, b.y, b.w, b.h));
     }
 
+    #[test]
+    fn test_pack_with_bleed() {
+        let mut pixels = vec![vec![Colour::rgb(0, 0, 255); 3]; 2];
+        pixels[0][0] = Colour::rgb(255, 0, 0);
+        pixels[1][2] = Colour::rgb(0, 255, 0);
+        let sprites = vec![RenderedShape::new("gem", pixels), make_sprite("b", 2, 2)];
+
+        let (sheet, meta) = SheetPacker::new(1).with_bleed(1).pack(&sprites).unwrap();
+        let gem = &meta.frames[0];
+        let (x, y) = (gem.x as usize, gem.y as usize);
+
+        // Frame rects cover just the sprite
+        assert_eq!((gem.w, gem.h), (3, 2));
+        assert_eq!(sheet.get(x, y), Some(Colour::rgb(255, 0, 0)));
+        // Just outside the frame is the nearest edge pixel, corners too
+        assert_eq!(sheet.get(x - 1, y), Some(Colour::rgb(255, 0, 0)));
+        assert_eq!(sheet.get(x - 1, y - 1), Some(Colour::rgb(255, 0, 0)));
+        assert_eq!(sheet.get(x + 1, y - 1), Some(Colour::rgb(0, 0, 255)));
+        assert_eq!(sheet.get(x + 3, y + 1), Some(Colour::rgb(0, 255, 0)));
+        assert_eq!(sheet.get(x + 3, y + 2), Some(Colour::rgb(0, 255, 0)));
+
+        // Two bled borders and a pixel of padding still separate the frames
+        let b = &meta.frames[1];
+        let apart = |a: u32, a_len: u32, b: u32| b > a + a_len + 2;
+        assert!(apart(gem.x, gem.w, b.x) || apart(gem.y, gem.h, b.y) || apart(b.x, b.w, gem.x) || apart(b.y, b.h, gem.y));
+    }
+
     #[test]
     fn test_pack_different_sizes() {
         let packer = SheetPacker::new(0);
@@ -335,7 +853,7 @@ mod tests {
             make_sprite("wide", 8, 2),
             make_sprite("small", 2, 2),
         ];
-        let (_sheet, meta) = packer.pack(&sprites);
+        let (_sheet, meta) = packer.pack(&sprites).unwrap();
 
         assert_eq!(meta.frames.len(), 3);
         // Verify no frames overlap
@@ -365,7 +883,7 @@ mod tests {
         let sprite_a = RenderedShape::new("a", vec![vec![red]]);
         let sprite_b = RenderedShape::new("b", vec![vec![blue]]);
 
-        let (sheet, meta) = packer.pack(&[sprite_a, sprite_b]);
+        let (sheet, meta) = packer.pack(&[sprite_a, sprite_b]).unwrap();
 
         // Find where each sprite was placed and verify pixel data
         for frame in &meta.frames {
@@ -387,7 +905,7 @@ mod tests {
             make_sprite("b", 5, 1),
             make_sprite("c", 5, 1),
         ];
-        let (_sheet, meta) = packer.pack(&sprites);
+        let (_sheet, meta) = packer.pack(&sprites).unwrap();
         assert!(
             meta.size.0.is_power_of_two(),
             "sheet width {} should be power of two",
@@ -405,6 +923,9 @@ mod tests {
                     y: 0,
                     w: 4,
                     h: 4,
+                    trim: None,
+                    pivot: None,
+                    cell: None,
                 },
             ],
             image: "sheet.png".to_string(),
@@ -440,4 +961,339 @@ mod tests {
         assert_eq!(next_power_of_two(16), 16);
         assert_eq!(next_power_of_two(17), 32);
     }
+
+    fn varied_sprites() -> Vec<RenderedShape> {
+        vec![
+            make_sprite("tall", 4, 16),
+            make_sprite("wide", 16, 4),
+            make_sprite("big", 8, 8),
+            make_sprite("a", 2, 2),
+            make_sprite("b", 3, 5),
+            make_sprite("c", 6, 2),
+            make_sprite("d", 1, 7),
+            make_sprite("e", 5, 5),
+        ]
+    }
+
+    fn assert_no_overlap(meta: &SheetMeta) {
+        for (i, a) in meta.frames.iter().enumerate() {
+            assert!(a.x + a.w <= meta.size.0 && a.y + a.h <= meta.size.1, "{} out of bounds", a.name);
+            for b in &meta.frames[i + 1..] {
+                let overlap = a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h;
+                assert!(!overlap, "{} overlaps {}", a.name, b.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_maxrects_area_not_larger_than_shelf() {
+        let sprites = varied_sprites();
+
+        let (_, shelf) = SheetPacker::new(0).pack(&sprites).unwrap();
+        let (_, maxrects) = SheetPacker::with_strategy(0, PackStrategy::MaxRects).pack(&sprites).unwrap();
+
+        assert!(maxrects.size.0 * maxrects.size.1 <= shelf.size.0 * shelf.size.1);
+        assert_no_overlap(&maxrects);
+    }
+
+    #[test]
+    fn test_maxrects_padding() {
+        let sprites = varied_sprites();
+        let (_, meta) = SheetPacker::with_strategy(1, PackStrategy::MaxRects).pack(&sprites).unwrap();
+
+        // Frames keep a 1px gap between each other
+        for (i, a) in meta.frames.iter().enumerate() {
+            for b in &meta.frames[i + 1..] {
+                let overlap = a.x < b.x + b.w + 1 && b.x < a.x + a.w + 1 && a.y < b.y + b.h + 1 && b.y < a.y + a.h + 1;
+                assert!(!overlap, "{} touches {}", a.name, b.name);
+            }
+        }
+        assert_no_overlap(&meta);
+    }
+
+    #[test]
+    fn test_maxrects_keeps_frame_order_and_pixels() {
+        let sprites = varied_sprites();
+        let (sheet, meta) = SheetPacker::with_strategy(0, PackStrategy::MaxRects).pack(&sprites).unwrap();
+
+        let names: Vec<&str> = meta.frames.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["tall", "wide", "big", "a", "b", "c", "d", "e"]);
+        for f in &meta.frames {
+            assert_eq!(sheet.get(f.x as usize, f.y as usize), Some(Colour::BLACK));
+        }
+    }
+
+    #[test]
+    fn test_default_strategy_is_shelf() {
+        assert_eq!(SheetPacker::new(0).strategy, PackStrategy::Shelf);
+    }
+
+    #[test]
+    fn test_grid_places_sprites_on_cells() {
+        let sprites: Vec<_> = ["a", "b", "c", "d"].iter().map(|n| make_sprite(n, 4, 4)).collect();
+        let packer = SheetPacker::with_strategy(0, PackStrategy::Grid { width: 4, height: 4 });
+        let (sheet, meta) = packer.pack(&sprites).unwrap();
+
+        let cells: Vec<(u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y)).collect();
+        assert_eq!(cells, vec![(0, 0), (4, 0), (0, 4), (4, 4)]);
+        assert_eq!(meta.size, (8, 8));
+        assert_eq!(sheet.size(), (8, 8));
+    }
+
+    #[test]
+    fn test_grid_top_left_aligns_small_sprites() {
+        let sprites = vec![make_sprite("small", 2, 3), make_sprite("full", 4, 4), make_sprite("dot", 1, 1)];
+        let packer = SheetPacker::with_strategy(1, PackStrategy::Grid { width: 4, height: 4 }).with_sizing(SheetSizing::Tight);
+        let (_, meta) = packer.pack(&sprites).unwrap();
+
+        let frames: Vec<(u32, u32, u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y, f.w, f.h)).collect();
+        assert_eq!(frames, vec![(0, 0, 2, 3), (5, 0, 4, 4), (0, 5, 1, 1)]);
+        assert_eq!(meta.size, (9, 9));
+    }
+
+    #[test]
+    fn test_grid_rejects_oversized_sprite() {
+        let sprites = vec![make_sprite("a", 4, 4), make_sprite("wide", 5, 2)];
+        let packer = SheetPacker::with_strategy(0, PackStrategy::Grid { width: 4, height: 4 });
+        let Err(err) = packer.pack(&sprites) else { panic!("oversized sprite was packed") };
+        let err = err.to_string();
+        assert!(err.contains("'wide' (5x2) does not fit a 4x4 grid cell"), "{}", err);
+    }
+
+    #[test]
+    fn test_grid_with_bleed_widens_cells() {
+        let sprites: Vec<_> = ["a", "b"].iter().map(|n| make_sprite(n, 4, 4)).collect();
+        let packer = SheetPacker::with_strategy(0, PackStrategy::Grid { width: 4, height: 4 })
+            .with_bleed(1)
+            .with_sizing(SheetSizing::Tight);
+        let (sheet, meta) = packer.pack(&sprites).unwrap();
+
+        // Sprites fill their cells exactly; each cell gains a pixel of bleed per side
+        let frames: Vec<(u32, u32, u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y, f.w, f.h)).collect();
+        assert_eq!(frames, vec![(1, 1, 4, 4), (7, 1, 4, 4)]);
+        assert_eq!(meta.size, (12, 6));
+        assert_eq!(sheet.get(6, 0), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_three_columns_break_rows() {
+        let heights = [2, 5, 3, 4, 1, 2, 6];
+        let sprites: Vec<_> = heights.iter().enumerate().map(|(i, &h)| make_sprite(&format!("s{}", i), 3, h)).collect();
+        let packer = SheetPacker::new(1).with_columns(Some(3)).with_sizing(SheetSizing::Tight);
+        let (sheet, meta) = packer.pack(&sprites).unwrap();
+
+        // Rows of three in sprite order, each as tall as its tallest sprite
+        let cells: Vec<(u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y)).collect();
+        assert_eq!(cells, vec![(0, 0), (4, 0), (8, 0), (0, 6), (4, 6), (8, 6), (0, 11)]);
+        assert_eq!(meta.size, (11, 17));
+        assert_eq!(sheet.size(), (11, 17));
+    }
+
+    #[test]
+    fn test_frames_per_row_wraps_into_grid() {
+        let sprites: Vec<_> = (0..5).map(|i| make_sprite(&format!("walk-{}", i), 4, 3)).collect();
+        let packer = SheetPacker::new(0).with_frames_per_row(Some(2)).with_sizing(SheetSizing::Tight);
+        let (sheet, meta) = packer.pack(&sprites).unwrap();
+
+        // Two columns, three rows, in frame order
+        let coords: Vec<(u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y)).collect();
+        assert_eq!(coords, vec![(0, 0), (4, 0), (0, 3), (4, 3), (0, 6)]);
+        let cells: Vec<_> = meta.frames.iter().map(|f| f.cell).collect();
+        assert_eq!(cells, [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)].map(Some));
+        assert_eq!(sheet.size(), (8, 9));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sheet.json");
+        write_sheet_json(&meta, &path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(parsed["frames"]["walk-3"]["row"], 1);
+        assert_eq!(parsed["frames"]["walk-3"]["column"], 1);
+        assert_eq!(parsed["frames"]["walk-4"]["frame"], serde_json::json!({"x": 0, "y": 6, "w": 4, "h": 3}));
+    }
+
+    #[test]
+    fn test_frames_per_row_zero_is_one_row() {
+        let sprites: Vec<_> = (0..5).map(|i| make_sprite(&format!("walk-{}", i), 4, 3)).collect();
+        let packer = SheetPacker::new(1).with_frames_per_row(Some(0)).with_sizing(SheetSizing::Tight);
+        let (sheet, meta) = packer.pack(&sprites).unwrap();
+
+        assert!(meta.frames.iter().enumerate().all(|(i, f)| f.cell == Some((i as u32, 0)) && f.y == 0));
+        assert_eq!(meta.frames[4].x, 20);
+        assert_eq!(sheet.size(), (24, 3));
+
+        // Plain packing records no cells
+        let (_, meta) = SheetPacker::new(0).with_columns(Some(2)).pack(&sprites).unwrap();
+        assert!(meta.frames.iter().all(|f| f.cell.is_none()));
+    }
+
+    #[test]
+    fn test_columns_set_grid_width() {
+        let sprites: Vec<_> = ["a", "b", "c", "d"].iter().map(|n| make_sprite(n, 4, 4)).collect();
+        let packer = SheetPacker::with_strategy(0, PackStrategy::Grid { width: 4, height: 4 }).with_columns(Some(1));
+        let (_, meta) = packer.pack(&sprites).unwrap();
+
+        let cells: Vec<(u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y)).collect();
+        assert_eq!(cells, vec![(0, 0), (0, 4), (0, 8), (0, 12)]);
+    }
+
+    #[test]
+    fn test_max_width_wraps_shelves() {
+        let sprites: Vec<_> = ["a", "b", "c", "d"].iter().map(|n| make_sprite(n, 4, 4)).collect();
+        let (_, meta) = SheetPacker::new(0).with_max_width(Some(10)).pack(&sprites).unwrap();
+
+        let cells: Vec<(u32, u32)> = meta.frames.iter().map(|f| (f.x, f.y)).collect();
+        assert_eq!(cells, vec![(0, 0), (4, 0), (0, 4), (4, 4)]);
+        assert_eq!(meta.size, (10, 8));
+    }
+
+    /// A `size`x`size` sprite with a 1px transparent ring around opaque fill.
+    fn ringed_sprite(name: &str, size: usize) -> RenderedShape {
+        let mut pixels = vec![vec![Colour::TRANSPARENT; size]; size];
+        for row in &mut pixels[1..size - 1] {
+            for pixel in &mut row[1..size - 1] {
+                *pixel = Colour::BLACK;
+            }
+        }
+        RenderedShape::new(name, pixels)
+    }
+
+    #[test]
+    fn test_trim_transparent_ring() {
+        let sprites = vec![ringed_sprite("ring", 6)];
+        let (sheet, meta) = SheetPacker::new(0).with_trim(true).pack(&sprites).unwrap();
+
+        let frame = &meta.frames[0];
+        assert_eq!((frame.w, frame.h), (4, 4));
+        assert_eq!(
+            frame.trim,
+            Some(Trim { x: 1, y: 1, source_w: 6, source_h: 6 })
+        );
+        assert_eq!(sheet.get(frame.x as usize, frame.y as usize), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_trim_json_source_size() {
+        let sprites = vec![ringed_sprite("ring", 6), make_sprite("solid", 2, 2)];
+        let (_, mut meta) = SheetPacker::new(0).with_trim(true).pack(&sprites).unwrap();
+        meta.scale = 2;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sheet.json");
+        write_sheet_json(&meta, &path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        let ring = &parsed["frames"]["ring"];
+        assert_eq!(ring["trimmed"], true);
+        assert_eq!(ring["frame"]["w"], 8);
+        assert_eq!(ring["sourceSize"], serde_json::json!({"w": 12, "h": 12}));
+        assert_eq!(ring["spriteSourceSize"], serde_json::json!({"x": 2, "y": 2, "w": 8, "h": 8}));
+
+        // Nothing to trim: reported as untrimmed
+        let solid = &parsed["frames"]["solid"];
+        assert_eq!(solid["trimmed"], false);
+        assert_eq!(solid["sourceSize"], serde_json::json!({"w": 4, "h": 4}));
+    }
+
+    #[test]
+    fn test_json_distinct_source_size() {
+        let frame = |name: &str, trim| Frame { name: name.into(), x: 0, y: 0, w: 4, h: 3, trim, pivot: None, cell: None };
+        let meta = SheetMeta {
+            frames: vec![
+                frame("cut", Some(Trim { x: 0, y: 0, source_w: 6, source_h: 5 })),
+                frame("whole", None),
+            ],
+            image: "sheet.png".to_string(),
+            size: (4, 3),
+            scale: 1,
+        };
+        assert_eq!(meta.frames[0].source_size(), (6, 5));
+        assert_eq!(meta.frames[1].source_size(), (4, 3));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sheet.json");
+        write_sheet_json(&meta, &path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        let cut = &parsed["frames"]["cut"];
+        assert_eq!(cut["sourceSize"], serde_json::json!({"w": 6, "h": 5}));
+        assert_ne!(cut["sourceSize"]["w"], cut["frame"]["w"]);
+        assert_eq!(cut["spriteSourceSize"], serde_json::json!({"x": 0, "y": 0, "w": 4, "h": 3}));
+
+        // Defaults to the frame size
+        assert_eq!(parsed["frames"]["whole"]["sourceSize"], serde_json::json!({"w": 4, "h": 3}));
+    }
+
+    #[test]
+    fn test_trim_fully_transparent() {
+        let sprites = vec![RenderedShape::new("blank", vec![vec![Colour::TRANSPARENT; 3]; 2])];
+        let (_, meta) = SheetPacker::new(0).with_trim(true).pack(&sprites).unwrap();
+
+        let frame = &meta.frames[0];
+        assert_eq!((frame.w, frame.h), (1, 1));
+        assert_eq!(frame.trim.map(|t| (t.source_w, t.source_h)), Some((3, 2)));
+    }
+
+    #[test]
+    fn test_sheet_sizing_modes() {
+        // 4 sprites 6x6: area 144 -> layout width 12 (pot 16), two per row
+        let sprites: Vec<RenderedShape> = (0..4).map(|i| make_sprite(&format!("s{}", i), 6, 6)).collect();
+
+        let size = |sizing| SheetPacker::new(0).with_sizing(sizing).pack(&sprites).unwrap().1.size;
+
+        assert_eq!(size(SheetSizing::PotWidth), (16, 12));
+        assert_eq!(size(SheetSizing::Tight), (12, 12));
+        assert_eq!(size(SheetSizing::SquarePot), (16, 16));
+    }
+
+    #[test]
+    fn test_square_pot_fits_tall_content() {
+        // One wide-enough column of tall sprites: height outgrows the pot width
+        let sprites: Vec<RenderedShape> = (0..3).map(|i| make_sprite(&format!("s{}", i), 8, 30)).collect();
+
+        let (sheet, meta) = SheetPacker::new(0).with_sizing(SheetSizing::SquarePot).pack(&sprites).unwrap();
+        assert_eq!(meta.size.0, meta.size.1);
+        assert!(meta.size.0.is_power_of_two());
+        assert_eq!((sheet.width() as u32, sheet.height() as u32), meta.size);
+        for f in &meta.frames {
+            assert!(f.x + f.w <= meta.size.0 && f.y + f.h <= meta.size.1);
+        }
+    }
+
+    #[test]
+    fn test_sheet_sizing_from_str() {
+        assert_eq!(SheetSizing::from_str_lossy("tight"), SheetSizing::Tight);
+        assert_eq!(SheetSizing::from_str_lossy("square-pot"), SheetSizing::SquarePot);
+        assert_eq!(SheetSizing::from_str_lossy("pot-width"), SheetSizing::PotWidth);
+        assert_eq!(SheetSizing::SquarePot.to_string(), "square-pot");
+    }
+
+    #[test]
+    fn test_trim_adjusts_pivot() {
+        // 6x6 with a 1px ring, centre anchor at (3, 3)
+        let sprite = ringed_sprite("ring", 6).with_anchor((3.0, 3.0));
+        let (_, meta) = SheetPacker::new(0).with_trim(true).pack(&[sprite]).unwrap();
+
+        let frame = &meta.frames[0];
+        assert_eq!((frame.w, frame.h), (4, 4));
+        assert_eq!(frame.pivot, Some((2.0, 2.0)));
+    }
+
+    #[test]
+    fn test_pivot_json() {
+        let sprites = vec![
+            ringed_sprite("ring", 6).with_anchor((3.0, 6.0)),
+            make_sprite("plain", 2, 2),
+        ];
+        let (_, meta) = SheetPacker::new(0).with_trim(true).pack(&sprites).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sheet.json");
+        write_sheet_json(&meta, &path).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        // Bottom-centre of the 6x6 source, not of the 4x4 trimmed frame
+        assert_eq!(parsed["frames"]["ring"]["pivot"], serde_json::json!({"x": 0.5, "y": 1.0}));
+        assert_eq!(meta.frames.iter().find(|f| f.name == "ring").unwrap().pivot, Some((2.0, 5.0)));
+        assert!(parsed["frames"]["plain"].get("pivot").is_none());
+    }
 }
@@ -0,0 +1,136 @@
+//! SVG output for rendered shapes.
+//!
+//! Emits one `<rect>` per horizontal run of identical colour, so output stays
+//! small while scaling crisply at any size.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::error::{PxError, Result};
+use crate::types::Colour;
+
+use super::RenderedShape;
+
+/// Write a rendered shape to an SVG file.
+///
+/// # Arguments
+///
+/// * `shape` - The rendered shape to write
+/// * `path` - Output file path
+/// * `scale` - Integer scale factor applied to the SVG's width/height
+pub fn write_svg(shape: &RenderedShape, path: &Path, scale: u32) -> Result<()> {
+    fs::write(path, svg_document(shape, scale)).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write SVG: {}", e),
+    })
+}
+
+/// Build the SVG document for a rendered shape.
+///
+/// The viewBox is in source pixels; `scale` only sets the display size.
+/// Fully transparent pixels are skipped, and partial alpha is emitted as
+/// `fill-opacity`.
+fn svg_document(shape: &RenderedShape, scale: u32) -> String {
+    let scale = scale.max(1) as usize;
+    let (width, height) = shape.size();
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+        width * scale,
+        height * scale,
+        width,
+        height
+    );
+
+    for (y, runs) in shape.rle_rows().iter().enumerate() {
+        let mut x = 0;
+        for &(colour, run) in runs {
+            if !colour.is_transparent() {
+                let _ = write!(
+                    svg,
+                    r#"  <rect x="{}" y="{}" width="{}" height="1" fill="{}""#,
+                    x,
+                    y,
+                    run,
+                    fill_hex(colour)
+                );
+                if !colour.is_opaque() {
+                    let _ = write!(svg, r#" fill-opacity="{:.3}""#, colour.a as f32 / 255.0);
+                }
+                svg.push_str("/>\n");
+            }
+
+            x += run;
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// RGB hex for an SVG fill (alpha is emitted separately).
+fn fill_hex(colour: Colour) -> String {
+    format!("#{:02X}{:02X}{:02X}", colour.r, colour.g, colour.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn rect_count(svg: &str) -> usize {
+        svg.matches("<rect").count()
+    }
+
+    #[test]
+    fn test_svg_checker_rects() {
+        let b = Colour::BLACK;
+        let w = Colour::WHITE;
+        let shape = RenderedShape::new("checker", vec![vec![b, w], vec![w, b]]);
+
+        let svg = svg_document(&shape, 1);
+        assert_eq!(rect_count(&svg), 4);
+        assert!(svg.contains(r##"fill="#000000""##));
+        assert!(svg.contains(r##"fill="#FFFFFF""##));
+    }
+
+    #[test]
+    fn test_svg_merges_runs() {
+        let shape = RenderedShape::new("bar", vec![vec![Colour::BLACK; 5]]);
+
+        let svg = svg_document(&shape, 4);
+        assert_eq!(rect_count(&svg), 1);
+        assert!(svg.contains(r#"width="5" height="1""#));
+        assert!(svg.contains(r#"width="20" height="4" viewBox="0 0 5 1""#));
+    }
+
+    #[test]
+    fn test_svg_skips_transparent() {
+        let shape = RenderedShape::new("empty", vec![vec![Colour::TRANSPARENT; 3]; 2]);
+
+        let svg = svg_document(&shape, 1);
+        assert_eq!(rect_count(&svg), 0);
+    }
+
+    #[test]
+    fn test_svg_fill_opacity() {
+        let shape = RenderedShape::new("ghost", vec![vec![Colour::new(255, 0, 0, 128), Colour::BLACK]]);
+
+        let svg = svg_document(&shape, 1);
+        assert!(svg.contains(r#"fill-opacity="0.502""#));
+        assert_eq!(svg.matches("fill-opacity").count(), 1);
+    }
+
+    #[test]
+    fn test_write_svg_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dot.svg");
+        let shape = RenderedShape::new("dot", vec![vec![Colour::BLACK]]);
+
+        write_svg(&shape, &path, 2).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("<svg"));
+    }
+}
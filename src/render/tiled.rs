@@ -0,0 +1,246 @@
+//! Tiled map export.
+//!
+//! Converts a tilemap (see [`MapRenderer::render_tilemap`](super::MapRenderer::render_tilemap))
+//! into a Tiled JSON map (`.tmj`) with one tile layer and an embedded
+//! tileset pointing at the packed tileset image.
+//!
+//! The tileset is written as an image collection whose tiles are
+//! sub-rectangles of the sheet, since shelf-packed sheets are not a uniform
+//! grid. Tile IDs are the sheet frame indices, so GID = frame index + 1 and
+//! 0 marks an empty cell.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::{PxError, Result};
+use crate::types::TilemapMetadata;
+
+use super::SheetMeta;
+
+/// First GID of the px tileset (Tiled reserves 0 for "no tile").
+const FIRST_GID: u32 = 1;
+
+/// A Tiled JSON map.
+#[derive(Debug, Serialize)]
+pub struct TiledMap {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub version: &'static str,
+    pub orientation: &'static str,
+    pub renderorder: &'static str,
+    pub infinite: bool,
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: usize,
+    pub tileheight: usize,
+    pub nextlayerid: u32,
+    pub nextobjectid: u32,
+    pub layers: Vec<TiledLayer>,
+    pub tilesets: Vec<TiledTileset>,
+}
+
+/// A Tiled tile layer.
+#[derive(Debug, Serialize)]
+pub struct TiledLayer {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub width: usize,
+    pub height: usize,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: f32,
+    pub visible: bool,
+    /// Row-major GIDs, 0 for empty cells.
+    pub data: Vec<u32>,
+}
+
+/// An embedded Tiled image-collection tileset.
+#[derive(Debug, Serialize)]
+pub struct TiledTileset {
+    pub firstgid: u32,
+    pub name: String,
+    pub columns: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    pub tilecount: usize,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    pub tiles: Vec<TiledTile>,
+}
+
+/// A tileset tile: a sub-rectangle of the tileset image.
+#[derive(Debug, Serialize)]
+pub struct TiledTile {
+    pub id: usize,
+    pub image: String,
+    pub imagewidth: u32,
+    pub imageheight: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Build a Tiled map from a tilemap and the tileset it indexes into.
+///
+/// Pixel sizes are multiplied by the tileset's scale so they match the
+/// written tileset image.
+pub fn tiled_map(tilemap: &TilemapMetadata, tileset: &SheetMeta) -> TiledMap {
+    let scale = tileset.scale.max(1);
+    let [cols, rows] = tilemap.grid;
+    let [cell_w, cell_h] = tilemap.cell_size;
+
+    let data = tilemap
+        .tiles
+        .iter()
+        .flatten()
+        .map(|&index| if index < 0 { 0 } else { index as u32 + FIRST_GID })
+        .collect();
+
+    let tiles: Vec<TiledTile> = tileset
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(id, frame)| TiledTile {
+            id,
+            image: tileset.image.clone(),
+            imagewidth: tileset.size.0 * scale,
+            imageheight: tileset.size.1 * scale,
+            x: frame.x * scale,
+            y: frame.y * scale,
+            width: frame.w * scale,
+            height: frame.h * scale,
+        })
+        .collect();
+
+    let tile_w = tileset.frames.iter().map(|f| f.w).max().unwrap_or(1) * scale;
+    let tile_h = tileset.frames.iter().map(|f| f.h).max().unwrap_or(1) * scale;
+
+    TiledMap {
+        kind: "map",
+        version: "1.10",
+        orientation: "orthogonal",
+        renderorder: "right-down",
+        infinite: false,
+        width: cols,
+        height: rows,
+        tilewidth: cell_w * scale as usize,
+        tileheight: cell_h * scale as usize,
+        nextlayerid: 2,
+        nextobjectid: 1,
+        layers: vec![TiledLayer {
+            id: 1,
+            name: tilemap.name.clone(),
+            kind: "tilelayer",
+            width: cols,
+            height: rows,
+            x: 0,
+            y: 0,
+            opacity: 1.0,
+            visible: true,
+            data,
+        }],
+        tilesets: vec![TiledTileset {
+            firstgid: FIRST_GID,
+            name: tileset.image.trim_end_matches(".png").to_string(),
+            columns: 0,
+            margin: 0,
+            spacing: 0,
+            tilecount: tiles.len(),
+            tilewidth: tile_w,
+            tileheight: tile_h,
+            tiles,
+        }],
+    }
+}
+
+/// Write a tilemap as a Tiled JSON map.
+pub fn write_tiled(tilemap: &TilemapMetadata, tileset: &SheetMeta, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(&tiled_map(tilemap, tileset)).map_err(|e| PxError::Build {
+        message: format!("Failed to serialize Tiled map: {}", e),
+        help: None,
+    })?;
+
+    fs::write(path, json).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write Tiled map: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{MapRenderer, RenderedShape, SheetPacker};
+    use crate::types::{Colour, Map};
+    use std::collections::HashMap;
+
+    fn tile(name: &str) -> RenderedShape {
+        RenderedShape::new(name, vec![vec![Colour::BLACK; 2]; 2])
+    }
+
+    fn setup() -> (TilemapMetadata, SheetMeta) {
+        let tiles = vec![tile("grass"), tile("wall")];
+        let (_, meta) = SheetPacker::new(0).pack(&tiles).unwrap();
+
+        let mut renderer = MapRenderer::new();
+        for t in tiles {
+            renderer.add_rendered(t);
+        }
+
+        let mut legend = HashMap::new();
+        legend.insert('#', "wall".to_string());
+        legend.insert('.', "grass".to_string());
+        legend.insert('_', "empty".to_string());
+        let map = Map::new("level", vec![], vec![vec!['#', '#', '#'], vec!['.', '_', '#']], legend);
+
+        let tilemap = renderer.render_tilemap(&map, &meta).unwrap();
+        (tilemap, meta)
+    }
+
+    #[test]
+    fn test_tiled_layer_matches_grid() {
+        let (tilemap, meta) = setup();
+        let tiled = tiled_map(&tilemap, &meta);
+
+        assert_eq!((tiled.width, tiled.height), (3, 2));
+        let layer = &tiled.layers[0];
+        assert_eq!((layer.width, layer.height), (3, 2));
+        assert_eq!(layer.data.len(), 6);
+        assert_eq!((tiled.tilewidth, tiled.tileheight), (2, 2));
+    }
+
+    #[test]
+    fn test_tiled_gids_consistent() {
+        let (tilemap, meta) = setup();
+        let tiled = tiled_map(&tilemap, &meta);
+        let data = &tiled.layers[0].data;
+
+        // Same glyph, same GID; empty cells are 0
+        let wall = data[0];
+        assert!(wall >= FIRST_GID);
+        assert_eq!(&data[..3], &[wall, wall, wall]);
+        assert_eq!(data[5], wall);
+        assert_eq!(data[4], 0);
+        assert_ne!(data[3], wall);
+
+        // GIDs point at the matching tileset tile
+        let wall_tile = &tiled.tilesets[0].tiles[(wall - FIRST_GID) as usize];
+        let frame = meta.frames.iter().find(|f| f.name == "wall").unwrap();
+        assert_eq!((wall_tile.x, wall_tile.y), (frame.x, frame.y));
+    }
+
+    #[test]
+    fn test_tiled_json_shape() {
+        let (tilemap, meta) = setup();
+        let value = serde_json::to_value(tiled_map(&tilemap, &meta)).unwrap();
+
+        assert_eq!(value["type"], "map");
+        assert_eq!(value["layers"][0]["type"], "tilelayer");
+        assert_eq!(value["tilesets"][0]["firstgid"], 1);
+        assert_eq!(value["tilesets"][0]["tilecount"], 2);
+    }
+}
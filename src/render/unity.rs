@@ -0,0 +1,223 @@
+//! Unity sprite sheet import settings.
+//!
+//! Writes a `.png.meta` file beside a packed sheet so Unity imports it as a
+//! multiple-sprite texture, already sliced into one sprite per frame.
+//!
+//! Unity's texture origin is bottom-left, so each frame's y is flipped
+//! against the sheet height. GUIDs and sprite IDs are hashed from the sheet
+//! and frame names, so rebuilding the sheet keeps references in scenes.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::{PxError, Result};
+
+use super::{Frame, SheetMeta};
+
+/// Unity's custom sprite alignment, which uses the `pivot` field.
+const ALIGNMENT_CUSTOM: u8 = 9;
+
+/// A `.meta` file for a texture.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnityMeta {
+    pub file_format_version: u32,
+    pub guid: String,
+    #[serde(rename = "TextureImporter")]
+    pub texture_importer: TextureImporter,
+}
+
+/// Import settings for a sliced sprite sheet.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextureImporter {
+    pub serialized_version: u32,
+    /// 8 = Sprite (2D and UI).
+    pub texture_type: u32,
+    /// 2 = Multiple.
+    pub sprite_mode: u32,
+    pub sprite_pixels_to_units: u32,
+    /// 0 = Point, for crisp pixel art.
+    pub filter_mode: u32,
+    pub alpha_is_transparency: u32,
+    pub sprite_sheet: SpriteSheet,
+}
+
+/// The slices of a sprite sheet.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpriteSheet {
+    pub serialized_version: u32,
+    pub sprites: Vec<UnitySprite>,
+}
+
+/// One sliced sprite.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitySprite {
+    pub serialized_version: u32,
+    pub name: String,
+    pub rect: UnityRect,
+    /// 0 = Center, or [`ALIGNMENT_CUSTOM`] for `pivot`.
+    pub alignment: u8,
+    pub pivot: UnityPoint,
+    #[serde(rename = "spriteID")]
+    pub sprite_id: String,
+}
+
+/// A rect in texture pixels, from the bottom-left.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnityRect {
+    pub serialized_version: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A normalized point, from the bottom-left.
+#[derive(Debug, Serialize)]
+pub struct UnityPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Build the Unity import settings for a packed sheet.
+///
+/// Rects are in scaled sheet pixels, one sprite per frame.
+pub fn unity_meta(meta: &SheetMeta) -> UnityMeta {
+    let s = meta.scale.max(1);
+    let sheet_h = meta.size.1 * s;
+
+    let sprites = meta
+        .frames
+        .iter()
+        .map(|frame| {
+            let (alignment, pivot) = match pivot(frame) {
+                Some(pivot) => (ALIGNMENT_CUSTOM, pivot),
+                None => (0, UnityPoint { x: 0.5, y: 0.5 }),
+            };
+            UnitySprite {
+                serialized_version: 2,
+                name: frame.name.clone(),
+                rect: UnityRect {
+                    serialized_version: 2,
+                    x: frame.x * s,
+                    y: sheet_h - (frame.y + frame.h) * s,
+                    width: frame.w * s,
+                    height: frame.h * s,
+                },
+                alignment,
+                pivot,
+                sprite_id: guid(&format!("{}#{}", meta.image, frame.name)),
+            }
+        })
+        .collect();
+
+    UnityMeta {
+        file_format_version: 2,
+        guid: guid(&meta.image),
+        texture_importer: TextureImporter {
+            serialized_version: 12,
+            texture_type: 8,
+            sprite_mode: 2,
+            sprite_pixels_to_units: 100,
+            filter_mode: 0,
+            alpha_is_transparency: 1,
+            sprite_sheet: SpriteSheet {
+                serialized_version: 2,
+                sprites,
+            },
+        },
+    }
+}
+
+/// Write the Unity import settings for a packed sheet to `path`
+/// (conventionally `{image}.meta`).
+pub fn write_unity_meta(meta: &SheetMeta, path: &Path) -> Result<()> {
+    let yaml = serde_yaml::to_string(&unity_meta(meta)).map_err(|e| PxError::Build {
+        message: format!("Failed to serialize Unity meta: {}", e),
+        help: None,
+    })?;
+    fs::write(path, yaml).map_err(|e| PxError::Io {
+        path: path.to_path_buf(),
+        message: format!("Failed to write Unity meta: {}", e),
+    })
+}
+
+/// A frame's pivot, normalized and flipped to Unity's bottom-left origin.
+fn pivot(frame: &Frame) -> Option<UnityPoint> {
+    let (px, py) = frame.pivot?;
+    Some(UnityPoint {
+        x: px / frame.w.max(1) as f32,
+        y: 1.0 - py / frame.h.max(1) as f32,
+    })
+}
+
+/// A stable 32-digit hex ID for `key` (two 64-bit FNV-1a hashes).
+fn guid(key: &str) -> String {
+    let fnv = |seed: u64| {
+        key.bytes().fold(seed, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+    };
+    format!("{:016x}{:016x}", fnv(0xcbf2_9ce4_8422_2325), fnv(0x6c62_272e_07bb_0142))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(name: &str, x: u32, y: u32, w: u32, h: u32) -> Frame {
+        Frame { name: name.to_string(), x, y, w, h, trim: None, pivot: None, cell: None }
+    }
+
+    #[test]
+    fn test_unity_meta_flips_y() {
+        let meta = SheetMeta {
+            frames: vec![frame("hero", 0, 0, 4, 4), frame("coin", 4, 6, 2, 2)],
+            image: "sheet.png".to_string(),
+            size: (8, 8),
+            scale: 1,
+        };
+        let yaml = serde_yaml::to_string(&unity_meta(&meta)).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+
+        let sprites = &parsed["TextureImporter"]["spriteSheet"]["sprites"];
+        assert_eq!(sprites.as_sequence().unwrap().len(), 2);
+        // Top-left of an 8-tall sheet sits 4 up from the bottom
+        assert_eq!(sprites[0]["name"], "hero");
+        assert_eq!(sprites[0]["rect"]["x"], 0);
+        assert_eq!(sprites[0]["rect"]["y"], 4);
+        assert_eq!(sprites[0]["rect"]["height"], 4);
+        // Bottom row lands on y = 0
+        assert_eq!(sprites[1]["rect"]["y"], 0);
+        assert_eq!(parsed["TextureImporter"]["spriteMode"], 2);
+        assert_eq!(parsed["guid"].as_str().unwrap().len(), 32);
+        assert_ne!(sprites[0]["spriteID"], sprites[1]["spriteID"]);
+    }
+
+    #[test]
+    fn test_unity_rect_y_is_height_minus_bottom_edge() {
+        // Top-left y = 0 of an H-tall sheet maps to H - (y + h), in scaled pixels
+        let mut centred = frame("gem", 2, 0, 2, 3);
+        centred.pivot = Some((1.0, 3.0));
+        let meta = SheetMeta {
+            frames: vec![centred, frame("key", 0, 3, 2, 2)],
+            image: "sheet.png".to_string(),
+            size: (4, 5),
+            scale: 2,
+        };
+        let unity = unity_meta(&meta);
+        let sprites = &unity.texture_importer.sprite_sheet.sprites;
+
+        let h = 5 * 2;
+        assert_eq!(sprites[0].rect.y, h - 3 * 2);
+        assert_eq!((sprites[0].rect.x, sprites[0].rect.width, sprites[0].rect.height), (4, 4, 6));
+        assert_eq!(sprites[1].rect.y, h - (3 + 2) * 2);
+        // A bottom-centre pivot flips to y = 0
+        assert_eq!(sprites[0].alignment, ALIGNMENT_CUSTOM);
+        assert_eq!((sprites[0].pivot.x, sprites[0].pivot.y), (0.5, 0.0));
+    }
+}
@@ -0,0 +1,68 @@
+//! WebP output for rendered shapes.
+//!
+//! Encodes losslessly, so hard pixel edges and exact palette colours survive
+//! while files stay smaller than PNG.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use image::codecs::webp::WebPEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+
+use crate::error::{PxError, Result};
+
+use super::{scale_pixels, RenderedShape};
+
+/// Write a rendered shape to a lossless WebP file.
+///
+/// # Arguments
+///
+/// * `rendered` - The rendered shape to write
+/// * `path` - Output file path
+/// * `scale` - Integer scale factor (1 = no scaling)
+pub fn write_webp(rendered: &RenderedShape, path: &Path, scale: u32) -> Result<()> {
+    let scale = scale.max(1);
+    let width = rendered.width() as u32 * scale;
+    let height = rendered.height() as u32 * scale;
+
+    let bytes: Vec<u8> = scale_pixels(rendered.pixels(), scale)
+        .iter()
+        .flatten()
+        .flat_map(|colour| colour.to_rgba())
+        .collect();
+
+    let io_error = |message: String| PxError::Io {
+        path: path.to_path_buf(),
+        message,
+    };
+    let file = File::create(path).map_err(|e| io_error(format!("Failed to create WebP: {}", e)))?;
+    WebPEncoder::new_lossless(BufWriter::new(file))
+        .write_image(&bytes, width, height, ExtendedColorType::Rgba8)
+        .map_err(|e| io_error(format!("Failed to write WebP: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Colour;
+    use image::{GenericImageView, Rgba};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_webp_scaled_lossless() {
+        let red = Colour::rgb(255, 0, 0);
+        let clear = Colour::TRANSPARENT;
+        let rendered = RenderedShape::new("dot", vec![vec![red, clear, red], vec![clear, red, clear]]);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dot.webp");
+        write_webp(&rendered, &path, 2).unwrap();
+
+        let img = image::open(&path).unwrap();
+        assert_eq!(img.dimensions(), (6, 4));
+        assert_eq!(img.get_pixel(1, 1), Rgba([255, 0, 0, 255]));
+        assert_eq!(img.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+        assert_eq!(img.get_pixel(2, 0)[3], 0);
+    }
+}
@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use crate::types::Colour;
 
 /// A brush definition - a tiling pattern with positional colour tokens.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Brush {
     /// Brush name (unique identifier).
     pub name: String,
@@ -55,6 +55,11 @@ impl Brush {
         self.pattern.is_empty() || self.width() == 0
     }
 
+    /// Content key for dedupe and caching: the pattern alone, without the name.
+    pub fn content_key(&self) -> &[Vec<char>] {
+        &self.pattern
+    }
+
     /// Get a token at the given position (with wrapping for tiling).
     pub fn get(&self, x: usize, y: usize) -> Option<char> {
         if self.is_empty() {
@@ -175,6 +180,16 @@ impl BuiltinBrushes {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_brush_content_key_ignores_name() {
+        let a = Brush::new("checker", vec![vec!['A', 'B'], vec!['B', 'A']]);
+        let b = Brush::new("check-2", vec![vec!['A', 'B'], vec!['B', 'A']]);
+
+        assert_eq!(a.content_key(), b.content_key());
+        assert_ne!(a, b);
+        assert_ne!(a.content_key(), Brush::single("solid", 'A').content_key());
+    }
+
     #[test]
     fn test_brush_single() {
         let brush = Brush::single("test", 'A');
@@ -3,6 +3,8 @@
 use std::fmt;
 use std::str::FromStr;
 
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::error::{PxError, Result};
 
 /// An RGBA colour value.
@@ -86,6 +88,16 @@ impl Colour {
         }
     }
 
+    /// Hex form that [`from_hex`](Self::from_hex) reads back: `#RRGGBB`
+    /// when opaque, `#RRGGBBAA` otherwise.
+    pub fn to_hex(self) -> String {
+        if self.a == 255 {
+            format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
+        }
+    }
+
     /// Convert to RGBA tuple.
     pub fn to_rgba(self) -> [u8; 4] {
         [self.r, self.g, self.b, self.a]
@@ -100,6 +112,33 @@ impl Colour {
     pub fn is_opaque(self) -> bool {
         self.a == 255
     }
+
+    /// Check if the colour is a shade of grey (equal red, green and blue).
+    pub fn is_grayscale(self) -> bool {
+        self.r == self.g && self.g == self.b
+    }
+
+    /// Composite this colour over `below` (Porter-Duff source-over).
+    pub fn over(self, below: Colour) -> Colour {
+        if self.is_opaque() || below.is_transparent() {
+            return self;
+        }
+        if self.is_transparent() {
+            return below;
+        }
+
+        let src_a = self.a as f32 / 255.0;
+        let dst_a = below.a as f32 / 255.0 * (1.0 - src_a);
+        let out_a = src_a + dst_a;
+        let channel = |s: u8, d: u8| ((s as f32 * src_a + d as f32 * dst_a) / out_a).round() as u8;
+
+        Colour::new(
+            channel(self.r, below.r),
+            channel(self.g, below.g),
+            channel(self.b, below.b),
+            (out_a * 255.0).round() as u8,
+        )
+    }
 }
 
 impl FromStr for Colour {
@@ -112,11 +151,22 @@ impl FromStr for Colour {
 
 impl fmt::Display for Colour {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.a == 255 {
-            write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
-        } else {
-            write!(f, "#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
-        }
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Serializes as a hex string, the same form `Display` prints.
+impl Serialize for Colour {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from any hex form `from_hex` accepts.
+impl<'de> Deserialize<'de> for Colour {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Colour::from_hex(&hex).map_err(de::Error::custom)
     }
 }
 
@@ -191,6 +241,18 @@ mod tests {
         assert_eq!(format!("{}", Colour::new(255, 0, 0, 128)), "#FF000080");
     }
 
+    #[test]
+    fn test_to_hex_round_trip() {
+        let opaque = Colour::rgb(0x1a, 0x1a, 0x2e);
+        assert_eq!(opaque.to_hex(), "#1A1A2E");
+        assert_eq!(Colour::from_hex(&opaque.to_hex()).unwrap(), opaque);
+
+        let glass = Colour::new(255, 0, 0, 128);
+        assert_eq!(glass.to_hex(), "#FF000080");
+        assert_eq!(Colour::from_hex(&glass.to_hex()).unwrap(), glass);
+        assert_eq!(Colour::TRANSPARENT.to_hex(), "#00000000");
+    }
+
     #[test]
     fn test_constants() {
         assert_eq!(Colour::BLACK, Colour::rgb(0, 0, 0));
@@ -198,4 +260,37 @@ mod tests {
         assert!(Colour::TRANSPARENT.is_transparent());
         assert!(Colour::BLACK.is_opaque());
     }
+
+    #[test]
+    fn test_is_grayscale() {
+        assert!(Colour::rgb(128, 128, 128).is_grayscale());
+        assert!(Colour::BLACK.is_grayscale());
+        assert!(Colour::new(40, 40, 40, 100).is_grayscale());
+        assert!(!Colour::rgb(128, 128, 129).is_grayscale());
+        assert!(!Colour::MAGENTA.is_grayscale());
+    }
+
+    #[test]
+    fn test_over() {
+        let red = Colour::rgb(255, 0, 0);
+        let half_blue = Colour::new(0, 0, 255, 128);
+
+        assert_eq!(red.over(Colour::WHITE), red);
+        assert_eq!(Colour::TRANSPARENT.over(red), red);
+        assert_eq!(half_blue.over(Colour::TRANSPARENT), half_blue);
+        assert_eq!(half_blue.over(red), Colour::new(127, 0, 128, 255));
+        assert_eq!(half_blue.over(half_blue).a, 192);
+    }
+
+    #[test]
+    fn test_serde_hex_round_trip() {
+        let colour = Colour::new(255, 128, 0, 64);
+        let json = serde_json::to_string(&colour).unwrap();
+        assert_eq!(json, "\"#FF800040\"");
+        assert_eq!(serde_json::from_str::<Colour>(&json).unwrap(), colour);
+
+        assert_eq!(serde_json::to_string(&Colour::rgb(0, 0, 255)).unwrap(), "\"#0000FF\"");
+        assert_eq!(serde_json::from_str::<Colour>("\"#fff\"").unwrap(), Colour::WHITE);
+        assert!(serde_json::from_str::<Colour>("\"tomato\"").is_err());
+    }
 }
@@ -330,7 +330,7 @@ fn adjust_saturation(colour: Colour, percent: f32) -> Colour {
 }
 
 /// Mix two colours by a factor (0.0 = first colour, 1.0 = second colour).
-fn mix_colours(a: Colour, b: Colour, factor: f32) -> Colour {
+pub(crate) fn mix_colours(a: Colour, b: Colour, factor: f32) -> Colour {
     let factor = factor.clamp(0.0, 1.0);
     let inv = 1.0 - factor;
 
@@ -0,0 +1,79 @@
+//! Name-based `inherits` resolution shared by shaders and targets.
+
+use std::path::PathBuf;
+
+/// A parsed definition that can inherit from another of its kind by name.
+pub(crate) trait Inherits: Clone {
+    /// What the builder builds.
+    type Built;
+
+    /// Kind of definition, lowercase, for error messages.
+    const KIND: &'static str;
+
+    /// Name of the definition.
+    fn name(&self) -> &str;
+
+    /// Name of the definition this one inherits from, if any.
+    fn parent_name(&self) -> Option<&str>;
+
+    /// The builtin called `name`, if there is one.
+    fn builtin(name: &str) -> Option<Self::Built>;
+
+    /// Build on top of the resolved `parent`.
+    fn build_inherited(self, parent: Option<&Self::Built>) -> Result<Self::Built, &'static str>;
+}
+
+/// Resolve `name`, following `inherits` chains.
+///
+/// Looks in `defined` first, then the builtins; builtins can also be
+/// parents. Returns `Ok(None)` if the name is unknown, and an error for
+/// unknown parents or circular inheritance.
+pub(crate) fn resolve_inherited<B: Inherits>(name: &str, defined: &[B]) -> Result<Option<B::Built>, String> {
+    resolve_chain(name, defined, &mut Vec::new())
+}
+
+fn resolve_chain<B: Inherits>(name: &str, defined: &[B], chain: &mut Vec<String>) -> Result<Option<B::Built>, String> {
+    if chain.iter().any(|n| n == name) {
+        chain.push(name.to_string());
+        return Err(format!("Circular {} inheritance: {}", B::KIND, chain.join(" -> ")));
+    }
+
+    let Some(builder) = defined.iter().find(|b| b.name() == name) else {
+        return Ok(B::builtin(name));
+    };
+
+    chain.push(name.to_string());
+    let parent = match builder.parent_name() {
+        Some(parent_name) => Some(resolve_chain(parent_name, defined, chain)?.ok_or_else(|| {
+            format!("{} '{}' inherits unknown {} '{}'", capitalized(B::KIND), name, B::KIND, parent_name)
+        })?),
+        None => None,
+    };
+    chain.pop();
+
+    builder
+        .clone()
+        .build_inherited(parent.as_ref())
+        .map(Some)
+        .map_err(|e| format!("{} '{}': {}", capitalized(B::KIND), name, e))
+}
+
+/// Build every definition in `pending`, resolving inheritance against all
+/// of them, then builtins. Failures are collected into `errors`, prefixed
+/// with the definition's file.
+pub(crate) fn build_inherited<B: Inherits>(pending: &[(PathBuf, B)], errors: &mut Vec<String>) -> Vec<B::Built> {
+    let defined: Vec<B> = pending.iter().map(|(_, b)| b.clone()).collect();
+    let mut built = Vec::new();
+    for (path, builder) in pending {
+        match resolve_inherited(builder.name(), &defined) {
+            Ok(item) => built.extend(item),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    built
+}
+
+fn capitalized(kind: &str) -> String {
+    let mut chars = kind.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
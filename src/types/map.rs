@@ -3,7 +3,8 @@
 //! Maps are structurally identical to prefabs (ASCII grid + legend referencing
 //! shapes/prefabs) but semantically distinct: they represent level layouts
 //! rather than reusable components. The key addition is instance metadata
-//! generation for JSON output.
+//! generation for JSON output, and maps can stack several grids as layers
+//! (see [`Map::with_layers`]).
 //!
 //! # Example
 //!
@@ -30,6 +31,8 @@ use std::collections::HashMap;
 
 use serde::Serialize;
 
+use super::shape::EmbeddedSource;
+
 /// A map definition - an ASCII placement grid representing a level layout.
 #[derive(Debug, Clone)]
 pub struct Map {
@@ -39,8 +42,8 @@ pub struct Map {
     /// Tags for metadata.
     pub tags: Vec<String>,
 
-    /// ASCII placement grid (row-major: grid[y][x]).
-    grid: Vec<Vec<char>>,
+    /// ASCII placement grids, bottom layer first (row-major: layer[y][x]).
+    layers: Vec<Vec<Vec<char>>>,
 
     /// Legend mappings (glyph -> shape/prefab name).
     legend: HashMap<char, String>,
@@ -60,7 +63,7 @@ impl Map {
         Self {
             name: name.into(),
             tags,
-            grid,
+            layers: vec![grid],
             legend,
             scale: None,
         }
@@ -77,20 +80,34 @@ impl Map {
         Self {
             name: name.into(),
             tags,
-            grid,
+            layers: vec![grid],
             legend,
             scale,
         }
     }
 
-    /// Get the width of the map in cells.
+    /// Replace the grid with stacked layers, bottom first. Each layer is
+    /// drawn over the ones below, so its unmapped and `empty` cells let
+    /// them show through.
+    pub fn with_layers(mut self, layers: Vec<Vec<Vec<char>>>) -> Self {
+        if !layers.is_empty() {
+            self.layers = layers;
+        }
+        self
+    }
+
+    /// Get the width of the map in cells (of its widest layer).
     pub fn width(&self) -> usize {
-        self.grid.first().map_or(0, |row| row.len())
+        self.layers
+            .iter()
+            .map(|grid| grid.first().map_or(0, |row| row.len()))
+            .max()
+            .unwrap_or(0)
     }
 
-    /// Get the height of the map in cells.
+    /// Get the height of the map in cells (of its tallest layer).
     pub fn height(&self) -> usize {
-        self.grid.len()
+        self.layers.iter().map(|grid| grid.len()).max().unwrap_or(0)
     }
 
     /// Get the dimensions as (width, height).
@@ -100,17 +117,22 @@ impl Map {
 
     /// Check if the map is empty.
     pub fn is_empty(&self) -> bool {
-        self.grid.is_empty() || self.width() == 0
+        self.height() == 0 || self.width() == 0
     }
 
-    /// Get a character at the given position.
+    /// Get a character at the given position in the bottom layer.
     pub fn get(&self, x: usize, y: usize) -> Option<char> {
-        self.grid.get(y).and_then(|row| row.get(x)).copied()
+        self.grid().get(y).and_then(|row| row.get(x)).copied()
     }
 
-    /// Get a reference to the grid.
+    /// Get a reference to the bottom layer's grid.
     pub fn grid(&self) -> &[Vec<char>] {
-        &self.grid
+        &self.layers[0]
+    }
+
+    /// Get every layer's grid, bottom first.
+    pub fn layers(&self) -> &[Vec<Vec<char>>] {
+        &self.layers
     }
 
     /// Get the referenced name for a glyph.
@@ -128,20 +150,24 @@ impl Map {
         self.legend.contains_key(&glyph)
     }
 
-    /// Iterate over all cells with their positions.
+    /// Iterate over all cells with their positions, layer by layer from
+    /// the bottom.
     pub fn iter_cells(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
-        self.grid.iter().enumerate().flat_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .map(move |(x, &c)| (x, y, c))
+        self.layers.iter().flat_map(|grid| {
+            grid.iter().enumerate().flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, &c)| (x, y, c))
+            })
         })
     }
 
     /// Get all unique glyphs used in this map.
     pub fn glyphs(&self) -> Vec<char> {
         let mut glyphs: Vec<char> = self
-            .grid
+            .layers
             .iter()
+            .flatten()
             .flat_map(|row| row.iter())
             .copied()
             .collect();
@@ -176,6 +202,10 @@ pub struct MapMetadata {
 
     /// Instances placed on the map.
     pub shapes: Vec<MapInstance>,
+
+    /// Grid layers and legend, with `--embed-source`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<EmbeddedSource>,
 }
 
 /// A shape/prefab instance placed on the map.
@@ -191,6 +221,25 @@ pub struct MapInstance {
     pub positions: Vec<[usize; 2]>,
 }
 
+/// A map exported as a tile index grid into a shared tileset sheet.
+#[derive(Debug, Clone, Serialize)]
+pub struct TilemapMetadata {
+    /// Map name.
+    pub name: String,
+
+    /// Cell dimensions [cols, rows].
+    pub grid: [usize; 2],
+
+    /// Pixel size of each cell [width, height].
+    pub cell_size: [usize; 2],
+
+    /// Tileset image the indices refer to.
+    pub tileset: String,
+
+    /// Frame index per cell (row-major), or -1 for empty cells.
+    pub tiles: Vec<Vec<i32>>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -323,6 +372,7 @@ mod tests {
                     positions: vec![[0, 0], [8, 0], [16, 0]],
                 },
             ],
+            source: None,
         };
 
         let json = serde_json::to_string(&metadata).unwrap();
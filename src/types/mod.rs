@@ -13,6 +13,7 @@
 mod brush;
 mod colour;
 mod expr;
+mod inherit;
 mod map;
 mod palette;
 mod prefab;
@@ -24,10 +25,12 @@ mod target;
 pub use brush::{Brush, BuiltinBrushes};
 pub use colour::Colour;
 pub use expr::{ColourExpr, ExprEvaluator};
-pub use map::{Map, MapInstance, MapMetadata};
+pub(crate) use expr::mix_colours;
+pub(crate) use inherit::build_inherited;
+pub use map::{Map, MapInstance, MapMetadata, TilemapMetadata};
 pub use palette::{Palette, PaletteBuilder};
 pub use prefab::{Prefab, PrefabInstance, PrefabMetadata};
 pub use shader::{BuiltinShaders, Effect, EffectParam, Shader, ShaderBuilder};
-pub use shape::{LegendEntry, Shape, ShapeMetadata};
+pub use shape::{Anchor, CellSize, EmbeddedSource, GradientDirection, LegendEntry, NineSlice, Shape, ShapeKey, ShapeMetadata};
 pub use stamp::{BuiltinStamps, PixelToken, Stamp};
 pub use target::{BuiltinTargets, PaletteMode, SheetConfig, Target, TargetBuilder};
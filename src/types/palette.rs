@@ -4,7 +4,7 @@ use std::collections::{HashMap, HashSet};
 
 use crate::error::{PxError, Result};
 
-use super::expr::{ColourExpr, ExprEvaluator};
+use super::expr::{mix_colours, ColourExpr, ExprEvaluator};
 use super::Colour;
 
 /// A collection of named colours with optional variants.
@@ -18,6 +18,15 @@ pub struct Palette {
 
     /// Variant overrides (variant_name -> colour_name -> colour)
     variants: HashMap<String, HashMap<String, Colour>>,
+
+    /// Name of the palette this one inherits from, if any
+    parent: Option<String>,
+
+    /// Scheme the palette belongs to (`scheme:` in frontmatter), if any
+    scheme: Option<String>,
+
+    /// Aliases (`$primary: =blue`): alias name -> the colour it names
+    aliases: HashMap<String, String>,
 }
 
 impl Palette {
@@ -27,6 +36,9 @@ impl Palette {
             name: name.into(),
             colours: HashMap::new(),
             variants: HashMap::new(),
+            parent: None,
+            scheme: None,
+            aliases: HashMap::new(),
         }
     }
 
@@ -51,14 +63,40 @@ impl Palette {
     pub fn get_with_variant(&self, name: &str, variant: &str) -> Option<Colour> {
         let name = name.strip_prefix('$').unwrap_or(name);
 
-        // Check variant first, fall back to base
+        // Check variant first; an alias the variant doesn't override
+        // follows its target's variant colour, then fall back to base
         self.variants
             .get(variant)
             .and_then(|v| v.get(name))
             .copied()
+            .or_else(|| self.aliases.get(name).and_then(|target| self.get_with_variant(target, variant)))
             .or_else(|| self.colours.get(name).copied())
     }
 
+    /// The colour an alias names (`$primary: =blue` gives `blue` for
+    /// `primary`), or `None` if `name` isn't an alias.
+    pub fn alias_target(&self, name: &str) -> Option<&str> {
+        let name = name.strip_prefix('$').unwrap_or(name);
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// Every alias and the colour it names, in alias name order.
+    pub fn aliases(&self) -> Vec<(&str, &str)> {
+        let mut aliases: Vec<(&str, &str)> = self.aliases.iter().map(|(a, t)| (a.as_str(), t.as_str())).collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// Name of the parent palette, if this one inherits.
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    /// Scheme the palette belongs to, if it names one.
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
     /// Get all colour names.
     pub fn colour_names(&self) -> impl Iterator<Item = &str> {
         self.colours.keys().map(|s| s.as_str())
@@ -84,6 +122,11 @@ impl Palette {
         self.colours.is_empty()
     }
 
+    /// Check if every base colour is a shade of grey.
+    pub fn is_grayscale(&self) -> bool {
+        self.colours.values().all(|c| c.is_grayscale())
+    }
+
     /// Insert a resolved colour.
     pub(crate) fn insert(&mut self, name: String, colour: Colour) {
         self.colours.insert(name, colour);
@@ -97,12 +140,106 @@ impl Palette {
             .insert(name, colour);
     }
 
+    /// Blend this palette towards another (0.0 = this palette, 1.0 = `other`).
+    ///
+    /// Colours named in both palettes are mixed; colours in only one pass
+    /// through unchanged. Variants are blended the same way.
+    pub fn blend(&self, other: &Palette, factor: f32) -> Palette {
+        let mut blended = Palette::new(format!("{}-{}", self.name, other.name));
+        blended.colours = blend_colours(&self.colours, &other.colours, factor);
+
+        let variant_names: HashSet<&String> = self.variants.keys().chain(other.variants.keys()).collect();
+        let empty = HashMap::new();
+        for name in variant_names {
+            let a = self.variants.get(name).unwrap_or(&empty);
+            let b = other.variants.get(name).unwrap_or(&empty);
+            blended.variants.insert(name.clone(), blend_colours(a, b, factor));
+        }
+        blended.aliases = other.aliases.clone();
+        blended.aliases.extend(self.aliases.clone());
+
+        blended
+    }
+
+    /// Resolve a variant into a standalone palette named `{name}-{variant}`.
+    ///
+    /// Every base colour is included, with the variant's overrides applied;
+    /// the result has no variants of its own. `None` resolves the base colours
+    /// under the palette's own name.
+    pub fn resolve_variant(&self, variant: Option<&str>) -> Palette {
+        let name = match variant {
+            Some(variant) => format!("{}-{}", self.name, variant),
+            None => self.name.clone(),
+        };
+        let mut resolved = Palette::new(name);
+        for name in self.colours.keys() {
+            let colour = match variant {
+                Some(variant) => self.get_with_variant(name, variant),
+                None => self.get(name),
+            };
+            if let Some(colour) = colour {
+                resolved.colours.insert(name.clone(), colour);
+            }
+        }
+        resolved.aliases = self.aliases.clone();
+        resolved
+    }
+
+    /// Render the palette as `.palette.md` source, with colours in name order.
+    pub fn to_source(&self) -> String {
+        let mut source = format!("---\nname: {}\n", self.name);
+        if let Some(scheme) = &self.scheme {
+            source.push_str(&format!("scheme: {}\n", scheme));
+        }
+        source.push_str("---\n\n");
+
+        let mut names: Vec<&String> = self.colours.keys().collect();
+        names.sort();
+        for name in names {
+            match self.aliases.get(name) {
+                Some(target) => source.push_str(&format!("${}: ={}\n", name, target)),
+                None => source.push_str(&format!("${}: {}\n", name, self.colours[name].to_hex())),
+            }
+        }
+
+        let mut variants: Vec<&String> = self.variants.keys().collect();
+        variants.sort();
+        for variant in variants {
+            source.push_str(&format!("\n@{}:\n", variant));
+            let colours = &self.variants[variant];
+            let mut names: Vec<&String> = colours.keys().collect();
+            names.sort();
+            for name in names {
+                source.push_str(&format!("  ${}: {}\n", name, colours[name].to_hex()));
+            }
+        }
+
+        source
+    }
+
+    /// Render the base colours as a GIMP `.gpl` palette, in name order.
+    pub fn to_gpl(&self) -> String {
+        let mut gpl = format!("GIMP Palette\nName: {}\nColumns: 0\n#\n", self.name);
+
+        let mut names: Vec<&String> = self.colours.keys().collect();
+        names.sort();
+        for name in names {
+            let c = self.colours[name];
+            gpl.push_str(&format!("{:3} {:3} {:3}\t{}\n", c.r, c.g, c.b, name));
+        }
+
+        gpl
+    }
+
     /// Merge another palette into this one (for inheritance).
     pub fn merge_from(&mut self, other: &Palette) {
         // Copy base colours (don't overwrite existing)
         for (name, colour) in &other.colours {
             self.colours.entry(name.clone()).or_insert(*colour);
         }
+        for (name, target) in &other.aliases {
+            self.aliases.entry(name.clone()).or_insert_with(|| target.clone());
+        }
 
         // Copy variants (don't overwrite existing)
         for (variant_name, colours) in &other.variants {
@@ -114,6 +251,23 @@ impl Palette {
     }
 }
 
+/// Mix colours present in both maps; the rest pass through.
+fn blend_colours(
+    a: &HashMap<String, Colour>,
+    b: &HashMap<String, Colour>,
+    factor: f32,
+) -> HashMap<String, Colour> {
+    let mut blended = b.clone();
+    for (name, &colour) in a {
+        let mixed = match b.get(name) {
+            Some(&other) => mix_colours(colour, other, factor),
+            None => colour,
+        };
+        blended.insert(name.clone(), mixed);
+    }
+    blended
+}
+
 impl Default for Palette {
     fn default() -> Self {
         Self::default_palette()
@@ -121,7 +275,7 @@ impl Default for Palette {
 }
 
 /// Builder for constructing palettes from parsed definitions.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PaletteBuilder {
     name: String,
     /// Unresolved definitions: name -> value (hex or reference)
@@ -130,6 +284,8 @@ pub struct PaletteBuilder {
     variant_defs: HashMap<String, Vec<(String, ColourDef)>>,
     /// Parent palette name for inheritance
     inherits: Option<String>,
+    /// Scheme the palette belongs to
+    scheme: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -138,6 +294,8 @@ enum ColourDef {
     Hex(String),
     /// A reference to another colour: $dark
     Reference(String),
+    /// An alias for another colour that follows it into variants: =blue
+    Alias(String),
     /// A colour expression: darken($gold, 20%)
     Expression(ColourExpr),
 }
@@ -150,6 +308,7 @@ impl PaletteBuilder {
             definitions: Vec::new(),
             variant_defs: HashMap::new(),
             inherits: None,
+            scheme: None,
         }
     }
 
@@ -186,16 +345,28 @@ impl PaletteBuilder {
         self.inherits = Some(parent.into());
     }
 
+    /// Set the scheme the palette belongs to.
+    pub fn scheme(&mut self, scheme: impl Into<String>) {
+        self.scheme = Some(scheme.into());
+    }
+
     /// Get the parent palette name if set.
     pub fn parent_name(&self) -> Option<&str> {
         self.inherits.as_deref()
     }
 
+    /// Get the palette name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Build the palette, resolving all references.
     ///
     /// If `parent` is provided, colours will be inherited from it.
     pub fn build(self, parent: Option<&Palette>) -> Result<Palette> {
         let mut palette = Palette::new(self.name.clone());
+        palette.parent = self.inherits.clone();
+        palette.scheme = self.scheme.clone();
 
         // Inherit from parent first
         if let Some(parent) = parent {
@@ -208,6 +379,15 @@ impl PaletteBuilder {
             palette.insert(name, colour);
         }
 
+        // A local colour replaces an inherited alias of the same name
+        for (name, def) in &self.definitions {
+            match def {
+                ColourDef::Alias(target) => palette.aliases.insert(name.clone(), target.clone()),
+                _ => palette.aliases.remove(name),
+            };
+        }
+        check_alias_cycles(&palette.aliases)?;
+
         // Resolve variant colours
         for (variant_name, defs) in self.variant_defs {
             let resolved = resolve_colours(&defs, &palette)?;
@@ -232,6 +412,9 @@ fn parse_colour_def(value: &str) -> ColourDef {
             // Fall back to treating as hex if parse fails
             Err(_) => ColourDef::Hex(value.to_string()),
         }
+    } else if let Some(target) = value.strip_prefix('=') {
+        let target = target.trim();
+        ColourDef::Alias(target.strip_prefix('$').unwrap_or(target).to_string())
     } else if value.starts_with('$') {
         ColourDef::Reference(value.to_string())
     } else {
@@ -304,6 +487,7 @@ fn resolve_single(
             let ref_name = ref_name.strip_prefix('$').unwrap_or(ref_name);
             resolve_single(ref_name, definitions, existing, resolved, resolving)?
         }
+        ColourDef::Alias(target) => resolve_single(target, definitions, existing, resolved, resolving)?,
         ColourDef::Expression(expr) => {
             // Create an evaluator that can look up colours
             let evaluator = ExprEvaluator::new(|ref_name| {
@@ -316,7 +500,7 @@ fn resolve_single(
                     // For simple cases, resolve inline
                     match def {
                         ColourDef::Hex(hex) => Colour::from_hex(hex).ok(),
-                        ColourDef::Reference(r) => {
+                        ColourDef::Reference(r) | ColourDef::Alias(r) => {
                             let r = r.strip_prefix('$').unwrap_or(r);
                             existing.get(r)
                         }
@@ -341,6 +525,28 @@ fn resolve_single(
     Ok(colour)
 }
 
+/// Fail if following aliases from any name comes back round, which can
+/// happen when a palette aliases back to an inherited alias.
+fn check_alias_cycles(aliases: &HashMap<String, String>) -> Result<()> {
+    for start in aliases.keys() {
+        let mut chain = vec![start.as_str()];
+        let mut name = start.as_str();
+        while let Some(target) = aliases.get(name) {
+            if chain.contains(&target.as_str()) {
+                chain.push(target);
+                let chain: Vec<String> = chain.iter().map(|n| format!("${}", n)).collect();
+                return Err(PxError::Parse {
+                    message: format!("Circular colour alias: {}", chain.join(" -> ")),
+                    help: Some("Point one of these aliases at a colour value".to_string()),
+                });
+            }
+            chain.push(target);
+            name = target;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -581,4 +787,143 @@ mod tests {
         assert!(dark_grey.r > 0);
         assert!(dark_grey.r < 128);
     }
+
+    #[test]
+    fn test_blend_midpoint_and_passthrough() {
+        let mut a = PaletteBuilder::new("day");
+        a.define("sky", "#0000FF");
+        a.define("sun", "#FFFF00");
+        let a = a.build(None).unwrap();
+
+        let mut b = PaletteBuilder::new("night");
+        b.define("sky", "#000000");
+        b.define("moon", "#CCCCCC");
+        let b = b.build(None).unwrap();
+
+        let mid = a.blend(&b, 0.5);
+
+        assert_eq!(mid.get("sky"), Some(Colour::rgb(0, 0, 128)));
+        assert_eq!(mid.get("sun"), Some(Colour::rgb(255, 255, 0)));
+        assert_eq!(mid.get("moon"), Some(Colour::rgb(204, 204, 204)));
+        assert_eq!(mid.len(), 3);
+    }
+
+    #[test]
+    fn test_blend_variants() {
+        let mut a = PaletteBuilder::new("a");
+        a.define("fg", "#FFFFFF");
+        a.define_variant("dim", "fg", "#000000");
+        let a = a.build(None).unwrap();
+
+        let mut b = PaletteBuilder::new("b");
+        b.define("fg", "#FFFFFF");
+        b.define_variant("dim", "fg", "#FFFFFF");
+        let b = b.build(None).unwrap();
+
+        let mid = a.blend(&b, 0.5);
+        assert_eq!(mid.get_with_variant("fg", "dim"), Some(Colour::rgb(128, 128, 128)));
+    }
+
+    #[test]
+    fn test_is_grayscale() {
+        let mut greys = Palette::new("greys");
+        greys.insert("black".to_string(), Colour::BLACK);
+        greys.insert("mid".to_string(), Colour::rgb(128, 128, 128));
+        greys.insert("white".to_string(), Colour::WHITE);
+        assert!(greys.is_grayscale());
+
+        let mut mixed = greys.clone();
+        mixed.insert("red".to_string(), Colour::rgb(200, 0, 0));
+        assert!(!mixed.is_grayscale());
+    }
+
+    #[test]
+    fn test_resolve_variant() {
+        let mut builder = PaletteBuilder::new("sky");
+        builder.define("sky", "#0000FF");
+        builder.define("sun", "#FFFF00");
+        builder.define_variant("night", "sky", "#000000");
+        let palette = builder.build(None).unwrap();
+
+        let night = palette.resolve_variant(Some("night"));
+        assert_eq!(night.name, "sky-night");
+        assert_eq!(night.get("sky"), Some(Colour::BLACK));
+        assert_eq!(night.get("sun"), Some(Colour::rgb(255, 255, 0)));
+        assert_eq!(night.variant_names().count(), 0);
+
+        let base = palette.resolve_variant(None);
+        assert_eq!(base.name, "sky");
+        assert_eq!(base.get("sky"), Some(Colour::rgb(0, 0, 255)));
+        assert!(!base.has_variant("night"));
+    }
+
+    #[test]
+    fn test_to_source_round_trip() {
+        let mut builder = PaletteBuilder::new("theme");
+        builder.define("b", "#112233");
+        builder.define("a", "#44556680");
+        builder.define_variant("dark", "a", "#000000");
+        let palette = builder.build(None).unwrap();
+
+        let source = palette.to_source();
+        assert!(source.find("$a:").unwrap() < source.find("$b:").unwrap());
+
+        let parsed = crate::parser::parse_palette(&source).unwrap();
+        let reparsed = parsed.into_iter().next().unwrap().build(None).unwrap();
+        assert_eq!(reparsed.name, "theme");
+        assert_eq!(reparsed.get("a"), palette.get("a"));
+        assert_eq!(reparsed.get("b"), palette.get("b"));
+        assert_eq!(reparsed.get_with_variant("a", "dark"), Some(Colour::BLACK));
+    }
+
+    #[test]
+    fn test_alias_tracks_its_target() {
+        let build = |blue: &str| {
+            let mut builder = PaletteBuilder::new("brand");
+            builder.define("blue", blue);
+            builder.define("primary", "=blue");
+            builder.define("edge", "$blue");
+            builder.define_variant("dark", "blue", "#000080");
+            builder.build(None).unwrap()
+        };
+
+        let palette = build("#0000FF");
+        assert_eq!(palette.get("primary"), Some(Colour::rgb(0, 0, 255)));
+        assert_eq!(palette.alias_target("$primary"), Some("blue"));
+        assert_eq!(palette.alias_target("edge"), None);
+        assert_eq!(palette.aliases(), [("primary", "blue")]);
+        // Editing the target moves the alias with it
+        assert_eq!(build("#3366FF").get("primary"), Some(Colour::rgb(0x33, 0x66, 0xFF)));
+
+        // Unlike a plain reference, an alias follows its target into variants
+        assert_eq!(palette.get_with_variant("primary", "dark"), Some(Colour::rgb(0, 0, 0x80)));
+        assert_eq!(palette.get_with_variant("edge", "dark"), Some(Colour::rgb(0, 0, 255)));
+        assert_eq!(palette.resolve_variant(Some("dark")).get("primary"), Some(Colour::rgb(0, 0, 0x80)));
+
+        // Exports keep the alias
+        assert!(palette.to_source().contains("$primary: =blue\n"));
+    }
+
+    #[test]
+    fn test_cyclic_alias_errors() {
+        let mut builder = PaletteBuilder::new("loop");
+        builder.define("a", "=b");
+        builder.define("b", "=$a");
+        let err = builder.build(None).unwrap_err().to_string();
+        assert!(err.contains("Circular"), "{}", err);
+
+        let mut builder = PaletteBuilder::new("missing");
+        builder.define("primary", "=blue");
+        assert!(builder.build(None).is_err());
+
+        // Aliasing back to an inherited alias
+        let mut parent = PaletteBuilder::new("base");
+        parent.define("blue", "#0000FF");
+        parent.define("primary", "=blue");
+        let parent = parent.build(None).unwrap();
+        let mut child = PaletteBuilder::new("child");
+        child.define("blue", "=primary");
+        let err = child.build(Some(&parent)).unwrap_err().to_string();
+        assert!(err.contains("Circular colour alias: $"), "{}", err);
+    }
 }
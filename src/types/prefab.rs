@@ -29,6 +29,8 @@ use std::collections::HashMap;
 
 use serde::Serialize;
 
+use super::shape::EmbeddedSource;
+
 /// Metadata about a rendered prefab, for JSON export.
 #[derive(Debug, Clone, Serialize)]
 pub struct PrefabMetadata {
@@ -49,6 +51,10 @@ pub struct PrefabMetadata {
 
     /// Instances placed in the prefab.
     pub shapes: Vec<PrefabInstance>,
+
+    /// Grid and legend, with `--embed-source`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<EmbeddedSource>,
 }
 
 /// A shape/prefab instance placed in a prefab.
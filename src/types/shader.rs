@@ -19,6 +19,8 @@
 
 use std::collections::HashMap;
 
+use super::inherit::{resolve_inherited, Inherits};
+
 /// A shader definition for rendering configuration.
 #[derive(Debug, Clone)]
 pub struct Shader {
@@ -126,6 +128,43 @@ pub enum Effect {
         amount: f32,
     },
 
+    /// Draws a ring around opaque pixels.
+    Outline {
+        /// Outline colour (palette reference like `$edge`, or hex).
+        colour: String,
+        /// Ring thickness in pixels (default: 1).
+        thickness: u32,
+    },
+
+    /// Composites a shifted, single-colour copy of the sprite beneath it.
+    DropShadow {
+        /// Horizontal offset in pixels (negative = left).
+        dx: i32,
+        /// Vertical offset in pixels (negative = up).
+        dy: i32,
+        /// Shadow colour (palette reference like `$edge`, or hex).
+        colour: String,
+        /// Shadow opacity (0 - 255).
+        alpha: u8,
+    },
+
+    /// Tonal adjustment of every drawn pixel: brightness, then contrast,
+    /// then gamma.
+    Levels {
+        /// Added to each channel (-255 to 255, 0 = no change).
+        brightness: f32,
+        /// Contrast around mid-grey (-255 to 255, 0 = no change).
+        contrast: f32,
+        /// Gamma (above 1 lightens mid-tones, 1 = no change).
+        gamma: f32,
+    },
+
+    /// Quantize each channel of every drawn pixel to evenly spaced steps.
+    Posterize {
+        /// Steps per channel (at least 2; 2 snaps each channel to 0 or 255).
+        levels: u8,
+    },
+
     /// Custom/unknown effect with raw parameters.
     Custom {
         /// Effect type name.
@@ -186,6 +225,38 @@ impl Effect {
         }
     }
 
+    /// Create an outline effect.
+    pub fn outline(colour: impl Into<String>, thickness: u32) -> Self {
+        Self::Outline {
+            colour: colour.into(),
+            thickness,
+        }
+    }
+
+    /// Create a drop shadow effect.
+    pub fn drop_shadow(dx: i32, dy: i32, colour: impl Into<String>, alpha: u8) -> Self {
+        Self::DropShadow {
+            dx,
+            dy,
+            colour: colour.into(),
+            alpha,
+        }
+    }
+
+    /// Create a levels effect.
+    pub fn levels(brightness: f32, contrast: f32, gamma: f32) -> Self {
+        Self::Levels {
+            brightness: brightness.clamp(-255.0, 255.0),
+            contrast: contrast.clamp(-255.0, 255.0),
+            gamma: gamma.max(0.01),
+        }
+    }
+
+    /// Create a posterize effect.
+    pub fn posterize(levels: u8) -> Self {
+        Self::Posterize { levels: levels.max(2) }
+    }
+
     /// Get the effect type name.
     pub fn type_name(&self) -> &str {
         match self {
@@ -193,6 +264,10 @@ impl Effect {
             Effect::Scanlines { .. } => "scanlines",
             Effect::Brightness { .. } => "brightness",
             Effect::Contrast { .. } => "contrast",
+            Effect::Outline { .. } => "outline",
+            Effect::DropShadow { .. } => "drop-shadow",
+            Effect::Levels { .. } => "levels",
+            Effect::Posterize { .. } => "posterize",
             Effect::Custom { name, .. } => name,
         }
     }
@@ -216,6 +291,37 @@ impl BuiltinShaders {
     pub fn get(name: &str) -> Option<Shader> {
         Self::all().into_iter().find(|s| s.name == name)
     }
+
+    /// Resolve a shader by name, following `inherits` chains.
+    ///
+    /// Looks in `defined` first, then the builtins; builtins can also be
+    /// parents. Returns `Ok(None)` if the name is unknown, and an error for
+    /// unknown parents or circular inheritance.
+    pub fn resolve(name: &str, defined: &[ShaderBuilder]) -> Result<Option<Shader>, String> {
+        resolve_inherited(name, defined)
+    }
+}
+
+impl Inherits for ShaderBuilder {
+    type Built = Shader;
+
+    const KIND: &'static str = "shader";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parent_name(&self) -> Option<&str> {
+        ShaderBuilder::parent_name(self)
+    }
+
+    fn builtin(name: &str) -> Option<Shader> {
+        BuiltinShaders::get(name)
+    }
+
+    fn build_inherited(self, parent: Option<&Shader>) -> Result<Shader, &'static str> {
+        self.build(parent)
+    }
 }
 
 /// Builder for constructing shaders from parsed definitions.
@@ -240,6 +346,11 @@ impl ShaderBuilder {
         }
     }
 
+    /// Get the shader name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Set the palette.
     pub fn palette(&mut self, palette: impl Into<String>) -> &mut Self {
         self.palette = Some(palette.into());
@@ -440,4 +551,58 @@ mod tests {
         // Child's variant should take precedence
         assert_eq!(child.palette_variant, Some("light".to_string()));
     }
+
+    #[test]
+    fn test_resolve_builtin_parent_with_outline() {
+        let mut child = ShaderBuilder::new("outlined");
+        child.inherits("default");
+        child.add_effect(Effect::outline("$edge", 1));
+
+        let shader = BuiltinShaders::resolve("outlined", &[child]).unwrap().unwrap();
+        assert_eq!(shader.palette, "default");
+        assert_eq!(shader.effects.len(), 1);
+        assert_eq!(shader.effects[0].type_name(), "outline");
+    }
+
+    #[test]
+    fn test_resolve_chain_appends_effects() {
+        let mut base = ShaderBuilder::new("base");
+        base.palette("gameboy").palette_variant("dark");
+        base.add_effect(Effect::scanlines(0.2));
+
+        let mut child = ShaderBuilder::new("child");
+        child.inherits("base");
+        child.add_effect(Effect::outline("$edge", 1));
+
+        let shader = BuiltinShaders::resolve("child", &[base, child]).unwrap().unwrap();
+        assert_eq!(shader.palette, "gameboy");
+        assert_eq!(shader.palette_variant, Some("dark".to_string()));
+        let names: Vec<&str> = shader.effects.iter().map(|e| e.type_name()).collect();
+        assert_eq!(names, vec!["scanlines", "outline"]);
+    }
+
+    #[test]
+    fn test_resolve_unknown_parent() {
+        let mut child = ShaderBuilder::new("child");
+        child.inherits("missing");
+
+        let err = BuiltinShaders::resolve("child", &[child]).unwrap_err();
+        assert_eq!(err, "Shader 'child' inherits unknown shader 'missing'");
+    }
+
+    #[test]
+    fn test_resolve_circular() {
+        let mut a = ShaderBuilder::new("a");
+        a.inherits("b");
+        let mut b = ShaderBuilder::new("b");
+        b.inherits("a");
+
+        let err = BuiltinShaders::resolve("a", &[a, b]).unwrap_err();
+        assert_eq!(err, "Circular shader inheritance: a -> b -> a");
+    }
+
+    #[test]
+    fn test_resolve_unknown_name() {
+        assert!(BuiltinShaders::resolve("nope", &[]).unwrap().is_none());
+    }
 }
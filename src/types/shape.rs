@@ -22,7 +22,9 @@
 //! B: brick
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 use serde::Serialize;
 
@@ -37,10 +39,36 @@ pub struct ShapeMetadata {
 
     /// Tags from frontmatter.
     pub tags: Vec<String>,
+
+    /// 9-slice borders, if the shape defines them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slice: Option<NineSlice>,
+
+    /// Size before padding, when padded to a uniform size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_size: Option<[usize; 2]>,
+
+    /// Position of the unpadded sprite within `size`, when padded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<[usize; 2]>,
+
+    /// Grid and legend, with `--embed-source`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<EmbeddedSource>,
+}
+
+/// A definition's grid and legend, embedded in its JSON so editors can
+/// rebuild the source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmbeddedSource {
+    /// Grid rows, padded to the grid's width.
+    pub grid: Vec<String>,
+    /// Legend values by glyph, in legend syntax.
+    pub legend: BTreeMap<char, String>,
 }
 
 /// A shape definition - an ASCII grid that maps to stamps/brushes.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Shape {
     /// Shape name (unique identifier).
     pub name: String,
@@ -56,10 +84,109 @@ pub struct Shape {
 
     /// Optional scale factor from frontmatter (1 = no scaling).
     pub scale: Option<u32>,
+
+    /// Optional anchor (pivot) point from frontmatter.
+    pub anchor: Option<Anchor>,
+
+    /// Optional 9-slice borders from frontmatter.
+    pub slice: Option<NineSlice>,
+
+    /// Optional background colour (`$name` or `#hex`) from frontmatter.
+    pub background: Option<String>,
+
+    /// Pixels each glyph covers, from `cell:` in frontmatter. `None` draws
+    /// one pixel per glyph.
+    pub cell: Option<CellSize>,
+}
+
+/// 9-slice borders in pixels, for stretching UI panels.
+///
+/// Corners keep their size, edges stretch along one axis and the
+/// centre stretches along both. Written as `slice: [left, right, top, bottom]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct NineSlice {
+    pub left: usize,
+    pub right: usize,
+    pub top: usize,
+    pub bottom: usize,
+}
+
+impl NineSlice {
+    /// Create slice borders.
+    pub fn new(left: usize, right: usize, top: usize, bottom: usize) -> Self {
+        Self { left, right, top, bottom }
+    }
+}
+
+/// How many pixels each glyph of a shape covers (`cell:` in frontmatter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellSize {
+    /// A fixed block: `cell: 4x4`.
+    Pixels(usize, usize),
+    /// As large as the largest stamp the shape places: `cell: stamp`.
+    Stamp,
+}
+
+impl CellSize {
+    /// Parse `WxH` (both non-zero) or `stamp`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("stamp") {
+            return Some(CellSize::Stamp);
+        }
+        let (w, h) = s.split_once(['x', 'X'])?;
+        match (w.trim().parse().ok()?, h.trim().parse().ok()?) {
+            (0, _) | (_, 0) => None,
+            (w, h) => Some(CellSize::Pixels(w, h)),
+        }
+    }
+}
+
+/// A shape's anchor point, used as the sprite pivot in sheet metadata.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    /// Fraction of the sprite size: `center` is (0.5, 0.5).
+    Relative(f32, f32),
+    /// Pixel position from the top-left: `anchor: 3, 7`.
+    Pixel(f32, f32),
+}
+
+impl Anchor {
+    /// Parse an anchor: a position name (`center`, `bottom`, `top-left`...)
+    /// or pixel coordinates `x, y`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        let named = match s.as_str() {
+            "top-left" => Some((0.0, 0.0)),
+            "top" | "top-center" | "top-centre" => Some((0.5, 0.0)),
+            "top-right" => Some((1.0, 0.0)),
+            "left" => Some((0.0, 0.5)),
+            "center" | "centre" => Some((0.5, 0.5)),
+            "right" => Some((1.0, 0.5)),
+            "bottom-left" => Some((0.0, 1.0)),
+            "bottom" | "bottom-center" | "bottom-centre" => Some((0.5, 1.0)),
+            "bottom-right" => Some((1.0, 1.0)),
+            _ => None,
+        };
+        if let Some((x, y)) = named {
+            return Some(Anchor::Relative(x, y));
+        }
+
+        let (x, y) = s.split_once(',')?;
+        Some(Anchor::Pixel(x.trim().parse().ok()?, y.trim().parse().ok()?))
+    }
+
+    /// The anchor in pixels for a sprite of the given size.
+    pub fn resolve(&self, width: usize, height: usize) -> (f32, f32) {
+        match *self {
+            Anchor::Relative(fx, fy) => (fx * width as f32, fy * height as f32),
+            Anchor::Pixel(x, y) => (x, y),
+        }
+    }
 }
 
 /// A legend entry describing what a glyph maps to.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LegendEntry {
     /// Reference to a stamp by name: `B: brick`
     StampRef(String),
@@ -74,9 +201,98 @@ pub enum LegendEntry {
     Fill {
         name: String,
         bindings: HashMap<char, String>,
+        /// Phase shift `(dx, dy)` added to each cell before indexing the
+        /// pattern: `~: checker offset 1,0`
+        offset: (i32, i32),
+    },
+
+    /// Construction guide (`G: guide`): transparent in output, highlighted in previews
+    Guide,
+
+    /// Gradient between two colours across the shape:
+    /// `{ gradient: vertical, from: $sky, to: $sea }`
+    Gradient {
+        from: String,
+        to: String,
+        direction: GradientDirection,
     },
 }
 
+/// Which way a gradient legend entry runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GradientDirection {
+    /// Top to bottom, across the shape's height.
+    Vertical,
+    /// Left to right, across the shape's width.
+    Horizontal,
+}
+
+/// Legend syntax, as it would be written after the glyph.
+impl fmt::Display for LegendEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let with_bindings = |f: &mut fmt::Formatter<'_>, key: &str, name: &str, bindings: &HashMap<char, String>| {
+            let mut bindings: Vec<_> = bindings.iter().collect();
+            bindings.sort();
+            write!(f, "{{ {}: {}", key, name)?;
+            for (token, colour) in bindings {
+                write!(f, ", {}: {}", token, colour)?;
+            }
+            write!(f, " }}")
+        };
+        match self {
+            LegendEntry::StampRef(name) => write!(f, "{}", name),
+            LegendEntry::BrushRef { name, bindings } => with_bindings(f, "stamp", name, bindings),
+            LegendEntry::Fill { name, bindings, offset: (0, 0) } => with_bindings(f, "fill", name, bindings),
+            LegendEntry::Fill { name, bindings, offset: (dx, dy) } => {
+                with_bindings(f, "fill", &format!("{} offset {},{}", name, dx, dy), bindings)
+            }
+            LegendEntry::Guide => write!(f, "guide"),
+            LegendEntry::Gradient { from, to, direction } => {
+                let direction = match direction {
+                    GradientDirection::Vertical => "vertical",
+                    GradientDirection::Horizontal => "horizontal",
+                };
+                write!(f, "{{ gradient: {}, from: {}, to: {} }}", direction, from, to)
+            }
+        }
+    }
+}
+
+impl Hash for LegendEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            LegendEntry::StampRef(name) => name.hash(state),
+            LegendEntry::BrushRef { name, bindings } | LegendEntry::Fill { name, bindings, .. } => {
+                name.hash(state);
+                // Bindings hash in token order so equal maps hash equally
+                let mut bindings: Vec<_> = bindings.iter().collect();
+                bindings.sort();
+                bindings.hash(state);
+                if let LegendEntry::Fill { offset, .. } = self {
+                    offset.hash(state);
+                }
+            }
+            LegendEntry::Guide => {}
+            LegendEntry::Gradient { from, to, direction } => {
+                from.hash(state);
+                to.hash(state);
+                direction.hash(state);
+            }
+        }
+    }
+}
+
+/// Content key for a shape: its grid and legend, without name or metadata.
+///
+/// Shapes that draw the same thing share a key, so it can be used to
+/// dedupe or cache renders. See [`Shape::content_key`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShapeKey<'a> {
+    grid: &'a [Vec<char>],
+    legend: Vec<(char, &'a LegendEntry)>,
+}
+
 impl Shape {
     /// Create a new shape.
     pub fn new(
@@ -91,6 +307,10 @@ impl Shape {
             grid,
             legend,
             scale: None,
+            anchor: None,
+            slice: None,
+            background: None,
+            cell: None,
         }
     }
 
@@ -108,6 +328,10 @@ impl Shape {
             grid,
             legend,
             scale,
+            anchor: None,
+            slice: None,
+            background: None,
+            cell: None,
         }
     }
 
@@ -165,6 +389,17 @@ impl Shape {
         })
     }
 
+    /// Content key for dedupe and caching, ignoring name, tags, scale,
+    /// anchor and slice.
+    pub fn content_key(&self) -> ShapeKey<'_> {
+        let mut legend: Vec<_> = self.legend.iter().map(|(&glyph, entry)| (glyph, entry)).collect();
+        legend.sort_by_key(|&(glyph, _)| glyph);
+        ShapeKey {
+            grid: &self.grid,
+            legend,
+        }
+    }
+
     /// Get all unique glyphs used in this shape.
     pub fn glyphs(&self) -> Vec<char> {
         let mut glyphs: Vec<char> = self
@@ -270,6 +505,30 @@ mod tests {
         assert_eq!(glyphs, vec!['A', 'B', 'C']);
     }
 
+    #[test]
+    fn test_legend_entry_display_matches_syntax() {
+        let fill = LegendEntry::Fill {
+            name: "checker".to_string(),
+            bindings: [('B', "$fill".to_string()), ('A', "$edge".to_string())].into_iter().collect(),
+            offset: (0, 0),
+        };
+        assert_eq!(fill.to_string(), "{ fill: checker, A: $edge, B: $fill }");
+        let shifted = LegendEntry::Fill {
+            name: "checker".to_string(),
+            bindings: [('A', "$edge".to_string())].into_iter().collect(),
+            offset: (1, -2),
+        };
+        assert_eq!(shifted.to_string(), "{ fill: checker offset 1,-2, A: $edge }");
+        assert_eq!(LegendEntry::StampRef("brick".to_string()).to_string(), "brick");
+        assert_eq!(LegendEntry::Guide.to_string(), "guide");
+        let gradient = LegendEntry::Gradient {
+            from: "$sky".to_string(),
+            to: "$sea".to_string(),
+            direction: GradientDirection::Vertical,
+        };
+        assert_eq!(gradient.to_string(), "{ gradient: vertical, from: $sky, to: $sea }");
+    }
+
     #[test]
     fn test_legend_entry_variants() {
         let stamp = LegendEntry::StampRef("brick".to_string());
@@ -282,10 +541,53 @@ mod tests {
             bindings: [('A', "$edge".to_string()), ('B', "$fill".to_string())]
                 .into_iter()
                 .collect(),
+            offset: (0, 0),
         };
 
         assert!(matches!(stamp, LegendEntry::StampRef(_)));
         assert!(matches!(brush, LegendEntry::BrushRef { .. }));
         assert!(matches!(fill, LegendEntry::Fill { .. }));
     }
+
+    #[test]
+    fn test_shape_content_key() {
+        use std::collections::HashSet;
+
+        let grid = vec![vec!['B', 'C']];
+        let bindings: HashMap<char, String> =
+            [('A', "$edge".to_string()), ('B', "$fill".to_string())].into_iter().collect();
+        let legend: HashMap<char, LegendEntry> = [
+            ('B', LegendEntry::StampRef("brick".to_string())),
+            ('C', LegendEntry::Fill { name: "checker".to_string(), bindings, offset: (0, 0) }),
+        ]
+        .into_iter()
+        .collect();
+
+        let a = Shape::new("a", vec!["wall".to_string()], grid.clone(), legend.clone());
+        let mut b = Shape::with_scale("b", vec![], grid.clone(), legend.clone(), Some(4));
+        b.anchor = Some(Anchor::Relative(0.5, 1.0));
+        let c = Shape::new("a", vec![], grid, HashMap::new());
+
+        assert_eq!(a.content_key(), b.content_key());
+        assert_ne!(a, b);
+        assert_ne!(a.content_key(), c.content_key());
+
+        let keys: HashSet<_> = [&a, &b, &c].iter().map(|s| s.content_key()).collect();
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn test_anchor_parse() {
+        assert_eq!(Anchor::parse("center"), Some(Anchor::Relative(0.5, 0.5)));
+        assert_eq!(Anchor::parse("Bottom"), Some(Anchor::Relative(0.5, 1.0)));
+        assert_eq!(Anchor::parse("3, 7"), Some(Anchor::Pixel(3.0, 7.0)));
+        assert_eq!(Anchor::parse("middle"), None);
+        assert_eq!(Anchor::parse("3"), None);
+    }
+
+    #[test]
+    fn test_anchor_resolve() {
+        assert_eq!(Anchor::Relative(0.5, 1.0).resolve(6, 4), (3.0, 4.0));
+        assert_eq!(Anchor::Pixel(1.0, 2.0).resolve(6, 4), (1.0, 2.0));
+    }
 }
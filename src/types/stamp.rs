@@ -11,7 +11,7 @@
 use crate::types::Colour;
 
 /// A pixel token in a stamp grid.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PixelToken {
     /// Edge colour (`$` in source)
     Edge,
@@ -19,6 +19,9 @@ pub enum PixelToken {
     Fill,
     /// Transparent (`x` in source)
     Transparent,
+    /// Hole (`o` in source): transparent, and cuts through anything it is
+    /// layered over
+    Hole,
 }
 
 impl PixelToken {
@@ -28,6 +31,7 @@ impl PixelToken {
             '$' => Some(PixelToken::Edge),
             '.' => Some(PixelToken::Fill),
             'x' | 'X' => Some(PixelToken::Transparent),
+            'o' => Some(PixelToken::Hole),
             ' ' => Some(PixelToken::Fill), // Space defaults to fill
             _ => None,
         }
@@ -39,6 +43,7 @@ impl PixelToken {
             PixelToken::Edge => '$',
             PixelToken::Fill => '.',
             PixelToken::Transparent => 'x',
+            PixelToken::Hole => 'o',
         }
     }
 
@@ -47,13 +52,13 @@ impl PixelToken {
         match self {
             PixelToken::Edge => edge,
             PixelToken::Fill => fill,
-            PixelToken::Transparent => Colour::TRANSPARENT,
+            PixelToken::Transparent | PixelToken::Hole => Colour::TRANSPARENT,
         }
     }
 }
 
 /// A stamp definition - a small pixel art pattern.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Stamp {
     /// Stamp name (unique identifier).
     pub name: String,
@@ -112,6 +117,12 @@ impl Stamp {
         &self.pixels
     }
 
+    /// Content key for dedupe and caching: the pixel grid alone.
+    /// Stamps with the same tokens share a key whatever their name or glyph.
+    pub fn content_key(&self) -> &[Vec<PixelToken>] {
+        &self.pixels
+    }
+
     /// Iterate over all pixels with their positions.
     pub fn iter_pixels(&self) -> impl Iterator<Item = (usize, usize, PixelToken)> + '_ {
         self.pixels.iter().enumerate().flat_map(|(y, row)| {
@@ -169,6 +180,27 @@ impl BuiltinStamps {
 mod tests {
     use super::*;
 
+    fn tokens(rows: &[&str]) -> Vec<Vec<PixelToken>> {
+        rows.iter()
+            .map(|row| row.chars().filter_map(PixelToken::from_char).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_stamp_content_key_ignores_name() {
+        let a = Stamp::new("brick", Some('B'), tokens(&["$$", ".."]));
+        let b = Stamp::new("wall", None, tokens(&["$$", ".."]));
+        let c = Stamp::new("brick", Some('B'), tokens(&["$$", "x."]));
+
+        assert_eq!(a.content_key(), b.content_key());
+        assert_ne!(a, b);
+        assert_ne!(a.content_key(), c.content_key());
+
+        let mut seen = std::collections::HashSet::new();
+        assert!(seen.insert(a.content_key()));
+        assert!(!seen.insert(b.content_key()));
+    }
+
     #[test]
     fn test_pixel_token_from_char() {
         assert_eq!(PixelToken::from_char('$'), Some(PixelToken::Edge));
@@ -176,6 +208,8 @@ mod tests {
         assert_eq!(PixelToken::from_char('x'), Some(PixelToken::Transparent));
         assert_eq!(PixelToken::from_char('X'), Some(PixelToken::Transparent));
         assert_eq!(PixelToken::from_char(' '), Some(PixelToken::Fill));
+        assert_eq!(PixelToken::from_char('o'), Some(PixelToken::Hole));
+        assert_eq!(PixelToken::Hole.to_char(), 'o');
         assert_eq!(PixelToken::from_char('?'), None);
     }
 
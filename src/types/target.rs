@@ -15,6 +15,11 @@
 //! padding: 1
 //! shader: default
 //! ```
+//!
+//! Targets can inherit from another target (builtin or user-defined) with
+//! `inherits: <target>`. Fields set locally override the parent's.
+
+use super::inherit::{resolve_inherited, Inherits};
 
 /// How sprites are packed into sheets.
 #[derive(Debug, Clone, PartialEq)]
@@ -74,7 +79,7 @@ pub enum PaletteMode {
 pub struct Target {
     /// Target name (unique identifier).
     pub name: String,
-    /// Output format (currently only "png").
+    /// Output format: "png", "auto" (indexed PNG when it fits), "p8", "svg", "webp", "tiled", "godot" or "unity".
     pub format: String,
     /// Scale factor for output.
     pub scale: Option<u32>,
@@ -82,10 +87,17 @@ pub struct Target {
     pub sheet: SheetConfig,
     /// Padding between sprites in sheet (pixels).
     pub padding: Option<u32>,
-    /// Palette output mode.
+    /// Palette output mode: how `png` output stores colours.
     pub palette_mode: PaletteMode,
     /// Shader to use for rendering.
     pub shader: Option<String>,
+    /// Most distinct colours any one rendered asset may use, not counting
+    /// transparency.
+    pub max_colours: Option<usize>,
+    /// Fixed column count for sheet layout.
+    pub columns: Option<u32>,
+    /// Widest a packed sheet may grow before wrapping to a new shelf (pixels).
+    pub max_width: Option<u32>,
 }
 
 impl Target {
@@ -99,6 +111,18 @@ impl Target {
             padding: None,
             palette_mode: PaletteMode::Rgba,
             shader: None,
+            max_colours: None,
+            columns: None,
+            max_width: None,
+        }
+    }
+
+    /// Largest map this target's device supports, in cells (columns, rows).
+    /// `None` when maps are unconstrained.
+    pub fn map_limit(&self) -> Option<(usize, usize)> {
+        match self.format.as_str() {
+            "p8" => Some((128, 64)),
+            _ => None,
         }
     }
 }
@@ -122,6 +146,25 @@ impl BuiltinTargets {
             padding: None,
             palette_mode: PaletteMode::Rgba,
             shader: None,
+            max_colours: None,
+            columns: None,
+            max_width: None,
+        }
+    }
+
+    /// Get the "retina" target: PNG at 2x for high-density displays.
+    fn retina() -> Target {
+        Target {
+            scale: Some(2),
+            ..Target::new("retina", "png")
+        }
+    }
+
+    /// Get the "pico8" target: the "p8" target under its full name.
+    fn pico8() -> Target {
+        Target {
+            name: "pico8".to_string(),
+            ..Self::p8()
         }
     }
 
@@ -135,6 +178,9 @@ impl BuiltinTargets {
             padding: Some(0),
             palette_mode: PaletteMode::Indexed,
             shader: None,
+            max_colours: None,
+            columns: None,
+            max_width: None,
         }
     }
 
@@ -143,14 +189,47 @@ impl BuiltinTargets {
         match name {
             "web" => Some(Self::web()),
             "sheet" => Some(Self::sheet()),
+            "retina" => Some(Self::retina()),
             "p8" => Some(Self::p8()),
+            "pico8" => Some(Self::pico8()),
             _ => None,
         }
     }
 
     /// Get all builtin targets.
     pub fn all() -> Vec<Target> {
-        vec![Self::web(), Self::sheet(), Self::p8()]
+        vec![Self::web(), Self::sheet(), Self::retina(), Self::p8(), Self::pico8()]
+    }
+
+    /// Resolve a target by name, following `inherits` chains.
+    ///
+    /// Targets in `defined` take precedence over builtins, and builtins are
+    /// eligible as parents. Returns `Ok(None)` if no target has that name,
+    /// and an error if a parent is missing or the chain is cyclic.
+    pub fn resolve(name: &str, defined: &[TargetBuilder]) -> Result<Option<Target>, String> {
+        resolve_inherited(name, defined)
+    }
+}
+
+impl Inherits for TargetBuilder {
+    type Built = Target;
+
+    const KIND: &'static str = "target";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parent_name(&self) -> Option<&str> {
+        TargetBuilder::parent_name(self)
+    }
+
+    fn builtin(name: &str) -> Option<Target> {
+        BuiltinTargets::get(name)
+    }
+
+    fn build_inherited(self, parent: Option<&Target>) -> Result<Target, &'static str> {
+        self.build_with_parent(parent)
     }
 }
 
@@ -164,6 +243,10 @@ pub struct TargetBuilder {
     padding: Option<u32>,
     palette_mode: Option<PaletteMode>,
     shader: Option<String>,
+    max_colours: Option<usize>,
+    columns: Option<u32>,
+    max_width: Option<u32>,
+    inherits: Option<String>,
 }
 
 impl TargetBuilder {
@@ -177,9 +260,18 @@ impl TargetBuilder {
             padding: None,
             palette_mode: None,
             shader: None,
+            max_colours: None,
+            columns: None,
+            max_width: None,
+            inherits: None,
         }
     }
 
+    /// Get the target name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Set the output format.
     pub fn format(&mut self, format: impl Into<String>) -> &mut Self {
         self.format = Some(format.into());
@@ -216,18 +308,67 @@ impl TargetBuilder {
         self
     }
 
-    /// Build the target.
+    /// Set the per-asset colour limit.
+    pub fn max_colours(&mut self, max: usize) -> &mut Self {
+        self.max_colours = Some(max);
+        self
+    }
+
+    /// Set the sheet column count.
+    pub fn columns(&mut self, columns: u32) -> &mut Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Set the widest a packed sheet may grow.
+    pub fn max_width(&mut self, width: u32) -> &mut Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Set the parent target for inheritance.
+    pub fn inherits(&mut self, parent: impl Into<String>) -> &mut Self {
+        self.inherits = Some(parent.into());
+        self
+    }
+
+    /// Get the parent target name if set.
+    pub fn parent_name(&self) -> Option<&str> {
+        self.inherits.as_deref()
+    }
+
+    /// Build the target without a parent.
     pub fn build(self) -> Result<Target, &'static str> {
-        let format = self.format.unwrap_or_else(|| "png".to_string());
+        self.build_with_parent(None)
+    }
+
+    /// Build the target.
+    ///
+    /// If `parent` is provided, any field not set on this builder is
+    /// inherited from it.
+    pub fn build_with_parent(self, parent: Option<&Target>) -> Result<Target, &'static str> {
+        let format = self
+            .format
+            .or_else(|| parent.map(|p| p.format.clone()))
+            .unwrap_or_else(|| "png".to_string());
 
         Ok(Target {
             name: self.name,
             format,
-            scale: self.scale,
-            sheet: self.sheet.unwrap_or(SheetConfig::None),
-            padding: self.padding,
-            palette_mode: self.palette_mode.unwrap_or(PaletteMode::Rgba),
-            shader: self.shader,
+            scale: self.scale.or_else(|| parent.and_then(|p| p.scale)),
+            sheet: self
+                .sheet
+                .or_else(|| parent.map(|p| p.sheet.clone()))
+                .unwrap_or(SheetConfig::None),
+            padding: self.padding.or_else(|| parent.and_then(|p| p.padding)),
+            palette_mode: self
+                .palette_mode
+                .or_else(|| parent.map(|p| p.palette_mode.clone()))
+                .unwrap_or(PaletteMode::Rgba),
+            shader: self.shader.or_else(|| parent.and_then(|p| p.shader.clone())),
+            max_colours: self.max_colours.or_else(|| parent.and_then(|p| p.max_colours)),
+            columns: self.columns.or_else(|| parent.and_then(|p| p.columns)),
+            max_width: self.max_width.or_else(|| parent.and_then(|p| p.max_width)),
         })
     }
 }
@@ -303,6 +444,19 @@ mod tests {
         assert_eq!(target.palette_mode, PaletteMode::Indexed);
     }
 
+    #[test]
+    fn test_builtin_retina_and_pico8() {
+        let retina = BuiltinTargets::get("retina").unwrap();
+        assert_eq!(retina.format, "png");
+        assert_eq!(retina.scale, Some(2));
+        assert_eq!(retina.sheet, SheetConfig::None);
+
+        let pico8 = BuiltinTargets::get("pico8").unwrap();
+        assert_eq!(pico8.name, "pico8");
+        assert_eq!(pico8.format, "p8");
+        assert_eq!(pico8.sheet, SheetConfig::Fixed { width: 128, height: 128 });
+    }
+
     #[test]
     fn test_builtin_unknown() {
         assert!(BuiltinTargets::get("aseprite").is_none());
@@ -335,4 +489,64 @@ mod tests {
         assert_eq!(target.sheet, SheetConfig::None);
         assert_eq!(target.palette_mode, PaletteMode::Rgba);
     }
+
+    #[test]
+    fn test_target_inherits_builtin_web() {
+        let mut builder = TargetBuilder::new("web-4x");
+        builder.inherits("web");
+        builder.scale(4);
+
+        let target = BuiltinTargets::resolve("web-4x", &[builder]).unwrap().unwrap();
+        let web = BuiltinTargets::get("web").unwrap();
+
+        assert_eq!(target.name, "web-4x");
+        assert_eq!(target.format, web.format);
+        assert_eq!(target.sheet, web.sheet);
+        assert_eq!(target.scale, Some(4));
+    }
+
+    #[test]
+    fn test_target_inherits_chain_local_overrides() {
+        let mut base = TargetBuilder::new("base");
+        base.inherits("p8");
+        base.padding(2);
+
+        let mut child = TargetBuilder::new("child");
+        child.inherits("base");
+        child.format("png");
+
+        let target = BuiltinTargets::resolve("child", &[base, child]).unwrap().unwrap();
+        assert_eq!(target.format, "png");
+        assert_eq!(target.padding, Some(2));
+        assert_eq!(target.scale, Some(1));
+        assert_eq!(target.sheet, SheetConfig::Fixed { width: 128, height: 128 });
+        assert_eq!(target.palette_mode, PaletteMode::Indexed);
+    }
+
+    #[test]
+    fn test_target_inherits_cycle() {
+        let mut a = TargetBuilder::new("a");
+        a.inherits("b");
+        let mut b = TargetBuilder::new("b");
+        b.inherits("a");
+
+        let err = BuiltinTargets::resolve("a", &[a, b]).unwrap_err();
+        assert!(err.contains("Circular"));
+        assert!(err.contains("a -> b -> a"));
+    }
+
+    #[test]
+    fn test_target_inherits_unknown_parent() {
+        let mut builder = TargetBuilder::new("orphan");
+        builder.inherits("missing");
+
+        let err = BuiltinTargets::resolve("orphan", &[builder]).unwrap_err();
+        assert!(err.contains("missing"));
+    }
+
+    #[test]
+    fn test_resolve_unknown_target() {
+        assert!(BuiltinTargets::resolve("nope", &[]).unwrap().is_none());
+        assert_eq!(BuiltinTargets::resolve("sheet", &[]).unwrap().unwrap().sheet, SheetConfig::Auto);
+    }
 }
@@ -2,13 +2,43 @@
 //!
 //! Each check takes an `&AssetRegistry` and returns a `ValidationResult`.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::registry::AssetRegistry;
-use crate::types::{BuiltinBrushes, BuiltinStamps, LegendEntry};
+use crate::render::{colour_distance, is_png_ref, quantize_nearest, PICO8_PALETTE};
+use crate::types::{BuiltinBrushes, BuiltinStamps, LegendEntry, Target};
 
 use super::warning::{Diagnostic, ValidationResult};
 
+/// Every code the checks below can emit.
+pub const CODES: &[&str] = &[
+    "px::validate::duplicate-name",
+    "px::validate::empty-grid",
+    "px::validate::map-too-large",
+    "px::validate::missing-brush",
+    "px::validate::missing-palette-colour",
+    "px::validate::missing-ref",
+    "px::validate::missing-stamp",
+    "px::validate::near-duplicate-colour",
+    "px::validate::p8-out-of-gamut",
+    "px::validate::self-reference",
+    "px::validate::shadowed-builtin",
+    "px::validate::stamp-size-mismatch",
+    "px::validate::unmapped-glyph",
+    "px::validate::unsupported-target-format",
+    "px::validate::unused-asset",
+    "px::validate::unused-colour",
+    "px::validate::unused-legend",
+];
+
+/// Legend or binding entries sorted by glyph, so diagnostics come out in a
+/// stable order.
+fn by_glyph<V>(entries: &HashMap<char, V>) -> Vec<(char, &V)> {
+    let mut sorted: Vec<(char, &V)> = entries.iter().map(|(&glyph, v)| (glyph, v)).collect();
+    sorted.sort_by_key(|(glyph, _)| *glyph);
+    sorted
+}
+
 /// Check for shapes, prefabs, or maps with zero-size grids.
 pub fn check_empty_grids(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
@@ -83,9 +113,8 @@ pub fn check_duplicate_names(registry: &AssetRegistry) -> ValidationResult {
 
     // Check for name collisions across shapes/prefabs (a prefab referencing
     // a name could hit either). This is more of a consistency check.
-    let shape_names: HashSet<&str> = registry.shape_names().collect();
     let prefab_names: HashSet<&str> = registry.prefab_names().collect();
-    for name in &shape_names {
+    for name in registry.shape_names() {
         if prefab_names.contains(name) {
             result.push(
                 Diagnostic::warning(
@@ -108,7 +137,7 @@ pub fn check_shape_legend_refs(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     for shape in registry.shapes() {
-        for (glyph, entry) in shape.legend() {
+        for (glyph, entry) in by_glyph(shape.legend()) {
             match entry {
                 LegendEntry::StampRef(name) => {
                     if registry.get_stamp(name).is_none() && BuiltinStamps::get(name).is_none() {
@@ -143,6 +172,7 @@ pub fn check_shape_legend_refs(registry: &AssetRegistry) -> ValidationResult {
                         );
                     }
                 }
+                LegendEntry::Guide | LegendEntry::Gradient { .. } => {}
             }
         }
     }
@@ -155,7 +185,11 @@ pub fn check_prefab_legend_refs(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     for prefab in registry.prefabs() {
-        for (glyph, ref_name) in prefab.legend() {
+        for (glyph, ref_name) in by_glyph(prefab.legend()) {
+            // External PNGs are read (and reported missing) at build time
+            if is_png_ref(ref_name) {
+                continue;
+            }
             if registry.get_shape(ref_name).is_none()
                 && registry.get_prefab(ref_name).is_none()
             {
@@ -181,8 +215,8 @@ pub fn check_map_legend_refs(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     for map in registry.maps() {
-        for (glyph, ref_name) in map.legend() {
-            if ref_name == "empty" {
+        for (glyph, ref_name) in by_glyph(map.legend()) {
+            if ref_name == "empty" || is_png_ref(ref_name) {
                 continue;
             }
             if registry.get_shape(ref_name).is_none()
@@ -205,6 +239,32 @@ pub fn check_map_legend_refs(registry: &AssetRegistry) -> ValidationResult {
     result
 }
 
+/// Check for prefabs or maps whose legend references their own name.
+///
+/// The dependency graph would report these as a cycle; this says which
+/// glyph is at fault.
+pub fn check_self_reference(registry: &AssetRegistry) -> ValidationResult {
+    let mut result = ValidationResult::new();
+
+    let prefabs = registry.prefabs().map(|p| ("Prefab", &p.name, p.legend()));
+    let maps = registry.maps().map(|m| ("Map", &m.name, m.legend()));
+    for (kind, name, legend) in prefabs.chain(maps) {
+        for (glyph, ref_name) in by_glyph(legend) {
+            if ref_name == name {
+                result.push(
+                    Diagnostic::error(
+                        "px::validate::self-reference",
+                        format!("{} '{}': legend '{}' references itself", kind, name, glyph),
+                    )
+                    .with_help("Point the glyph at a shape or another prefab"),
+                );
+            }
+        }
+    }
+
+    result
+}
+
 /// Check for glyphs in grids that have no legend entry and are not builtin glyphs.
 pub fn check_unmapped_glyphs(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
@@ -275,8 +335,8 @@ pub fn check_unused_legends(registry: &AssetRegistry) -> ValidationResult {
 
     for shape in registry.shapes() {
         let grid_glyphs: HashSet<char> = shape.glyphs().into_iter().collect();
-        for glyph in shape.legend().keys() {
-            if !grid_glyphs.contains(glyph) {
+        for (glyph, _) in by_glyph(shape.legend()) {
+            if !grid_glyphs.contains(&glyph) {
                 result.push(
                     Diagnostic::warning(
                         "px::validate::unused-legend",
@@ -293,8 +353,8 @@ pub fn check_unused_legends(registry: &AssetRegistry) -> ValidationResult {
 
     for prefab in registry.prefabs() {
         let grid_glyphs: HashSet<char> = prefab.glyphs().into_iter().collect();
-        for glyph in prefab.legend().keys() {
-            if !grid_glyphs.contains(glyph) {
+        for (glyph, _) in by_glyph(prefab.legend()) {
+            if !grid_glyphs.contains(&glyph) {
                 result.push(
                     Diagnostic::warning(
                         "px::validate::unused-legend",
@@ -311,8 +371,8 @@ pub fn check_unused_legends(registry: &AssetRegistry) -> ValidationResult {
 
     for map in registry.maps() {
         let grid_glyphs: HashSet<char> = map.glyphs().into_iter().collect();
-        for glyph in map.legend().keys() {
-            if !grid_glyphs.contains(glyph) {
+        for (glyph, _) in by_glyph(map.legend()) {
+            if !grid_glyphs.contains(&glyph) {
                 result.push(
                     Diagnostic::warning(
                         "px::validate::unused-legend",
@@ -337,7 +397,7 @@ pub fn check_stamp_sizes(registry: &AssetRegistry) -> ValidationResult {
     for shape in registry.shapes() {
         let mut sizes: Vec<((usize, usize), String)> = Vec::new();
 
-        for entry in shape.legend().values() {
+        for (_, entry) in by_glyph(shape.legend()) {
             if let LegendEntry::StampRef(name) = entry {
                 if let Some(stamp) = registry.get_stamp(name) {
                     sizes.push((stamp.size(), stamp.name.clone()));
@@ -393,14 +453,16 @@ pub fn check_palette_refs(registry: &AssetRegistry) -> ValidationResult {
     }
 
     for shape in registry.shapes() {
-        for (glyph, entry) in shape.legend() {
-            let bindings = match entry {
-                LegendEntry::BrushRef { bindings, .. } => bindings,
-                LegendEntry::Fill { bindings, .. } => bindings,
+        for (glyph, entry) in by_glyph(shape.legend()) {
+            let colour_refs: Vec<(String, &String)> = match entry {
+                LegendEntry::BrushRef { bindings, .. } | LegendEntry::Fill { bindings, .. } => {
+                    by_glyph(bindings).into_iter().map(|(token, c)| (token.to_string(), c)).collect()
+                }
+                LegendEntry::Gradient { from, to, .. } => vec![("from".to_string(), from), ("to".to_string(), to)],
                 _ => continue,
             };
 
-            for (token, colour_ref) in bindings {
+            for (token, colour_ref) in colour_refs {
                 if colour_ref.starts_with('$') && !known_colours.contains(colour_ref) {
                     result.push(
                         Diagnostic::warning(
@@ -425,7 +487,7 @@ pub fn check_target_format(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     for target in registry.targets() {
-        if target.format != "png" && target.format != "p8" {
+        if !matches!(target.format.as_str(), "png" | "auto" | "p8" | "svg" | "webp" | "tiled" | "godot" | "unity") {
             result.push(
                 Diagnostic::warning(
                     "px::validate::unsupported-target-format",
@@ -434,7 +496,37 @@ pub fn check_target_format(registry: &AssetRegistry) -> ValidationResult {
                         target.name, target.format
                     ),
                 )
-                .with_help("Supported formats: 'png', 'p8'"),
+                .with_help("Supported formats: 'png', 'auto', 'p8', 'svg', 'tiled'"),
+            );
+        }
+    }
+
+    result
+}
+
+/// Check that maps fit the active target's device limits.
+pub fn check_map_size(registry: &AssetRegistry, target: &Target) -> ValidationResult {
+    let mut result = ValidationResult::new();
+    let Some((max_w, max_h)) = target.map_limit() else {
+        return result;
+    };
+
+    for map in registry.maps() {
+        if map.width() > max_w || map.height() > max_h {
+            result.push(
+                Diagnostic::warning(
+                    "px::validate::map-too-large",
+                    format!(
+                        "Map '{}' is {}x{} cells, larger than the {}x{} maximum for target '{}'",
+                        map.name,
+                        map.width(),
+                        map.height(),
+                        max_w,
+                        max_h,
+                        target.name
+                    ),
+                )
+                .with_help("Split the map or crop it; cells beyond the limit are dropped"),
             );
         }
     }
@@ -442,6 +534,88 @@ pub fn check_target_format(registry: &AssetRegistry) -> ValidationResult {
     result
 }
 
+/// Weighted distance beyond which a colour visibly shifts when quantized
+/// to PICO-8, about 20 levels on every channel.
+const P8_GAMUT_TOLERANCE: u32 = 3600;
+
+/// Check for palette colours that shift noticeably when quantized to the
+/// PICO-8 palette. Only runs when a `p8` target is defined.
+pub fn check_p8_gamut(registry: &AssetRegistry) -> ValidationResult {
+    let mut result = ValidationResult::new();
+    let Some(target) = registry.targets().find(|t| t.format == "p8") else {
+        return result;
+    };
+
+    for palette in registry.palettes() {
+        let mut colour_names: Vec<&str> = palette.colour_names().collect();
+        colour_names.sort_unstable();
+        for colour_name in colour_names {
+            let Some(colour) = palette.get(colour_name).filter(|c| !c.is_transparent()) else {
+                continue;
+            };
+            let index = quantize_nearest(&colour, &PICO8_PALETTE, 0);
+            let nearest = PICO8_PALETTE[index as usize];
+            if colour_distance(&colour, &nearest) > P8_GAMUT_TOLERANCE {
+                result.push(
+                    Diagnostic::warning(
+                        "px::validate::p8-out-of-gamut",
+                        format!(
+                            "Palette '{}': colour '{}' ({}) is outside the PICO-8 palette used by target '{}'",
+                            palette.name, colour_name, colour, target.name
+                        ),
+                    )
+                    .with_help(format!("Nearest PICO-8 colour is {} ({})", index, nearest)),
+                );
+            }
+        }
+    }
+
+    result
+}
+
+/// Weighted distance under which two palette colours are hard to tell
+/// apart, about 3 levels on every channel.
+const NEAR_DUPLICATE_TOLERANCE: u32 = 72;
+
+/// Check for palettes with two colours close enough to be mistaken for
+/// each other. Identical colours are left alone, as they are usually
+/// intentional aliases.
+pub fn check_near_duplicate_colours(registry: &AssetRegistry) -> ValidationResult {
+    let mut result = ValidationResult::new();
+
+    for palette in registry.palettes() {
+        if palette.name == "default" {
+            continue;
+        }
+        let mut colour_names: Vec<&str> = palette.colour_names().collect();
+        colour_names.sort_unstable();
+        let colours: Vec<(&str, _)> = colour_names
+            .into_iter()
+            .filter_map(|name| palette.get(name).filter(|c| !c.is_transparent()).map(|c| (name, c)))
+            .collect();
+
+        for (i, (name, colour)) in colours.iter().enumerate() {
+            for (other_name, other) in &colours[i + 1..] {
+                let distance = colour_distance(colour, other);
+                if colour.a == other.a && distance > 0 && distance <= NEAR_DUPLICATE_TOLERANCE {
+                    result.push(
+                        Diagnostic::warning(
+                            "px::validate::near-duplicate-colour",
+                            format!(
+                                "Palette '{}': colours '{}' ({}) and '{}' ({}) are nearly identical",
+                                palette.name, name, colour, other_name, other
+                            ),
+                        )
+                        .with_help("Merge them into one colour, or make them visibly different"),
+                    );
+                }
+            }
+        }
+    }
+
+    result
+}
+
 /// Check for assets that are never referenced by any shape, prefab, or map.
 pub fn check_unused_assets(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
@@ -462,6 +636,7 @@ pub fn check_unused_assets(registry: &AssetRegistry) -> ValidationResult {
                 LegendEntry::BrushRef { name, .. } | LegendEntry::Fill { name, .. } => {
                     used_brushes.insert(name.clone());
                 }
+                LegendEntry::Guide | LegendEntry::Gradient { .. } => {}
             }
         }
     }
@@ -648,15 +823,15 @@ pub fn check_unused_palette_colours(registry: &AssetRegistry) -> ValidationResul
     used_colours.insert("edge".to_string());
     used_colours.insert("fill".to_string());
 
-    // Collect colour refs from shape legends (brush bindings)
+    // Collect colour refs from shape legends (brush bindings and gradients)
     for shape in registry.shapes() {
         for entry in shape.legend().values() {
-            let bindings = match entry {
-                LegendEntry::BrushRef { bindings, .. } => bindings,
-                LegendEntry::Fill { bindings, .. } => bindings,
+            let colour_refs: Vec<&String> = match entry {
+                LegendEntry::BrushRef { bindings, .. } | LegendEntry::Fill { bindings, .. } => bindings.values().collect(),
+                LegendEntry::Gradient { from, to, .. } => vec![from, to],
                 _ => continue,
             };
-            for colour_ref in bindings.values() {
+            for colour_ref in colour_refs {
                 used_colours.insert(colour_ref.clone());
                 // Also add without $ prefix
                 if let Some(stripped) = colour_ref.strip_prefix('$') {
@@ -678,7 +853,9 @@ pub fn check_unused_palette_colours(registry: &AssetRegistry) -> ValidationResul
         if palette.name == "default" {
             continue;
         }
-        for colour_name in palette.colour_names() {
+        let mut colour_names: Vec<&str> = palette.colour_names().collect();
+        colour_names.sort_unstable();
+        for colour_name in colour_names {
             if !used_colours.contains(colour_name) && !used_colours.contains(&format!("${}", colour_name)) {
                 result.push(
                     Diagnostic::warning(
@@ -701,7 +878,7 @@ pub fn check_unused_palette_colours(registry: &AssetRegistry) -> ValidationResul
 mod tests {
     use super::*;
     use crate::registry::RegistryBuilder;
-    use crate::types::{Brush, Map, Palette, PaletteBuilder, Prefab, Shader, Shape, Stamp, PixelToken, Target};
+    use crate::types::{Brush, BuiltinTargets, Map, Palette, PaletteBuilder, Prefab, Shader, Shape, Stamp, PixelToken, Target};
     use std::collections::HashMap;
 
     fn build_registry(builder: RegistryBuilder) -> AssetRegistry {
@@ -764,6 +941,7 @@ mod tests {
             LegendEntry::Fill {
                 name: "nonexistent".to_string(),
                 bindings: HashMap::new(),
+                offset: (0, 0),
             },
         );
 
@@ -788,6 +966,24 @@ mod tests {
         assert!(result.has_errors());
     }
 
+    #[test]
+    fn test_check_self_reference_prefab() {
+        let mut builder = RegistryBuilder::new();
+        builder.add_shape(Shape::new("wall", vec![], vec![vec!['#']], HashMap::new()));
+
+        let mut legend = HashMap::new();
+        legend.insert('W', "wall".to_string());
+        legend.insert('T', "tower".to_string());
+        builder.add_prefab(Prefab::new("tower", vec![], vec![vec!['W', 'T']], legend));
+
+        let registry = build_registry(builder);
+        let result = check_self_reference(&registry);
+        assert_eq!(result.error_count(), 1);
+        let error = result.iter().next().unwrap();
+        assert_eq!(error.code, "px::validate::self-reference");
+        assert!(error.message.contains("Prefab 'tower': legend 'T' references itself"));
+    }
+
     #[test]
     fn test_check_prefab_legend_refs_valid() {
         let mut builder = RegistryBuilder::new();
@@ -935,6 +1131,29 @@ mod tests {
         assert!(result.has_warnings());
     }
 
+    // -- check_map_size --
+
+    fn wide_map_registry() -> AssetRegistry {
+        let legend: HashMap<char, String> = [('.', "empty".to_string())].into_iter().collect();
+        let mut builder = RegistryBuilder::new();
+        builder.add_map(Map::new("long", vec![], vec![vec!['.'; 200]; 10], legend));
+        build_registry(builder)
+    }
+
+    #[test]
+    fn test_check_map_size_p8_too_wide() {
+        let result = check_map_size(&wide_map_registry(), &BuiltinTargets::get("p8").unwrap());
+        assert!(result.has_warnings());
+        assert_eq!(result.iter().next().unwrap().code, "px::validate::map-too-large");
+    }
+
+    #[test]
+    fn test_check_map_size_web_unconstrained() {
+        let result = check_map_size(&wide_map_registry(), &BuiltinTargets::get("web").unwrap());
+        assert!(result.is_ok());
+        assert!(!result.has_warnings());
+    }
+
     // -- check_unused_assets --
 
     #[test]
@@ -1124,6 +1343,7 @@ mod tests {
         legend.insert('~', LegendEntry::Fill {
             name: "checker".to_string(),
             bindings,
+            offset: (0, 0),
         });
 
         let mut builder = RegistryBuilder::new();
@@ -1152,6 +1372,7 @@ mod tests {
         legend.insert('~', LegendEntry::Fill {
             name: "checker".to_string(),
             bindings,
+            offset: (0, 0),
         });
 
         let mut builder = RegistryBuilder::new();
@@ -1171,6 +1392,7 @@ mod tests {
         legend.insert('~', LegendEntry::Fill {
             name: "checker".to_string(),
             bindings,
+            offset: (0, 0),
         });
 
         let mut builder = RegistryBuilder::new();
@@ -1181,6 +1403,67 @@ mod tests {
         assert!(result.has_warnings());
     }
 
+    // -- check_p8_gamut --
+
+    fn gamut_registry(colour: &str, format: &str) -> AssetRegistry {
+        let mut pb = PaletteBuilder::new("game");
+        pb.define("accent", colour);
+        let mut builder = RegistryBuilder::new();
+        builder.add_palette(pb.build(None).unwrap());
+        builder.add_target(Target::new("cart", format));
+        build_registry(builder)
+    }
+
+    #[test]
+    fn test_check_p8_gamut_in_gamut() {
+        // PICO-8 red, give or take
+        let result = check_p8_gamut(&gamut_registry("#FE024C", "p8"));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_check_p8_gamut_out_of_gamut() {
+        let result = check_p8_gamut(&gamut_registry("#FF0000", "p8"));
+        let warnings: Vec<_> = result.iter().collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "px::validate::p8-out-of-gamut");
+        assert!(warnings[0].message.contains("'accent' (#FF0000)"));
+        assert_eq!(warnings[0].help.as_deref(), Some("Nearest PICO-8 colour is 8 (#FF004D)"));
+    }
+
+    #[test]
+    fn test_check_p8_gamut_needs_p8_target() {
+        let result = check_p8_gamut(&gamut_registry("#FF0000", "png"));
+        assert!(result.is_ok());
+    }
+
+    // -- check_near_duplicate_colours --
+
+    fn pair_registry(a: &str, b: &str) -> AssetRegistry {
+        let mut pb = PaletteBuilder::new("game");
+        pb.define("grass", a);
+        pb.define("moss", b);
+        let mut builder = RegistryBuilder::new();
+        builder.add_palette(pb.build(None).unwrap());
+        build_registry(builder)
+    }
+
+    #[test]
+    fn test_check_near_duplicate_colours_warns() {
+        let result = check_near_duplicate_colours(&pair_registry("#408020", "#418121"));
+        let warnings: Vec<_> = result.iter().collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "px::validate::near-duplicate-colour");
+        assert!(warnings[0].message.contains("'grass' (#408020) and 'moss' (#418121)"));
+    }
+
+    #[test]
+    fn test_check_near_duplicate_colours_distinct() {
+        assert!(check_near_duplicate_colours(&pair_registry("#408020", "#204080")).is_ok());
+        // Exact repeats are aliases, not mistakes
+        assert!(check_near_duplicate_colours(&pair_registry("#408020", "#408020")).is_ok());
+    }
+
     // -- check_duplicate_names --
 
     #[test]
@@ -6,13 +6,31 @@
 mod checks;
 mod warning;
 
-pub use warning::{Diagnostic, Severity, ValidationResult};
+pub use warning::{Diagnostic, LintLevel, Lints, Severity, ValidationResult};
 
+use crate::error::{PxError, Result};
 use crate::output::{plural, Printer};
 use crate::registry::AssetRegistry;
+use crate::types::Target;
 
-/// Run all validation checks against the registry.
-pub fn validate_registry(registry: &AssetRegistry) -> ValidationResult {
+/// Run all validation checks against the registry, with `lints` applied.
+pub fn validate_registry(registry: &AssetRegistry, lints: &Lints) -> ValidationResult {
+    apply_lints(run_checks(registry), lints)
+}
+
+/// Run all validation checks, plus any limits of the selected `target`,
+/// with `lints` applied.
+pub fn validate_for_target(registry: &AssetRegistry, target: Option<&Target>, lints: &Lints) -> ValidationResult {
+    let mut result = run_checks(registry);
+
+    if let Some(target) = target {
+        result.merge(checks::check_map_size(registry, target));
+    }
+
+    apply_lints(result, lints)
+}
+
+fn run_checks(registry: &AssetRegistry) -> ValidationResult {
     let mut result = ValidationResult::new();
 
     result.merge(checks::check_empty_grids(registry));
@@ -20,6 +38,7 @@ pub fn validate_registry(registry: &AssetRegistry) -> ValidationResult {
     result.merge(checks::check_shape_legend_refs(registry));
     result.merge(checks::check_prefab_legend_refs(registry));
     result.merge(checks::check_map_legend_refs(registry));
+    result.merge(checks::check_self_reference(registry));
     result.merge(checks::check_unmapped_glyphs(registry));
     result.merge(checks::check_unused_legends(registry));
     result.merge(checks::check_stamp_sizes(registry));
@@ -28,10 +47,47 @@ pub fn validate_registry(registry: &AssetRegistry) -> ValidationResult {
     result.merge(checks::check_unused_assets(registry));
     result.merge(checks::check_shadowed_definitions(registry));
     result.merge(checks::check_unused_palette_colours(registry));
+    result.merge(checks::check_p8_gamut(registry));
+    result.merge(checks::check_near_duplicate_colours(registry));
 
     result
 }
 
+/// Re-level or drop diagnostics by code. Unknown codes in `lints` are
+/// reported as warnings, so typos don't silently do nothing.
+fn apply_lints(result: ValidationResult, lints: &Lints) -> ValidationResult {
+    let level = |code: &str| {
+        let short = code.strip_prefix(LINT_PREFIX).unwrap_or(code);
+        lints.get(code).or_else(|| lints.get(short)).copied()
+    };
+
+    let mut linted = ValidationResult::new();
+    for code in lints.keys() {
+        let short = code.strip_prefix(LINT_PREFIX).unwrap_or(code);
+        if !checks::CODES.iter().any(|known| known.strip_prefix(LINT_PREFIX) == Some(short)) {
+            let known: Vec<&str> = checks::CODES.iter().map(|c| c.strip_prefix(LINT_PREFIX).unwrap_or(c)).collect();
+            linted.push(
+                Diagnostic::warning("px::validate::unknown-lint", format!("Unknown lint '{}' in px.yaml", code))
+                    .with_help(format!("Known lints: {}", known.join(", "))),
+            );
+        }
+    }
+
+    for diagnostic in result.iter() {
+        let severity = match level(&diagnostic.code) {
+            Some(LintLevel::Off) => continue,
+            Some(LintLevel::Warn) => Severity::Warning,
+            Some(LintLevel::Error) => Severity::Error,
+            None => diagnostic.severity,
+        };
+        linted.push(Diagnostic { severity, ..diagnostic.clone() });
+    }
+    linted
+}
+
+/// Prefix shared by every check's diagnostic code.
+const LINT_PREFIX: &str = "px::validate::";
+
 /// Print diagnostics to stderr with coloured output.
 pub fn print_diagnostics(result: &ValidationResult, printer: &Printer) {
     for d in result.iter() {
@@ -48,10 +104,16 @@ pub fn print_diagnostics(result: &ValidationResult, printer: &Printer) {
     let errors = result.error_count();
     let warnings = result.warning_count();
 
-    if errors > 0 {
+    if result.fails() {
+        let strict = if errors == 0 { " (strict)" } else { "" };
         printer.error(
             "Failed",
-            &format!("{}, {}", plural(errors, "error", "errors"), plural(warnings, "warning", "warnings")),
+            &format!(
+                "{}, {}{}",
+                plural(errors, "error", "errors"),
+                plural(warnings, "warning", "warnings"),
+                strict
+            ),
         );
     } else if warnings > 0 {
         printer.success(
@@ -63,6 +125,15 @@ pub fn print_diagnostics(result: &ValidationResult, printer: &Printer) {
     }
 }
 
+/// Serialize diagnostics as a JSON array, for CI and other tooling.
+pub fn diagnostics_json(result: &ValidationResult) -> Result<String> {
+    let diagnostics: Vec<&Diagnostic> = result.iter().collect();
+    serde_json::to_string_pretty(&diagnostics).map_err(|e| PxError::Build {
+        message: format!("Failed to serialize diagnostics: {}", e),
+        help: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,7 +144,7 @@ mod tests {
     #[test]
     fn test_validate_empty_registry() {
         let registry = RegistryBuilder::new().build().unwrap();
-        let result = validate_registry(&registry);
+        let result = validate_registry(&registry, &Lints::new());
         assert!(result.is_ok());
     }
 
@@ -88,7 +159,7 @@ mod tests {
         ));
         let registry = builder.build().unwrap();
 
-        let result = validate_registry(&registry);
+        let result = validate_registry(&registry, &Lints::new());
         assert!(!result.has_errors());
     }
 
@@ -101,7 +172,100 @@ mod tests {
         builder.add_shape(Shape::new("wall", vec![], vec![vec!['B']], legend));
         let registry = builder.build().unwrap();
 
-        let result = validate_registry(&registry);
+        let result = validate_registry(&registry, &Lints::new());
         assert!(result.has_errors());
     }
+
+    #[test]
+    fn test_diagnostics_json() {
+        let mut result = ValidationResult::new();
+        result.push(Diagnostic::error("px::a", "broken").with_help("fix it"));
+        result.warning("px::b", "odd");
+
+        let json: serde_json::Value = serde_json::from_str(&diagnostics_json(&result).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"severity": "error", "code": "px::a", "message": "broken", "help": "fix it"},
+                {"severity": "warning", "code": "px::b", "message": "odd", "help": null},
+            ])
+        );
+    }
+
+    /// A registry whose only shape references a missing stamp.
+    fn missing_stamp_registry() -> AssetRegistry {
+        let mut legend = HashMap::new();
+        legend.insert('B', LegendEntry::StampRef("nonexistent".to_string()));
+        let mut builder = RegistryBuilder::new();
+        builder.add_shape(Shape::new("wall", vec![], vec![vec!['B']], legend));
+        builder.build().unwrap()
+    }
+
+    fn codes(result: &ValidationResult) -> Vec<(&str, Severity)> {
+        result.iter().map(|d| (d.code.as_str(), d.severity)).collect()
+    }
+
+    #[test]
+    fn test_lint_off_drops_code() {
+        let registry = missing_stamp_registry();
+        let before = validate_registry(&registry, &Lints::new());
+        assert!(codes(&before).contains(&("px::validate::missing-stamp", Severity::Error)));
+
+        let lints = Lints::from([("missing-stamp".to_string(), LintLevel::Off)]);
+        let after = validate_registry(&registry, &lints);
+        assert!(!after.iter().any(|d| d.code == "px::validate::missing-stamp"));
+        assert_eq!(after.iter().count(), before.iter().count() - 1);
+    }
+
+    #[test]
+    fn test_lint_error_raises_warning() {
+        // 'X' is in the legend but not the grid
+        let mut legend = HashMap::new();
+        legend.insert('X', LegendEntry::StampRef("solid".to_string()));
+        let mut builder = RegistryBuilder::new();
+        builder.add_shape(Shape::new("wall", vec![], vec![vec!['#']], legend));
+        let registry = builder.build().unwrap();
+
+        let before = validate_registry(&registry, &Lints::new());
+        assert!(codes(&before).contains(&("px::validate::unused-legend", Severity::Warning)));
+        assert!(!before.has_errors());
+
+        let lints = Lints::from([("px::validate::unused-legend".to_string(), LintLevel::Error)]);
+        let after = validate_registry(&registry, &lints);
+        assert!(codes(&after).contains(&("px::validate::unused-legend", Severity::Error)));
+        assert!(after.has_errors());
+    }
+
+    #[test]
+    fn test_unknown_lint_warns() {
+        let lints = Lints::from([("no-such-check".to_string(), LintLevel::Off)]);
+        let result = validate_registry(&missing_stamp_registry(), &lints);
+        let unknown: Vec<&Diagnostic> = result.iter().filter(|d| d.code == "px::validate::unknown-lint").collect();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].severity, Severity::Warning);
+        assert!(unknown[0].message.contains("no-such-check"));
+    }
+
+    #[test]
+    fn test_validate_diagnostic_order_is_stable() {
+        fn build() -> AssetRegistry {
+            let mut legend = HashMap::new();
+            for glyph in ['A', 'B', 'C', 'D', 'E', 'F'] {
+                legend.insert(glyph, LegendEntry::StampRef(format!("missing-{}", glyph)));
+            }
+            let mut builder = RegistryBuilder::new();
+            builder.add_shape(Shape::new("wall", vec![], vec![vec!['#']], legend));
+            builder.build().unwrap()
+        }
+
+        let messages = |registry: &AssetRegistry| -> Vec<String> {
+            validate_registry(registry, &Lints::new()).iter().map(|d| d.message.clone()).collect()
+        };
+
+        let first = messages(&build());
+        assert_eq!(first, messages(&build()));
+
+        let missing: Vec<&String> = first.iter().filter(|m| m.contains("missing-")).collect();
+        assert!(missing[0].contains("legend 'A'"));
+    }
 }
@@ -1,9 +1,13 @@
 //! Diagnostic types for validation results.
 
+use std::collections::BTreeMap;
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 /// Severity level for a diagnostic.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Severity {
     Warning,
     Error,
@@ -18,8 +22,26 @@ impl fmt::Display for Severity {
     }
 }
 
+/// How a check's diagnostics are reported, overriding its own severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Drop the diagnostic.
+    Off,
+    /// Report as a warning.
+    Warn,
+    /// Report as an error.
+    Error,
+}
+
+/// Lint levels by diagnostic code, as set in `px.yaml` under `lints:`.
+///
+/// Codes may be written in full (`px::validate::unused-asset`) or without
+/// the `px::validate::` prefix.
+pub type Lints = BTreeMap<String, LintLevel>;
+
 /// A single validation diagnostic.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Diagnostic {
     /// Severity level.
     pub severity: Severity,
@@ -63,6 +85,7 @@ impl Diagnostic {
 #[derive(Debug, Clone, Default)]
 pub struct ValidationResult {
     diagnostics: Vec<Diagnostic>,
+    strict: bool,
 }
 
 impl ValidationResult {
@@ -88,30 +111,50 @@ impl ValidationResult {
 
     /// Check if there are any errors.
     pub fn has_errors(&self) -> bool {
-        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+        self.errors().next().is_some()
     }
 
     /// Check if there are any warnings.
     pub fn has_warnings(&self) -> bool {
-        self.diagnostics
-            .iter()
-            .any(|d| d.severity == Severity::Warning)
+        self.warnings().next().is_some()
     }
 
     /// Count errors.
     pub fn error_count(&self) -> usize {
-        self.diagnostics
-            .iter()
-            .filter(|d| d.severity == Severity::Error)
-            .count()
+        self.errors().count()
     }
 
     /// Count warnings.
     pub fn warning_count(&self) -> usize {
-        self.diagnostics
-            .iter()
-            .filter(|d| d.severity == Severity::Warning)
-            .count()
+        self.warnings().count()
+    }
+
+    /// Iterate over diagnostics of one severity, in the order reported.
+    pub fn by_severity(&self, severity: Severity) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(move |d| d.severity == severity)
+    }
+
+    /// Iterate over error diagnostics.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.by_severity(Severity::Error)
+    }
+
+    /// Iterate over warning diagnostics.
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.by_severity(Severity::Warning)
+    }
+
+    /// Treat warnings as failures too, for `--strict`. Diagnostics keep
+    /// their severity; only [`fails`](Self::fails) changes.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Check if the result should fail the run: any errors, or any
+    /// warnings in strict mode.
+    pub fn fails(&self) -> bool {
+        self.has_errors() || (self.strict && self.has_warnings())
     }
 
     /// Check if there are no diagnostics at all.
@@ -122,6 +165,7 @@ impl ValidationResult {
     /// Merge another result into this one.
     pub fn merge(&mut self, other: ValidationResult) {
         self.diagnostics.extend(other.diagnostics);
+        self.strict |= other.strict;
     }
 
     /// Iterate over diagnostics.
@@ -166,6 +210,19 @@ mod tests {
         assert_eq!(result.warning_count(), 1);
     }
 
+    #[test]
+    fn test_strict_fails_on_warnings() {
+        let mut result = ValidationResult::new();
+        result.warning("px::test", "something looks off");
+        assert!(!result.fails());
+
+        let result = result.with_strict(true);
+        assert!(result.fails());
+        // Still reported as a warning
+        assert_eq!(result.warning_count(), 1);
+        assert_eq!(result.error_count(), 0);
+    }
+
     #[test]
     fn test_merge() {
         let mut a = ValidationResult::new();
@@ -179,6 +236,21 @@ mod tests {
         assert_eq!(a.warning_count(), 1);
     }
 
+    #[test]
+    fn test_errors_and_warnings_filter_by_severity() {
+        let mut result = ValidationResult::new();
+        result.warning("px::a", "warning a");
+        result.error("px::b", "error b");
+        result.warning("px::c", "warning c");
+        result.error("px::d", "error d");
+
+        let errors: Vec<&str> = result.errors().map(|d| d.code.as_str()).collect();
+        assert_eq!(errors, ["px::b", "px::d"]);
+        let warnings: Vec<&str> = result.warnings().map(|d| d.code.as_str()).collect();
+        assert_eq!(warnings, ["px::a", "px::c"]);
+        assert_eq!(result.by_severity(Severity::Error).count(), 2);
+    }
+
     #[test]
     fn test_diagnostic_with_help() {
         let d = Diagnostic::error("px::test", "missing stamp")